@@ -0,0 +1,68 @@
+/* A C API for ripgrep's core searching routines.
+ *
+ * See the crate documentation in src/lib.rs for ownership and lifetime
+ * rules. In short: everything returned by this library must be freed with
+ * the matching *_free function, and the pointers inside an rg_match are
+ * only valid until the next call on the result set that produced it.
+ */
+
+#ifndef GREP_CAPI_H
+#define GREP_CAPI_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* An opaque, validated pattern. */
+typedef struct rg_matcher rg_matcher;
+
+/* An opaque set of search results being iterated. */
+typedef struct rg_results rg_results;
+
+/* A single matching line. */
+typedef struct rg_match {
+    /* The path of the file containing the match, NUL terminated. */
+    const char *path;
+    /* The 1-based line number of the match, or 0 when unavailable. */
+    uint64_t line_number;
+    /* The absolute byte offset of the matching line within its file. */
+    uint64_t byte_offset;
+    /* The contents of the matching line, without its line terminator.
+     * Not NUL terminated; may contain arbitrary bytes. */
+    const uint8_t *line;
+    /* The length of line, in bytes. */
+    size_t line_len;
+} rg_match;
+
+/* Validate pattern and return a matcher for it, or NULL if the pattern is
+ * invalid, in which case an error message is copied into errbuf. */
+rg_matcher *rg_matcher_new(const char *pattern, char *errbuf,
+                           size_t errbuf_len);
+
+/* Free a matcher returned by rg_matcher_new. */
+void rg_matcher_free(rg_matcher *matcher);
+
+/* Search the file or directory at path. Directories are traversed
+ * recursively, respecting gitignore rules. Returns NULL on error, in
+ * which case an error message is copied into errbuf. */
+rg_results *rg_search_path(const rg_matcher *matcher, const char *path,
+                           char *errbuf, size_t errbuf_len);
+
+/* Fill out with the next match. Returns 1 when a match was produced and 0
+ * when the results are exhausted. */
+int rg_results_next(rg_results *results, rg_match *out);
+
+/* Return the total number of matches in the result set. */
+size_t rg_results_count(const rg_results *results);
+
+/* Free a result set returned by rg_search_path. */
+void rg_results_free(rg_results *results);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* GREP_CAPI_H */
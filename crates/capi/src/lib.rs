@@ -0,0 +1,274 @@
+/*!
+A C API for ripgrep's core searching routines.
+
+This crate builds a C dynamic (and static) library exposing a small, stable
+ABI over the high level facade in the `grep` crate, so that bindings from
+Python, Node, Go and friends can run searches in-process instead of
+shelling out to the `rg` binary.
+
+The surface is deliberately minimal:
+
+* `rg_matcher_new` validates a pattern and returns an opaque matcher.
+* `rg_search_path` searches a file or directory tree (respecting gitignore
+  rules) and returns an opaque result set.
+* `rg_results_next` iterates the result set, filling a caller provided
+  `rg_match` struct for each matching line.
+
+Errors are reported by copying a message into a caller provided buffer,
+which keeps allocation and ownership on one side of the boundary. All
+pointers handed out by this library must be released with the matching
+`*_free` function, and the pointers inside an `rg_match` are only valid
+until the next call on the result set that produced it.
+
+See `include/grep_capi.h` for the corresponding C declarations.
+*/
+
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use grep::{matcher_for, EngineChoice, SearchBuilder};
+
+/// An opaque, validated pattern.
+pub struct RgMatcher {
+    pattern: String,
+}
+
+/// An opaque set of search results being iterated.
+pub struct RgResults {
+    matches: Vec<OwnedMatch>,
+    pos: usize,
+}
+
+/// The owned backing data for one match, kept alive by its `RgResults`.
+struct OwnedMatch {
+    path: CString,
+    line_number: u64,
+    byte_offset: u64,
+    line: Vec<u8>,
+}
+
+/// A single matching line, as seen by C callers.
+///
+/// The `path` and `line` pointers borrow from the result set that produced
+/// this match and are invalidated by the next call to `rg_results_next` or
+/// `rg_results_free` on that result set.
+#[repr(C)]
+pub struct rg_match {
+    /// The path of the file containing the match, NUL terminated.
+    pub path: *const c_char,
+    /// The 1-based line number of the match, or 0 when unavailable.
+    pub line_number: u64,
+    /// The absolute byte offset of the matching line within its file.
+    pub byte_offset: u64,
+    /// The contents of the matching line, without its line terminator.
+    /// This is not NUL terminated and may contain arbitrary bytes.
+    pub line: *const u8,
+    /// The length of `line`, in bytes.
+    pub line_len: usize,
+}
+
+/// Copy an error message into the caller provided buffer, truncating it to
+/// fit and always NUL terminating when the buffer is non-empty.
+unsafe fn write_error(errbuf: *mut c_char, errbuf_len: usize, msg: &str) {
+    if errbuf.is_null() || errbuf_len == 0 {
+        return;
+    }
+    let bytes = msg.as_bytes();
+    let len = bytes.len().min(errbuf_len - 1);
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), errbuf as *mut u8, len);
+    *errbuf.add(len) = 0;
+}
+
+/// Validate the given NUL terminated pattern and return a matcher for it,
+/// or NULL if the pattern is invalid. On error, a message is copied into
+/// `errbuf`, which should have room for `errbuf_len` bytes.
+///
+/// The returned matcher must be freed with `rg_matcher_free`.
+///
+/// # Safety
+///
+/// `pattern` must be NULL or point to a valid NUL terminated string, and
+/// `errbuf` must be NULL or point to a writable buffer of at least
+/// `errbuf_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rg_matcher_new(
+    pattern: *const c_char,
+    errbuf: *mut c_char,
+    errbuf_len: usize,
+) -> *mut RgMatcher {
+    catch_unwind(|| {
+        if pattern.is_null() {
+            write_error(errbuf, errbuf_len, "pattern is NULL");
+            return std::ptr::null_mut();
+        }
+        let pattern = match CStr::from_ptr(pattern).to_str() {
+            Ok(pattern) => pattern,
+            Err(_) => {
+                write_error(errbuf, errbuf_len, "pattern is not valid UTF-8");
+                return std::ptr::null_mut();
+            }
+        };
+        // The matcher itself is rebuilt by each search; building one here
+        // reports pattern errors at creation time, where C callers expect
+        // them.
+        if let Err(err) = matcher_for(pattern, EngineChoice::Auto) {
+            write_error(errbuf, errbuf_len, &err.to_string());
+            return std::ptr::null_mut();
+        }
+        Box::into_raw(Box::new(RgMatcher { pattern: pattern.to_string() }))
+    })
+    .unwrap_or(std::ptr::null_mut())
+}
+
+/// Free a matcher returned by `rg_matcher_new`. Does nothing when given
+/// NULL.
+///
+/// # Safety
+///
+/// `matcher` must be NULL or a pointer previously returned by
+/// `rg_matcher_new` that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn rg_matcher_free(matcher: *mut RgMatcher) {
+    if !matcher.is_null() {
+        drop(Box::from_raw(matcher));
+    }
+}
+
+/// Search the file or directory at the given NUL terminated path with the
+/// given matcher. Directories are traversed recursively, respecting
+/// gitignore rules. Returns a result set to iterate with
+/// `rg_results_next`, or NULL on error, in which case a message is copied
+/// into `errbuf`.
+///
+/// The returned result set must be freed with `rg_results_free`.
+///
+/// # Safety
+///
+/// `matcher` must be a pointer returned by `rg_matcher_new`, `path` must
+/// be NULL or point to a valid NUL terminated string, and `errbuf` must be
+/// NULL or point to a writable buffer of at least `errbuf_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rg_search_path(
+    matcher: *const RgMatcher,
+    path: *const c_char,
+    errbuf: *mut c_char,
+    errbuf_len: usize,
+) -> *mut RgResults {
+    catch_unwind(|| {
+        if matcher.is_null() {
+            write_error(errbuf, errbuf_len, "matcher is NULL");
+            return std::ptr::null_mut();
+        }
+        if path.is_null() {
+            write_error(errbuf, errbuf_len, "path is NULL");
+            return std::ptr::null_mut();
+        }
+        let matcher = &*matcher;
+        let path = path_from_c(CStr::from_ptr(path));
+        let mut matches = vec![];
+        let result = SearchBuilder::new()
+            .pattern(&matcher.pattern)
+            .path(path)
+            .search(|m| {
+                let path =
+                    m.path().to_string_lossy().into_owned().into_bytes();
+                matches.push(OwnedMatch {
+                    // Paths never contain NUL bytes on the platforms we
+                    // support, but don't crash if one does.
+                    path: CString::new(path).unwrap_or_default(),
+                    line_number: m.line_number().unwrap_or(0),
+                    byte_offset: m.byte_offset(),
+                    line: m.bytes().to_vec(),
+                });
+                true
+            });
+        if let Err(err) = result {
+            write_error(errbuf, errbuf_len, &err.to_string());
+            return std::ptr::null_mut();
+        }
+        Box::into_raw(Box::new(RgResults { matches, pos: 0 }))
+    })
+    .unwrap_or(std::ptr::null_mut())
+}
+
+/// Advance the given result set, filling `out` with the next match.
+/// Returns 1 when a match was produced and 0 when the results are
+/// exhausted.
+///
+/// The pointers written to `out` are owned by the result set and remain
+/// valid until the next call to `rg_results_next` or `rg_results_free` on
+/// it.
+///
+/// # Safety
+///
+/// `results` must be a pointer returned by `rg_search_path` and `out`
+/// must point to a writable `rg_match`.
+#[no_mangle]
+pub unsafe extern "C" fn rg_results_next(
+    results: *mut RgResults,
+    out: *mut rg_match,
+) -> c_int {
+    catch_unwind(AssertUnwindSafe(|| {
+        if results.is_null() || out.is_null() {
+            return 0;
+        }
+        let results = &mut *results;
+        let m = match results.matches.get(results.pos) {
+            None => return 0,
+            Some(m) => m,
+        };
+        results.pos += 1;
+        *out = rg_match {
+            path: m.path.as_ptr(),
+            line_number: m.line_number,
+            byte_offset: m.byte_offset,
+            line: m.line.as_ptr(),
+            line_len: m.line.len(),
+        };
+        1
+    }))
+    .unwrap_or(0)
+}
+
+/// Return the total number of matches in the given result set.
+///
+/// # Safety
+///
+/// `results` must be NULL or a pointer returned by `rg_search_path`.
+#[no_mangle]
+pub unsafe extern "C" fn rg_results_count(
+    results: *const RgResults,
+) -> usize {
+    if results.is_null() {
+        return 0;
+    }
+    (*results).matches.len()
+}
+
+/// Free a result set returned by `rg_search_path`. Does nothing when given
+/// NULL.
+///
+/// # Safety
+///
+/// `results` must be NULL or a pointer previously returned by
+/// `rg_search_path` that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn rg_results_free(results: *mut RgResults) {
+    if !results.is_null() {
+        drop(Box::from_raw(results));
+    }
+}
+
+/// Convert a C path to an owned platform path.
+#[cfg(unix)]
+fn path_from_c(path: &CStr) -> std::path::PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    std::ffi::OsStr::from_bytes(path.to_bytes()).into()
+}
+
+/// Convert a C path to an owned platform path.
+#[cfg(not(unix))]
+fn path_from_c(path: &CStr) -> std::path::PathBuf {
+    path.to_string_lossy().into_owned().into()
+}
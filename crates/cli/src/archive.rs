@@ -0,0 +1,170 @@
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// The kind of archive detected from a file path.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+/// Returns the kind of archive the given path refers to, if any. Detection
+/// is based purely on the file extension.
+fn archive_kind(path: &Path) -> Option<ArchiveKind> {
+    let name = match path.file_name().and_then(|n| n.to_str()) {
+        None => return None,
+        Some(name) => name,
+    };
+    if name.ends_with(".zip") {
+        Some(ArchiveKind::Zip)
+    } else if name.ends_with(".tar") {
+        Some(ArchiveKind::Tar)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(ArchiveKind::TarGz)
+    } else {
+        None
+    }
+}
+
+/// A reader for enumerating the file entries of an archive.
+///
+/// An archive is a single file containing many file entries, such as a zip
+/// or tar file. Search oriented applications can search each entry as if it
+/// were a file of its own, reported under a virtual path of the form
+/// `archive.tar!/path/inner.txt` (as built by the
+/// [`virtual_path`](struct.ArchiveReader.html#method.virtual_path) method).
+///
+/// Currently, zip, tar and gzip compressed tar files are supported, and are
+/// recognized purely by their file extensions. Unlike
+/// [`DecompressionReader`](struct.DecompressionReader.html), archives are
+/// read in-process, since enumerating entries and streaming each of their
+/// contents does not map cleanly onto invoking an external command.
+///
+/// This type is only concerned with recognizing archives and producing their
+/// entries; filtering and searching the entries is the responsibility of the
+/// caller.
+///
+/// # Example
+///
+/// This example prints the name and size of every file entry in an archive.
+///
+/// ```no_run
+/// use std::io::Read;
+/// use grep_cli::ArchiveReader;
+///
+/// # fn example() -> Result<(), Box<::std::error::Error>> {
+/// let mut total = 0;
+/// let archive = ArchiveReader::new("foo.tar.gz")?;
+/// archive.for_each_entry(|name, rdr| {
+///     let mut contents = vec![];
+///     rdr.read_to_end(&mut contents)?;
+///     total += contents.len();
+///     println!("{}: {}", name.display(), contents.len());
+///     Ok(())
+/// })?;
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ArchiveReader {
+    kind: ArchiveKind,
+    path: PathBuf,
+}
+
+impl ArchiveReader {
+    /// Create a new reader for enumerating the entries of the archive at the
+    /// given file path.
+    ///
+    /// If the given path is not recognized as a supported archive format,
+    /// then an error is returned. Note that this inspects only the file
+    /// path; the file itself is not opened until entries are read.
+    pub fn new<P: AsRef<Path>>(path: P) -> io::Result<ArchiveReader> {
+        let path = path.as_ref();
+        match archive_kind(path) {
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "not a supported archive format",
+            )),
+            Some(kind) => Ok(ArchiveReader { kind, path: path.to_path_buf() }),
+        }
+    }
+
+    /// Returns true if and only if the given path looks like a supported
+    /// archive. Currently, zip, tar and gzip compressed tar files are
+    /// recognized.
+    pub fn is_archive<P: AsRef<Path>>(path: P) -> bool {
+        archive_kind(path.as_ref()).is_some()
+    }
+
+    /// Return the virtual path of an entry inside this archive, e.g.,
+    /// `archive.zip!/path/inner.txt`.
+    pub fn virtual_path(&self, entry: &Path) -> PathBuf {
+        let mut path = OsString::from(self.path.as_os_str());
+        path.push("!/");
+        path.push(entry.as_os_str());
+        PathBuf::from(path)
+    }
+
+    /// Execute the given function on every file entry in this archive. The
+    /// function is handed the path of the entry relative to the root of the
+    /// archive along with a reader for the entry's contents.
+    ///
+    /// Directory entries are skipped, as are zip entries with paths that
+    /// escape the root of the archive.
+    pub fn for_each_entry<F>(&self, f: F) -> io::Result<()>
+    where
+        F: FnMut(&Path, &mut dyn Read) -> io::Result<()>,
+    {
+        match self.kind {
+            ArchiveKind::Zip => each_zip_entry(File::open(&self.path)?, f),
+            ArchiveKind::Tar => each_tar_entry(File::open(&self.path)?, f),
+            ArchiveKind::TarGz => each_tar_entry(
+                flate2::read::GzDecoder::new(File::open(&self.path)?),
+                f,
+            ),
+        }
+    }
+}
+
+fn each_zip_entry<F>(file: File, mut f: F) -> io::Result<()>
+where
+    F: FnMut(&Path, &mut dyn Read) -> io::Result<()>,
+{
+    let mut archive = zip::ZipArchive::new(file).map_err(archive_error)?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(archive_error)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = match entry.enclosed_name() {
+            // The entry's path escapes the root of the archive.
+            None => continue,
+            Some(name) => name.to_path_buf(),
+        };
+        f(&name, &mut entry)?;
+    }
+    Ok(())
+}
+
+fn each_tar_entry<R: Read, F>(rdr: R, mut f: F) -> io::Result<()>
+where
+    F: FnMut(&Path, &mut dyn Read) -> io::Result<()>,
+{
+    let mut archive = tar::Archive::new(rdr);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path()?.to_path_buf();
+        f(&name, &mut entry)?;
+    }
+    Ok(())
+}
+
+/// Convert an error from an archive reader into an I/O error.
+fn archive_error<E: std::fmt::Display>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
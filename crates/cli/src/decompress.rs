@@ -1,3 +1,4 @@
+use std::collections::VecDeque;
 use std::ffi::{OsStr, OsString};
 use std::fs::File;
 use std::io;
@@ -279,6 +280,20 @@ impl DecompressionReaderBuilder {
         self.command_builder.async_stderr(yes);
         self
     }
+
+    /// Build a pool that spawns decompression processes ahead of time.
+    ///
+    /// The pool uses this builder's configuration for every reader it
+    /// creates. See [`DecompressionPool`](struct.DecompressionPool.html) for
+    /// details.
+    pub fn build_pool(&self, capacity: usize) -> DecompressionPool {
+        DecompressionPool {
+            builder: self.clone(),
+            capacity: std::cmp::max(1, capacity),
+            queue: VecDeque::new(),
+            ready: VecDeque::new(),
+        }
+    }
 }
 
 /// A streaming reader for decompressing the contents of a file.
@@ -401,6 +416,125 @@ impl io::Read for DecompressionReader {
     }
 }
 
+/// A pool that amortizes the cost of spawning decompression processes.
+///
+/// Spawning one process per file adds noticeable overhead when searching
+/// thousands of small compressed files, since each spawn must complete before
+/// any of the file's contents can be read. This pool overlaps that cost with
+/// consumption: callers enqueue the paths they are about to read, and the
+/// pool keeps up to `capacity` decompression processes spawned ahead of the
+/// reader most recently handed out. By the time a pre-spawned reader is
+/// claimed, its process has typically already decompressed data into the
+/// pipe's buffer.
+///
+/// Readers are claimed with [`DecompressionPool::reader`], which returns a
+/// streaming [`DecompressionReader`](struct.DecompressionReader.html) for a
+/// single file, just as if it had been built directly. Claiming a path that
+/// was never enqueued (or whose process has not been spawned yet) falls back
+/// to spawning a process on the spot.
+///
+/// Paths with no matching decompression command are never spawned ahead of
+/// time, since opening a passthru reader is cheap.
+///
+/// Note that each pre-spawned process holds open file descriptors and an OS
+/// pipe buffer, which is why the number of them is bounded. Pre-spawned
+/// readers that are never claimed are closed when the pool is dropped.
+#[derive(Debug)]
+pub struct DecompressionPool {
+    builder: DecompressionReaderBuilder,
+    capacity: usize,
+    queue: VecDeque<PathBuf>,
+    ready: VecDeque<(PathBuf, DecompressionReader)>,
+}
+
+impl DecompressionPool {
+    /// Create a new pool with the default matching rules that keeps up to
+    /// `capacity` decompression processes spawned ahead of the caller.
+    ///
+    /// To customize the matching rules or other reader options, build a pool
+    /// with
+    /// [`DecompressionReaderBuilder::build_pool`](struct.DecompressionReaderBuilder.html#method.build_pool).
+    pub fn new(capacity: usize) -> DecompressionPool {
+        DecompressionReaderBuilder::new().build_pool(capacity)
+    }
+
+    /// Enqueue a file path that the caller intends to read soon.
+    ///
+    /// Paths should be enqueued in the order in which their readers will be
+    /// claimed. Enqueueing may immediately spawn a decompression process for
+    /// this or a previously enqueued path, up to the pool's capacity.
+    pub fn enqueue<P: AsRef<Path>>(&mut self, path: P) {
+        self.queue.push_back(path.as_ref().to_path_buf());
+        self.fill();
+    }
+
+    /// Return a streaming reader for decompressing the given file.
+    ///
+    /// If a process was spawned ahead of time for this path, then its reader
+    /// is returned without spawning anything. Otherwise, this behaves like
+    /// [`DecompressionReaderBuilder::build`](struct.DecompressionReaderBuilder.html#method.build).
+    ///
+    /// Pre-spawned readers for paths enqueued before this one are closed,
+    /// since readers are expected to be claimed in the order their paths
+    /// were enqueued.
+    pub fn reader<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<DecompressionReader, CommandError> {
+        let path = path.as_ref();
+        let mut claimed = None;
+        if self.ready.iter().any(|(ready_path, _)| ready_path == path) {
+            while let Some((ready_path, mut rdr)) = self.ready.pop_front() {
+                if ready_path == path {
+                    claimed = Some(rdr);
+                    break;
+                }
+                // This reader was skipped, so free its process eagerly
+                // rather than letting it linger until the pool is dropped.
+                if let Err(err) = rdr.close() {
+                    log::debug!(
+                        "{}: error closing unclaimed reader: {}",
+                        ready_path.display(),
+                        err,
+                    );
+                }
+            }
+        }
+        self.queue.retain(|queued| queued != path);
+        let result = match claimed {
+            Some(rdr) => Ok(rdr),
+            None => self.builder.build(path),
+        };
+        self.fill();
+        result
+    }
+
+    /// Spawn processes for enqueued paths until the pool is at capacity.
+    fn fill(&mut self) {
+        while self.ready.len() < self.capacity {
+            let path = match self.queue.pop_front() {
+                None => return,
+                Some(path) => path,
+            };
+            // Passthru readers are cheap to open, so there's no point in
+            // opening them (and their file descriptors) ahead of time.
+            if !self.builder.matcher.has_command(&path) {
+                continue;
+            }
+            match self.builder.build(&path) {
+                Ok(rdr) => self.ready.push_back((path, rdr)),
+                Err(err) => {
+                    log::debug!(
+                        "{}: error spawning pooled reader: {}",
+                        path.display(),
+                        err,
+                    );
+                }
+            }
+        }
+    }
+}
+
 /// Resolves a path to a program to a path by searching for the program in
 /// `PATH`.
 ///
@@ -538,3 +672,44 @@ fn default_decompression_commands() -> Vec<DecompressionCommand> {
     add("*.Z", ARGS_UNCOMPRESS, &mut cmds);
     cmds
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn pool_prespawns_and_claims() {
+        use std::io::{Read, Write};
+
+        let tmpdir = std::env::temp_dir().join("grep-cli-pool-test");
+        std::fs::create_dir_all(&tmpdir).unwrap();
+        let path1 = tmpdir.join("a.fake");
+        let path2 = tmpdir.join("b.fake");
+        File::create(&path1).unwrap().write_all(b"first").unwrap();
+        File::create(&path2).unwrap().write_all(b"second").unwrap();
+
+        // Use `cat` as a stand-in decompressor so that the test doesn't
+        // depend on any real compression tools being installed.
+        let mut builder = DecompressionMatcherBuilder::new();
+        builder.defaults(false);
+        builder.associate("*.fake", "cat", std::iter::empty::<&str>());
+        let matcher = builder.build().unwrap();
+        let mut pool =
+            DecompressionReaderBuilder::new().matcher(matcher).build_pool(2);
+
+        pool.enqueue(&path1);
+        pool.enqueue(&path2);
+
+        let mut contents = String::new();
+        pool.reader(&path1).unwrap().read_to_string(&mut contents).unwrap();
+        assert_eq!("first", contents);
+
+        // Claiming out of order still works: the reader for path2 was
+        // pre-spawned and is returned directly.
+        pool.enqueue(&path1);
+        let mut contents = String::new();
+        pool.reader(&path2).unwrap().read_to_string(&mut contents).unwrap();
+        assert_eq!("second", contents);
+    }
+}
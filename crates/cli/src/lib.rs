@@ -147,6 +147,16 @@ whether to read stderr asynchronously in order to avoid deadlock (which is
 enabled by default).
 
 
+# Searching archives
+
+While `DecompressionReader` handles files that decompress to a single stream,
+[`ArchiveReader`](struct.ArchiveReader.html)
+enumerates the file entries of archives such as zip and tar files. Each entry
+is handed to the caller as its own reader, along with its path inside the
+archive, which makes it possible to search each entry as if it were a file of
+its own.
+
+
 # Miscellaneous parsing
 
 The
@@ -158,6 +168,7 @@ error message is crafted that typically tells the user how to fix the problem.
 
 #![deny(missing_docs)]
 
+mod archive;
 mod decompress;
 mod escape;
 mod human;
@@ -167,9 +178,10 @@ mod wtr;
 
 use std::io::IsTerminal;
 
+pub use crate::archive::ArchiveReader;
 pub use crate::decompress::{
     resolve_binary, DecompressionMatcher, DecompressionMatcherBuilder,
-    DecompressionReader, DecompressionReaderBuilder,
+    DecompressionPool, DecompressionReader, DecompressionReaderBuilder,
 };
 pub use crate::escape::{escape, escape_os, unescape, unescape_os};
 pub use crate::human::{parse_human_readable_size, ParseSizeError};
@@ -9,6 +9,8 @@
 // is where we read clap's configuration from the end user's arguments and turn
 // it into a ripgrep-specific configuration type that is not coupled with clap.
 
+use std::io::{self, Write};
+
 use clap::{self, crate_authors, crate_version, App, AppSettings};
 use lazy_static::lazy_static;
 
@@ -152,6 +154,122 @@ fn runtime_cpu_features() -> Vec<&'static str> {
     vec![]
 }
 
+/// The kind of artifact that the --generate flag should produce.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GenerateKind {
+    /// Completions for bash.
+    CompleteBash,
+    /// Completions for zsh.
+    CompleteZsh,
+    /// Completions for fish.
+    CompleteFish,
+    /// Completions for PowerShell.
+    CompletePowerShell,
+    /// A man page in roff format.
+    Man,
+}
+
+/// Write the artifact described by the given kind to `wtr`.
+///
+/// This is used by the --generate flag so that completions and the man page
+/// can be produced by the installed binary, in addition to the copies written
+/// at build time.
+#[allow(dead_code)]
+pub fn generate<W: Write>(kind: GenerateKind, wtr: &mut W) -> io::Result<()> {
+    use clap::Shell;
+
+    match kind {
+        GenerateKind::CompleteBash => {
+            app().gen_completions_to("rg", Shell::Bash, wtr);
+        }
+        GenerateKind::CompleteZsh => {
+            // clap's support for zsh is not used. Instead, zsh completions
+            // are manually maintained in `complete/_rg`.
+            wtr.write_all(include_bytes!("../../complete/_rg"))?;
+        }
+        GenerateKind::CompleteFish => {
+            app().gen_completions_to("rg", Shell::Fish, wtr);
+        }
+        GenerateKind::CompletePowerShell => {
+            app().gen_completions_to("rg", Shell::PowerShell, wtr);
+        }
+        GenerateKind::Man => generate_man(wtr)?,
+    }
+    Ok(())
+}
+
+/// Write a man page in roff format to `wtr`, generated from the argument
+/// documentation in this module.
+fn generate_man<W: Write>(wtr: &mut W) -> io::Result<()> {
+    writeln!(
+        wtr,
+        ".TH RG 1 \"\" \"ripgrep {}\" \"User Commands\"",
+        crate_version!(),
+    )?;
+    writeln!(wtr, ".SH NAME")?;
+    writeln!(
+        wtr,
+        "rg \\- recursively search the current directory for lines \
+         matching a pattern",
+    )?;
+    writeln!(wtr, ".SH SYNOPSIS")?;
+    for line in USAGE.lines().filter(|line| !line.trim().is_empty()) {
+        writeln!(wtr, ".br")?;
+        writeln!(wtr, "\\fB{}\\fR", roff_escape(line.trim()))?;
+    }
+    writeln!(wtr, ".SH DESCRIPTION")?;
+    for line in ABOUT.trim().lines() {
+        writeln!(wtr, "{}", roff_escape(line))?;
+    }
+    writeln!(wtr, ".SH OPTIONS")?;
+    for arg in all_args_and_flags() {
+        if arg.hidden {
+            continue;
+        }
+        writeln!(wtr, ".TP")?;
+        match arg.kind {
+            RGArgKind::Positional { value_name, .. } => {
+                writeln!(wtr, "\\fI{}\\fR", roff_escape(value_name))?;
+            }
+            RGArgKind::Switch { long, short, .. } => {
+                if let Some(short) = short {
+                    write!(wtr, "\\fB\\-{}\\fR, ", roff_escape(short))?;
+                }
+                writeln!(wtr, "\\fB\\-\\-{}\\fR", roff_escape(long))?;
+            }
+            RGArgKind::Flag { long, short, value_name, .. } => {
+                if let Some(short) = short {
+                    write!(wtr, "\\fB\\-{}\\fR, ", roff_escape(short))?;
+                }
+                writeln!(
+                    wtr,
+                    "\\fB\\-\\-{}\\fR=\\fI{}\\fR",
+                    roff_escape(long),
+                    roff_escape(value_name),
+                )?;
+            }
+        }
+        for line in arg.doc_long.trim().lines() {
+            if line.is_empty() {
+                writeln!(wtr, ".sp")?;
+            } else {
+                writeln!(wtr, "{}", roff_escape(line))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Escape a line of text for use in the body of a roff document.
+fn roff_escape(line: &str) -> String {
+    let escaped = line.replace('\\', "\\e").replace('-', "\\-");
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        format!("\\&{}", escaped)
+    } else {
+        escaped
+    }
+}
+
 /// Arg is a light alias for a clap::Arg that is specialized to compile time
 /// string literals.
 type Arg = clap::Arg<'static, 'static>;
@@ -456,6 +574,31 @@ impl RGArg {
         self
     }
 
+    /// Make this flag's value optional.
+    ///
+    /// When the flag is given without a value, e.g., `--all-match` instead
+    /// of `--all-match=line`, clap reports no value for it. A value, when
+    /// given, must be attached with `=`.
+    ///
+    /// This rebuilds the underlying clap argument, so it must be called
+    /// before any other builder method. It panics if this arg is not a flag.
+    fn value_optional(mut self) -> RGArg {
+        match self.kind {
+            RGArgKind::Positional { .. } => panic!("expected flag"),
+            RGArgKind::Switch { .. } => panic!("expected flag"),
+            RGArgKind::Flag { value_name, .. } => {
+                self.claparg = Arg::with_name(self.name)
+                    .long(self.name)
+                    .value_name(value_name)
+                    .takes_value(true)
+                    .min_values(0)
+                    .max_values(1)
+                    .require_equals(true);
+            }
+        }
+        self
+    }
+
     /// Add an alias to this argument.
     ///
     /// Aliases are not show in the output of -h/--help.
@@ -493,6 +636,13 @@ impl RGArg {
         self
     }
 
+    /// Sets a required argument. That is, if this argument is used without
+    /// the given argument, then clap will report an error.
+    fn requires(mut self, name: &'static str) -> RGArg {
+        self.claparg = self.claparg.requires(name);
+        self
+    }
+
     /// Sets an overriding argument. That is, if this argument and the given
     /// argument are both provided by an end user, then the "last" one will
     /// win. ripgrep will behave as if any previous instantiations did not
@@ -550,36 +700,57 @@ pub fn all_args_and_flags() -> Vec<RGArg> {
     // flags are hidden and merely mentioned in the docs of the corresponding
     // "positive" flag.
     flag_after_context(&mut args);
+    flag_all_match(&mut args);
+    flag_allow_special_files(&mut args);
     flag_auto_hybrid_regex(&mut args);
+    flag_backup_suffix(&mut args);
     flag_before_context(&mut args);
+    flag_bench(&mut args);
     flag_binary(&mut args);
+    flag_binary_files(&mut args);
     flag_block_buffered(&mut args);
     flag_byte_offset(&mut args);
     flag_case_sensitive(&mut args);
+    flag_checkpoint(&mut args);
     flag_color(&mut args);
     flag_colors(&mut args);
     flag_column(&mut args);
     flag_context(&mut args);
     flag_context_separator(&mut args);
+    flag_context_start(&mut args);
     flag_count(&mut args);
+    flag_count_lines_and_matches(&mut args);
     flag_count_matches(&mut args);
+    flag_count_total(&mut args);
     flag_crlf(&mut args);
     flag_debug(&mut args);
+    flag_decompress_cmd(&mut args);
+    flag_dedup_hardlinks(&mut args);
     flag_dfa_size_limit(&mut args);
+    flag_dry_run(&mut args);
     flag_encoding(&mut args);
     flag_engine(&mut args);
+    flag_errors(&mut args);
+    flag_explain(&mut args);
     flag_field_context_separator(&mut args);
     flag_field_match_separator(&mut args);
     flag_file(&mut args);
     flag_files(&mut args);
+    flag_files_format(&mut args);
+    flag_files_from(&mut args);
+    flag_files_from0(&mut args);
     flag_files_with_matches(&mut args);
     flag_files_without_match(&mut args);
     flag_fixed_strings(&mut args);
+    flag_flush_interval(&mut args);
     flag_follow(&mut args);
+    flag_generate(&mut args);
     flag_glob(&mut args);
     flag_glob_case_insensitive(&mut args);
+    flag_group_by(&mut args);
     flag_heading(&mut args);
     flag_hidden(&mut args);
+    flag_hyperlink_format(&mut args);
     flag_iglob(&mut args);
     flag_ignore_case(&mut args);
     flag_ignore_file(&mut args);
@@ -587,6 +758,7 @@ pub fn all_args_and_flags() -> Vec<RGArg> {
     flag_include_zero(&mut args);
     flag_invert_match(&mut args);
     flag_json(&mut args);
+    flag_label(&mut args);
     flag_line_buffered(&mut args);
     flag_line_number(&mut args);
     flag_line_regexp(&mut args);
@@ -595,9 +767,14 @@ pub fn all_args_and_flags() -> Vec<RGArg> {
     flag_max_count(&mut args);
     flag_max_depth(&mut args);
     flag_max_filesize(&mut args);
+    flag_max_filesize_searched(&mut args);
+    flag_max_memory(&mut args);
+    flag_max_total_matches(&mut args);
     flag_mmap(&mut args);
+    flag_mmap_advice(&mut args);
     flag_multiline(&mut args);
     flag_multiline_dotall(&mut args);
+    flag_newer_than(&mut args);
     flag_no_config(&mut args);
     flag_no_ignore(&mut args);
     flag_no_ignore_dot(&mut args);
@@ -610,31 +787,46 @@ pub fn all_args_and_flags() -> Vec<RGArg> {
     flag_no_messages(&mut args);
     flag_no_pcre2_unicode(&mut args);
     flag_no_require_git(&mut args);
+    flag_no_rgtypes(&mut args);
     flag_no_unicode(&mut args);
     flag_null(&mut args);
     flag_null_data(&mut args);
+    flag_older_than(&mut args);
     flag_one_file_system(&mut args);
     flag_only_matching(&mut args);
+    flag_only_replace(&mut args);
+    flag_path_config(&mut args);
+    flag_path_format(&mut args);
     flag_path_separator(&mut args);
     flag_passthru(&mut args);
     flag_pcre2(&mut args);
     flag_pcre2_version(&mut args);
     flag_pre(&mut args);
+    flag_pre_cache(&mut args);
+    flag_pre_cache_clear(&mut args);
+    flag_pre_cache_limit(&mut args);
     flag_pre_glob(&mut args);
     flag_pretty(&mut args);
+    flag_profile(&mut args);
     flag_quiet(&mut args);
     flag_regex_size_limit(&mut args);
     flag_regexp(&mut args);
     flag_replace(&mut args);
+    flag_resume(&mut args);
+    flag_search_archives(&mut args);
     flag_search_zip(&mut args);
+    flag_server(&mut args);
     flag_smart_case(&mut args);
     flag_sort_files(&mut args);
     flag_sort(&mut args);
     flag_sortr(&mut args);
     flag_stats(&mut args);
+    flag_stats_format(&mut args);
+    flag_stop_after_gap(&mut args);
     flag_stop_on_nonmatch(&mut args);
     flag_text(&mut args);
     flag_threads(&mut args);
+    flag_timeout_per_file(&mut args);
     flag_trim(&mut args);
     flag_type(&mut args);
     flag_type_add(&mut args);
@@ -643,8 +835,11 @@ pub fn all_args_and_flags() -> Vec<RGArg> {
     flag_type_not(&mut args);
     flag_unrestricted(&mut args);
     flag_vimgrep(&mut args);
+    flag_vimgrep_ranges(&mut args);
+    flag_watch(&mut args);
     flag_with_filename(&mut args);
     flag_word_regexp(&mut args);
+    flag_write_replace(&mut args);
     args
 }
 
@@ -669,9 +864,12 @@ will be provided. Namely, the following is equivalent to the above:
         .help(SHORT)
         .long_help(LONG)
         .required_unless(&[
+            "explain",
             "file",
             "files",
+            "generate",
             "regexp",
+            "server",
             "type-list",
             "pcre2-version",
         ]);
@@ -711,6 +909,56 @@ This overrides the --passthru flag and partially overrides --context.
     args.push(arg);
 }
 
+fn flag_all_match(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Require every pattern to match.";
+    const LONG: &str = long!(
+        "\
+Require every pattern given (e.g., via multiple -e/--regexp or -f/--file
+flags) to match before anything is reported.
+
+The granularity of the requirement may be set with an optional value. When
+given as --all-match or --all-match=line, a line is only reported when every
+pattern matches it. When given as --all-match=file, a file is only searched
+when every pattern matches somewhere in its contents.
+
+Note that --all-match=file requires contents that can be read more than once,
+so it does not apply to searches of stdin, to files searched with --pre or to
+entries inside archives searched with --search-archives. Such searches behave
+as if this flag was absent.
+
+This flag has no effect when only one pattern is given.
+"
+    );
+    let arg = RGArg::flag("all-match", "KIND")
+        .value_optional()
+        .help(SHORT)
+        .long_help(LONG)
+        .possible_values(&["line", "file"]);
+    args.push(arg);
+}
+
+fn flag_allow_special_files(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Search special files given as arguments.";
+    const LONG: &str = long!(
+        "\
+Permit searching special files, such as FIFOs, sockets and character or
+block devices, that are passed explicitly as arguments. For example:
+
+    rg --allow-special-files foo /dev/stdin
+
+Special files are read as streams, without memory maps or other heuristics
+that depend on a file length. They are never searched during directory
+traversal, only when named on the command line.
+
+When this flag isn't given, a special file argument is skipped with a
+message. Note that reading a special file can block indefinitely (e.g., a
+FIFO with no writer attached), so this is not enabled by default.
+"
+    );
+    let arg = RGArg::switch("allow-special-files").help(SHORT).long_help(LONG);
+    args.push(arg);
+}
+
 fn flag_auto_hybrid_regex(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Dynamically use PCRE2 if necessary.";
     const LONG: &str = long!(
@@ -762,6 +1010,25 @@ This flag can be disabled with --no-auto-hybrid-regex.
     args.push(arg);
 }
 
+fn flag_backup_suffix(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Keep a backup of each file modified by -W.";
+    const LONG: &str = long!(
+        "\
+When used with -W/--write-replace, keep a backup of each modified file by
+renaming the original to its name with SUFFIX appended before the rewritten
+contents are put in place. For example, with --backup-suffix .bak, the
+original contents of foo.txt are kept in foo.txt.bak.
+
+This flag has no effect without -W/--write-replace.
+"
+    );
+    let arg = RGArg::flag("backup-suffix", "SUFFIX")
+        .help(SHORT)
+        .long_help(LONG)
+        .requires("write-replace");
+    args.push(arg);
+}
+
 fn flag_before_context(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Show NUM lines before each match.";
     const LONG: &str = long!(
@@ -780,6 +1047,31 @@ This overrides the --passthru flag and partially overrides --context.
     args.push(arg);
 }
 
+fn flag_bench(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Benchmark the search instead of printing results.";
+    const LONG: &str = long!(
+        "\
+Run the configured search repeatedly with its output suppressed and report
+how long each run takes, along with the search throughput and how the time
+splits between walking the directory tree and searching. An optional count
+sets the number of runs, e.g., --bench=10; the default is 3.
+
+All other flags apply as usual, so this provides a supported way to measure
+the effect of options like -j/--threads, --mmap or --dfa-size-limit on a
+real corpus without an external harness:
+
+    rg --bench -j8 --mmap PATTERN /path/to/corpus
+
+The exit code reflects whether a match was found, as with a normal search.
+"
+    );
+    let arg = RGArg::flag("bench", "COUNT")
+        .value_optional()
+        .help(SHORT)
+        .long_help(LONG);
+    args.push(arg);
+}
+
 fn flag_binary(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Search binary files.";
     const LONG: &str = long!(
@@ -832,6 +1124,31 @@ flag.
     args.push(arg);
 }
 
+fn flag_binary_files(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "How to handle binary files (GNU grep compatible).";
+    const LONG: &str = long!(
+        "\
+Control how binary files are handled, using the same TYPE names as GNU grep.
+This is meant to ease porting scripts written for GNU grep.
+
+When TYPE is 'text', binary files are searched as if they were text, just
+like the '-a/--text' flag. When TYPE is 'without-match', binary files are
+never searched, even when given explicitly on the command line, like GNU
+grep's '-I' flag. When TYPE is 'binary', binary files are searched with NUL
+bytes replaced by the line terminator, like the '--binary' flag, except that
+it also applies to files given explicitly on the command line.
+
+When this flag is present, it takes precedence over the '-a/--text' and
+'--binary' flags.
+"
+    );
+    let arg = RGArg::flag("binary-files", "TYPE")
+        .help(SHORT)
+        .long_help(LONG)
+        .possible_values(&["binary", "text", "without-match"]);
+    args.push(arg);
+}
+
 fn flag_block_buffered(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Force block buffering.";
     const LONG: &str = long!(
@@ -904,6 +1221,31 @@ This overrides the -i/--ignore-case and -S/--smart-case flags.
     args.push(arg);
 }
 
+fn flag_checkpoint(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Record search progress in FILE.";
+    const LONG: &str = long!(
+        "\
+Record search progress in FILE. The path of each file is appended to FILE,
+one per line, as soon as it has been completely searched. If a long-running
+scan is interrupted, passing the same FILE to the --resume flag skips the
+files that were already searched instead of restarting from scratch.
+
+Using the same FILE for both --checkpoint and --resume continues extending it
+across runs, which is the common way to use this flag:
+
+    rg --checkpoint progress --resume progress PATTERN /huge/tree
+
+Files that were only partially searched when a scan was interrupted are not
+recorded, so they are searched again (in full) on resume.
+"
+    );
+    let arg = RGArg::flag("checkpoint", "FILE")
+        .help(SHORT)
+        .long_help(LONG)
+        .allow_leading_hyphen();
+    args.push(arg);
+}
+
 fn flag_color(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Controls when to use color.";
     const LONG: &str = long!(
@@ -931,7 +1273,8 @@ When the --vimgrep flag is given to ripgrep, then the default value for the
         .help(SHORT)
         .long_help(LONG)
         .possible_values(&["never", "auto", "always", "ansi"])
-        .default_value_if("never", "vimgrep");
+        .default_value_if("never", "vimgrep")
+        .default_value_if("never", "vimgrep-ranges");
     args.push(arg);
 }
 
@@ -942,13 +1285,16 @@ fn flag_colors(args: &mut Vec<RGArg>) {
 This flag specifies color settings for use in the output. This flag may be
 provided multiple times. Settings are applied iteratively. Colors are limited
 to one of eight choices: red, blue, green, cyan, magenta, yellow, white and
-black. Styles are limited to nobold, bold, nointense, intense, nounderline
-or underline.
+black. Styles are limited to nobold, bold, nointense, intense, nounderline,
+underline, noitalic, italic, nodimmed, dimmed, nostrikethrough or
+strikethrough.
 
 The format of the flag is '{type}:{attribute}:{value}'. '{type}' should be
 one of path, line, column or match. '{attribute}' can be fg, bg or style.
 '{value}' is either a color (for fg and bg) or a text style. A special format,
-'{type}:none', will clear all color settings for '{type}'.
+'{type}:none', will clear all color settings for '{type}'. The special color
+'none' resets the foreground or background to the terminal's default, e.g.,
+'match:bg:none' removes the background color while preserving other styles.
 
 For example, the following command will change the match color to magenta and
 the background color for line numbers to yellow:
@@ -1046,6 +1392,36 @@ is still inserted. To completely disable context separators, use the
     args.push(arg);
 }
 
+fn flag_context_start(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Show the context block delimited by REGEX lines.";
+    const LONG: &str = long!(
+        "\
+Show the block of lines around each match that is delimited by lines matching
+REGEX. The context extends upward to the closest preceding line matching REGEX
+and downward until (but not including) the next such line. This can be used to
+show the function enclosing each match, in the spirit of git grep's -W flag,
+by providing a regex that matches function definitions.
+
+If no line above a match satisfies REGEX, the context extends to the line
+following the previous match, or to the beginning of the file. When combined
+with -A/-B/-C, the larger of the two contexts is shown.
+
+Note that this requires reading each file entirely into memory, just like
+multiline search.
+
+Example: --context-start '^\\w' for source code in which function definitions
+start in the first column.
+
+This overrides the --passthru flag.
+"
+    );
+    let arg = RGArg::flag("context-start", "REGEX")
+        .help(SHORT)
+        .long_help(LONG)
+        .overrides("passthru");
+    args.push(arg);
+}
+
 fn flag_count(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Only show the count of matching lines for each file.";
     const LONG: &str = long!(
@@ -1069,6 +1445,32 @@ with --only-matching, then ripgrep behaves as if --count-matches was given.
         .short("c")
         .help(SHORT)
         .long_help(LONG)
+        .overrides("count-matches")
+        .overrides("count-lines-and-matches");
+    args.push(arg);
+}
+
+fn flag_count_lines_and_matches(args: &mut Vec<RGArg>) {
+    const SHORT: &str =
+        "Show both the matching line and match counts for each file.";
+    const LONG: &str = long!(
+        "\
+This flag suppresses normal output and shows, for each file searched, the
+number of lines that match followed by the total number of individual matches,
+separated by the field separator. Both counts are computed in a single search,
+so this is cheaper than running -c/--count and --count-matches separately.
+
+If only one file is given to ripgrep, then only the counts are printed if
+there is a match. The --with-filename flag can be used to force printing the
+file path in this case.
+
+This overrides the --count and --count-matches flags.
+"
+    );
+    let arg = RGArg::switch("count-lines-and-matches")
+        .help(SHORT)
+        .long_help(LONG)
+        .overrides("count")
         .overrides("count-matches");
     args.push(arg);
 }
@@ -1095,7 +1497,32 @@ This overrides the --count flag. Note that when --count is combined with
     let arg = RGArg::switch("count-matches")
         .help(SHORT)
         .long_help(LONG)
-        .overrides("count");
+        .overrides("count")
+        .overrides("count-lines-and-matches");
+    args.push(arg);
+}
+
+fn flag_count_total(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Only show the total count across all files.";
+    const LONG: &str = long!(
+        "\
+This flag suppresses normal output and shows a single number: the total
+number of lines that match across all files searched. When combined with
+--count-matches, the total number of individual matches is shown instead.
+
+This is equivalent to summing the per-file counts printed by -c/--count,
+without listing any file paths.
+"
+    );
+    let arg =
+        RGArg::switch("count-total").help(SHORT).long_help(LONG).conflicts(&[
+            "count",
+            "files-with-matches",
+            "files-without-match",
+            "json",
+            "quiet",
+            "stats",
+        ]);
     args.push(arg);
 }
 
@@ -1144,7 +1571,78 @@ large and is generally more useful for development.
     let arg = RGArg::switch("debug").help(SHORT).long_help(LONG);
     args.push(arg);
 
-    let arg = RGArg::switch("trace").hidden().overrides("debug");
+    const TRACE_SHORT: &str = "Show trace messages.";
+    const TRACE_LONG: &str = long!(
+        "\
+Show trace messages. This implies --debug and shows even more detail. In
+particular, for each file searched, the trace messages report which search
+strategy was used (memory map or incremental reading, line oriented or multi
+line), whether a fast literal prefilter was extracted from the pattern,
+whether and where binary data was detected, and how long the search took.
+
+The output could be quite large and is generally more useful for development
+than for filing bug reports.
+"
+    );
+    let arg = RGArg::switch("trace")
+        .help(TRACE_SHORT)
+        .long_help(TRACE_LONG)
+        .overrides("debug");
+    args.push(arg);
+}
+
+fn flag_decompress_cmd(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Map a file extension to a decompression command.";
+    const LONG: &str = long!(
+        "\
+Associate a file extension with a custom command that decompresses matching
+files to stdout, for use with the -z/--search-zip flag. The format is
+'ext=command', where the command is split on whitespace and invoked with the
+file path appended as its final argument. For example:
+
+    rg -z --decompress-cmd 'br=brotli -dc' foo
+
+searches *.br files by piping each one through 'brotli -dc'. A leading dot in
+the extension is ignored. This flag may be provided multiple times, and custom
+commands take precedence over the built-in rules when both match a path. Like
+any other flag, it may be set persistently in a configuration file (see
+RIPGREP_CONFIG_PATH).
+
+This flag has no effect unless -z/--search-zip is enabled.
+"
+    );
+    let arg = RGArg::flag("decompress-cmd", "EXT=COMMAND")
+        .help(SHORT)
+        .long_help(LONG)
+        .multiple();
+    args.push(arg);
+}
+
+fn flag_dedup_hardlinks(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Search identical files only once.";
+    const LONG: &str = long!(
+        "\
+When enabled, files that resolve to the same underlying file are searched
+only once, with results reported for the first path encountered. This
+suppresses duplicate results from hard links, from the same file being
+reachable through multiple provided search roots, and from symlinked paths
+when the -L/--follow flag is used.
+
+On Unix, files are identified by their device and inode numbers. On other
+platforms, the canonical path is used instead.
+
+This flag can be disabled with --no-dedup-hardlinks.
+"
+    );
+    let arg = RGArg::switch("dedup-hardlinks")
+        .help(SHORT)
+        .long_help(LONG)
+        .overrides("no-dedup-hardlinks");
+    args.push(arg);
+
+    let arg = RGArg::switch("no-dedup-hardlinks")
+        .hidden()
+        .overrides("dedup-hardlinks");
     args.push(arg);
 }
 
@@ -1166,17 +1664,42 @@ The argument accepts the same size suffixes as allowed in with the
     args.push(arg);
 }
 
+fn flag_dry_run(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Report replacements without modifying any files.";
+    const LONG: &str = long!(
+        "\
+When used with -W/--write-replace, report the replacements that would be made
+without modifying any files. The summary output is identical to what a real
+run would print.
+
+This flag has no effect without -W/--write-replace.
+"
+    );
+    let arg = RGArg::switch("dry-run")
+        .help(SHORT)
+        .long_help(LONG)
+        .requires("write-replace");
+    args.push(arg);
+}
+
 fn flag_encoding(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Specify the text encoding of files to search.";
     const LONG: &str = long!(
         "\
-Specify the text encoding that ripgrep will use on all files searched. The
-default value is 'auto', which will cause ripgrep to do a best effort automatic
-detection of encoding on a per-file basis. Automatic detection in this case
-only applies to files that begin with a UTF-8 or UTF-16 byte-order mark (BOM).
-No other automatic detection is performed. One can also specify 'none' which
-will then completely disable BOM sniffing and always result in searching the
-raw bytes, including a BOM if it's present, regardless of its encoding.
+Specify the text encoding that ripgrep will use on all files searched. By
+default, ripgrep does a best effort automatic detection of encoding on a
+per-file basis that only applies to files that begin with a UTF-8 or UTF-16
+byte-order mark (BOM); no other automatic detection is performed.
+
+Specifying 'auto' explicitly enables an additional lightweight content based
+detection for files without a BOM: valid UTF-8 is searched as-is, UTF-16
+without a BOM is recognized by its byte pattern, and otherwise Shift-JIS, GBK
+and Latin-1 are tried heuristically. This is useful for legacy codebases that
+mix encodings, but the heuristics can guess wrong, so it is not the default.
+
+One can also specify 'none' which will then completely disable BOM sniffing
+and always result in searching the raw bytes, including a BOM if it's present,
+regardless of its encoding.
 
 Other supported values can be found in the list of labels here:
 https://encoding.spec.whatwg.org/#concept-encoding-get
@@ -1196,6 +1719,34 @@ This flag can be disabled with --no-encoding.
     args.push(arg);
 }
 
+fn flag_errors(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Choose how error messages are printed.";
+    const LONG: &str = long!(
+        "\
+Choose how non-fatal error messages are printed to stderr. Accepted values
+are 'text' or 'json'.
+
+The default value is 'text', which prints each diagnostic as a plain line of
+text. When 'json' is used, each diagnostic is printed as a JSON object on its
+own line, with the shape {\"type\":\"error\",\"data\":{\"kind\":...,
+\"path\":...,\"message\":...}}. The kind distinguishes classes of errors
+(for example, 'permission-denied' or 'invalid-data'), and the path names the
+file the error applies to, or is null when the error isn't tied to any
+particular file. This makes it possible for tools wrapping ripgrep to handle
+errors without parsing free-form text.
+
+Error messages can be suppressed entirely with --no-messages, regardless of
+this flag.
+"
+    );
+    let arg = RGArg::flag("errors", "FORMAT")
+        .help(SHORT)
+        .long_help(LONG)
+        .possible_values(&["text", "json"])
+        .default_value("text");
+    args.push(arg);
+}
+
 fn flag_engine(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Specify which regexp engine to use.";
     const LONG: &str = long!(
@@ -1231,6 +1782,37 @@ This overrides previous uses of --pcre2 and --auto-hybrid-regex flags.
     args.push(arg);
 }
 
+fn flag_explain(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Explain why a path would or wouldn't be searched.";
+    const LONG: &str = long!(
+        "\
+Explain why the given file path would or would not be searched, instead of
+performing a search. For each PATH given, this prints the chain of decisions
+leading to it: the override glob, ignore rule, file type filter, hidden file
+rule or size limit that applies to the path and to each directory above it.
+An ignored directory prunes the chain, since ripgrep never descends into it.
+
+All of the normal filtering flags are respected, so the easiest way to debug
+a particular invocation is to re-run it with --explain PATH added. For
+example:
+
+    rg --glob '!*.log' --explain target/debug/build.log foo
+
+Note that file paths given as positional arguments to ripgrep are always
+searched, regardless of ignore rules. This flag explains how a path is
+treated during directory traversal.
+
+This flag may be provided multiple times.
+"
+    );
+    let arg = RGArg::flag("explain", "PATH")
+        .help(SHORT)
+        .long_help(LONG)
+        .multiple()
+        .allow_leading_hyphen();
+    args.push(arg);
+}
+
 fn flag_field_context_separator(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Set the field context separator.";
     const LONG: &str = long!(
@@ -1273,6 +1855,15 @@ then all patterns provided are searched. Empty pattern lines will match all
 input lines, and the newline is not counted as part of the pattern.
 
 A line is printed if and only if it matches at least one of the patterns.
+
+Each pattern line may start with an optional flag prefix, which is one or more
+of the letters 'i' (case insensitive), 'w' (word match) or 'x' (literal),
+followed by a colon. For example, 'i:foo' matches foo case insensitively and
+'x:a.b' matches the literal string a.b. This makes it possible to mix patterns
+with different case or word semantics in one file without forcing a global
+flag onto the whole search. A pattern that starts with a colon is used
+verbatim with the colon stripped, so ':i:foo' searches for the pattern i:foo.
+The prefix syntax does not apply when -F/--fixed-strings is used.
 "
     );
     let arg = RGArg::flag("file", "PATTERNFILE")
@@ -1301,6 +1892,81 @@ This is useful to determine whether a particular file is being searched or not.
     args.push(arg);
 }
 
+fn flag_files_format(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Print metadata columns with --files.";
+    const LONG: &str = long!(
+        "\
+Print metadata columns before each path listed by --files. COLUMNS is a
+comma separated list of the columns to print, in the order given, where each
+column is one of 'size', 'mtime' or 'type'.
+
+The 'size' column is the size of the file in bytes. The 'mtime' column is the
+last modification time of the file, in seconds since the Unix epoch. The
+'type' column is one of 'file', 'dir' or 'symlink'.
+
+Columns are separated from each other and from the path by a single tab. When
+a column's value cannot be determined, a '-' is printed in its place.
+
+This flag has no effect when --files --json is used, since the JSON output
+always includes every metadata field.
+"
+    );
+    let arg = RGArg::flag("files-format", "COLUMNS")
+        .help(SHORT)
+        .long_help(LONG)
+        .requires("files");
+    args.push(arg);
+}
+
+fn flag_files_from(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Read the list of paths to search from a file.";
+    const LONG: &str = long!(
+        "\
+Read the list of paths to search from the given file, with one path per line.
+When FILE is -, then the list is read from stdin. Paths read this way are
+searched directly, without any directory traversal, and gitignore rules do
+not apply to them. Filters that constrain the files themselves, such as
+--type, --max-filesize, --newer-than and --older-than, are still applied.
+
+This is useful for scoping a search to a precise set of files produced by
+another tool. For example:
+
+    git diff --name-only | rg --files-from - foobar
+
+Paths given as positional arguments are searched in addition to the paths
+read from FILE. If the list is empty and no positional paths are given, then
+nothing is searched.
+
+Empty lines are ignored. To read paths separated by NUL bytes instead, use
+the --files-from0 flag.
+"
+    );
+    let arg = RGArg::flag("files-from", "FILE")
+        .help(SHORT)
+        .long_help(LONG)
+        .conflicts(&["files-from0"]);
+    args.push(arg);
+}
+
+fn flag_files_from0(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Read the NUL separated list of paths to search.";
+    const LONG: &str = long!(
+        "\
+Read the list of paths to search from the given file, with each path
+terminated by a NUL byte. When FILE is -, then the list is read from stdin.
+This is otherwise equivalent to the --files-from flag, and is meant to be
+used with tools that can emit NUL separated paths, such as
+'git diff --name-only -z' or 'find -print0', so that paths containing line
+terminators are handled correctly.
+"
+    );
+    let arg = RGArg::flag("files-from0", "FILE")
+        .help(SHORT)
+        .long_help(LONG)
+        .conflicts(&["files-from"]);
+    args.push(arg);
+}
+
 fn flag_files_with_matches(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Print the paths with at least one match.";
     const LONG: &str = long!(
@@ -1358,14 +2024,39 @@ This flag can be disabled with --no-fixed-strings.
     args.push(arg);
 }
 
-fn flag_follow(args: &mut Vec<RGArg>) {
-    const SHORT: &str = "Follow symbolic links.";
+fn flag_flush_interval(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Flush buffered search output at a regular interval.";
     const LONG: &str = long!(
         "\
-When this flag is enabled, ripgrep will follow symbolic links while traversing
-directories. This is disabled by default. Note that ripgrep will check for
-symbolic link loops and report errors if it finds one.
-
+Flush buffered search output at least once every DURATION, even when a file
+is still being searched. The duration is a number with an optional 'ms' or
+'s' suffix. A bare number is interpreted as milliseconds.
+
+This flag only applies to parallel searches, where the output for each file
+is normally buffered in full before being printed. With this flag set, a long
+search of a single large file shows its results as they are found, which is
+useful for 'rg ... | tail -f' style pipelines. The cost is that results from
+different files may interleave at flush boundaries. (Results are only ever
+flushed at line boundaries, so individual lines remain intact.)
+
+This flag has no effect when combined with --sort or --sortr, since sorted
+output must be held back until it can be printed in order. For controlling
+the buffering of single-threaded searches, use --line-buffered instead.
+"
+    );
+    let arg =
+        RGArg::flag("flush-interval", "DURATION").help(SHORT).long_help(LONG);
+    args.push(arg);
+}
+
+fn flag_follow(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Follow symbolic links.";
+    const LONG: &str = long!(
+        "\
+When this flag is enabled, ripgrep will follow symbolic links while traversing
+directories. This is disabled by default. Note that ripgrep will check for
+symbolic link loops and report errors if it finds one.
+
 This flag can be disabled with --no-follow.
 "
     );
@@ -1380,6 +2071,36 @@ This flag can be disabled with --no-follow.
     args.push(arg);
 }
 
+fn flag_generate(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Generate man page or completions for rg.";
+    const LONG: &str = long!(
+        "\
+Generate supporting artifacts for ripgrep and print them to stdout. KIND may
+be one of:
+
+    complete-bash        Completions for bash.
+    complete-zsh         Completions for zsh.
+    complete-fish        Completions for fish.
+    complete-powershell  Completions for PowerShell.
+    man                  A man page in roff format.
+
+This makes it possible to produce completions and a man page that match the
+installed binary, instead of relying on artifacts written at build time.
+"
+    );
+    let arg = RGArg::flag("generate", "KIND")
+        .help(SHORT)
+        .long_help(LONG)
+        .possible_values(&[
+            "complete-bash",
+            "complete-zsh",
+            "complete-fish",
+            "complete-powershell",
+            "man",
+        ]);
+    args.push(arg);
+}
+
 fn flag_glob(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Include or exclude files.";
     const LONG: &str = long!(
@@ -1434,6 +2155,28 @@ This flag can be disabled with the --no-glob-case-insensitive flag.
     args.push(arg);
 }
 
+fn flag_group_by(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Group results by directory.";
+    const LONG: &str = long!(
+        "\
+Group search results by directory. With --group-by dir, a heading with the
+directory path is printed before the results of the files it contains, and a
+count of the matches found in the directory is printed after them. This makes
+it easier to review matches spread across a large tree, such as a monorepo.
+
+Grouping prints results in directory order, so it implies a single-threaded
+search, like the --sort flag. It only applies to the default output format:
+the flag is ignored when a summary format (such as --count) or --json is
+used.
+"
+    );
+    let arg = RGArg::flag("group-by", "KIND")
+        .help(SHORT)
+        .long_help(LONG)
+        .possible_values(&["none", "dir"]);
+    args.push(arg);
+}
+
 fn flag_heading(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Print matches grouped by each file.";
     const LONG: &str = long!(
@@ -1494,6 +2237,33 @@ This flag can be disabled with --no-hidden.
     args.push(arg);
 }
 
+fn flag_hyperlink_format(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Set the format of hyperlinks around file paths.";
+    const LONG: &str = long!(
+        "\
+Set the format of hyperlinks to emit around file paths in search results,
+using the OSC 8 terminal escape sequence. Terminal emulators that support
+OSC 8, such as iTerm2, WezTerm and Windows Terminal, turn the printed paths
+into clickable links.
+
+FORMAT may be 'none', 'default' or a custom URL template. The 'default'
+format is 'file://{host}{path}'. A custom template must contain the {path}
+variable, which expands to the absolute path of the printed file, and may
+also use {host} (this machine's hostname) and {line} (the line number of the
+first match on the printed line). For example, an editor scheme:
+
+    rg --hyperlink-format 'vscode://file{path}:{line}' PATTERN
+
+When this flag is absent, or given the value 'auto', hyperlinks are enabled
+with the default format only when stdout is a tty connected to a terminal
+emulator known to support them.
+"
+    );
+    let arg =
+        RGArg::flag("hyperlink-format", "FORMAT").help(SHORT).long_help(LONG);
+    args.push(arg);
+}
+
 fn flag_iglob(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Include or exclude files case insensitively.";
     const LONG: &str = long!(
@@ -1579,15 +2349,27 @@ This flag can be disabled with the --no-ignore-file-case-insensitive flag.
 }
 
 fn flag_include_zero(args: &mut Vec<RGArg>) {
-    const SHORT: &str = "Include files with zero matches in summary";
+    const SHORT: &str = "Include files with zero matches in summary.";
     const LONG: &str = long!(
         "\
 When used with --count or --count-matches, print the number of matches for
 each file even if there were zero matches. This is disabled by default but can
 be enabled to make ripgrep behave more like grep.
+
+Note that the exit status is unaffected by this flag: it is still 1 when no
+matches are found anywhere.
+
+This flag can be disabled with '--no-include-zero'.
 "
     );
-    let arg = RGArg::switch("include-zero").help(SHORT).long_help(LONG);
+    let arg = RGArg::switch("include-zero")
+        .help(SHORT)
+        .long_help(LONG)
+        .overrides("no-include-zero");
+    args.push(arg);
+
+    let arg =
+        RGArg::switch("no-include-zero").hidden().overrides("include-zero");
     args.push(arg);
 }
 
@@ -1634,11 +2416,20 @@ all data elements as objects with one of two keys: 'text' or 'bytes'. 'text' is
 a normal JSON string when the data is valid UTF-8 while 'bytes' is the base64
 encoded contents of the data.
 
-The JSON Lines format is only supported for showing search results. It cannot
-be used with other flags that emit other types of output, such as --files,
---files-with-matches, --files-without-match, --count or --count-matches.
-ripgrep will report an error if any of the aforementioned flags are used in
-concert with --json.
+When --json is combined with --files, no search is performed and ripgrep
+instead emits one **file** message per path that would be searched, where the
+data of each message includes the path along with its size, mtime and file
+type.
+
+Similarly, when --json is combined with --type-list, ripgrep emits one
+**file-type** message per file type definition, where the data of each message
+includes the name of the type along with its globs.
+
+Otherwise, the JSON Lines format is only supported for showing search
+results. It cannot be used with other flags that emit other types of output,
+such as --files-with-matches, --files-without-match, --count or
+--count-matches. ripgrep will report an error if any of the aforementioned
+flags are used in concert with --json.
 
 Other flags that control aspects of the standard output such as
 --only-matching, --heading, --replace, --max-columns, etc., have no effect
@@ -1657,7 +2448,6 @@ The JSON Lines format can be disabled with --no-json.
         .conflicts(&[
             "count",
             "count-matches",
-            "files",
             "files-with-matches",
             "files-without-match",
         ]);
@@ -1667,6 +2457,25 @@ The JSON Lines format can be disabled with --no-json.
     args.push(arg);
 }
 
+fn flag_label(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Set the display path for searches of stdin.";
+    const LONG: &str = long!(
+        "\
+Set the path shown for matches when searching stdin. By default, searches of
+stdin display the special path '<stdin>', and only when file names are being
+printed. With this flag, the given NAME is displayed instead and file name
+printing is enabled, so that output, headings and the JSON 'path' field all
+carry a meaningful name. For example:
+
+    git show HEAD:foo.rs | rg --label 'foo.rs@HEAD' pattern
+
+This flag has no effect when not searching stdin.
+"
+    );
+    let arg = RGArg::flag("label", "NAME").help(SHORT).long_help(LONG);
+    args.push(arg);
+}
+
 fn flag_line_buffered(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Force line buffering.";
     const LONG: &str = long!(
@@ -1852,6 +2661,79 @@ Examples: --max-filesize 50K or --max-filesize 80M
     args.push(arg);
 }
 
+fn flag_max_filesize_searched(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Search only the first NUM bytes of each file.";
+    const LONG: &str = long!(
+        "\
+Search only the first NUM bytes of each file. Unlike --max-filesize, which
+skips larger files entirely, this flag still searches larger files but
+ignores everything past the limit. This is useful for skimming through giant
+artifacts where matches near the top are what matter.
+
+When a file is truncated by this limit, a message noting so is printed to
+stderr. (Unless messages are suppressed, e.g., via --no-messages.) Note also
+that the limit may fall in the middle of a line, in which case the final line
+searched is itself truncated.
+
+The input format accepts suffixes of K, M or G which correspond to kilobytes,
+megabytes and gigabytes, respectively. If no suffix is provided the input is
+treated as bytes.
+
+Examples: --max-filesize-searched 50K or --max-filesize-searched 80M
+"
+    );
+    let arg = RGArg::flag("max-filesize-searched", "NUM+SUFFIX?")
+        .help(SHORT)
+        .long_help(LONG);
+    args.push(arg);
+}
+
+fn flag_max_memory(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Set an overall memory budget for searching.";
+    const LONG: &str = long!(
+        "\
+Set an overall memory budget for searching. The budget is divided evenly
+across search threads. Half of each thread's share bounds the searcher's
+internal buffer, so searching a line longer than that fails with an error
+instead of allocating without bound. A quarter bounds the regex engine's DFA
+cache, unless --dfa-size-limit is given explicitly. The rest is left as slack
+for traversal queues and output buffers, which grow on demand.
+
+If the budget is too small to give each search thread a workable share (one
+megabyte per thread), then ripgrep refuses to start. Lowering --threads
+stretches a small budget further.
+
+The input format accepts suffixes of K, M or G which correspond to kilobytes,
+megabytes and gigabytes, respectively. If no suffix is provided the input is
+treated as bytes.
+
+Examples: --max-memory 100M or --max-memory 2G
+"
+    );
+    let arg =
+        RGArg::flag("max-memory", "NUM+SUFFIX?").help(SHORT).long_help(LONG);
+    args.push(arg);
+}
+
+fn flag_max_total_matches(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Stop searching after NUM total matches.";
+    const LONG: &str = long!(
+        "\
+Stop the entire search once NUM matches have been found across all files. This
+differs from -m/--max-count, which limits the number of matches per file.
+
+Note that the file in which the limit is reached has its matches printed in
+full, so slightly more than NUM matches may be printed. Combine this flag with
+-m/--max-count to bound how far past the limit a single file can go.
+"
+    );
+    let arg = RGArg::flag("max-total-matches", "NUM")
+        .help(SHORT)
+        .long_help(LONG)
+        .number();
+    args.push(arg);
+}
+
 fn flag_mmap(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Search using memory maps when possible.";
     const LONG: &str = long!(
@@ -1888,6 +2770,28 @@ This flag overrides --mmap.
     args.push(arg);
 }
 
+fn flag_mmap_advice(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Advise the OS on memory map access patterns.";
+    const LONG: &str = long!(
+        "\
+Advise the operating system about how memory maps will be accessed. This can
+improve performance for large cold-cache searches, and is mostly useful for
+benchmarking. The advice may be one of the following:
+
+    sequential  Expect page references in sequential order.
+    willneed    Expect access in the near future.
+
+This advice is only applied when memory maps are actually used, and is only
+supported on Unix systems. On other systems, this flag is ignored.
+"
+    );
+    let arg = RGArg::flag("mmap-advice", "KIND")
+        .help(SHORT)
+        .long_help(LONG)
+        .possible_values(&["sequential", "willneed"]);
+    args.push(arg);
+}
+
 fn flag_multiline(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Enable matching across multiple lines.";
     const LONG: &str = long!(
@@ -1928,7 +2832,7 @@ is always better to disable multiline mode.
 
 This flag can be disabled with --no-multiline.
 
-This overrides the --stop-on-nonmatch flag.
+This overrides the --stop-on-nonmatch and --stop-after-gap flags.
 "
     );
     let arg = RGArg::switch("multiline")
@@ -1936,7 +2840,8 @@ This overrides the --stop-on-nonmatch flag.
         .help(SHORT)
         .long_help(LONG)
         .overrides("no-multiline")
-        .overrides("stop-on-nonmatch");
+        .overrides("stop-on-nonmatch")
+        .overrides("stop-after-gap");
     args.push(arg);
 
     let arg = RGArg::switch("no-multiline").hidden().overrides("multiline");
@@ -1977,6 +2882,29 @@ This flag can be disabled with --no-multiline-dotall.
     args.push(arg);
 }
 
+fn flag_newer_than(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Only search files modified after the given time.";
+    const LONG: &str = long!(
+        "\
+Only search files that were last modified after the given time. This does not
+apply to directories.
+
+The time may be given either as a duration relative to now or as a date. A
+duration is an integer followed by one of the suffixes s, m, h, d or w, which
+correspond to seconds, minutes, hours, days and weeks, respectively. A date is
+of the form YYYY-MM-DD and is interpreted as midnight UTC on that day.
+
+This is useful for incremental scans of large trees, since files that have not
+been touched recently are skipped without being opened.
+
+Examples: --newer-than 2d or --newer-than 2021-01-01
+"
+    );
+    let arg =
+        RGArg::flag("newer-than", "DURATION|DATE").help(SHORT).long_help(LONG);
+    args.push(arg);
+}
+
 fn flag_no_config(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Never read configuration files.";
     const LONG: &str = long!(
@@ -2177,10 +3105,18 @@ fn flag_no_messages(args: &mut Vec<RGArg>) {
 Suppress all error messages related to opening and reading files. Error
 messages related to the syntax of the pattern given are still shown.
 
+This flag also accepts an optional comma-separated list of message classes
+to suppress, e.g., --no-messages=io,decode. The classes are: io (failures
+to open or read files), decode (file contents that could not be decoded),
+ignore (problems with ignore rules, equivalent to --no-ignore-messages) and
+other (everything else). When a list is given, only the listed classes are
+suppressed; without a value, all messages are suppressed as before.
+
 This flag can be disabled with the --messages flag.
 "
     );
-    let arg = RGArg::switch("no-messages")
+    let arg = RGArg::flag("no-messages", "CLASSES")
+        .value_optional()
         .help(SHORT)
         .long_help(LONG)
         .overrides("messages");
@@ -2238,6 +3174,22 @@ This flag can be disabled with --require-git.
     args.push(arg);
 }
 
+fn flag_no_rgtypes(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Don't load type definitions from .rgtypes files.";
+    const LONG: &str = long!(
+        "\
+Don't load project-local type definitions. By default, ripgrep looks for a
+file named .rgtypes in the current directory or an ancestor of it, stopping
+at the root of the enclosing repository, and loads extra type definitions
+from it. Each line of that file uses the same format as the --type-add flag,
+e.g., 'proto-gen:*.pb.go'. Empty lines and lines starting with '#' are
+ignored.
+"
+    );
+    let arg = RGArg::switch("no-rgtypes").help(SHORT).long_help(LONG);
+    args.push(arg);
+}
+
 fn flag_no_unicode(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Disable Unicode mode.";
     const LONG: &str = long!(
@@ -2330,6 +3282,26 @@ Using this flag implies -a/--text.
     args.push(arg);
 }
 
+fn flag_older_than(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Only search files modified before the given time.";
+    const LONG: &str = long!(
+        "\
+Only search files that were last modified before the given time. This does not
+apply to directories.
+
+The time may be given either as a duration relative to now or as a date. A
+duration is an integer followed by one of the suffixes s, m, h, d or w, which
+correspond to seconds, minutes, hours, days and weeks, respectively. A date is
+of the form YYYY-MM-DD and is interpreted as midnight UTC on that day.
+
+Examples: --older-than 2d or --older-than 2021-01-01
+"
+    );
+    let arg =
+        RGArg::flag("older-than", "DURATION|DATE").help(SHORT).long_help(LONG);
+    args.push(arg);
+}
+
 fn flag_one_file_system(args: &mut Vec<RGArg>) {
     const SHORT: &str =
         "Do not descend into directories on other file systems.";
@@ -2373,6 +3345,90 @@ part on a separate output line.
     args.push(arg);
 }
 
+fn flag_only_replace(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Print only the expanded replacement per match.";
+    const LONG: &str = long!(
+        "\
+Print only the replacement text, expanded for each match, with each expansion
+on a separate output line. This combines the semantics of -o/--only-matching
+and -r/--replace, but additionally suppresses file names, line numbers and
+heading output so that results can be consumed by scripts without trimming.
+File names and line numbers can be turned back on explicitly with
+-H/--with-filename and -n/--line-number.
+
+Capture group indices (e.g., $5) and names (e.g., $foo) are supported in the
+replacement string, just as with the -r/--replace flag. For example, with the
+pattern '(\\w+)@(\\w+)', the flag '--only-replace '$2,$1'' prints a CSV-style
+line per match.
+
+This flag conflicts with -r/--replace.
+"
+    );
+    let arg = RGArg::flag("only-replace", "REPLACEMENT")
+        .help(SHORT)
+        .long_help(LONG)
+        .conflicts(&["replace"]);
+    args.push(arg);
+}
+
+fn flag_path_config(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Set traversal options for a specific path.";
+    const LONG: &str = long!(
+        "\
+Set traversal options that apply only beneath the given path. SPEC has the
+form PATH:--OPTION=VALUE, where OPTION is one of 'max-depth' or 'glob'. This
+flag may be given multiple times, including multiple times for the same PATH,
+so that a single invocation can treat different parts of the tree differently.
+For example:
+
+    rg foobar --path-config 'src:--max-depth=2' \\
+        --path-config 'vendor:--glob=!*.min.js'
+
+searches at most two directory levels below src and skips minified JavaScript
+files below vendor, while searching everything else normally.
+
+A max-depth limit is measured from the configured PATH rather than from the
+root of the search. A glob is matched relative to the configured PATH, just
+as the -g/--glob flag is matched relative to the root of the search. PATH is
+compared textually against the paths visited during traversal, so it should
+be spelled the same way as the search path it applies beneath.
+"
+    );
+    let arg = RGArg::flag("path-config", "SPEC")
+        .help(SHORT)
+        .long_help(LONG)
+        .multiple();
+    args.push(arg);
+}
+
+fn flag_path_format(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Set the format used for printing file paths.";
+    const LONG: &str = long!(
+        "\
+Set the format used for printing file paths when listing files with --files.
+The accepted values are 'relative', 'absolute' or 'canonical'.
+
+'relative' prints paths relative to the current working directory, when they
+are absolute paths below it. 'absolute' prints absolute paths, by joining
+relative paths to the current working directory. 'canonical' additionally
+resolves all symbolic links and relative components. In each case, if the
+path cannot be transformed (e.g., with 'canonical', if the file has been
+removed), then it is printed as given.
+
+By default, paths are printed as they were given on the command line, which
+are typically relative paths. This flag is useful when the output is consumed
+by tools that need absolute paths, without post-processing. For example:
+
+    rg --files --path-format absolute
+"
+    );
+    let arg = RGArg::flag("path-format", "FORMAT")
+        .help(SHORT)
+        .long_help(LONG)
+        .possible_values(&["relative", "absolute", "canonical"]);
+    args.push(arg);
+}
+
 fn flag_path_separator(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Set the path separator.";
     const LONG: &str = long!(
@@ -2529,6 +3585,67 @@ This overrides the -z/--search-zip flag.
     args.push(arg);
 }
 
+fn flag_pre_cache(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Cache preprocessor output in the given directory.";
+    const LONG: &str = long!(
+        "\
+Cache the output of the --pre preprocessor command in the given directory.
+Entries are keyed by the preprocessor command along with the path, size and
+last modified time of each file, so a cached entry is reused only while the
+underlying file is unchanged. This makes repeated searches over files with
+expensive conversions (such as PDFs or Office documents) much faster.
+
+The directory is created if it does not exist. Use --pre-cache-limit to
+bound the total size of the cache and --pre-cache-clear to empty it.
+
+This flag requires the --pre flag.
+"
+    );
+    let arg = RGArg::flag("pre-cache", "DIR")
+        .help(SHORT)
+        .long_help(LONG)
+        .requires("pre");
+    args.push(arg);
+}
+
+fn flag_pre_cache_clear(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Clear the preprocessor output cache.";
+    const LONG: &str = long!(
+        "\
+Remove every entry from the preprocessor output cache given by --pre-cache
+before the search begins.
+
+This flag requires the --pre-cache flag.
+"
+    );
+    let arg = RGArg::switch("pre-cache-clear")
+        .help(SHORT)
+        .long_help(LONG)
+        .requires("pre-cache");
+    args.push(arg);
+}
+
+fn flag_pre_cache_limit(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "The maximum total size of the preprocessor cache.";
+    const LONG: &str = long!(
+        "\
+The maximum total size of the preprocessor output cache given by --pre-cache.
+When the cache grows beyond this limit, the oldest entries are removed until
+it fits. By default, the cache may grow without bound.
+
+The argument accepts the same size suffixes as allowed in the --max-filesize
+flag.
+
+This flag requires the --pre-cache flag.
+"
+    );
+    let arg = RGArg::flag("pre-cache-limit", "NUM+SUFFIX?")
+        .help(SHORT)
+        .long_help(LONG)
+        .requires("pre-cache");
+    args.push(arg);
+}
+
 fn flag_pre_glob(args: &mut Vec<RGArg>) {
     const SHORT: &str =
         "Include or exclude files from a preprocessing command.";
@@ -2578,6 +3695,32 @@ to another program or file. For example: 'rg -p foo | less -R'.
     args.push(arg);
 }
 
+fn flag_profile(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Select a named profile from the config file.";
+    const LONG: &str = long!(
+        "\
+Select a named profile from the config file pointed to by the
+RIPGREP_CONFIG_PATH environment variable.
+
+A profile is a section in the config file introduced by a line of the form
+'[profile NAME]' or '[profile.NAME]'. Arguments before any profile section
+always apply. When a
+profile is selected, the arguments in its section apply as well; otherwise,
+all profile sections are ignored. For example:
+
+    --smart-case
+
+    [profile work]
+    --glob=!vendor
+
+The RIPGREP_PROFILE environment variable may also be used to select a
+profile. This flag takes precedence over the environment variable.
+"
+    );
+    let arg = RGArg::flag("profile", "NAME").help(SHORT).long_help(LONG);
+    args.push(arg);
+}
+
 fn flag_quiet(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Do not print anything to stdout.";
     const LONG: &str = long!(
@@ -2642,8 +3785,8 @@ fn flag_replace(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Replace matches with the given text.";
     const LONG: &str = long!(
         "\
-Replace every match with the text given when printing results. Neither this
-flag nor any other ripgrep flag will modify your files.
+Replace every match with the text given when printing results. This flag does
+not modify your files unless -W/--write-replace is also given.
 
 Capture group indices (e.g., $5) and names (e.g., $foo) are supported in the
 replacement string. Capture group indices are numbered based on the position of
@@ -2667,6 +3810,12 @@ expanded shell variables which will most likely be empty.
 
 To write a literal '$', use '$$'.
 
+The case of the replacement text can be changed. '\\U' converts everything
+that follows to uppercase and '\\L' to lowercase, until a '\\E' (or the end of
+the replacement) is seen. A single capture group may also be converted by
+attaching a transform to its brace form, e.g., '${1:upper}' or '${foo:lower}'.
+Case conversion is limited to ASCII.
+
 Note that the replacement by default replaces each match, and NOT the entire
 line. To replace the entire line, you should match the entire line.
 
@@ -2681,6 +3830,53 @@ This flag can be used with the -o/--only-matching flag.
     args.push(arg);
 }
 
+fn flag_resume(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Skip files recorded as searched in FILE.";
+    const LONG: &str = long!(
+        "\
+Skip files whose paths are recorded in FILE, as written by the --checkpoint
+flag in a previous run. This resumes an interrupted scan instead of
+restarting it from scratch. A missing FILE is not an error; it simply means
+a fresh scan.
+
+See the --checkpoint flag for the file format and a usage example.
+"
+    );
+    let arg = RGArg::flag("resume", "FILE")
+        .help(SHORT)
+        .long_help(LONG)
+        .allow_leading_hyphen();
+    args.push(arg);
+}
+
+fn flag_search_archives(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Search inside archive files.";
+    const LONG: &str = long!(
+        "\
+Search inside archive files. Currently zip, tar and gzip compressed tar files
+are supported. Each file entry in an archive is searched as if it were a file
+of its own, and is reported under a virtual path that reflects the nesting,
+e.g., archive.zip!/path/inner.txt.
+
+Glob overrides given with -g/--glob and file type filters given with
+-t/--type are applied to the entry names inside archives, so that, e.g.,
+'--type rust' only searches Rust files inside each archive.
+
+This flag can be disabled with --no-search-archives.
+"
+    );
+    let arg = RGArg::switch("search-archives")
+        .help(SHORT)
+        .long_help(LONG)
+        .overrides("no-search-archives");
+    args.push(arg);
+
+    let arg = RGArg::switch("no-search-archives")
+        .hidden()
+        .overrides("search-archives");
+    args.push(arg);
+}
+
 fn flag_search_zip(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Search in compressed files.";
     const LONG: &str = long!(
@@ -2704,6 +3900,39 @@ This flag can be disabled with --no-search-zip.
     args.push(arg);
 }
 
+fn flag_server(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Serve search requests read as JSON from stdin.";
+    const LONG: &str = long!(
+        "\
+Run ripgrep as a server for editor integrations. Instead of executing a
+single search, ripgrep reads search requests as JSON objects from stdin, one
+per line, and streams the results of each request as JSON messages to stdout.
+This amortizes process startup and the compilation of file type definitions
+across many queries.
+
+A request object supports the following keys: 'pattern' (a pattern string) or
+'patterns' (an array of pattern strings), an optional 'paths' array of paths
+to search instead of the paths given on the command line, and an optional
+'id' which is echoed back in the messages for the request.
+
+Each request produces a 'query_begin' message, followed by the standard
+ripgrep JSON match messages (see the --json documentation), followed by a
+'query_end' message that reports whether anything matched. Invalid requests
+produce an 'error' message.
+
+All other command line flags (ignore rules, file types, case sensitivity and
+so on) are applied to every request.
+
+This flag cannot be used with --files, --json or --watch.
+"
+    );
+    let arg = RGArg::switch("server")
+        .help(SHORT)
+        .long_help(LONG)
+        .conflicts(&["files", "json", "watch", "write-replace"]);
+    args.push(arg);
+}
+
 fn flag_smart_case(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Smart case search.";
     const LONG: &str = long!(
@@ -2774,6 +4003,7 @@ for this flag are:
     modified  Sort by the last modified time on a file. Always single-threaded.
     accessed  Sort by the last accessed time on a file. Always single-threaded.
     created   Sort by the creation time on a file. Always single-threaded.
+    size      Sort by file size. Always single-threaded.
 
 If the chosen (manually or by-default) sorting criteria isn't available on your
 system (for example, creation time is not available on ext4 file systems), then
@@ -2789,7 +4019,9 @@ parallelism and run in a single thread.
     let arg = RGArg::flag("sort", "SORTBY")
         .help(SHORT)
         .long_help(LONG)
-        .possible_values(&["path", "modified", "accessed", "created", "none"])
+        .possible_values(&[
+            "path", "modified", "accessed", "created", "size", "none",
+        ])
         .overrides("sortr")
         .overrides("sort-files")
         .overrides("no-sort-files");
@@ -2809,6 +4041,7 @@ for this flag are:
     modified  Sort by the last modified time on a file. Always single-threaded.
     accessed  Sort by the last accessed time on a file. Always single-threaded.
     created   Sort by the creation time on a file. Always single-threaded.
+    size      Sort by file size. Always single-threaded.
 
 If the chosen (manually or by-default) sorting criteria isn't available on your
 system (for example, creation time is not available on ext4 file systems), then
@@ -2824,7 +4057,9 @@ parallelism and run in a single thread.
     let arg = RGArg::flag("sortr", "SORTBY")
         .help(SHORT)
         .long_help(LONG)
-        .possible_values(&["path", "modified", "accessed", "created", "none"])
+        .possible_values(&[
+            "path", "modified", "accessed", "created", "size", "none",
+        ])
         .overrides("sort")
         .overrides("sort-files")
         .overrides("no-sort-files");
@@ -2858,6 +4093,50 @@ This flag can be disabled with --no-stats.
     args.push(arg);
 }
 
+fn flag_stats_format(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Set the output format for --stats.";
+    const LONG: &str = long!(
+        "\
+Set the output format used for the aggregate statistics printed by --stats.
+FORMAT may be 'human' (the default) or 'json'.
+
+When 'json' is used, the statistics are emitted as a single JSON object on
+its own line, in the same format as the summary message emitted at the end
+of --json output.
+"
+    );
+    let arg = RGArg::flag("stats-format", "FORMAT")
+        .help(SHORT)
+        .long_help(LONG)
+        .possible_values(&["human", "json"])
+        .requires("stats");
+    args.push(arg);
+}
+
+fn flag_stop_after_gap(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Stop searching after NUM non-matching lines.";
+    const LONG: &str = long!(
+        "\
+Enabling this option will cause ripgrep to stop reading a file once it
+encounters NUM consecutive non-matching lines after it has encountered a
+matching line. This is a generalization of --stop-on-nonmatch that tolerates
+small gaps between matches, which is useful when searching files that are only
+mostly sorted, such as logs.
+
+A value of 0 disables this behavior.
+
+This overrides the -U/--multiline and --stop-on-nonmatch flags.
+"
+    );
+    let arg = RGArg::flag("stop-after-gap", "NUM")
+        .help(SHORT)
+        .long_help(LONG)
+        .number()
+        .overrides("multiline")
+        .overrides("stop-on-nonmatch");
+    args.push(arg);
+}
+
 fn flag_stop_on_nonmatch(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Stop searching after a non-match.";
     const LONG: &str = long!(
@@ -2867,13 +4146,14 @@ encounters a non-matching line after it has encountered a matching line.
 This is useful if it is expected that all matches in a given file will be on
 sequential lines, for example due to the lines being sorted.
 
-This overrides the -U/--multiline flag.
+This overrides the -U/--multiline and --stop-after-gap flags.
 "
     );
     let arg = RGArg::switch("stop-on-nonmatch")
         .help(SHORT)
         .long_help(LONG)
-        .overrides("multiline");
+        .overrides("multiline")
+        .overrides("stop-after-gap");
     args.push(arg);
 }
 
@@ -2925,6 +4205,29 @@ causes ripgrep to choose the thread count using heuristics.
     args.push(arg);
 }
 
+fn flag_timeout_per_file(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Limit the time spent searching each file.";
+    const LONG: &str = long!(
+        "\
+Limit the wall clock time spent searching any single file. A search that runs
+past the limit is stopped and a warning is printed to stderr, while results
+found up to that point are still reported. This protects against pathological
+combinations of regexes and files stalling an entire run, e.g., in CI.
+
+The duration may be given as a bare number, which is interpreted as
+milliseconds, or with an 'ms' or 's' suffix. For example, --timeout-per-file
+500ms stops searching any file after half a second.
+
+The limit is checked periodically while searching, so a search may overshoot
+it slightly. The warning can be suppressed with the --no-messages flag.
+"
+    );
+    let arg = RGArg::flag("timeout-per-file", "DURATION")
+        .help(SHORT)
+        .long_help(LONG);
+    args.push(arg);
+}
+
 fn flag_trim(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Trim prefixed whitespace from matches.";
     const LONG: &str = long!(
@@ -3087,6 +4390,44 @@ printed more than once.
     args.push(arg);
 }
 
+fn flag_vimgrep_ranges(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Like --vimgrep, but also print match end positions.";
+    const LONG: &str = long!(
+        "\
+Show results like --vimgrep does, but with the end line number and end column
+of each match printed after its starting column, i.e., in the format
+'path:line:column:endline:endcolumn:text'. The end column points at the
+position immediately following the last byte of the match, matching the
+convention used by vim's quickfix ranges. This allows editors to highlight
+exact match ranges rather than just start positions.
+"
+    );
+    let arg = RGArg::switch("vimgrep-ranges").help(SHORT).long_help(LONG);
+    args.push(arg);
+}
+
+fn flag_watch(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Keep running and re-search files as they change.";
+    const LONG: &str = long!(
+        "\
+After the initial search completes, keep running, watch the searched paths
+for changes and re-search files as they are created or modified. Only the
+results for the affected files are printed on each change. The same ignore
+and file type filters used for the initial search are applied to changed
+files.
+
+ripgrep runs until it is interrupted (e.g., with Ctrl-C).
+
+This flag cannot be used with --files or -q/--quiet.
+"
+    );
+    let arg = RGArg::switch("watch")
+        .help(SHORT)
+        .long_help(LONG)
+        .conflicts(&["files", "quiet"]);
+    args.push(arg);
+}
+
 fn flag_with_filename(args: &mut Vec<RGArg>) {
     const SHORT: &str = "Print the file path with the matched lines.";
     const LONG: &str = long!(
@@ -3140,3 +4481,35 @@ This overrides the --line-regexp flag.
         .overrides("line-regexp");
     args.push(arg);
 }
+
+fn flag_write_replace(args: &mut Vec<RGArg>) {
+    const SHORT: &str = "Apply -r/--replace to the matched files themselves.";
+    const LONG: &str = long!(
+        "\
+Apply the replacement given with -r/--replace to the matched files themselves
+instead of printing the results. Each modified file is rewritten by writing
+the new contents to a temporary file in the same directory and atomically
+renaming it over the original, preserving the original file's permissions.
+Files without matches are never touched. Use --backup-suffix to keep the
+original contents of each modified file.
+
+A summary of the modified files is printed, one line per file with the number
+of replacements made, followed by a total. Use --dry-run to see the summary
+without modifying anything.
+
+Since the replacement is applied to the bytes of the file as given, files that
+would require transcoding (e.g., UTF-16 via a byte-order mark) and files
+containing binary data are skipped with an error message. A UTF-8 byte-order
+mark is preserved.
+
+This flag requires the -r/--replace flag and cannot be used with --files.
+"
+    );
+    let arg = RGArg::switch("write-replace")
+        .short("W")
+        .help(SHORT)
+        .long_help(LONG)
+        .requires("replace")
+        .conflicts(&["files", "json"]);
+    args.push(arg);
+}
@@ -2,44 +2,54 @@ use std::cmp;
 use std::env;
 use std::ffi::{OsStr, OsString};
 use std::fs;
-use std::io::{self, Write};
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 use std::process;
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
+use bstr::ByteSlice;
 use clap;
 use grep::cli;
-use grep::matcher::LineTerminator;
+use grep::matcher::{LineTerminator, Matcher};
 #[cfg(feature = "pcre2")]
 use grep::pcre2::{
     RegexMatcher as PCRE2RegexMatcher,
     RegexMatcherBuilder as PCRE2RegexMatcherBuilder,
 };
 use grep::printer::{
-    default_color_specs, ColorSpecs, JSONBuilder, Standard, StandardBuilder,
-    Stats, Summary, SummaryBuilder, SummaryKind, JSON,
+    default_color_specs, ColorSpecs, HyperlinkFormat, JSONBuilder,
+    PathTransform, Standard, StandardBuilder, Stats, Summary, SummaryBuilder,
+    SummaryKind, JSON,
 };
 use grep::regex::{
     RegexMatcher as RustRegexMatcher,
     RegexMatcherBuilder as RustRegexMatcherBuilder,
 };
 use grep::searcher::{
-    BinaryDetection, Encoding, MmapChoice, Searcher, SearcherBuilder,
+    BinaryDetection, ContextStart, Encoding, MmapAdvice, MmapChoice, Searcher,
+    SearcherBuilder,
 };
 use ignore::overrides::{Override, OverrideBuilder};
 use ignore::types::{FileTypeDef, Types, TypesBuilder};
-use ignore::{Walk, WalkBuilder, WalkParallel};
+use ignore::{Explainer, Walk, WalkBuilder, WalkParallel};
 use log;
 use termcolor::{BufferWriter, ColorChoice, WriteColor};
 
-use crate::app;
+use crate::app::{self, GenerateKind};
+use crate::checkpoint::Checkpoint;
 use crate::config;
+use crate::interrupt;
 use crate::logger::Logger;
-use crate::messages::{set_ignore_messages, set_messages};
-use crate::path_printer::{PathPrinter, PathPrinterBuilder};
+use crate::messages::{
+    parse_message_classes, set_ignore_messages, set_json_errors, set_messages,
+    set_suppressed_classes,
+};
+use crate::path_printer::{FileColumn, PathPrinter, PathPrinterBuilder};
+use crate::precache::PreprocessorCache;
+use crate::replace::{ReplaceWorker, ReplaceWorkerBuilder};
 use crate::search::{
-    PatternMatcher, Printer, SearchWorker, SearchWorkerBuilder,
+    AllMatchKind, PatternMatcher, Printer, SearchWorker, SearchWorkerBuilder,
 };
 use crate::subject::{Subject, SubjectBuilder};
 use crate::Result;
@@ -56,27 +66,43 @@ pub enum Command {
     /// ripgrep knows that a match can never be found (e.g., no given patterns
     /// or --max-count=0).
     SearchNever,
+    /// Run the configured search repeatedly with its output suppressed,
+    /// reporting timings instead of results.
+    Bench,
+    /// Search files and rewrite them in place by applying the replacement
+    /// text to every match, using exactly one thread.
+    WriteReplace,
     /// Show the files that would be searched, but don't actually search them,
     /// and use exactly one thread.
     Files,
     /// Show the files that would be searched, but don't actually search them,
     /// and perform directory traversal using possibly many threads.
     FilesParallel,
+    /// Explain why each of the given paths would or would not be searched,
+    /// instead of searching.
+    Explain,
+    /// Read search requests as JSON from stdin and stream results as JSON
+    /// to stdout, one search per request.
+    Server,
     /// List all file type definitions configured, including the default file
     /// types and any additional file types added to the command line.
     Types,
     /// Print the version of PCRE2 in use.
     PCRE2Version,
+    /// Generate a supporting artifact, such as completions or a man page,
+    /// and print it to stdout.
+    Generate(GenerateKind),
 }
 
 impl Command {
     /// Returns true if and only if this command requires executing a search.
-    fn is_search(&self) -> bool {
+    pub fn is_search(&self) -> bool {
         use self::Command::*;
 
         match *self {
             Search | SearchParallel => true,
-            SearchNever | Files | FilesParallel | Types | PCRE2Version => {
+            SearchNever | Bench | WriteReplace | Server | Files
+            | FilesParallel | Explain | Types | PCRE2Version | Generate(_) => {
                 false
             }
         }
@@ -131,8 +157,7 @@ impl Args {
         // arguments, then we re-parse argv, otherwise we just use the matches
         // we have here.
         let early_matches = ArgMatches::new(clap_matches(env::args_os())?);
-        set_messages(!early_matches.is_present("no-messages"));
-        set_ignore_messages(!early_matches.is_present("no-ignore-messages"));
+        early_matches.configure_messages()?;
 
         if let Err(err) = Logger::init() {
             return Err(format!("failed to initialize logger: {}", err).into());
@@ -156,8 +181,7 @@ impl Args {
         } else {
             log::set_max_level(log::LevelFilter::Warn);
         }
-        set_messages(!matches.is_present("no-messages"));
-        set_ignore_messages(!matches.is_present("no-ignore-messages"));
+        matches.configure_messages()?;
         matches.to_args()
     }
 
@@ -174,7 +198,7 @@ impl Args {
     /// Return the paths found in the command line arguments. This is
     /// guaranteed to be non-empty. In the case where no explicit arguments are
     /// provided, a single default path is provided automatically.
-    fn paths(&self) -> &[PathBuf] {
+    pub fn paths(&self) -> &[PathBuf] {
         &self.0.paths
     }
 
@@ -219,6 +243,18 @@ impl Args {
         Ok(wtr)
     }
 
+    /// Returns the number of benchmark runs to execute for the --bench
+    /// flag.
+    pub fn bench_iterations(&self) -> Result<usize> {
+        self.matches().bench_iterations()
+    }
+
+    /// Build the checkpoint tracker from the --checkpoint and --resume
+    /// flags, if either is present.
+    pub fn checkpoint(&self) -> Result<Option<Checkpoint>> {
+        self.matches().checkpoint()
+    }
+
     /// Return the high-level command that ripgrep should run.
     pub fn command(&self) -> Command {
         self.0.command
@@ -236,10 +272,48 @@ impl Args {
         builder
             .color_specs(self.matches().color_specs()?)
             .separator(self.matches().path_separator()?)
-            .terminator(self.matches().path_terminator().unwrap_or(b'\n'));
+            .terminator(self.matches().path_terminator().unwrap_or(b'\n'))
+            .transform(self.matches().path_format()?)
+            .columns(self.matches().files_format()?)
+            .json(self.matches().is_present("json"));
         Ok(builder.build(wtr))
     }
 
+    /// The interval at which buffered parallel search output should be
+    /// flushed, if one was given.
+    pub fn flush_interval(&self) -> Result<Option<Duration>> {
+        self.matches().flush_interval()
+    }
+
+    /// The maximum number of matches permitted across all files searched,
+    /// if one was given.
+    pub fn max_total_matches(&self) -> Result<Option<u64>> {
+        self.matches().max_total_matches()
+    }
+
+    /// Returns the total count that should be printed for --count-total from
+    /// the given statistics, if the flag was given.
+    ///
+    /// The total is the number of individual matches when --count-matches is
+    /// given and the number of matched lines otherwise.
+    pub fn count_total(&self, stats: &Stats) -> Option<u64> {
+        if !self.matches().is_present("count-total") {
+            return None;
+        }
+        let (_, count_matches) = self.matches().counts();
+        Some(if count_matches {
+            stats.matches()
+        } else {
+            stats.matched_lines()
+        })
+    }
+
+    /// Returns true if and only if ripgrep should emit its output in a JSON
+    /// Lines format.
+    pub fn json(&self) -> bool {
+        self.matches().is_present("json")
+    }
+
     /// Returns true if and only if ripgrep should be "quiet."
     pub fn quiet(&self) -> bool {
         self.matches().is_present("quiet")
@@ -264,15 +338,94 @@ impl Args {
         let searcher = matches.searcher(self.paths())?;
         let mut builder = SearchWorkerBuilder::new();
         builder
-            .json_stats(matches.is_present("json"))
+            .json_stats(
+                matches.is_present("json") || matches.stats_format_json(),
+            )
+            .preprocessor(matches.preprocessor())?
+            .preprocessor_globs(matches.preprocessor_globs()?)
+            .preprocessor_cache(matches.preprocessor_cache()?)
+            .decompression_matcher(matches.decompression_matcher()?)
+            .search_zip(matches.is_present("search-zip"))
+            .search_archives(matches.is_present("search-archives"))
+            .archive_globs(matches.overrides()?)
+            .archive_types(matches.types()?)
+            .binary_detection_implicit(matches.binary_detection_implicit())
+            .binary_detection_explicit(matches.binary_detection_explicit())
+            .label(matches.label());
+        if let EncodingMode::Detect = matches.encoding()? {
+            builder.detect_encoding(true);
+        }
+        if let Some(kind) = matches.all_match() {
+            let patterns = matches.patterns()?;
+            if patterns.len() > 1 {
+                let mut pattern_matchers = Vec::with_capacity(patterns.len());
+                for pattern in patterns {
+                    pattern_matchers.push(matches.matcher(&[pattern])?);
+                }
+                builder
+                    .all_match(Some(kind))
+                    .pattern_matchers(pattern_matchers);
+            }
+        }
+        Ok(builder.build(matcher, searcher, printer))
+    }
+
+    /// Build a worker for executing searches with the given patterns instead
+    /// of the patterns from the command line, printing results as JSON to
+    /// the given writer.
+    ///
+    /// This is used by server mode, where every request supplies its own
+    /// patterns but shares the rest of the command line configuration.
+    pub fn search_worker_for_patterns<W: WriteColor>(
+        &self,
+        patterns: &[String],
+        wtr: W,
+    ) -> Result<SearchWorker<W>> {
+        let matches = self.matches();
+        let matcher = matches.matcher(patterns)?;
+        let printer = Printer::JSON(matches.printer_json(wtr)?);
+        let searcher = matches.searcher(self.paths())?;
+        let mut builder = SearchWorkerBuilder::new();
+        builder
+            .json_stats(false)
             .preprocessor(matches.preprocessor())?
             .preprocessor_globs(matches.preprocessor_globs()?)
+            .preprocessor_cache(matches.preprocessor_cache()?)
+            .decompression_matcher(matches.decompression_matcher()?)
             .search_zip(matches.is_present("search-zip"))
+            .search_archives(matches.is_present("search-archives"))
+            .archive_globs(matches.overrides()?)
+            .archive_types(matches.types()?)
             .binary_detection_implicit(matches.binary_detection_implicit())
             .binary_detection_explicit(matches.binary_detection_explicit());
+        if let EncodingMode::Detect = matches.encoding()? {
+            builder.detect_encoding(true);
+        }
         Ok(builder.build(matcher, searcher, printer))
     }
 
+    /// Build a worker for rewriting files in place.
+    ///
+    /// This returns an error if no replacement text was given.
+    pub fn replace_worker(&self) -> Result<ReplaceWorker> {
+        let matcher = self.matcher().clone();
+        let replacement =
+            match self.matches().replacement() {
+                Some(replacement) => replacement,
+                None => return Err(From::from(
+                    "the --write-replace flag requires the -r/--replace flag",
+                )),
+            };
+        let mut builder = ReplaceWorkerBuilder::new();
+        builder.dry_run(self.matches().is_present("dry-run"));
+        builder.backup_suffix(
+            self.matches()
+                .value_of_os("backup-suffix")
+                .map(|s| s.to_os_string()),
+        );
+        Ok(builder.build(matcher, replacement))
+    }
+
     /// Returns a zero value for tracking statistics if and only if it has been
     /// requested.
     ///
@@ -291,7 +444,12 @@ impl Args {
     /// file or a stream such as stdin.
     pub fn subject_builder(&self) -> SubjectBuilder {
         let mut builder = SubjectBuilder::new();
-        builder.strip_dot_prefix(self.using_default_path());
+        builder
+            .strip_dot_prefix(self.using_default_path())
+            .allow_special_files(
+                self.matches().is_present("allow-special-files"),
+            )
+            .dedup(self.matches().is_present("dedup-hardlinks"));
         builder
     }
 
@@ -324,6 +482,65 @@ impl Args {
             .build())
     }
 
+    /// Return a single threaded walker over just the given path, using the
+    /// same filtering configuration as the main walker.
+    pub fn walker_for(&self, path: &Path) -> Result<Walk> {
+        let paths = vec![path.to_path_buf()];
+        Ok(self.matches().walker_builder(&paths, 1)?.build())
+    }
+
+    /// Return an explainer that reports why a path would or wouldn't be
+    /// searched, using the same filtering configuration as the main walker.
+    pub fn walk_explainer(&self) -> Result<Explainer> {
+        Ok(self
+            .matches()
+            .walker_builder(self.paths(), self.0.threads)?
+            .build_explainer())
+    }
+
+    /// Return the paths given to --explain.
+    pub fn explain_paths(&self) -> Vec<PathBuf> {
+        self.matches().explain_paths()
+    }
+
+    /// Clear the preprocessor output cache if the user requested it with
+    /// the --pre-cache-clear flag.
+    pub fn clear_preprocessor_cache(&self) -> Result<()> {
+        if !self.matches().is_present("pre-cache-clear") {
+            return Ok(());
+        }
+        if let Some(cache) = self.matches().preprocessor_cache()? {
+            cache.clear()?;
+        }
+        Ok(())
+    }
+
+    /// Returns true if and only if ripgrep should keep running after the
+    /// initial search and re-search files as they change.
+    pub fn watch(&self) -> bool {
+        self.matches().is_present("watch")
+    }
+
+    /// Returns true if and only if search results should be grouped by
+    /// directory, with a heading and count per directory.
+    pub fn group_by_dir(&self) -> bool {
+        self.matches().group_by_dir()
+    }
+
+    /// Returns true if and only if search results must be emitted in sorted
+    /// order. The search itself may still run in parallel; see
+    /// `search_parallel_sorted` in `main.rs`.
+    pub fn needs_sorted_output(&self) -> bool {
+        self.matches()
+            .sort_by()
+            .map_or(false, |sort_by| sort_by.kind != SortByKind::None)
+    }
+
+    /// Return the number of threads that should be used for parallelism.
+    pub fn threads(&self) -> usize {
+        self.0.threads
+    }
+
     /// Returns true if and only if `stat`-related sorting is required
     pub fn needs_stat_sort(&self) -> bool {
         return self.matches().sort_by().map_or(
@@ -331,7 +548,8 @@ impl Args {
             |sort_by| match sort_by.kind {
                 SortByKind::LastModified
                 | SortByKind::Created
-                | SortByKind::LastAccessed => sort_by.check().is_ok(),
+                | SortByKind::LastAccessed
+                | SortByKind::Size => sort_by.check().is_ok(),
                 _ => false,
             },
         );
@@ -358,6 +576,13 @@ impl Args {
             LastModified => load_timestamps(subjects, |m| m.modified()),
             LastAccessed => load_timestamps(subjects, |m| m.accessed()),
             Created => load_timestamps(subjects, |m| m.created()),
+            Size => {
+                let mut keyed = load_sizes(subjects);
+                keyed.sort_by(|a, b| {
+                    sort_by_option(&a.0, &b.0, sorter.reverse)
+                });
+                return keyed.into_iter().map(|v| v.1).collect();
+            }
             _ => return subjects.collect(),
         };
         keyed.sort_by(|a, b| sort_by_option(&a.0, &b.0, sorter.reverse));
@@ -411,6 +636,8 @@ enum SortByKind {
     LastAccessed,
     /// Sort by creation time.
     Created,
+    /// Sort by file size.
+    Size,
 }
 
 impl SortBy {
@@ -440,6 +667,10 @@ impl SortBy {
             SortByKind::Created => {
                 env::current_exe()?.metadata()?.created()?;
             }
+            SortByKind::Size => {
+                // A size is available whenever metadata is.
+                env::current_exe()?.metadata()?;
+            }
         }
         Ok(())
     }
@@ -459,7 +690,7 @@ impl SortBy {
                 builder.sort_by_file_name(|a, b| a.cmp(b));
             }
             // these use `stat` calls and will be sorted in Args::sort_by_stat()
-            LastModified | LastAccessed | Created | None => {}
+            LastModified | LastAccessed | Created | Size | None => {}
         };
     }
 }
@@ -472,11 +703,21 @@ impl SortByKind {
             "modified" => SortByKind::LastModified,
             "accessed" => SortByKind::LastAccessed,
             "created" => SortByKind::Created,
+            "size" => SortByKind::Size,
             _ => SortByKind::None,
         }
     }
 }
 
+/// A traversal configuration that applies only beneath a particular path,
+/// parsed from a single occurrence of the --path-config flag.
+#[derive(Clone, Debug)]
+struct PathConfig {
+    path: PathBuf,
+    max_depth: Option<usize>,
+    globs: Vec<String>,
+}
+
 /// Encoding mode the searcher will use.
 #[derive(Clone, Debug)]
 enum EncodingMode {
@@ -484,6 +725,9 @@ enum EncodingMode {
     Some(Encoding),
     /// Use only BOM sniffing to auto-detect an encoding.
     Auto,
+    /// Use BOM sniffing, and when no BOM is present, detect the encoding
+    /// from the content of each file.
+    Detect,
     /// Use no explicit encoding and disable all BOM sniffing. This will
     /// always result in searching the raw bytes, regardless of their
     /// true encoding.
@@ -512,8 +756,13 @@ impl ArgMatches {
             return Ok(self);
         }
         // If the user wants ripgrep to use a config file, then parse args
-        // from that first.
-        let mut args = config::args();
+        // from that first. The profile selected with --profile (or with
+        // RIPGREP_PROFILE) determines which sections of the config apply.
+        let profile = self
+            .value_of_lossy("profile")
+            .or_else(|| env::var("RIPGREP_PROFILE").ok())
+            .filter(|name| !name.is_empty());
+        let mut args = config::args(profile.as_deref());
         if args.is_empty() {
             return Ok(self);
         }
@@ -533,12 +782,21 @@ impl ArgMatches {
         let patterns = self.patterns()?;
         let matcher = self.matcher(&patterns)?;
         let mut paths = self.paths();
+        let mut files_from_empty = false;
+        if let Some(mut from) = self.files_from()? {
+            files_from_empty = paths.is_empty() && from.is_empty();
+            paths.append(&mut from);
+        }
         let using_default_path = if paths.is_empty() {
             paths.push(self.path_default());
             true
         } else {
             false
         };
+        // Only some commands use these, but invalid values should be
+        // reported up front, regardless of which command runs.
+        self.flush_interval()?;
+        self.memory_budget_per_thread()?;
         // Now figure out the number of threads we'll use and which
         // command will run.
         let is_one_search = self.is_one_search(&paths);
@@ -548,18 +806,34 @@ impl ArgMatches {
         } else {
             log::debug!("running with {threads} threads for parallelism");
         }
-        let command = if self.is_present("pcre2-version") {
+        let command = if let Some(kind) = self.generate() {
+            Command::Generate(kind)
+        } else if self.is_present("pcre2-version") {
             Command::PCRE2Version
         } else if self.is_present("type-list") {
             Command::Types
+        } else if self.is_present("server") {
+            Command::Server
+        } else if self.is_present("explain") {
+            Command::Explain
+        } else if files_from_empty {
+            // An empty file list (after filtering) means there is nothing
+            // to search, so don't fall back to the default path.
+            Command::SearchNever
         } else if self.is_present("files") {
-            if threads == 1 {
+            // Listing files has no reorder buffer, so any sorting requires
+            // a sequential traversal.
+            if threads == 1 || self.sort_by()?.kind != SortByKind::None {
                 Command::Files
             } else {
                 Command::FilesParallel
             }
         } else if self.can_never_match(&patterns) {
             Command::SearchNever
+        } else if self.is_present("bench") {
+            Command::Bench
+        } else if self.is_present("write-replace") {
+            Command::WriteReplace
         } else if threads == 1 {
             Command::Search
         } else {
@@ -628,6 +902,9 @@ impl ArgMatches {
             "auto" => {
                 let rust_err = match self.matcher_rust(patterns) {
                     Ok(matcher) => {
+                        log::trace!(
+                            "chose default regex engine in hybrid mode"
+                        );
                         return Ok(PatternMatcher::RustRegex(matcher));
                     }
                     Err(err) => err,
@@ -638,7 +915,10 @@ impl ArgMatches {
                 );
 
                 let pcre_err = match self.matcher_engine("pcre2", patterns) {
-                    Ok(matcher) => return Ok(matcher),
+                    Ok(matcher) => {
+                        log::trace!("chose PCRE2 regex engine in hybrid mode");
+                        return Ok(matcher);
+                    }
                     Err(err) => err,
                 };
                 Err(From::from(format!(
@@ -699,6 +979,12 @@ impl ArgMatches {
         if let Some(limit) = self.dfa_size_limit()? {
             builder.dfa_size_limit(limit);
         }
+        // The JSON printer reports which pattern produced each submatch when
+        // the matcher tracks that information, so only pay for the tracking
+        // when it will actually be surfaced.
+        if self.is_present("json") && patterns.len() > 1 {
+            builder.pattern_indices(true);
+        }
         match builder.build_many(patterns) {
             Ok(m) => Ok(m),
             Err(err) => Err(From::from(suggest_multiline(err.to_string()))),
@@ -772,12 +1058,18 @@ impl ArgMatches {
         let mut builder = StandardBuilder::new();
         builder
             .color_specs(self.color_specs()?)
-            .stats(self.stats())
+            .stats(
+                self.stats()
+                    || self.is_present("max-total-matches")
+                    || self.is_present("bench")
+                    || self.group_by_dir(),
+            )
             .heading(self.heading())
             .path(self.with_filename(paths))
-            .only_matching(self.is_present("only-matching"))
-            .per_match(self.is_present("vimgrep"))
+            .only_matching(self.only_matching())
+            .per_match(self.vimgrep())
             .per_match_one_line(true)
+            .match_ranges(self.is_present("vimgrep-ranges"))
             .replacement(self.replacement())
             .max_columns(self.max_columns()?)
             .max_columns_preview(self.max_columns_preview())
@@ -790,7 +1082,8 @@ impl ArgMatches {
             .separator_field_match(self.field_match_separator())
             .separator_field_context(self.field_context_separator())
             .separator_path(self.path_separator()?)
-            .path_terminator(self.path_terminator());
+            .path_terminator(self.path_terminator())
+            .hyperlink_format(self.hyperlink_format()?);
         if separator_search {
             builder.separator_search(self.file_separator()?);
         }
@@ -811,7 +1104,7 @@ impl ArgMatches {
         builder
             .kind(self.summary_kind().expect("summary format"))
             .color_specs(self.color_specs()?)
-            .stats(self.stats())
+            .stats(self.stats() || self.is_present("max-total-matches"))
             .path(self.with_filename(paths))
             .max_matches(self.max_count()?)
             .exclude_zero(!self.is_present("include-zero"))
@@ -839,14 +1132,28 @@ impl ArgMatches {
             .multi_line(self.is_present("multiline"))
             .before_context(ctx_before)
             .after_context(ctx_after)
+            .context_start(self.context_start()?)
             .passthru(self.is_present("passthru"))
-            .memory_map(self.mmap_choice(paths))
-            .stop_on_nonmatch(self.is_present("stop-on-nonmatch"));
+            .memory_map(self.mmap_choice(paths)?)
+            .stop_on_nonmatch(self.is_present("stop-on-nonmatch"))
+            .stop_after_gap(self.stop_after_gap()?)
+            .max_bytes_searched(self.max_file_size_searched()?)
+            .cancel_token(Some(interrupt::token()))
+            .timeout(self.timeout_per_file()?);
+        if let Some(per_thread) = self.memory_budget_per_thread()? {
+            // Half of each thread's share bounds the searcher's internal
+            // buffer. The rest is left for the regex engines and output
+            // buffering.
+            builder.heap_limit(Some((per_thread / 2) as usize));
+        }
         match self.encoding()? {
             EncodingMode::Some(enc) => {
                 builder.encoding(Some(enc));
             }
             EncodingMode::Auto => {} // default for the searcher
+            // Per file detection happens in the search worker, so BOM
+            // sniffing (the searcher's default) is all we need here.
+            EncodingMode::Detect => {}
             EncodingMode::Disabled => {
                 builder.bom_sniffing(false);
             }
@@ -879,6 +1186,8 @@ impl ArgMatches {
             .max_depth(self.usize_of("max-depth")?)
             .follow_links(self.is_present("follow"))
             .max_filesize(self.max_file_size()?)
+            .modified_after(self.newer_than()?)
+            .modified_before(self.older_than()?)
             .threads(threads)
             .same_file_system(self.is_present("one-file-system"))
             .skip_stdout(!self.is_present("files"))
@@ -891,10 +1200,53 @@ impl ArgMatches {
             .git_ignore(!self.no_ignore_vcs())
             .git_exclude(!self.no_ignore_vcs() && !self.no_ignore_exclude())
             .require_git(!self.is_present("no-require-git"))
-            .ignore_case_insensitive(self.ignore_file_case_insensitive());
+            .ignore_case_insensitive(self.ignore_file_case_insensitive())
+            .cancel_token(Some(interrupt::token()));
         if !self.no_ignore() && !self.no_ignore_dot() {
             builder.add_custom_ignore_filename(".rgignore");
         }
+        // The walker supports only a single entry filter, so the per-path
+        // depth limits from --path-config and the preprocessor cache filter
+        // are combined into one predicate.
+        //
+        // The preprocessor cache directory is managed by ripgrep itself and
+        // commonly lives inside the search tree, so never descend into it.
+        // Otherwise, its entries would be fed back through the preprocessor.
+        let cache_dir = match self.value_of_os("pre-cache") {
+            None => None,
+            Some(dir) => match env::current_dir() {
+                Err(_) => None,
+                Ok(cwd) => Some((cwd.clone(), cwd.join(dir))),
+            },
+        };
+        let depth_limits: Vec<(PathBuf, usize)> = self
+            .path_configs()?
+            .into_iter()
+            .filter_map(|c| c.max_depth.map(|depth| (c.path, depth)))
+            .collect();
+        if cache_dir.is_some() || !depth_limits.is_empty() {
+            builder.filter_entry(move |ent| {
+                if let Some((ref cwd, ref cache_dir)) = cache_dir {
+                    let path = cwd.join(ent.path());
+                    if path.components().eq(cache_dir.components()) {
+                        return false;
+                    }
+                }
+                // Entries beneath the default "./" search path carry that
+                // prefix, so strip it before comparing against the
+                // configured path.
+                let entpath = ent
+                    .path()
+                    .strip_prefix("./")
+                    .unwrap_or_else(|_| ent.path());
+                depth_limits.iter().all(|&(ref path, depth)| {
+                    match entpath.strip_prefix(path) {
+                        Err(_) => true,
+                        Ok(rel) => rel.components().count() <= depth,
+                    }
+                })
+            });
+        }
         self.sort_by()?.configure_builder_sort(&mut builder);
         Ok(builder)
     }
@@ -905,9 +1257,26 @@ impl ArgMatches {
 ///
 /// Methods are sorted alphabetically.
 impl ArgMatches {
+    /// Returns the granularity at which every pattern must match, if the
+    /// --all-match flag was given.
+    ///
+    /// When the flag is given without a value, line granularity is used.
+    fn all_match(&self) -> Option<AllMatchKind> {
+        if !self.is_present("all-match") {
+            return None;
+        }
+        match self.value_of_lossy("all-match") {
+            Some(ref kind) if kind == "file" => Some(AllMatchKind::File),
+            _ => Some(AllMatchKind::Line),
+        }
+    }
+
     /// Returns the form of binary detection to perform on files that are
     /// implicitly searched via recursive directory traversal.
     fn binary_detection_implicit(&self) -> BinaryDetection {
+        if let Some(detection) = self.binary_files() {
+            return detection;
+        }
         let none = self.is_present("text") || self.is_present("null-data");
         let convert =
             self.is_present("binary") || self.unrestricted_count() >= 3;
@@ -928,6 +1297,12 @@ impl ArgMatches {
     /// as a filter (but quitting immediately once a NUL byte is seen), and we
     /// should never filter out files that the user wants to explicitly search.
     fn binary_detection_explicit(&self) -> BinaryDetection {
+        // The --binary-files flag is an exception to the rule below: GNU
+        // grep's 'without-match' mode filters binary files even when they
+        // are given explicitly, and we match that for compatibility.
+        if let Some(detection) = self.binary_files() {
+            return detection;
+        }
         let none = self.is_present("text") || self.is_present("null-data");
         if none {
             BinaryDetection::none()
@@ -936,6 +1311,29 @@ impl ArgMatches {
         }
     }
 
+    /// Returns the binary detection strategy corresponding to the GNU grep
+    /// compatible --binary-files flag, if it was given.
+    fn binary_files(&self) -> Option<BinaryDetection> {
+        match self.value_of_lossy("binary-files").as_deref() {
+            Some("text") => Some(BinaryDetection::none()),
+            Some("binary") => Some(BinaryDetection::convert(b'\x00')),
+            Some("without-match") => Some(BinaryDetection::quit(b'\x00')),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of benchmark runs to execute for the --bench
+    /// flag.
+    ///
+    /// Without an explicit count, a small default is used.
+    fn bench_iterations(&self) -> Result<usize> {
+        match self.usize_of("bench")? {
+            None => Ok(3),
+            Some(0) => Err(From::from("--bench count must be at least 1")),
+            Some(n) => Ok(n),
+        }
+    }
+
     /// Returns true if the command line configuration implies that a match
     /// can never be shown.
     fn can_never_match(&self, patterns: &[String]) -> bool {
@@ -960,6 +1358,21 @@ impl ArgMatches {
             && !self.is_present("case-sensitive")
     }
 
+    /// Build the checkpoint tracker from the --checkpoint and --resume
+    /// flags.
+    ///
+    /// This returns `None` when neither flag is present.
+    fn checkpoint(&self) -> Result<Option<Checkpoint>> {
+        let record = self.value_of_os("checkpoint").map(PathBuf::from);
+        let resume = self.value_of_os("resume").map(PathBuf::from);
+        if record.is_none() && resume.is_none() {
+            return Ok(None);
+        }
+        let checkpoint =
+            Checkpoint::new(record.as_deref(), resume.as_deref())?;
+        Ok(Some(checkpoint))
+    }
+
     /// Returns the user's color choice based on command line parameters and
     /// environment.
     fn color_choice(&self) -> ColorChoice {
@@ -1000,7 +1413,30 @@ impl ArgMatches {
         if self.is_present("no-column") {
             return false;
         }
-        self.is_present("column") || self.is_present("vimgrep")
+        self.is_present("column") || self.vimgrep()
+    }
+
+    /// Configure the global message state from the command line.
+    ///
+    /// With a bare --no-messages, all non-essential messages are suppressed.
+    /// With --no-messages=CLASSES, only the listed message classes are
+    /// suppressed and everything else is still shown.
+    fn configure_messages(&self) -> Result<()> {
+        let mut messages = true;
+        let mut classes = 0;
+        if self.is_present("no-messages") {
+            match self.value_of_lossy("no-messages") {
+                None => messages = false,
+                Some(list) => classes = parse_message_classes(&list)?,
+            }
+        }
+        set_messages(messages);
+        set_suppressed_classes(classes);
+        set_ignore_messages(!self.is_present("no-ignore-messages"));
+        set_json_errors(
+            self.value_of_lossy("errors").as_deref() == Some("json"),
+        );
+        Ok(())
     }
 
     /// Returns the before and after contexts from the command line.
@@ -1016,6 +1452,21 @@ impl ArgMatches {
         Ok((before, after))
     }
 
+    /// Returns the context block start predicate from the command line.
+    ///
+    /// If the --context-start flag wasn't given, then `None` is returned.
+    /// If the given regex is invalid, then an error is returned.
+    fn context_start(&self) -> Result<Option<ContextStart>> {
+        let pattern = match self.value_of_lossy("context-start") {
+            None => return Ok(None),
+            Some(pattern) => pattern,
+        };
+        let matcher = RustRegexMatcherBuilder::new().build(&pattern)?;
+        Ok(Some(ContextStart::new(move |line| {
+            matcher.is_match(line).unwrap_or(false)
+        })))
+    }
+
     /// Returns the unescaped context separator in UTF-8 bytes.
     ///
     /// If one was not provided, the default `--` is returned.
@@ -1040,7 +1491,7 @@ impl ArgMatches {
         let count = self.is_present("count");
         let count_matches = self.is_present("count-matches");
         let invert_matches = self.is_present("invert-match");
-        let only_matching = self.is_present("only-matching");
+        let only_matching = self.only_matching();
         if count_matches && invert_matches {
             // Treat `-v --count-matches` as `-v -c`.
             (true, false)
@@ -1052,9 +1503,57 @@ impl ArgMatches {
         }
     }
 
+    /// Build the matcher that maps file paths to decompression commands,
+    /// including any custom commands given via --decompress-cmd.
+    ///
+    /// Custom commands are added after the built-in rules, so they take
+    /// precedence when both match a path.
+    fn decompression_matcher(&self) -> Result<cli::DecompressionMatcher> {
+        let mut builder = cli::DecompressionMatcherBuilder::new();
+        if let Some(specs) = self.values_of_lossy("decompress-cmd") {
+            for spec in specs {
+                let (ext, cmd) = match spec.split_once('=') {
+                    Some((ext, cmd)) => (ext.trim_start_matches('.'), cmd),
+                    None => {
+                        return Err(From::from(format!(
+                            "invalid --decompress-cmd value {:?}: format \
+                             must be ext=command",
+                            spec,
+                        )));
+                    }
+                };
+                let mut parts = cmd.split_whitespace();
+                let program = match parts.next() {
+                    Some(program) => program,
+                    None => {
+                        return Err(From::from(format!(
+                            "invalid --decompress-cmd value {:?}: no \
+                             command given",
+                            spec,
+                        )));
+                    }
+                };
+                builder
+                    .try_associate(&format!("*.{}", ext), program, parts)
+                    .map_err(|err| {
+                    format!("--decompress-cmd {}: {}", spec, err)
+                })?;
+            }
+        }
+        Ok(builder.build()?)
+    }
+
     /// Parse the dfa-size-limit argument option into a byte count.
+    ///
+    /// When the flag is absent but a --max-memory budget was given, a
+    /// quarter of each thread's share of the budget is used instead.
     fn dfa_size_limit(&self) -> Result<Option<usize>> {
         let r = self.parse_human_readable_size("dfa-size-limit")?;
+        if r.is_none() {
+            if let Some(per_thread) = self.memory_budget_per_thread()? {
+                return u64_to_usize("max-memory", Some(per_thread / 4));
+            }
+        }
         u64_to_usize("dfa-size-limit", r)
     }
 
@@ -1075,7 +1574,7 @@ impl ArgMatches {
         };
 
         if label == "auto" {
-            return Ok(EncodingMode::Auto);
+            return Ok(EncodingMode::Detect);
         } else if label == "none" {
             return Ok(EncodingMode::Disabled);
         }
@@ -1083,6 +1582,14 @@ impl ArgMatches {
         Ok(EncodingMode::Some(Encoding::new(&label)?))
     }
 
+    /// Return the paths that should be explained via --explain.
+    fn explain_paths(&self) -> Vec<PathBuf> {
+        match self.values_of_os("explain") {
+            None => vec![],
+            Some(paths) => paths.map(PathBuf::from).collect(),
+        }
+    }
+
     /// Return the file separator to use based on the CLI configuration.
     fn file_separator(&self) -> Result<Option<Vec<u8>>> {
         // File separators are only used for the standard grep-line format.
@@ -1100,10 +1607,184 @@ impl ArgMatches {
         })
     }
 
+    /// Returns the metadata columns that should be printed before each path
+    /// listed by --files.
+    ///
+    /// If there was a problem parsing the column names from the user, then
+    /// an error is returned.
+    fn files_format(&self) -> Result<Vec<FileColumn>> {
+        let format = match self.value_of_lossy("files-format") {
+            None => return Ok(vec![]),
+            Some(format) => format,
+        };
+        let mut columns = vec![];
+        for name in format.split(',') {
+            columns.push(match name.trim() {
+                "size" => FileColumn::Size,
+                "mtime" => FileColumn::Mtime,
+                "type" => FileColumn::FileType,
+                unknown => {
+                    return Err(From::from(format!(
+                        "invalid value for --files-format: {} \
+                         (expected size, mtime or type)",
+                        unknown,
+                    )));
+                }
+            });
+        }
+        Ok(columns)
+    }
+
+    /// Reads the list of paths to search given by the --files-from or
+    /// --files-from0 flags, if either is present.
+    ///
+    /// Paths read this way bypass directory traversal, but filters that
+    /// apply to the files themselves---such as the type, size and
+    /// modification time filters---are still applied here, mirroring what
+    /// the directory traversal would do for the same files.
+    fn files_from(&self) -> Result<Option<Vec<PathBuf>>> {
+        let (list_path, delim) =
+            if let Some(path) = self.value_of_os("files-from") {
+                (path, b'\n')
+            } else if let Some(path) = self.value_of_os("files-from0") {
+                (path, b'\x00')
+            } else {
+                return Ok(None);
+            };
+        let data = if list_path == "-" {
+            let mut data = vec![];
+            io::stdin().read_to_end(&mut data).map_err(|err| {
+                format!("failed to read file list from stdin: {}", err)
+            })?;
+            data
+        } else {
+            fs::read(list_path).map_err(|err| {
+                format!("{}: {}", Path::new(list_path).display(), err)
+            })?
+        };
+
+        let types = self.types()?;
+        let max_filesize = self.max_file_size()?;
+        let newer_than = self.newer_than()?;
+        let older_than = self.older_than()?;
+        let needs_metadata = !types.is_empty()
+            || max_filesize.is_some()
+            || newer_than.is_some()
+            || older_than.is_some();
+        let mut paths = vec![];
+        for (i, mut entry) in data.split(|&b| b == delim).enumerate() {
+            if delim == b'\n' && entry.last() == Some(&b'\r') {
+                entry = &entry[..entry.len() - 1];
+            }
+            if entry.is_empty() {
+                continue;
+            }
+            let path = match entry.to_os_str() {
+                Ok(osstr) => PathBuf::from(osstr),
+                Err(err) => {
+                    return Err(From::from(format!(
+                        "invalid path in file list entry {}: {}",
+                        i + 1,
+                        err,
+                    )));
+                }
+            };
+            if needs_metadata {
+                let md = path.metadata().ok();
+                let is_dir = md.as_ref().map_or(false, |md| md.is_dir());
+                if types.matched(&path, is_dir).is_ignore() {
+                    continue;
+                }
+                if !is_dir {
+                    if let Some(ref md) = md {
+                        if max_filesize.map_or(false, |max| md.len() > max) {
+                            continue;
+                        }
+                        if let Ok(mtime) = md.modified() {
+                            let too_old =
+                                newer_than.map_or(false, |t| mtime <= t);
+                            let too_new =
+                                older_than.map_or(false, |t| mtime >= t);
+                            if too_old || too_new {
+                                continue;
+                            }
+                        }
+                    }
+                }
+            }
+            paths.push(path);
+        }
+        Ok(Some(paths))
+    }
+
+    /// Parses the --flush-interval flag into a duration, if present.
+    fn flush_interval(&self) -> Result<Option<Duration>> {
+        let value = match self.value_of_lossy("flush-interval") {
+            None => return Ok(None),
+            Some(value) => value,
+        };
+        match parse_duration(&value) {
+            Some(duration) => Ok(Some(duration)),
+            None => Err(From::from(format!(
+                "invalid value for --flush-interval: {} \
+                 (expected a duration like 500ms or 2s)",
+                value,
+            ))),
+        }
+    }
+
+    /// Returns the kind of artifact to generate, if the --generate flag was
+    /// given.
+    fn generate(&self) -> Option<GenerateKind> {
+        match self.value_of_lossy("generate").as_deref() {
+            Some("complete-bash") => Some(GenerateKind::CompleteBash),
+            Some("complete-zsh") => Some(GenerateKind::CompleteZsh),
+            Some("complete-fish") => Some(GenerateKind::CompleteFish),
+            Some("complete-powershell") => {
+                Some(GenerateKind::CompletePowerShell)
+            }
+            Some("man") => Some(GenerateKind::Man),
+            _ => None,
+        }
+    }
+
+    /// Returns true if and only if search results should be grouped by
+    /// directory.
+    ///
+    /// Grouping only applies to the standard output format. The summary and
+    /// JSON formats are aggregate- or machine-oriented, so the flag is
+    /// ignored for them.
+    fn group_by_dir(&self) -> bool {
+        self.output_kind() == OutputKind::Standard
+            && self.value_of_lossy("group-by").as_deref() == Some("dir")
+    }
+
+    /// Returns the hyperlink format to use when printing paths.
+    ///
+    /// Without an explicit --hyperlink-format flag (or with the value
+    /// 'auto'), hyperlinks are enabled with the default format only when
+    /// stdout is a tty connected to a terminal emulator known to support
+    /// OSC 8 sequences.
+    fn hyperlink_format(&self) -> Result<HyperlinkFormat> {
+        let value = self.value_of_lossy("hyperlink-format");
+        match value.as_deref() {
+            None | Some("auto") => {
+                if cli::is_tty_stdout() && hyperlinks_supported() {
+                    Ok(HyperlinkFormat::parse("default").unwrap())
+                } else {
+                    Ok(HyperlinkFormat::none())
+                }
+            }
+            Some(template) => {
+                HyperlinkFormat::parse(template).map_err(From::from)
+            }
+        }
+    }
+
     /// Returns true if and only if matches should be grouped with file name
     /// headings.
     fn heading(&self) -> bool {
-        if self.is_present("no-heading") || self.is_present("vimgrep") {
+        if self.is_present("no-heading") || self.vimgrep() {
             false
         } else {
             cli::is_tty_stdout()
@@ -1147,6 +1828,12 @@ impl ArgMatches {
         paths == [Path::new("-")]
     }
 
+    /// Returns the display path to use for searches of stdin, if one was
+    /// given.
+    fn label(&self) -> Option<PathBuf> {
+        self.value_of_os("label").map(PathBuf::from)
+    }
+
     /// Returns true if and only if we should show line numbers.
     fn line_number(&self, paths: &[PathBuf]) -> bool {
         if self.output_kind() == OutputKind::Summary {
@@ -1158,6 +1845,12 @@ impl ArgMatches {
         if self.output_kind() == OutputKind::JSON {
             return true;
         }
+        // Like file names, line numbers are opt-in under --only-replace.
+        if self.is_present("only-replace") {
+            return self.is_present("line-number")
+                || self.is_present("column")
+                || self.vimgrep();
+        }
 
         // A few things can imply counting line numbers. In particular, we
         // generally want to show line numbers by default when printing to a
@@ -1167,7 +1860,7 @@ impl ArgMatches {
             || self.is_present("line-number")
             || self.is_present("column")
             || self.is_present("pretty")
-            || self.is_present("vimgrep")
+            || self.vimgrep()
     }
 
     /// The maximum number of columns allowed on each line.
@@ -1193,8 +1886,65 @@ impl ArgMatches {
         self.parse_human_readable_size("max-filesize")
     }
 
+    /// Parses the max-filesize-searched argument option into a byte count.
+    fn max_file_size_searched(&self) -> Result<Option<u64>> {
+        self.parse_human_readable_size("max-filesize-searched")
+    }
+
+    /// Parses the max-memory argument option into a byte count.
+    fn max_memory(&self) -> Result<Option<u64>> {
+        self.parse_human_readable_size("max-memory")
+    }
+
+    /// Computes the portion of the --max-memory budget available to each
+    /// search thread, if a budget was given.
+    ///
+    /// The budget is split evenly across search threads. An error is
+    /// returned if the budget is too small to give each thread a workable
+    /// share.
+    fn memory_budget_per_thread(&self) -> Result<Option<u64>> {
+        const MIN_PER_THREAD: u64 = 1 << 20;
+
+        let budget = match self.max_memory()? {
+            None => return Ok(None),
+            Some(budget) => budget,
+        };
+        let threads = cmp::max(1, self.threads()?) as u64;
+        let per_thread = budget / threads;
+        if per_thread < MIN_PER_THREAD {
+            return Err(From::from(format!(
+                "--max-memory budget of {} bytes is too small: each of {} \
+                 search threads needs at least {} bytes",
+                budget, threads, MIN_PER_THREAD,
+            )));
+        }
+        Ok(Some(per_thread))
+    }
+
+    /// The maximum number of matches permitted across all files searched.
+    fn max_total_matches(&self) -> Result<Option<u64>> {
+        Ok(self.usize_of("max-total-matches")?.map(|n| n as u64))
+    }
+
+    /// Returns the memory map access advice from the --mmap-advice flag.
+    fn mmap_advice(&self) -> Result<Option<MmapAdvice>> {
+        let advice = match self.value_of_lossy("mmap-advice") {
+            None => None,
+            Some(value) => match &*value {
+                "sequential" => Some(MmapAdvice::Sequential),
+                "willneed" => Some(MmapAdvice::WillNeed),
+                unk => {
+                    return Err(
+                        format!("invalid mmap advice: '{}'", unk).into()
+                    )
+                }
+            },
+        };
+        Ok(advice)
+    }
+
     /// Returns whether we should attempt to use memory maps or not.
-    fn mmap_choice(&self, paths: &[PathBuf]) -> MmapChoice {
+    fn mmap_choice(&self, paths: &[PathBuf]) -> Result<MmapChoice> {
         // SAFETY: Memory maps are difficult to impossible to encapsulate
         // safely in a portable way that doesn't simultaneously negate some of
         // the benfits of using memory maps. For ripgrep's use, we never mutate
@@ -1203,9 +1953,12 @@ impl ArgMatches {
         // speaking, the worst thing that can happen is a SIGBUS (if the
         // underlying file is truncated while reading it), which will cause
         // ripgrep to abort. This reasoning should be treated as suspect.
-        let maybe = unsafe { MmapChoice::auto() };
+        let maybe = match self.mmap_advice()? {
+            None => unsafe { MmapChoice::auto() },
+            Some(advice) => unsafe { MmapChoice::auto_with_advice(advice) },
+        };
         let never = MmapChoice::never();
-        if self.is_present("no-mmap") {
+        Ok(if self.is_present("no-mmap") {
             never
         } else if self.is_present("mmap") {
             maybe
@@ -1215,7 +1968,12 @@ impl ArgMatches {
             maybe
         } else {
             never
-        }
+        })
+    }
+
+    /// Parses the newer-than argument option into a point in time.
+    fn newer_than(&self) -> Result<Option<SystemTime>> {
+        self.parse_time_filter("newer-than")
     }
 
     /// Returns true if ignore files should be ignored.
@@ -1256,6 +2014,18 @@ impl ArgMatches {
         self.is_present("no-ignore-vcs") || self.no_ignore()
     }
 
+    /// Parses the older-than argument option into a point in time.
+    fn older_than(&self) -> Result<Option<SystemTime>> {
+        self.parse_time_filter("older-than")
+    }
+
+    /// Returns true if and only if only the matching parts of each line
+    /// should be printed. --only-replace implies this, since it prints only
+    /// the expanded replacement for each match.
+    fn only_matching(&self) -> bool {
+        self.is_present("only-matching") || self.is_present("only-replace")
+    }
+
     /// Determine the type of output we should produce.
     fn output_kind(&self) -> OutputKind {
         if self.is_present("quiet") {
@@ -1270,6 +2040,8 @@ impl ArgMatches {
         let (count, count_matches) = self.counts();
         let summary = count
             || count_matches
+            || self.is_present("count-lines-and-matches")
+            || self.is_present("count-total")
             || self.is_present("files-with-matches")
             || self.is_present("files-without-match");
         if summary {
@@ -1283,7 +2055,11 @@ impl ArgMatches {
     fn overrides(&self) -> Result<Override> {
         let globs = self.values_of_lossy_vec("glob");
         let iglobs = self.values_of_lossy_vec("iglob");
-        if globs.is_empty() && iglobs.is_empty() {
+        let path_configs = self.path_configs()?;
+        if globs.is_empty()
+            && iglobs.is_empty()
+            && path_configs.iter().all(|c| c.globs.is_empty())
+        {
             return Ok(Override::empty());
         }
 
@@ -1295,6 +2071,19 @@ impl ArgMatches {
         for glob in globs {
             builder.add(&glob)?;
         }
+        // Globs from --path-config only apply beneath their configured path,
+        // so scope them by prefixing the path and a recursive wildcard.
+        for config in path_configs {
+            for glob in config.globs {
+                let (neg, rest) = match glob.strip_prefix('!') {
+                    Some(rest) => ("!", rest),
+                    None => ("", glob.as_str()),
+                };
+                let scoped =
+                    format!("{}{}/**/{}", neg, config.path.display(), rest);
+                builder.add(&scoped)?;
+            }
+        }
         // This only enables case insensitivity for subsequent globs.
         builder.case_insensitive(true).unwrap();
         for glob in iglobs {
@@ -1303,6 +2092,66 @@ impl ArgMatches {
         Ok(builder.build()?)
     }
 
+    /// Parses the --path-config flag into a set of per-path traversal
+    /// configurations. Multiple occurrences naming the same path are merged
+    /// into a single configuration.
+    fn path_configs(&self) -> Result<Vec<PathConfig>> {
+        let mut configs: Vec<PathConfig> = vec![];
+        for spec in self.values_of_lossy_vec("path-config") {
+            let i = spec.find(":--").ok_or_else(|| {
+                format!(
+                    "invalid --path-config: {} \
+                     (expected PATH:--OPTION=VALUE)",
+                    spec,
+                )
+            })?;
+            let (path, rest) = (&spec[..i], &spec[i + 3..]);
+            let (option, value) = rest.split_once('=').ok_or_else(|| {
+                format!(
+                    "invalid --path-config: {} \
+                     (expected PATH:--OPTION=VALUE)",
+                    spec,
+                )
+            })?;
+
+            let path = Path::new(path);
+            let path = path.strip_prefix("./").unwrap_or(path);
+            let config = match configs.iter_mut().find(|c| c.path == path) {
+                Some(config) => config,
+                None => {
+                    configs.push(PathConfig {
+                        path: path.to_path_buf(),
+                        max_depth: None,
+                        globs: vec![],
+                    });
+                    configs.last_mut().unwrap()
+                }
+            };
+            match option {
+                "max-depth" => {
+                    config.max_depth = Some(value.parse().map_err(|err| {
+                        format!(
+                            "invalid --path-config max-depth value \
+                                 {}: {}",
+                            value, err,
+                        )
+                    })?);
+                }
+                "glob" => {
+                    config.globs.push(value.to_string());
+                }
+                unknown => {
+                    return Err(From::from(format!(
+                        "invalid --path-config option: {} \
+                         (expected max-depth or glob)",
+                        unknown,
+                    )));
+                }
+            }
+        }
+        Ok(configs)
+    }
+
     /// Return all file paths that ripgrep should search.
     ///
     /// If no paths were given, then this returns an empty list.
@@ -1311,11 +2160,12 @@ impl ArgMatches {
             None => vec![],
             Some(paths) => paths.map(|p| Path::new(p).to_path_buf()).collect(),
         };
-        // If --file, --files or --regexp is given, then the first path is
-        // always in `pattern`.
+        // If --file, --files, --regexp or --server is given, then the first
+        // path is always in `pattern`.
         if self.is_present("file")
             || self.is_present("files")
             || self.is_present("regexp")
+            || self.is_present("server")
         {
             if let Some(path) = self.value_of_os("pattern") {
                 paths.insert(0, Path::new(path).to_path_buf());
@@ -1335,7 +2185,10 @@ impl ArgMatches {
             || (self.is_present("file") && file_is_stdin)
             || self.is_present("files")
             || self.is_present("type-list")
-            || self.is_present("pcre2-version");
+            || self.is_present("pcre2-version")
+            // In server mode, stdin carries the requests, so it can never
+            // be a search target.
+            || self.is_present("server");
         if search_cwd {
             Path::new("./").to_path_buf()
         } else {
@@ -1343,6 +2196,20 @@ impl ArgMatches {
         }
     }
 
+    /// Returns the transform to apply to paths before printing them in
+    /// files mode.
+    fn path_format(&self) -> Result<PathTransform> {
+        match self.value_of_lossy("path-format").as_deref() {
+            None => Ok(PathTransform::None),
+            Some("relative") => Ok(PathTransform::Relative),
+            Some("absolute") => Ok(PathTransform::Absolute),
+            Some("canonical") => Ok(PathTransform::Canonical),
+            Some(unk) => {
+                Err(From::from(format!("invalid path format: '{}'", unk)))
+            }
+        }
+    }
+
     /// Returns the unescaped path separator as a single byte, if one exists.
     ///
     /// If the provided path separator is more than a single byte, then an
@@ -1403,7 +2270,10 @@ impl ArgMatches {
     ///
     /// If any pattern is invalid UTF-8, then an error is returned.
     fn patterns(&self) -> Result<Vec<String>> {
-        if self.is_present("files") || self.is_present("type-list") {
+        if self.is_present("files")
+            || self.is_present("type-list")
+            || self.is_present("server")
+        {
             return Ok(vec![]);
         }
         let mut pats = vec![];
@@ -1422,18 +2292,25 @@ impl ArgMatches {
             }
         }
         if let Some(paths) = self.values_of_os("file") {
+            // With -F/--fixed-strings, every line is a literal, so the
+            // per-pattern flag prefix syntax doesn't apply.
+            let expand = !self.is_present("fixed-strings");
+            let from_line = |p: String| {
+                let p = if expand { expand_pattern_flags(p) } else { p };
+                self.pattern_from_string(p)
+            };
             for path in paths {
                 if path == "-" {
                     pats.extend(
                         cli::patterns_from_stdin()?
                             .into_iter()
-                            .map(|p| self.pattern_from_string(p)),
+                            .map(&from_line),
                     );
                 } else {
                     pats.extend(
                         cli::patterns_from_path(path)?
                             .into_iter()
-                            .map(|p| self.pattern_from_string(p)),
+                            .map(&from_line),
                     );
                 }
             }
@@ -1482,6 +2359,17 @@ impl ArgMatches {
         Some(Path::new(path).to_path_buf())
     }
 
+    /// Opens the preprocessor output cache, if one was requested with the
+    /// --pre-cache flag.
+    fn preprocessor_cache(&self) -> Result<Option<PreprocessorCache>> {
+        let dir = match self.value_of_os("pre-cache") {
+            None => return Ok(None),
+            Some(dir) => PathBuf::from(dir),
+        };
+        let limit = self.parse_human_readable_size("pre-cache-limit")?;
+        Ok(Some(PreprocessorCache::new(dir, limit)?))
+    }
+
     /// Builds the set of globs for filtering files to apply to the --pre
     /// flag. If no --pre-globs are available, then this always returns an
     /// empty set of globs.
@@ -1504,8 +2392,13 @@ impl ArgMatches {
     }
 
     /// Returns the replacement string as UTF-8 bytes if it exists.
+    ///
+    /// The template may come from either --replace or --only-replace. The
+    /// two flags conflict, so at most one of them can be present.
     fn replacement(&self) -> Option<Vec<u8>> {
-        self.value_of_lossy("replace").map(|s| s.into_bytes())
+        self.value_of_lossy("only-replace")
+            .or_else(|| self.value_of_lossy("replace"))
+            .map(|s| s.into_bytes())
     }
 
     /// Returns the sorting criteria based on command line parameters.
@@ -1531,7 +2424,26 @@ impl ArgMatches {
     /// command line arguments via the --stats flag, but this can also be
     /// enabled implicitly via the output format, e.g., for JSON Lines.
     fn stats(&self) -> bool {
-        self.output_kind() == OutputKind::JSON || self.is_present("stats")
+        self.output_kind() == OutputKind::JSON
+            || self.is_present("stats")
+            || self.is_present("count-total")
+    }
+
+    /// Returns true if and only if aggregate statistics should be emitted
+    /// as JSON rather than as human readable text.
+    fn stats_format_json(&self) -> bool {
+        self.value_of_lossy("stats-format").map_or(false, |f| f == "json")
+    }
+
+    /// Returns the number of consecutive non-matching lines after a matching
+    /// line at which to stop searching, if present.
+    ///
+    /// A value of `0` disables this behavior, just like its absence.
+    fn stop_after_gap(&self) -> Result<Option<usize>> {
+        match self.usize_of("stop-after-gap")? {
+            None | Some(0) => Ok(None),
+            Some(gap) => Ok(Some(gap)),
+        }
     }
 
     /// When the output format is `Summary`, this returns the type of summary
@@ -1540,8 +2452,12 @@ impl ArgMatches {
     /// This returns `None` if the output format is not `Summary`.
     fn summary_kind(&self) -> Option<SummaryKind> {
         let (count, count_matches) = self.counts();
-        if self.is_present("quiet") {
+        if self.is_present("quiet") || self.is_present("count-total") {
+            // A total count suppresses all per-file output, so the Quiet
+            // summary printer is used purely for its statistics tracking.
             Some(SummaryKind::Quiet)
+        } else if self.is_present("count-lines-and-matches") {
+            Some(SummaryKind::CountLinesAndMatches)
         } else if count_matches {
             Some(SummaryKind::CountMatches)
         } else if count {
@@ -1557,7 +2473,24 @@ impl ArgMatches {
 
     /// Return the number of threads that should be used for parallelism.
     fn threads(&self) -> Result<usize> {
-        if self.sort_by()?.kind != SortByKind::None {
+        // Sorting by path still admits a parallel search, since the
+        // traversal itself produces subjects in sorted order and a reorder
+        // buffer puts the results back in sequence. Stat-based sorting
+        // criteria (e.g., modification time) also admit a parallel search
+        // by sorting the collected subjects before searching them, but only
+        // when the caller has explicitly asked for threads, since otherwise
+        // a sequential search preserves ripgrep's historical behavior.
+        match self.sort_by()?.kind {
+            SortByKind::None | SortByKind::Path => {}
+            _ => {
+                if self.usize_of("threads")?.unwrap_or(0) <= 1 {
+                    return Ok(1);
+                }
+            }
+        }
+        // Grouping results by directory requires printing them in directory
+        // order, which is handled by a sequential search.
+        if self.group_by_dir() {
             return Ok(1);
         }
         let threads = self.usize_of("threads")?.unwrap_or(0);
@@ -1566,10 +2499,33 @@ impl ArgMatches {
         Ok(if threads == 0 { cmp::min(12, available) } else { threads })
     }
 
+    /// Parses the --timeout-per-file flag into a duration, if present.
+    fn timeout_per_file(&self) -> Result<Option<Duration>> {
+        let value = match self.value_of_lossy("timeout-per-file") {
+            None => return Ok(None),
+            Some(value) => value,
+        };
+        match parse_duration(&value) {
+            Some(duration) => Ok(Some(duration)),
+            None => Err(From::from(format!(
+                "invalid value for --timeout-per-file: {} \
+                 (expected a duration like 500ms or 2s)",
+                value,
+            ))),
+        }
+    }
+
     /// Builds a file type matcher from the command line flags.
     fn types(&self) -> Result<Types> {
         let mut builder = TypesBuilder::new();
         builder.add_defaults();
+        // Project-local definitions load before any flags, so that
+        // definitions given on the command line take precedence.
+        if !self.is_present("no-rgtypes") {
+            if let Some(path) = find_rgtypes() {
+                builder.add_def_file(&path)?;
+            }
+        }
         for ty in self.values_of_lossy_vec("type-clear") {
             builder.clear(&ty);
         }
@@ -1597,19 +2553,37 @@ impl ArgMatches {
         !(self.is_present("no-unicode") || self.is_present("no-pcre2-unicode"))
     }
 
+    /// Returns true if and only if vimgrep style per match output was
+    /// requested, with or without match end positions.
+    fn vimgrep(&self) -> bool {
+        self.is_present("vimgrep") || self.is_present("vimgrep-ranges")
+    }
+
     /// Returns true if and only if file names containing each match should
     /// be emitted.
     fn with_filename(&self, paths: &[PathBuf]) -> bool {
         if self.is_present("no-filename") {
             false
+        } else if self.is_present("only-replace") {
+            // --only-replace emits just the expanded template, so that its
+            // output can be consumed without any trimming. A file name is
+            // only shown when explicitly requested.
+            self.is_present("with-filename")
         } else {
             let path_stdin = Path::new("-");
             self.is_present("with-filename")
-                || self.is_present("vimgrep")
+                || self.is_present("label")
+                || self.vimgrep()
                 || paths.len() > 1
                 || paths
                     .get(0)
                     .map_or(false, |p| p != path_stdin && p.is_dir())
+                // A single archive expands into many virtual paths, so the
+                // file name is needed to tell results apart.
+                || (self.is_present("search-archives")
+                    && paths
+                        .get(0)
+                        .map_or(false, |p| cli::ArchiveReader::is_archive(p)))
         }
     }
 }
@@ -1658,6 +2632,27 @@ impl ArgMatches {
         };
         Ok(Some(cli::parse_human_readable_size(&size)?))
     }
+
+    /// Parses an argument of the form `[0-9]+(smhdw)?` or `YYYY-MM-DD` into
+    /// a point in time. Durations are interpreted relative to now and dates
+    /// as midnight UTC.
+    ///
+    /// If the aforementioned format is not recognized, then this returns an
+    /// error.
+    fn parse_time_filter(&self, arg_name: &str) -> Result<Option<SystemTime>> {
+        let value = match self.value_of_lossy(arg_name) {
+            None => return Ok(None),
+            Some(value) => value,
+        };
+        match parse_time(&value) {
+            Some(time) => Ok(Some(time)),
+            None => Err(From::from(format!(
+                "invalid value for --{}: {} \
+                 (expected a duration like 2d or a date like 2021-01-01)",
+                arg_name, value,
+            ))),
+        }
+    }
 }
 
 /// The following methods mostly dispatch to the underlying clap methods
@@ -1757,6 +2752,168 @@ fn u64_to_usize(arg_name: &str, value: Option<u64>) -> Result<Option<usize>> {
     }
 }
 
+/// Expand an optional per-pattern flag prefix on a pattern read from a
+/// -f/--file pattern file.
+///
+/// A prefix consists of one or more of the letters `i` (case insensitive),
+/// `w` (word match) or `x` (literal), followed by a colon. The prefix is
+/// stripped and the rest of the line is wrapped accordingly. A line starting
+/// with a colon has the colon stripped and the rest used verbatim, which
+/// serves as an escape hatch for patterns that would otherwise look like a
+/// prefix. Lines that don't match the prefix syntax are used as-is.
+fn expand_pattern_flags(pat: String) -> String {
+    if let Some(rest) = pat.strip_prefix(':') {
+        return rest.to_string();
+    }
+    let colon = match pat.find(':') {
+        None => return pat,
+        Some(i) => i,
+    };
+    let flags = &pat[..colon];
+    if flags.is_empty()
+        || !flags.chars().all(|c| c == 'i' || c == 'w' || c == 'x')
+    {
+        return pat;
+    }
+    let mut expanded = pat[colon + 1..].to_string();
+    if flags.contains('x') {
+        expanded = escape_literal(&expanded);
+    }
+    if flags.contains('w') {
+        expanded = format!(r"\b(?:{})\b", expanded);
+    }
+    if flags.contains('i') {
+        expanded = format!("(?i:{})", expanded);
+    }
+    expanded
+}
+
+/// Escape a string so that a regex engine interprets it literally.
+///
+/// This is used for the `x:` pattern file prefix, where the escaping must
+/// happen before other prefix flags wrap the pattern, so it can't be deferred
+/// to the matcher builder like -F/--fixed-strings is.
+fn escape_literal(pat: &str) -> String {
+    let mut escaped = String::with_capacity(pat.len());
+    for ch in pat.chars() {
+        if ch.is_ascii_punctuation() {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+/// Parse a human readable point in time.
+///
+/// This accepts either a duration relative to now, written as an integer
+/// followed by one of the suffixes `s`, `m`, `h`, `d` or `w`, or a date of
+/// the form `YYYY-MM-DD`, which is interpreted as midnight UTC on that day.
+///
+/// If the value could not be parsed, then this returns `None`.
+fn parse_time(value: &str) -> Option<SystemTime> {
+    if let Some(date) = parse_date(value) {
+        return Some(date);
+    }
+    let suffix_at = value.find(|c: char| !c.is_ascii_digit())?;
+    let number: u64 = value[..suffix_at].parse().ok()?;
+    let seconds = match &value[suffix_at..] {
+        "s" => number,
+        "m" => number.checked_mul(60)?,
+        "h" => number.checked_mul(3_600)?,
+        "d" => number.checked_mul(86_400)?,
+        "w" => number.checked_mul(604_800)?,
+        _ => return None,
+    };
+    SystemTime::now().checked_sub(Duration::from_secs(seconds))
+}
+
+/// Returns true if the environment suggests that the terminal emulator on
+/// the other end of stdout understands OSC 8 hyperlinks.
+fn hyperlinks_supported() -> bool {
+    if let Ok(program) = env::var("TERM_PROGRAM") {
+        match &*program {
+            "iTerm.app" | "WezTerm" | "vscode" | "Hyper" => return true,
+            _ => {}
+        }
+    }
+    // Windows Terminal doesn't set TERM_PROGRAM, but does set WT_SESSION.
+    if env::var_os("WT_SESSION").is_some() {
+        return true;
+    }
+    // VTE based terminals (GNOME Terminal and friends) support OSC 8 since
+    // version 0.50.
+    if let Ok(version) = env::var("VTE_VERSION") {
+        if version.parse().map_or(false, |v: u32| v >= 5000) {
+            return true;
+        }
+    }
+    env::var_os("KONSOLE_VERSION").is_some()
+}
+
+/// Looks for a `.rgtypes` file in the current directory or an ancestor of
+/// it.
+///
+/// The search stops at the root of the enclosing repository, i.e., the first
+/// ancestor that contains a `.git` entry, so that one project's type
+/// definitions don't leak into searches of another project.
+fn find_rgtypes() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    for dir in cwd.ancestors() {
+        let candidate = dir.join(".rgtypes");
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if dir.join(".git").exists() {
+            return None;
+        }
+    }
+    None
+}
+
+/// Parse a duration of the form `N`, `Nms` or `Ns` into a `Duration`.
+///
+/// A bare number is interpreted as milliseconds. If the value is not a valid
+/// duration, then this returns `None`.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let suffix_at =
+        value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let number: u64 = value[..suffix_at].parse().ok()?;
+    match &value[suffix_at..] {
+        "" | "ms" => Some(Duration::from_millis(number)),
+        "s" => Some(Duration::from_secs(number)),
+        _ => None,
+    }
+}
+
+/// Parse a date of the form `YYYY-MM-DD` into the corresponding midnight UTC.
+///
+/// If the value is not a valid date in that format, then this returns `None`.
+fn parse_date(value: &str) -> Option<SystemTime> {
+    let mut pieces = value.splitn(3, '-');
+    let year: i64 = pieces.next()?.parse().ok()?;
+    let month: i64 = pieces.next()?.parse().ok()?;
+    let day: i64 = pieces.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // Convert a proleptic Gregorian calendar date to days since the Unix
+    // epoch. See: http://howardhinnant.github.io/date_algorithms.html
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let yoe = year - era * 400;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5
+        + day
+        - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719_468;
+    if days < 0 {
+        return None;
+    }
+    let seconds = (days as u64).checked_mul(86_400)?;
+    SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(seconds))
+}
+
 /// Sorts by an optional parameter.
 //
 /// If parameter is found to be `None`, both entries compare equal.
@@ -1836,3 +2993,11 @@ where
         .map(|s| (s.path().metadata().and_then(|m| get_time(&m)).ok(), s))
         .collect()
 }
+
+/// Tries to assign a file size to every `Subject` in the vector to help with
+/// sorting Subjects by size.
+fn load_sizes(
+    subjects: impl Iterator<Item = Subject>,
+) -> Vec<(Option<u64>, Subject)> {
+    subjects.map(|s| (s.path().metadata().map(|m| m.len()).ok(), s)).collect()
+}
@@ -0,0 +1,83 @@
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Persists search progress so that an interrupted scan can be resumed.
+///
+/// A checkpoint file is a list of file paths, one per line, that have been
+/// completely searched. When resuming, paths already present in the file are
+/// skipped, and as the search proceeds, newly completed paths are appended.
+/// Pointing --checkpoint and --resume at the same file therefore continues
+/// extending it across runs.
+///
+/// Only fully searched files are recorded, so a file that was being searched
+/// when a scan was interrupted is searched again (in full) on resume.
+#[derive(Debug)]
+pub struct Checkpoint {
+    /// The paths completed in previous runs.
+    done: HashSet<PathBuf>,
+    /// Where to append newly completed paths, if recording was requested.
+    wtr: Option<Mutex<File>>,
+}
+
+impl Checkpoint {
+    /// Create a new checkpoint.
+    ///
+    /// When `resume` is given, previously completed paths are loaded from it.
+    /// A missing resume file is not an error; it just means a fresh scan.
+    /// When `record` is given, completed paths are appended to it as the
+    /// search proceeds.
+    pub fn new(
+        record: Option<&Path>,
+        resume: Option<&Path>,
+    ) -> io::Result<Checkpoint> {
+        let mut done = HashSet::new();
+        if let Some(path) = resume {
+            match File::open(path) {
+                Ok(file) => {
+                    for line in BufReader::new(file).lines() {
+                        let line = line?;
+                        if !line.is_empty() {
+                            done.insert(PathBuf::from(line));
+                        }
+                    }
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err),
+            }
+        }
+        let wtr = match record {
+            None => None,
+            Some(path) => {
+                let file =
+                    OpenOptions::new().create(true).append(true).open(path)?;
+                Some(Mutex::new(file))
+            }
+        };
+        Ok(Checkpoint { done, wtr })
+    }
+
+    /// Returns true if and only if the given path was completely searched in
+    /// a previous run.
+    pub fn is_done(&self, path: &Path) -> bool {
+        self.done.contains(path)
+    }
+
+    /// Record the given path as completely searched.
+    ///
+    /// The path is written to the checkpoint file immediately, so that
+    /// progress survives an abrupt interruption. Write errors are reported
+    /// but do not stop the search.
+    pub fn record(&self, path: &Path) {
+        let wtr = match self.wtr {
+            Some(ref wtr) => wtr,
+            None => return,
+        };
+        let mut file = wtr.lock().unwrap();
+        if let Err(err) = writeln!(file, "{}", path.display()) {
+            err_file_message!(path, err);
+        }
+    }
+}
@@ -15,7 +15,12 @@ use log;
 use crate::Result;
 
 /// Return a sequence of arguments derived from ripgrep rc configuration files.
-pub fn args() -> Vec<OsString> {
+///
+/// When a profile name is given, arguments from the config file's
+/// corresponding `[profile name]` section are included in addition to the
+/// arguments preceding any profile section. Otherwise, all profile sections
+/// are ignored.
+pub fn args(profile: Option<&str>) -> Vec<OsString> {
     let config_path = match env::var_os("RIPGREP_CONFIG_PATH") {
         None => return vec![],
         Some(config_path) => {
@@ -25,7 +30,7 @@ pub fn args() -> Vec<OsString> {
             PathBuf::from(config_path)
         }
     };
-    let (args, errs) = match parse(&config_path) {
+    let (args, errs) = match parse(&config_path, profile) {
         Ok((args, errs)) => (args, errs),
         Err(err) => {
             message!(
@@ -58,10 +63,11 @@ pub fn args() -> Vec<OsString> {
 /// for each line in addition to successfully parsed arguments.
 fn parse<P: AsRef<Path>>(
     path: P,
+    profile: Option<&str>,
 ) -> Result<(Vec<OsString>, Vec<Box<dyn Error>>)> {
     let path = path.as_ref();
     match File::open(&path) {
-        Ok(file) => parse_reader(file),
+        Ok(file) => parse_reader(file, profile),
         Err(err) => Err(From::from(format!("{}: {}", path.display(), err))),
     }
 }
@@ -79,10 +85,15 @@ fn parse<P: AsRef<Path>>(
 /// in addition to successfully parsed arguments.
 fn parse_reader<R: io::Read>(
     rdr: R,
+    profile: Option<&str>,
 ) -> Result<(Vec<OsString>, Vec<Box<dyn Error>>)> {
     let mut bufrdr = io::BufReader::new(rdr);
     let (mut args, mut errs) = (vec![], vec![]);
     let mut line_number = 0;
+    // Arguments preceding any profile section always apply. Arguments in a
+    // profile section only apply when that profile has been selected.
+    let mut selected = true;
+    let mut profile_found = false;
     bufrdr.for_byte_line_with_terminator(|line| {
         line_number += 1;
 
@@ -90,6 +101,29 @@ fn parse_reader<R: io::Read>(
         if line.is_empty() || line[0] == b'#' {
             return Ok(true);
         }
+        if line[0] == b'[' && line[line.len() - 1] == b']' {
+            match parse_profile_name(&line[1..line.len() - 1]) {
+                Some(name) => {
+                    selected = profile == Some(name);
+                    profile_found = profile_found || selected;
+                }
+                None => {
+                    errs.push(
+                        format!(
+                            "{}: invalid profile section (expected \
+                             '[profile name]' or '[profile.name]')",
+                            line_number,
+                        )
+                        .into(),
+                    );
+                    selected = false;
+                }
+            }
+            return Ok(true);
+        }
+        if !selected {
+            return Ok(true);
+        }
         match line.to_os_str() {
             Ok(osstr) => {
                 args.push(osstr.to_os_string());
@@ -100,9 +134,36 @@ fn parse_reader<R: io::Read>(
         }
         Ok(true)
     })?;
+    if let Some(name) = profile {
+        if !profile_found {
+            errs.push(format!("profile '{}' not found", name).into());
+        }
+    }
     Ok((args, errs))
 }
 
+/// Parse the name out of the contents of a profile section header, i.e., the
+/// text between the brackets in `[profile name]` or `[profile.name]`.
+///
+/// If the contents are not a valid profile header, then `None` is returned.
+fn parse_profile_name(inner: &[u8]) -> Option<&str> {
+    let inner = inner.trim();
+    let rest = inner.strip_prefix(b"profile")?;
+    // The name must be separated from the 'profile' keyword by either a dot
+    // or whitespace. Anything else (e.g., '[profiles]') is not a profile
+    // section.
+    let rest = match rest.first() {
+        Some(&b'.') => &rest[1..],
+        Some(&b) if b.is_ascii_whitespace() => rest,
+        _ => return None,
+    };
+    let name = rest.trim();
+    if name.is_empty() {
+        return None;
+    }
+    name.to_str().ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::parse_reader;
@@ -121,6 +182,7 @@ mod tests {
    # --bar
 --foo
 "[..],
+            None,
         )
         .unwrap();
         assert!(errs.is_empty());
@@ -129,6 +191,62 @@ mod tests {
         assert_eq!(args, vec!["--context=0", "--smart-case", "-u", "--foo",]);
     }
 
+    #[test]
+    fn profiles() {
+        const CONFIG: &[u8] = b"\
+--smart-case
+
+[profile work]
+--glob=!vendor
+--context=3
+
+[profile.docs]
+--type=md
+";
+        let parse = |profile| {
+            let (args, errs) = parse_reader(CONFIG, profile).unwrap();
+            let args: Vec<String> =
+                args.into_iter().map(|s| s.into_string().unwrap()).collect();
+            (args, errs)
+        };
+
+        let (args, errs) = parse(None);
+        assert!(errs.is_empty());
+        assert_eq!(args, vec!["--smart-case"]);
+
+        let (args, errs) = parse(Some("work"));
+        assert!(errs.is_empty());
+        assert_eq!(
+            args,
+            vec!["--smart-case", "--glob=!vendor", "--context=3"]
+        );
+
+        let (args, errs) = parse(Some("docs"));
+        assert!(errs.is_empty());
+        assert_eq!(args, vec!["--smart-case", "--type=md"]);
+
+        let (args, errs) = parse(Some("nope"));
+        assert_eq!(errs.len(), 1);
+        assert_eq!(args, vec!["--smart-case"]);
+    }
+
+    #[test]
+    fn profile_invalid_section() {
+        let (args, errs) = parse_reader(
+            &b"\
+--smart-case
+[not a profile]
+--foo
+"[..],
+            None,
+        )
+        .unwrap();
+        assert_eq!(errs.len(), 1);
+        let args: Vec<String> =
+            args.into_iter().map(|s| s.into_string().unwrap()).collect();
+        assert_eq!(args, vec!["--smart-case"]);
+    }
+
     // We test that we can handle invalid UTF-8 on Unix-like systems.
     #[test]
     #[cfg(unix)]
@@ -141,6 +259,7 @@ quux
 foo\xFFbar
 baz
 "[..],
+            None,
         )
         .unwrap();
         assert!(errs.is_empty());
@@ -164,6 +283,7 @@ quux
 foo\xFFbar
 baz
 "[..],
+            None,
         )
         .unwrap();
         assert_eq!(errs.len(), 1);
@@ -0,0 +1,60 @@
+// This module provides a process wide cancellation token that is set when an
+// interrupt (Ctrl-C) is received. The token is handed to the directory
+// walkers and searchers, which check it periodically, so that an interrupt
+// during a deep traversal or a search of a huge file winds down quickly and
+// cleanly (flushing any output and statistics gathered so far) instead of
+// waiting for the current file to finish.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+/// The token observed by walkers and searchers. It is set by the signal
+/// handler installed via `install`.
+static TOKEN: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+// We bind the C standard `signal` routine directly instead of pulling in a
+// platform dependency. Both the constants below are fixed by every C runtime
+// we support: SIGINT is 2 on POSIX and Windows, and SIG_DFL is the null
+// handler.
+const SIGINT: i32 = 2;
+const SIG_DFL: usize = 0;
+
+extern "C" {
+    fn signal(signum: i32, handler: usize) -> usize;
+}
+
+extern "C" fn handle(_: i32) {
+    // Only async-signal-safe operations are permitted here. Atomic stores
+    // and re-registering a signal handler both qualify.
+    if let Some(token) = TOKEN.get() {
+        token.store(true, Ordering::SeqCst);
+    }
+    // Restore the default handler so that a second interrupt terminates the
+    // process immediately, e.g., when shutdown stalls on a blocked read.
+    unsafe {
+        signal(SIGINT, SIG_DFL);
+    }
+}
+
+/// Return the global cancellation token, creating it if necessary.
+///
+/// The token is always safe to hand out. It only ever becomes `true` when
+/// `install` has been called and an interrupt was received.
+pub fn token() -> Arc<AtomicBool> {
+    TOKEN.get_or_init(|| Arc::new(AtomicBool::new(false))).clone()
+}
+
+/// Returns true if an interrupt has been received.
+pub fn cancelled() -> bool {
+    TOKEN.get().map_or(false, |t| t.load(Ordering::SeqCst))
+}
+
+/// Install a signal handler that sets the cancellation token on the first
+/// interrupt received.
+pub fn install() {
+    // Make sure the token exists before the handler can run.
+    token();
+    unsafe {
+        signal(SIGINT, handle as extern "C" fn(i32) as usize);
+    }
+}
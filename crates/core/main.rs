@@ -2,9 +2,10 @@ use std::error;
 use std::io::{self, Write};
 use std::process;
 use std::sync::Mutex;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use ignore::WalkState;
+use termcolor::{Buffer, BufferWriter, ColorSpec, WriteColor};
 
 use args::Args;
 use subject::Subject;
@@ -14,11 +15,17 @@ mod messages;
 
 mod app;
 mod args;
+mod checkpoint;
 mod config;
+mod interrupt;
 mod logger;
 mod path_printer;
+mod precache;
+mod replace;
 mod search;
+mod server;
 mod subject;
+mod watch;
 
 // Since Rust no longer uses jemalloc by default, ripgrep will, by default,
 // use the system allocator. On Linux, this would normally be glibc's
@@ -55,15 +62,34 @@ fn main() {
 fn try_main(args: Args) -> Result<()> {
     use args::Command::*;
 
+    args.clear_preprocessor_cache()?;
+    // Wind down promptly and cleanly on Ctrl-C. The server and watch modes
+    // are exceptions: they rely on an interrupt to terminate their loops,
+    // so they keep the default behavior of exiting immediately.
+    if args.command() != Server && !args.watch() {
+        interrupt::install();
+    }
     let matched = match args.command() {
         Search => search(&args),
         SearchParallel => search_parallel(&args),
         SearchNever => Ok(false),
+        Bench => bench(&args),
+        WriteReplace => write_replace(&args),
+        Server => server::server(&args),
         Files => files(&args),
         FilesParallel => files_parallel(&args),
+        Explain => explain(&args),
         Types => types(&args),
         PCRE2Version => pcre2_version(&args),
+        Generate(kind) => generate(&args, kind),
     }?;
+    if args.watch() && args.command().is_search() {
+        watch::watch(&args)?;
+    }
+    if interrupt::cancelled() {
+        // The conventional exit code for death by SIGINT.
+        process::exit(130)
+    }
     if matched && (args.quiet() || !messages::errored()) {
         process::exit(0)
     } else if messages::errored() {
@@ -77,6 +103,10 @@ fn try_main(args: Args) -> Result<()> {
 /// steps through the file list (current directory by default) and searches
 /// each file sequentially.
 fn search(args: &Args) -> Result<bool> {
+    if args.group_by_dir() {
+        return search_grouped(args);
+    }
+
     /// The meat of the routine is here. This lets us call the same iteration
     /// code over each file regardless of whether we stream over the files
     /// as they're produced by the underlying directory traversal or whether
@@ -87,26 +117,49 @@ fn search(args: &Args) -> Result<bool> {
         started_at: std::time::Instant,
     ) -> Result<bool> {
         let quit_after_match = args.quit_after_match()?;
+        let mut match_budget = args.max_total_matches()?;
+        if match_budget == Some(0) {
+            return Ok(false);
+        }
         let mut stats = args.stats()?;
+        let checkpoint = args.checkpoint()?;
         let mut searcher = args.search_worker(args.stdout())?;
         let mut matched = false;
         let mut searched = false;
 
         for subject in subjects {
+            if match_budget == Some(0) {
+                break;
+            }
+            if let Some(ref checkpoint) = checkpoint {
+                if !subject.is_stdin() && checkpoint.is_done(subject.path()) {
+                    continue;
+                }
+            }
             searched = true;
             let search_result = match searcher.search(&subject) {
                 Ok(search_result) => search_result,
                 // A broken pipe means graceful termination.
                 Err(err) if err.kind() == io::ErrorKind::BrokenPipe => break,
                 Err(err) => {
-                    err_message!("{}: {}", subject.path().display(), err);
+                    err_file_message!(subject.path(), err);
                     continue;
                 }
             };
             matched |= search_result.has_match();
+            if let Some(ref mut budget) = match_budget {
+                let found =
+                    search_result.stats().map_or(0, |stats| stats.matches());
+                *budget = budget.saturating_sub(found);
+            }
             if let Some(ref mut stats) = stats {
                 *stats += search_result.stats().unwrap();
             }
+            if let Some(ref checkpoint) = checkpoint {
+                if !subject.is_stdin() {
+                    checkpoint.record(subject.path());
+                }
+            }
             if matched && quit_after_match {
                 break;
             }
@@ -115,9 +168,13 @@ fn search(args: &Args) -> Result<bool> {
             eprint_nothing_searched();
         }
         if let Some(ref stats) = stats {
-            let elapsed = Instant::now().duration_since(started_at);
-            // We don't care if we couldn't print this successfully.
-            let _ = searcher.print_stats(elapsed, stats);
+            if let Some(total) = args.count_total(stats) {
+                // We don't care if we couldn't print this successfully.
+                let _ = writeln!(args.stdout(), "{}", total);
+            } else {
+                let elapsed = Instant::now().duration_since(started_at);
+                let _ = searcher.print_stats(elapsed, stats);
+            }
         }
         Ok(matched)
     }
@@ -135,17 +192,166 @@ fn search(args: &Args) -> Result<bool> {
     }
 }
 
+/// The entry point for searching with --group-by dir. This collects and
+/// sorts all subjects so that files sharing a directory are adjacent, then
+/// searches them sequentially, buffering each file's results so that a
+/// heading can be printed when a directory produces its first match and a
+/// count can be printed once all of its files have been searched.
+fn search_grouped(args: &Args) -> Result<bool> {
+    use std::path::{Path, PathBuf};
+
+    fn print_heading(
+        bufwtr: &BufferWriter,
+        first: bool,
+        dir: &Path,
+    ) -> io::Result<()> {
+        let dir =
+            if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+        let mut buf = bufwtr.buffer();
+        if !first {
+            buf.write_all(b"\n")?;
+        }
+        writeln!(buf, "{}", dir.display())?;
+        bufwtr.print(&buf)
+    }
+
+    fn print_group_count(
+        bufwtr: &BufferWriter,
+        matches: u64,
+        files: u64,
+    ) -> io::Result<()> {
+        let mut buf = bufwtr.buffer();
+        writeln!(buf, "({} matches in {} files)", matches, files)?;
+        bufwtr.print(&buf)
+    }
+
+    let started_at = Instant::now();
+    let quit_after_match = args.quit_after_match()?;
+    let mut match_budget = args.max_total_matches()?;
+    if match_budget == Some(0) {
+        return Ok(false);
+    }
+    let mut stats = args.stats()?;
+    let checkpoint = args.checkpoint()?;
+    let subject_builder = args.subject_builder();
+    let mut subjects: Vec<Subject> = args
+        .walker()?
+        .filter_map(|result| subject_builder.build_from_result(result))
+        .collect();
+    subjects.sort_by(|a, b| {
+        (a.path().parent(), a.path()).cmp(&(b.path().parent(), b.path()))
+    });
+
+    let bufwtr = args.buffer_writer()?;
+    let mut searcher = args.search_worker(bufwtr.buffer())?;
+    let mut matched = false;
+    let searched = !subjects.is_empty();
+    let mut current_dir: Option<PathBuf> = None;
+    let (mut dir_matches, mut dir_files) = (0u64, 0u64);
+
+    'subjects: for subject in &subjects {
+        if match_budget == Some(0) {
+            break;
+        }
+        if let Some(ref checkpoint) = checkpoint {
+            if !subject.is_stdin() && checkpoint.is_done(subject.path()) {
+                continue;
+            }
+        }
+        searcher.printer().get_mut().clear();
+        let search_result = match searcher.search(subject) {
+            Ok(search_result) => search_result,
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => break,
+            Err(err) => {
+                err_file_message!(subject.path(), err);
+                continue;
+            }
+        };
+        let found = search_result.stats().map_or(0, |stats| stats.matches());
+        if search_result.has_match() {
+            matched = true;
+            let dir = subject.path().parent().unwrap_or(Path::new(""));
+            if current_dir.as_deref() != Some(dir) {
+                if current_dir.is_some()
+                    && print_group_count(&bufwtr, dir_matches, dir_files)
+                        .is_err()
+                {
+                    break;
+                }
+                let first = current_dir.is_none();
+                if print_heading(&bufwtr, first, dir).is_err() {
+                    break;
+                }
+                current_dir = Some(dir.to_path_buf());
+                dir_matches = 0;
+                dir_files = 0;
+            }
+            dir_files += 1;
+            dir_matches += found;
+            if let Err(err) = bufwtr.print(searcher.printer().get_mut()) {
+                // A broken pipe means graceful termination.
+                if err.kind() == io::ErrorKind::BrokenPipe {
+                    break 'subjects;
+                }
+                err_file_message!(subject.path(), err);
+            }
+        }
+        if let Some(ref mut budget) = match_budget {
+            *budget = budget.saturating_sub(found);
+        }
+        if let Some(ref mut stats) = stats {
+            *stats += search_result.stats().unwrap();
+        }
+        if let Some(ref checkpoint) = checkpoint {
+            if !subject.is_stdin() {
+                checkpoint.record(subject.path());
+            }
+        }
+        if matched && quit_after_match {
+            break;
+        }
+    }
+    if current_dir.is_some() {
+        let _ = print_group_count(&bufwtr, dir_matches, dir_files);
+    }
+    if args.using_default_path() && !searched {
+        eprint_nothing_searched();
+    }
+    if let Some(ref stats) = stats {
+        if let Some(total) = args.count_total(stats) {
+            // We don't care if we couldn't print this successfully.
+            let _ = writeln!(args.stdout(), "{}", total);
+        } else {
+            let elapsed = Instant::now().duration_since(started_at);
+            let mut searcher = args.search_worker(args.stdout())?;
+            let _ = searcher.print_stats(elapsed, stats);
+        }
+    }
+    Ok(matched)
+}
+
 /// The top-level entry point for multi-threaded search. The parallelism is
 /// itself achieved by the recursive directory traversal. All we need to do is
 /// feed it a worker for performing a search on each file.
 ///
-/// Requesting a sorted output from ripgrep (such as with `--sort path`) will
-/// automatically disable parallelism and hence sorting is not handled here.
+/// Requesting sorted output (such as with `--sort path` or, with an explicit
+/// thread count, `--sort modified`) is handled by `search_parallel_sorted`.
 fn search_parallel(args: &Args) -> Result<bool> {
-    use std::sync::atomic::AtomicBool;
     use std::sync::atomic::Ordering::SeqCst;
+    use std::sync::atomic::{AtomicBool, AtomicU64};
+
+    if args.needs_sorted_output() {
+        return search_parallel_sorted(args);
+    }
 
     let quit_after_match = args.quit_after_match()?;
+    let max_total_matches = args.max_total_matches()?;
+    if max_total_matches == Some(0) {
+        return Ok(false);
+    }
+    let match_budget = max_total_matches.map(AtomicU64::new);
+    let flush_interval = args.flush_interval()?;
+    let checkpoint = args.checkpoint()?;
     let started_at = Instant::now();
     let subject_builder = args.subject_builder();
     let bufwtr = args.buffer_writer()?;
@@ -157,9 +363,12 @@ fn search_parallel(args: &Args) -> Result<bool> {
         let bufwtr = &bufwtr;
         let stats = &stats;
         let matched = &matched;
+        let match_budget = &match_budget;
         let searched = &searched;
         let subject_builder = &subject_builder;
-        let mut searcher = match args.search_worker(bufwtr.buffer()) {
+        let checkpoint = &checkpoint;
+        let wtr = IntervalBuffer::new(bufwtr, flush_interval);
+        let mut searcher = match args.search_worker(wtr) {
             Ok(searcher) => searcher,
             Err(err) => {
                 searcher_err = Some(err);
@@ -172,31 +381,57 @@ fn search_parallel(args: &Args) -> Result<bool> {
                 Some(subject) => subject,
                 None => return WalkState::Continue,
             };
+            if let Some(ref budget) = *match_budget {
+                if budget.load(SeqCst) == 0 {
+                    return WalkState::Quit;
+                }
+            }
+            if let Some(ref checkpoint) = *checkpoint {
+                if !subject.is_stdin() && checkpoint.is_done(subject.path()) {
+                    return WalkState::Continue;
+                }
+            }
             searched.store(true, SeqCst);
             searcher.printer().get_mut().clear();
             let search_result = match searcher.search(&subject) {
                 Ok(search_result) => search_result,
                 Err(err) => {
-                    err_message!("{}: {}", subject.path().display(), err);
+                    err_file_message!(subject.path(), err);
                     return WalkState::Continue;
                 }
             };
             if search_result.has_match() {
                 matched.store(true, SeqCst);
             }
+            let mut budget_exhausted = false;
+            if let Some(ref budget) = *match_budget {
+                let found =
+                    search_result.stats().map_or(0, |stats| stats.matches());
+                let prev = budget
+                    .fetch_update(SeqCst, SeqCst, |n| {
+                        Some(n.saturating_sub(found))
+                    })
+                    .unwrap();
+                budget_exhausted = prev <= found;
+            }
             if let Some(ref locked_stats) = *stats {
                 let mut stats = locked_stats.lock().unwrap();
                 *stats += search_result.stats().unwrap();
             }
-            if let Err(err) = bufwtr.print(searcher.printer().get_mut()) {
+            if let Err(err) = searcher.printer().get_mut().print() {
                 // A broken pipe means graceful termination.
                 if err.kind() == io::ErrorKind::BrokenPipe {
                     return WalkState::Quit;
                 }
                 // Otherwise, we continue on our merry way.
-                err_message!("{}: {}", subject.path().display(), err);
+                err_file_message!(subject.path(), err);
+            }
+            if let Some(ref checkpoint) = *checkpoint {
+                if !subject.is_stdin() {
+                    checkpoint.record(subject.path());
+                }
             }
-            if matched.load(SeqCst) && quit_after_match {
+            if budget_exhausted || (matched.load(SeqCst) && quit_after_match) {
                 WalkState::Quit
             } else {
                 WalkState::Continue
@@ -210,11 +445,384 @@ fn search_parallel(args: &Args) -> Result<bool> {
         eprint_nothing_searched();
     }
     if let Some(ref locked_stats) = stats {
-        let elapsed = Instant::now().duration_since(started_at);
         let stats = locked_stats.lock().unwrap();
-        let mut searcher = args.search_worker(args.stdout())?;
-        // We don't care if we couldn't print this successfully.
-        let _ = searcher.print_stats(elapsed, &stats);
+        if let Some(total) = args.count_total(&stats) {
+            // We don't care if we couldn't print this successfully.
+            let _ = writeln!(args.stdout(), "{}", total);
+        } else {
+            let elapsed = Instant::now().duration_since(started_at);
+            let mut searcher = args.search_worker(args.stdout())?;
+            let _ = searcher.print_stats(elapsed, &stats);
+        }
+    }
+    Ok(matched.load(SeqCst))
+}
+
+/// A writer for parallel search that prints accumulated results through a
+/// shared `BufferWriter` whenever a flush interval has elapsed.
+///
+/// When no interval is given, this behaves exactly like a plain buffer:
+/// results accumulate until `print` is called after a file's search is done.
+/// With an interval, results are also printed mid-search once the interval
+/// has elapsed, but only when the buffer ends at a line boundary. This keeps
+/// individual lines intact, although results from different files may
+/// interleave at flush boundaries.
+#[derive(Debug)]
+struct IntervalBuffer<'a> {
+    bufwtr: &'a BufferWriter,
+    buf: Buffer,
+    interval: Option<Duration>,
+    last_flush: Instant,
+}
+
+impl<'a> IntervalBuffer<'a> {
+    /// Create a new buffer that prints through the given writer.
+    fn new(
+        bufwtr: &'a BufferWriter,
+        interval: Option<Duration>,
+    ) -> IntervalBuffer<'a> {
+        IntervalBuffer {
+            bufwtr,
+            buf: bufwtr.buffer(),
+            interval,
+            last_flush: Instant::now(),
+        }
+    }
+
+    /// Clear any accumulated results without printing them.
+    fn clear(&mut self) {
+        self.buf.clear();
+        self.last_flush = Instant::now();
+    }
+
+    /// Print any accumulated results and clear the buffer.
+    fn print(&mut self) -> io::Result<()> {
+        self.bufwtr.print(&self.buf)?;
+        self.clear();
+        Ok(())
+    }
+
+    /// Returns true if the buffer ends at a line boundary. (The line
+    /// terminator is either a `\\n` or, with --null-data, a `NUL` byte.)
+    fn at_line_boundary(&self) -> bool {
+        self.buf
+            .as_slice()
+            .last()
+            .map_or(false, |&byte| byte == b'\n' || byte == b'\x00')
+    }
+}
+
+impl<'a> io::Write for IntervalBuffer<'a> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let n = self.buf.write(data)?;
+        if let Some(interval) = self.interval {
+            if self.last_flush.elapsed() >= interval && self.at_line_boundary()
+            {
+                self.print()?;
+            }
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.buf.flush()
+    }
+}
+
+impl<'a> WriteColor for IntervalBuffer<'a> {
+    fn supports_color(&self) -> bool {
+        self.buf.supports_color()
+    }
+
+    fn set_color(&mut self, spec: &ColorSpec) -> io::Result<()> {
+        self.buf.set_color(spec)
+    }
+
+    fn reset(&mut self) -> io::Result<()> {
+        self.buf.reset()
+    }
+}
+
+/// Multi-threaded search with results emitted in sorted order.
+///
+/// The single threaded walker yields subjects in sorted order (for path
+/// sorting) or the collected subjects are sorted up front (for stat-based
+/// criteria such as modification time), workers pick them off a shared
+/// queue and search them concurrently, and a reorder buffer writes each
+/// subject's output back in sequence. The buffer is bounded, so a slow file
+/// limits how far ahead the workers can run rather than how much memory
+/// they can consume.
+fn search_parallel_sorted(args: &Args) -> Result<bool> {
+    use std::collections::BTreeMap;
+    use std::sync::atomic::{
+        AtomicBool, AtomicU64, AtomicUsize, Ordering::SeqCst,
+    };
+    use std::sync::mpsc;
+    use std::thread;
+
+    let quit_after_match = args.quit_after_match()?;
+    let max_total_matches = args.max_total_matches()?;
+    if max_total_matches == Some(0) {
+        return Ok(false);
+    }
+    let match_budget = max_total_matches.map(AtomicU64::new);
+    let checkpoint = args.checkpoint()?;
+    let started_at = Instant::now();
+    let subject_builder = args.subject_builder();
+    let subjects: Vec<Subject> = args
+        .walker()?
+        .filter_map(|result| subject_builder.build_from_result(result))
+        .filter(|subject| match checkpoint {
+            Some(ref checkpoint) => {
+                subject.is_stdin() || !checkpoint.is_done(subject.path())
+            }
+            None => true,
+        })
+        .collect();
+    let subjects = if args.needs_stat_sort() {
+        args.sort_by_stat(subjects.into_iter())
+    } else {
+        subjects
+    };
+    let searched = !subjects.is_empty();
+    let bufwtr = args.buffer_writer()?;
+    let stats = args.stats()?.map(Mutex::new);
+    let matched = AtomicBool::new(false);
+    let next_subject = AtomicUsize::new(0);
+    // Bound the number of finished but unprinted results, so that one slow
+    // file early in the sequence can't cause unbounded buffering of the
+    // results that come after it.
+    let (tx, rx) = mpsc::sync_channel(args.threads() * 4);
+
+    let mut searcher_err = None;
+    thread::scope(|scope| {
+        for _ in 0..args.threads() {
+            let tx = tx.clone();
+            let (bufwtr, stats) = (&bufwtr, &stats);
+            let (matched, next_subject) = (&matched, &next_subject);
+            let match_budget = &match_budget;
+            let subjects = &subjects;
+            let mut searcher = match args.search_worker(bufwtr.buffer()) {
+                Ok(searcher) => searcher,
+                Err(err) => {
+                    searcher_err = Some(err);
+                    break;
+                }
+            };
+            scope.spawn(move || loop {
+                if matched.load(SeqCst) && quit_after_match {
+                    break;
+                }
+                if let Some(ref budget) = *match_budget {
+                    if budget.load(SeqCst) == 0 {
+                        break;
+                    }
+                }
+                let i = next_subject.fetch_add(1, SeqCst);
+                let subject = match subjects.get(i) {
+                    None => break,
+                    Some(subject) => subject,
+                };
+                searcher.printer().get_mut().clear();
+                let search_result = match searcher.search(subject) {
+                    Ok(search_result) => search_result,
+                    Err(err) => {
+                        err_file_message!(subject.path(), err);
+                        continue;
+                    }
+                };
+                if search_result.has_match() {
+                    matched.store(true, SeqCst);
+                }
+                if let Some(ref budget) = *match_budget {
+                    let found = search_result
+                        .stats()
+                        .map_or(0, |stats| stats.matches());
+                    budget
+                        .fetch_update(SeqCst, SeqCst, |n| {
+                            Some(n.saturating_sub(found))
+                        })
+                        .unwrap();
+                }
+                if let Some(ref locked_stats) = *stats {
+                    let mut stats = locked_stats.lock().unwrap();
+                    *stats += search_result.stats().unwrap();
+                }
+                let buf = std::mem::replace(
+                    searcher.printer().get_mut(),
+                    bufwtr.buffer(),
+                );
+                // The only way sending fails is if the printing side gave
+                // up (e.g., on a broken pipe), so just stop searching.
+                if tx.send((i, buf)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+
+        let mut pending = BTreeMap::new();
+        let mut next_print = 0;
+        'print: for (i, buf) in rx {
+            pending.insert(i, buf);
+            while let Some(buf) = pending.remove(&next_print) {
+                let subject = &subjects[next_print];
+                next_print += 1;
+                if let Err(err) = bufwtr.print(&buf) {
+                    // A broken pipe means graceful termination. Dropping
+                    // the receiver stops the workers.
+                    if err.kind() == io::ErrorKind::BrokenPipe {
+                        break 'print;
+                    }
+                    err_message!("{}", err);
+                    continue;
+                }
+                if let Some(ref checkpoint) = checkpoint {
+                    if !subject.is_stdin() {
+                        checkpoint.record(subject.path());
+                    }
+                }
+            }
+        }
+    });
+    if let Some(err) = searcher_err.take() {
+        return Err(err);
+    }
+    if args.using_default_path() && !searched {
+        eprint_nothing_searched();
+    }
+    if let Some(ref locked_stats) = stats {
+        let stats = locked_stats.lock().unwrap();
+        if let Some(total) = args.count_total(&stats) {
+            // We don't care if we couldn't print this successfully.
+            let _ = writeln!(args.stdout(), "{}", total);
+        } else {
+            let elapsed = Instant::now().duration_since(started_at);
+            let mut searcher = args.search_worker(args.stdout())?;
+            let _ = searcher.print_stats(elapsed, &stats);
+        }
+    }
+    Ok(matched.load(SeqCst))
+}
+
+/// The top-level entry point for the --bench flag. This runs the configured
+/// search repeatedly with its output suppressed and reports how the time is
+/// spent, so that the effect of flags like -j/--threads and --mmap can be
+/// measured without an external harness.
+fn bench(args: &Args) -> Result<bool> {
+    use std::sync::atomic::{
+        AtomicBool, AtomicU64, AtomicUsize, Ordering::SeqCst,
+    };
+    use std::thread;
+
+    use termcolor::NoColor;
+
+    fn mib_per_sec(bytes: u64, elapsed: Duration) -> f64 {
+        let secs = elapsed.as_secs_f64();
+        if secs == 0.0 {
+            return 0.0;
+        }
+        (bytes as f64 / (1 << 20) as f64) / secs
+    }
+
+    let iterations = args.bench_iterations()?;
+    let threads = args.threads();
+    let mut stdout = args.stdout();
+
+    let walk_started = Instant::now();
+    let subject_builder = args.subject_builder();
+    let subjects: Vec<Subject> = args
+        .walker()?
+        .filter_map(|result| subject_builder.build_from_result(result))
+        .collect();
+    let walk_elapsed = walk_started.elapsed();
+    let total_bytes: u64 = subjects
+        .iter()
+        .filter_map(|s| s.path().metadata().ok())
+        .map(|md| md.len())
+        .sum();
+    writeln!(
+        stdout,
+        "walked {} files ({:.1} MiB) in {:.3?}; \
+         searching with {} thread(s), {} run(s)",
+        subjects.len(),
+        total_bytes as f64 / (1 << 20) as f64,
+        walk_elapsed,
+        threads,
+        iterations,
+    )?;
+
+    let matched = AtomicBool::new(false);
+    let mut best: Option<Duration> = None;
+    for run in 1..=iterations {
+        let match_count = AtomicU64::new(0);
+        let next_subject = AtomicUsize::new(0);
+        let mut searcher_err = None;
+        let search_started = Instant::now();
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                let (matched, match_count) = (&matched, &match_count);
+                let next_subject = &next_subject;
+                let subjects = &subjects;
+                let mut searcher =
+                    match args.search_worker(NoColor::new(io::sink())) {
+                        Ok(searcher) => searcher,
+                        Err(err) => {
+                            searcher_err = Some(err);
+                            break;
+                        }
+                    };
+                scope.spawn(move || loop {
+                    let i = next_subject.fetch_add(1, SeqCst);
+                    let subject = match subjects.get(i) {
+                        None => break,
+                        Some(subject) => subject,
+                    };
+                    let search_result = match searcher.search(subject) {
+                        Ok(search_result) => search_result,
+                        Err(err) => {
+                            err_file_message!(subject.path(), err);
+                            continue;
+                        }
+                    };
+                    if search_result.has_match() {
+                        matched.store(true, SeqCst);
+                    }
+                    let found = search_result
+                        .stats()
+                        .map_or(0, |stats| stats.matches());
+                    match_count.fetch_add(found, SeqCst);
+                });
+            }
+        });
+        if let Some(err) = searcher_err.take() {
+            return Err(err);
+        }
+        let elapsed = search_started.elapsed();
+        writeln!(
+            stdout,
+            "run {}: searched in {:.3?} ({:.1} MiB/s, {} matches)",
+            run,
+            elapsed,
+            mib_per_sec(total_bytes, elapsed),
+            match_count.load(SeqCst),
+        )?;
+        if best.map_or(true, |b| elapsed < b) {
+            best = Some(elapsed);
+        }
+    }
+    if let Some(best) = best {
+        let total = walk_elapsed + best;
+        let walk_pct =
+            100.0 * walk_elapsed.as_secs_f64() / total.as_secs_f64();
+        writeln!(
+            stdout,
+            "best: {:.3?} ({:.1} MiB/s); walk/search split: {:.0}%/{:.0}%",
+            best,
+            mib_per_sec(total_bytes, best),
+            walk_pct,
+            100.0 - walk_pct,
+        )?;
     }
     Ok(matched.load(SeqCst))
 }
@@ -227,6 +835,65 @@ fn eprint_nothing_searched() {
     );
 }
 
+/// The top-level entry point for in-place replacement. This recursively
+/// steps through the file list (current directory by default) and rewrites
+/// each file with matches sequentially using a single thread, then prints a
+/// summary of what was (or, with --dry-run, would have been) modified.
+fn write_replace(args: &Args) -> Result<bool> {
+    let subject_builder = args.subject_builder();
+    let mut worker = args.replace_worker()?;
+    let mut wtr = args.stdout();
+    let mut matched = false;
+    let (mut files_modified, mut total_replacements) = (0u64, 0u64);
+
+    let subjects = args
+        .walker()?
+        .filter_map(|result| subject_builder.build_from_result(result));
+    for subject in subjects {
+        let result = match worker.replace(&subject) {
+            Ok(result) => result,
+            Err(err) => {
+                err_file_message!(subject.path(), err);
+                continue;
+            }
+        };
+        if result.replacements() == 0 {
+            continue;
+        }
+        matched = true;
+        files_modified += 1;
+        total_replacements += result.replacements();
+        if !args.quiet() {
+            let print_result = writeln!(
+                wtr,
+                "{}: {} replacement{}",
+                subject.path().display(),
+                result.replacements(),
+                if result.replacements() == 1 { "" } else { "s" },
+            );
+            if let Err(err) = print_result {
+                // A broken pipe means graceful termination, but the
+                // replacements themselves must carry on.
+                if err.kind() != io::ErrorKind::BrokenPipe {
+                    return Err(err.into());
+                }
+            }
+        }
+    }
+    if !args.quiet() {
+        let _ = writeln!(
+            wtr,
+            "{} replacement{} across {} file{}{}",
+            total_replacements,
+            if total_replacements == 1 { "" } else { "s" },
+            files_modified,
+            if files_modified == 1 { "" } else { "s" },
+            if worker.dry_run() { " (dry run)" } else { "" },
+        );
+    }
+    Ok(matched)
+}
+
 /// The top-level entry point for listing files without searching them. This
 /// recursively steps through the file list (current directory by default) and
 /// prints each path sequentially using a single thread.
@@ -330,10 +997,70 @@ fn files_parallel(args: &Args) -> Result<bool> {
     Ok(matched.load(SeqCst))
 }
 
+/// The top-level entry point for --generate. This prints the requested
+/// artifact, such as shell completions or a man page, to stdout.
+fn generate(args: &Args, kind: app::GenerateKind) -> Result<bool> {
+    let mut stdout = args.stdout();
+    app::generate(kind, &mut stdout)?;
+    Ok(true)
+}
+
+/// The top-level entry point for --explain. For each path given to
+/// --explain, this prints the chain of decisions that determines whether
+/// the path would be searched, using the same filtering configuration as
+/// the main walker. Returns true if any of the paths would be searched.
+fn explain(args: &Args) -> Result<bool> {
+    let explainer = args.walk_explainer()?;
+    let mut stdout = args.stdout();
+    let mut any_searched = false;
+    for path in args.explain_paths() {
+        let steps = explainer.explain(&path);
+        // An ignored directory ends the chain early, so the last step
+        // settles the path's fate. No step at all means nothing matched,
+        // in which case the path is searched.
+        let searched = steps.last().map_or(true, |step| !step.is_ignore());
+        writeln!(stdout, "{}:", path.display())?;
+        for step in &steps {
+            writeln!(
+                stdout,
+                "  {}: {} by {}",
+                step.path().display(),
+                if step.is_ignore() { "ignored" } else { "whitelisted" },
+                step.reason(),
+            )?;
+        }
+        if steps.is_empty() {
+            writeln!(stdout, "  no rule matched")?;
+        }
+        writeln!(
+            stdout,
+            "  {}",
+            if searched { "searched" } else { "not searched" },
+        )?;
+        any_searched = any_searched || searched;
+    }
+    Ok(any_searched)
+}
+
 /// The top-level entry point for --type-list.
 fn types(args: &Args) -> Result<bool> {
     let mut count = 0;
     let mut stdout = args.stdout();
+    if args.json() {
+        for def in args.type_defs()? {
+            count += 1;
+            let msg = serde_json::json!({
+                "type": "file-type",
+                "data": {
+                    "name": def.name(),
+                    "globs": def.globs(),
+                },
+            });
+            serde_json::to_writer(&mut stdout, &msg)?;
+            stdout.write_all(b"\n")?;
+        }
+        return Ok(count > 0);
+    }
     for def in args.type_defs()? {
         count += 1;
         stdout.write_all(def.name().as_bytes())?;
@@ -1,7 +1,9 @@
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 
 static MESSAGES: AtomicBool = AtomicBool::new(false);
+static SUPPRESSED_CLASSES: AtomicU32 = AtomicU32::new(0);
 static IGNORE_MESSAGES: AtomicBool = AtomicBool::new(false);
+static JSON_ERRORS: AtomicBool = AtomicBool::new(false);
 static ERRORED: AtomicBool = AtomicBool::new(false);
 
 /// Like eprintln, but locks STDOUT to prevent interleaving lines.
@@ -24,8 +26,18 @@ macro_rules! eprintln_locked {
 #[macro_export]
 macro_rules! message {
     ($($tt:tt)*) => {
-        if crate::messages::messages() {
-            eprintln_locked!($($tt)*);
+        if crate::messages::messages()
+            && !crate::messages::suppressed(crate::messages::CLASS_OTHER)
+        {
+            if crate::messages::json_errors() {
+                crate::messages::print_json_error(
+                    "message",
+                    None,
+                    &format!($($tt)*),
+                );
+            } else {
+                eprintln_locked!($($tt)*);
+            }
         }
     }
 }
@@ -40,13 +52,74 @@ macro_rules! err_message {
     }
 }
 
+/// Like err_message, but with an explicit message class, so that errors
+/// surfaced by the directory traversal can be suppressed as I/O errors when
+/// that's what they are.
+#[macro_export]
+macro_rules! err_class_message {
+    ($class:expr, $($tt:tt)*) => {
+        crate::messages::set_errored();
+        if crate::messages::messages()
+            && !crate::messages::suppressed($class)
+        {
+            if crate::messages::json_errors() {
+                crate::messages::print_json_error(
+                    "message",
+                    None,
+                    &format!($($tt)*),
+                );
+            } else {
+                eprintln_locked!($($tt)*);
+            }
+        }
+    }
+}
+
+/// Like err_message, but ties the diagnostic to a file path and an I/O
+/// error, so that --errors=json can report the path and the error kind as
+/// separate fields.
+#[macro_export]
+macro_rules! err_file_message {
+    ($path:expr, $err:expr) => {{
+        crate::messages::set_errored();
+        let path: &::std::path::Path = $path.as_ref();
+        let err = &$err;
+        if crate::messages::messages()
+            && !crate::messages::suppressed(crate::messages::io_error_class(
+                err,
+            ))
+        {
+            if crate::messages::json_errors() {
+                crate::messages::print_json_error(
+                    crate::messages::error_kind(err),
+                    Some(path),
+                    &err.to_string(),
+                );
+            } else {
+                eprintln_locked!("{}: {}", path.display(), err);
+            }
+        }
+    }};
+}
+
 /// Emit a non-fatal ignore-related error message (like a parse error), unless
 /// ignore-messages were disabled.
 #[macro_export]
 macro_rules! ignore_message {
     ($($tt:tt)*) => {
-        if crate::messages::messages() && crate::messages::ignore_messages() {
-            eprintln_locked!($($tt)*);
+        if crate::messages::messages()
+            && crate::messages::ignore_messages()
+            && !crate::messages::suppressed(crate::messages::CLASS_IGNORE)
+        {
+            if crate::messages::json_errors() {
+                crate::messages::print_json_error(
+                    "ignore",
+                    None,
+                    &format!($($tt)*),
+                );
+            } else {
+                eprintln_locked!($($tt)*);
+            }
         }
     }
 }
@@ -63,6 +136,66 @@ pub fn set_messages(yes: bool) {
     MESSAGES.store(yes, Ordering::SeqCst)
 }
 
+/// The class of messages about failing to open or read files.
+pub const CLASS_IO: u32 = 1 << 0;
+/// The class of messages about file contents that could not be decoded.
+pub const CLASS_DECODE: u32 = 1 << 1;
+/// The class of messages about problems with ignore rules.
+pub const CLASS_IGNORE: u32 = 1 << 2;
+/// The class of all other non-fatal messages.
+pub const CLASS_OTHER: u32 = 1 << 3;
+
+/// Returns true if and only if the given message class has been suppressed
+/// with --no-messages=CLASSES.
+pub fn suppressed(class: u32) -> bool {
+    SUPPRESSED_CLASSES.load(Ordering::SeqCst) & class != 0
+}
+
+/// Set the message classes that should be suppressed.
+///
+/// By default, no classes are suppressed.
+pub fn set_suppressed_classes(classes: u32) {
+    SUPPRESSED_CLASSES.store(classes, Ordering::SeqCst)
+}
+
+/// Parse a comma-separated list of message class names, as given to
+/// --no-messages=CLASSES, into a set of classes.
+pub fn parse_message_classes(list: &str) -> Result<u32, String> {
+    let mut classes = 0;
+    for name in list.split(',') {
+        classes |= match name.trim() {
+            "io" => CLASS_IO,
+            "decode" => CLASS_DECODE,
+            "ignore" => CLASS_IGNORE,
+            "other" => CLASS_OTHER,
+            unknown => {
+                return Err(format!(
+                    "unrecognized message class {:?} \
+                     (choose from io, decode, ignore, other)",
+                    unknown,
+                ));
+            }
+        };
+    }
+    Ok(classes)
+}
+
+/// Returns the class of a message about the given I/O error: decode for
+/// contents that could not be decoded, io for everything else.
+pub fn io_error_class(err: &std::io::Error) -> u32 {
+    match err.kind() {
+        std::io::ErrorKind::InvalidData => CLASS_DECODE,
+        _ => CLASS_IO,
+    }
+}
+
+/// Returns the class of a message about the given directory traversal
+/// error: the class of its underlying I/O error when it has one, other
+/// otherwise.
+pub fn ignore_error_class(err: &ignore::Error) -> u32 {
+    err.io_error().map_or(CLASS_OTHER, io_error_class)
+}
+
 /// Returns true if and only if "ignore" related messages should be shown.
 pub fn ignore_messages() -> bool {
     IGNORE_MESSAGES.load(Ordering::SeqCst)
@@ -79,6 +212,55 @@ pub fn set_ignore_messages(yes: bool) {
     IGNORE_MESSAGES.store(yes, Ordering::SeqCst)
 }
 
+/// Returns true if and only if diagnostics should be emitted as JSON.
+pub fn json_errors() -> bool {
+    JSON_ERRORS.load(Ordering::SeqCst)
+}
+
+/// Set whether diagnostics should be emitted as JSON objects on stderr.
+///
+/// By default, they are emitted as plain text.
+pub fn set_json_errors(yes: bool) {
+    JSON_ERRORS.store(yes, Ordering::SeqCst)
+}
+
+/// Print a diagnostic as a JSON Lines object on stderr.
+///
+/// The object has the shape
+/// `{"type":"error","data":{"kind":...,"path":...,"message":...}}`,
+/// mirroring the message envelope used by the --json printer. The path is
+/// null when the diagnostic isn't tied to a particular file.
+pub fn print_json_error(
+    kind: &str,
+    path: Option<&std::path::Path>,
+    message: &str,
+) {
+    let msg = serde_json::json!({
+        "type": "error",
+        "data": {
+            "kind": kind,
+            "path": path.map(|p| p.display().to_string()),
+            "message": message,
+        },
+    });
+    eprintln_locked!("{}", msg);
+}
+
+/// Returns a stable string describing the kind of an I/O error, e.g.,
+/// "permission-denied" or "invalid-data".
+pub fn error_kind(err: &std::io::Error) -> &'static str {
+    use std::io::ErrorKind::*;
+
+    match err.kind() {
+        NotFound => "not-found",
+        PermissionDenied => "permission-denied",
+        InvalidData => "invalid-data",
+        BrokenPipe => "broken-pipe",
+        Interrupted => "interrupted",
+        _ => "other",
+    }
+}
+
 /// Returns true if and only if ripgrep came across a non-fatal error.
 pub fn errored() -> bool {
     ERRORED.load(Ordering::SeqCst)
@@ -1,15 +1,32 @@
+use std::fs::Metadata;
 use std::io;
 use std::path::Path;
+use std::time::SystemTime;
 
-use grep::printer::{ColorSpecs, PrinterPath};
+use grep::printer::{ColorSpecs, PathTransform, PrinterPath};
+use serde_json::json;
 use termcolor::WriteColor;
 
+/// A metadata column that can be printed alongside each file path.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FileColumn {
+    /// The size of the file, in bytes.
+    Size,
+    /// The last modification time, in seconds since the Unix epoch.
+    Mtime,
+    /// The type of the file: `file`, `dir` or `symlink`.
+    FileType,
+}
+
 /// A configuration for describing how paths should be written.
 #[derive(Clone, Debug)]
 struct Config {
     colors: ColorSpecs,
     separator: Option<u8>,
     terminator: u8,
+    transform: PathTransform,
+    columns: Vec<FileColumn>,
+    json: bool,
 }
 
 impl Default for Config {
@@ -18,6 +35,9 @@ impl Default for Config {
             colors: ColorSpecs::default(),
             separator: None,
             terminator: b'\n',
+            transform: PathTransform::None,
+            columns: vec![],
+            json: false,
         }
     }
 }
@@ -73,6 +93,47 @@ impl PathPrinterBuilder {
         self.config.terminator = terminator;
         self
     }
+
+    /// The transform to apply to each path before printing it, e.g., making
+    /// it absolute or canonical.
+    ///
+    /// By default, paths are printed as they were given.
+    pub fn transform(
+        &mut self,
+        transform: PathTransform,
+    ) -> &mut PathPrinterBuilder {
+        self.config.transform = transform;
+        self
+    }
+
+    /// The metadata columns to print before each path, in the order given.
+    ///
+    /// Column values are separated from each other and from the path by a
+    /// single tab. When a column's value cannot be determined, a `-` is
+    /// printed in its place.
+    ///
+    /// This is empty by default, which prints just the path.
+    pub fn columns(
+        &mut self,
+        columns: Vec<FileColumn>,
+    ) -> &mut PathPrinterBuilder {
+        self.config.columns = columns;
+        self
+    }
+
+    /// Print each path as a JSON object on its own line.
+    ///
+    /// Every object has a `file` message type and its data includes the
+    /// path along with its size, mtime and file type, each of which is
+    /// `null` when it could not be determined. Paths that are not valid
+    /// UTF-8 are printed lossily.
+    ///
+    /// This is disabled by default. When enabled, it supersedes colors,
+    /// metadata columns and the path terminator.
+    pub fn json(&mut self, yes: bool) -> &mut PathPrinterBuilder {
+        self.config.json = yes;
+        self
+    }
 }
 
 /// A printer for emitting paths to a writer, with optional color support.
@@ -85,7 +146,17 @@ pub struct PathPrinter<W> {
 impl<W: WriteColor> PathPrinter<W> {
     /// Write the given path to the underlying writer.
     pub fn write_path(&mut self, path: &Path) -> io::Result<()> {
-        let ppath = PrinterPath::with_separator(path, self.config.separator);
+        if self.config.json {
+            return self.write_path_json(path);
+        }
+        if !self.config.columns.is_empty() {
+            self.write_columns(path)?;
+        }
+        let ppath = PrinterPath::with_transform(
+            path,
+            self.config.transform,
+            self.config.separator,
+        );
         if !self.wtr.supports_color() {
             self.wtr.write_all(ppath.as_bytes())?;
         } else {
@@ -95,4 +166,71 @@ impl<W: WriteColor> PathPrinter<W> {
         }
         self.wtr.write_all(&[self.config.terminator])
     }
+
+    /// Write the configured metadata columns for the given path, each
+    /// followed by a tab.
+    fn write_columns(&mut self, path: &Path) -> io::Result<()> {
+        let md = path.symlink_metadata().ok();
+        for column in &self.config.columns {
+            match *column {
+                FileColumn::Size => match md {
+                    Some(ref md) => write!(self.wtr, "{}", md.len())?,
+                    None => self.wtr.write_all(b"-")?,
+                },
+                FileColumn::Mtime => {
+                    match md.as_ref().and_then(mtime_seconds) {
+                        Some(secs) => write!(self.wtr, "{}", secs)?,
+                        None => self.wtr.write_all(b"-")?,
+                    }
+                }
+                FileColumn::FileType => {
+                    let label =
+                        md.as_ref().map_or("-", |md| file_type_label(md));
+                    self.wtr.write_all(label.as_bytes())?;
+                }
+            }
+            self.wtr.write_all(b"\t")?;
+        }
+        Ok(())
+    }
+
+    /// Write the given path as a JSON object on its own line.
+    fn write_path_json(&mut self, path: &Path) -> io::Result<()> {
+        let path = self.config.transform.apply(path);
+        let md = path.symlink_metadata().ok();
+        let msg = json!({
+            "type": "file",
+            "data": {
+                "path": { "text": path.to_string_lossy() },
+                "size": md.as_ref().map(|md| md.len()),
+                "mtime": md.as_ref().and_then(mtime_seconds),
+                "file_type": md.as_ref().map(file_type_label),
+            },
+        });
+        serde_json::to_writer(&mut self.wtr, &msg)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        self.wtr.write_all(b"\n")
+    }
+}
+
+/// Return the last modification time of a file, in seconds since the Unix
+/// epoch, if it is available.
+fn mtime_seconds(md: &Metadata) -> Option<u64> {
+    let mtime = md.modified().ok()?;
+    let since = mtime.duration_since(SystemTime::UNIX_EPOCH).ok()?;
+    Some(since.as_secs())
+}
+
+/// Return a human readable label for the type of the given file.
+fn file_type_label(md: &Metadata) -> &'static str {
+    let ft = md.file_type();
+    if ft.is_symlink() {
+        "symlink"
+    } else if ft.is_dir() {
+        "dir"
+    } else if ft.is_file() {
+        "file"
+    } else {
+        "unknown"
+    }
 }
@@ -0,0 +1,131 @@
+/*!
+A persistent cache for preprocessor output.
+
+Preprocessors (see the `--pre` flag) are commonly used to extract text from
+formats like PDFs or Office documents, where the conversion dwarfs the cost
+of the search itself. This cache stores the output of the preprocessor on
+disk, keyed by the preprocessor command along with the file's path, size and
+modification time. A change to any component of the key makes the old entry
+unreachable, so entries never need to be explicitly invalidated. Stale
+entries are instead reclaimed by the cache size limit, which evicts the
+oldest entries first.
+*/
+
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// A directory holding cached preprocessor output.
+#[derive(Clone, Debug)]
+pub struct PreprocessorCache {
+    dir: PathBuf,
+    limit: Option<u64>,
+}
+
+impl PreprocessorCache {
+    /// Open (and create, if necessary) a cache at the given directory.
+    ///
+    /// `limit` is the maximum total size of the cache in bytes. When the
+    /// cache grows beyond it, the oldest entries are removed. `None` means
+    /// the cache may grow without bound.
+    pub fn new(
+        dir: PathBuf,
+        limit: Option<u64>,
+    ) -> io::Result<PreprocessorCache> {
+        fs::create_dir_all(&dir)?;
+        Ok(PreprocessorCache { dir, limit })
+    }
+
+    /// Remove every entry from the cache.
+    pub fn clear(&self) -> io::Result<()> {
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Return the path of the cache entry for running the given preprocessor
+    /// command on the given file. The entry exists if and only if the cache
+    /// holds the preprocessor's output for the file as it is right now.
+    pub fn entry(&self, cmd: &Path, path: &Path) -> io::Result<PathBuf> {
+        let md = fs::metadata(path)?;
+        let mtime = md
+            .modified()?
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        // An absolute path keeps the key stable no matter which directory
+        // the search is run from.
+        let absolute = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            std::env::current_dir()?.join(path)
+        };
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        cmd.hash(&mut hasher);
+        absolute.hash(&mut hasher);
+        md.len().hash(&mut hasher);
+        mtime.hash(&mut hasher);
+        Ok(self.dir.join(format!("{:016x}", hasher.finish())))
+    }
+
+    /// Store the contents of the given reader as the given cache entry.
+    ///
+    /// The entry is written via a temporary file and a rename, so a crash
+    /// mid-write can never produce a truncated cache entry. After storing,
+    /// the cache size limit is enforced.
+    pub fn store<R: Read>(&self, entry: &Path, mut rdr: R) -> io::Result<()> {
+        let tmp = entry.with_extension("tmp");
+        let result = (|| {
+            let mut f = File::create(&tmp)?;
+            io::copy(&mut rdr, &mut f)?;
+            f.sync_all()?;
+            fs::rename(&tmp, entry)
+        })();
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp);
+            return result;
+        }
+        self.prune(entry)
+    }
+
+    /// Remove the oldest entries until the cache fits within its size
+    /// limit. The entry at `keep` is never removed, so that the entry for
+    /// the file currently being searched always survives.
+    fn prune(&self, keep: &Path) -> io::Result<()> {
+        let limit = match self.limit {
+            None => return Ok(()),
+            Some(limit) => limit,
+        };
+        let mut entries = vec![];
+        let mut total = 0;
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let md = entry.metadata()?;
+            if !md.is_file() {
+                continue;
+            }
+            total += md.len();
+            let mtime = md.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            entries.push((mtime, md.len(), entry.path()));
+        }
+        entries.sort();
+        for (_, len, path) in entries {
+            if total <= limit {
+                break;
+            }
+            if path == keep {
+                continue;
+            }
+            fs::remove_file(&path)?;
+            total -= len;
+        }
+        Ok(())
+    }
+}
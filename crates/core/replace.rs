@@ -0,0 +1,244 @@
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use grep::matcher::{Captures, Matcher};
+
+use crate::search::PatternMatcher;
+use crate::subject::Subject;
+
+/// The configuration for the replace worker.
+#[derive(Clone, Debug)]
+struct Config {
+    dry_run: bool,
+    backup_suffix: Option<OsString>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config { dry_run: false, backup_suffix: None }
+    }
+}
+
+/// A builder for configuring and constructing a replace worker.
+#[derive(Clone, Debug)]
+pub struct ReplaceWorkerBuilder {
+    config: Config,
+}
+
+impl Default for ReplaceWorkerBuilder {
+    fn default() -> ReplaceWorkerBuilder {
+        ReplaceWorkerBuilder::new()
+    }
+}
+
+impl ReplaceWorkerBuilder {
+    /// Create a new builder for configuring and constructing a replace
+    /// worker.
+    pub fn new() -> ReplaceWorkerBuilder {
+        ReplaceWorkerBuilder { config: Config::default() }
+    }
+
+    /// Create a new replace worker using the given matcher and replacement.
+    pub fn build(
+        &self,
+        matcher: PatternMatcher,
+        replacement: Vec<u8>,
+    ) -> ReplaceWorker {
+        ReplaceWorker { config: self.config.clone(), matcher, replacement }
+    }
+
+    /// When enabled, report the replacements that would be made without
+    /// modifying any files.
+    pub fn dry_run(&mut self, yes: bool) -> &mut ReplaceWorkerBuilder {
+        self.config.dry_run = yes;
+        self
+    }
+
+    /// When set, keep a backup of each modified file by renaming the
+    /// original to its name with the given suffix appended before the
+    /// rewritten contents are put in place.
+    pub fn backup_suffix(
+        &mut self,
+        suffix: Option<OsString>,
+    ) -> &mut ReplaceWorkerBuilder {
+        self.config.backup_suffix = suffix;
+        self
+    }
+}
+
+/// The result of performing replacements on a single file.
+#[derive(Clone, Debug, Default)]
+pub struct ReplaceResult {
+    replacements: u64,
+}
+
+impl ReplaceResult {
+    /// Return the number of replacements made (or, in a dry run, the number
+    /// of replacements that would have been made).
+    pub fn replacements(&self) -> u64 {
+        self.replacements
+    }
+}
+
+/// A worker for rewriting files in place.
+///
+/// The worker reads the entirety of each file into memory, applies the
+/// replacement to every match and, when anything changed, writes the new
+/// contents to a temporary file in the same directory before atomically
+/// renaming it over the original. The original file's permissions are
+/// preserved. Files that see no matches are never touched.
+#[derive(Clone, Debug)]
+pub struct ReplaceWorker {
+    config: Config,
+    matcher: PatternMatcher,
+    replacement: Vec<u8>,
+}
+
+impl ReplaceWorker {
+    /// Execute replacements on the given subject.
+    pub fn replace(&mut self, subject: &Subject) -> io::Result<ReplaceResult> {
+        if subject.is_stdin() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "stdin cannot be modified in place",
+            ));
+        }
+        let path = subject.path();
+        let contents = fs::read(path)?;
+        // The matcher operates on the bytes as given, so files that require
+        // transcoding can't be safely rewritten. A UTF-8 BOM is fine: it
+        // passes through untouched since only matched spans are spliced.
+        if contents.starts_with(b"\xFF\xFE")
+            || contents.starts_with(b"\xFE\xFF")
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "cannot rewrite UTF-16 encoded file",
+            ));
+        }
+        if contents.contains(&b'\x00') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "cannot rewrite file containing binary data",
+            ));
+        }
+
+        let mut dst = vec![];
+        let replacements = self.replace_contents(&contents, &mut dst)?;
+        if replacements == 0 || self.config.dry_run {
+            return Ok(ReplaceResult { replacements });
+        }
+        write_atomically(path, &dst, self.config.backup_suffix.as_deref())?;
+        Ok(ReplaceResult { replacements })
+    }
+
+    /// Returns true if and only if this worker reports replacements without
+    /// making them.
+    pub fn dry_run(&self) -> bool {
+        self.config.dry_run
+    }
+
+    /// Write the replaced form of `contents` to `dst` and return the number
+    /// of replacements made.
+    fn replace_contents(
+        &self,
+        contents: &[u8],
+        dst: &mut Vec<u8>,
+    ) -> io::Result<u64> {
+        use self::PatternMatcher::*;
+
+        match self.matcher {
+            RustRegex(ref m) => {
+                replace_contents(m, contents, &self.replacement, dst)
+            }
+            #[cfg(feature = "pcre2")]
+            PCRE2(ref m) => {
+                replace_contents(m, contents, &self.replacement, dst)
+            }
+        }
+    }
+}
+
+/// Replace every match of `matcher` in `contents` with the interpolation of
+/// `replacement`, writing the result to `dst`.
+fn replace_contents<M: Matcher>(
+    matcher: M,
+    contents: &[u8],
+    replacement: &[u8],
+    dst: &mut Vec<u8>,
+) -> io::Result<u64> {
+    let mut caps = matcher.new_captures().map_err(matcher_error)?;
+    let mut replacements = 0;
+    matcher
+        .replace_with_captures(contents, &mut caps, dst, |caps, dst| {
+            replacements += 1;
+            caps.interpolate(
+                |name| matcher.capture_index(name),
+                contents,
+                replacement,
+                dst,
+            );
+            true
+        })
+        .map_err(matcher_error)?;
+    Ok(replacements)
+}
+
+/// Write the given contents to the given path via a temporary file in the
+/// same directory followed by an atomic rename, preserving the original
+/// file's permissions.
+///
+/// When a backup suffix is given, the original file is renamed to its name
+/// with the suffix appended before the temporary file takes its place.
+fn write_atomically(
+    path: &Path,
+    contents: &[u8],
+    backup_suffix: Option<&OsStr>,
+) -> io::Result<()> {
+    let perm = fs::metadata(path)?.permissions();
+    let tmp = tmp_path(path);
+    let result = (|| {
+        let mut f =
+            fs::OpenOptions::new().write(true).create_new(true).open(&tmp)?;
+        f.write_all(contents)?;
+        f.sync_all()?;
+        fs::set_permissions(&tmp, perm)?;
+        if let Some(suffix) = backup_suffix {
+            fs::rename(path, backup_path(path, suffix))?;
+        }
+        fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        // Best effort: don't leave the temporary file behind.
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// Return a path for a temporary file in the same directory as the given
+/// path. Staying in the same directory is what makes the final rename
+/// atomic.
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = std::ffi::OsString::from(".");
+    if let Some(file_name) = path.file_name() {
+        name.push(file_name);
+    }
+    name.push(format!(".rg{}", process::id()));
+    path.with_file_name(name)
+}
+
+/// Return the path of the backup for the given path, which is its file name
+/// with the given suffix appended.
+fn backup_path(path: &Path, suffix: &OsStr) -> PathBuf {
+    let mut name = path.file_name().map_or(OsString::new(), |n| n.into());
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Convert an error from a matcher into an I/O error.
+fn matcher_error<E: std::fmt::Display>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err.to_string())
+}
@@ -2,7 +2,7 @@ use std::fs::File;
 use std::io;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use grep::cli;
 use grep::matcher::Matcher;
@@ -10,36 +10,66 @@ use grep::matcher::Matcher;
 use grep::pcre2::RegexMatcher as PCRE2RegexMatcher;
 use grep::printer::{Standard, Stats, Summary, JSON};
 use grep::regex::RegexMatcher as RustRegexMatcher;
-use grep::searcher::{BinaryDetection, Searcher};
+use grep::searcher::{
+    BinaryDetection, Encoding, Searcher, Sink, SinkContext, SinkFinish,
+    SinkMatch,
+};
 use ignore::overrides::Override;
+use ignore::types::Types;
 use serde_json as json;
 use serde_json::json;
 use termcolor::WriteColor;
 
+use crate::precache::PreprocessorCache;
 use crate::subject::Subject;
 
+/// The granularity at which `--all-match` requires every pattern to match.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AllMatchKind {
+    /// Every pattern must match the same line.
+    Line,
+    /// Every pattern must match somewhere in the same file.
+    File,
+}
+
 /// The configuration for the search worker. Among a few other things, the
 /// configuration primarily controls the way we show search results to users
 /// at a very high level.
 #[derive(Clone, Debug)]
 struct Config {
     json_stats: bool,
+    all_match: Option<AllMatchKind>,
+    pattern_matchers: Vec<PatternMatcher>,
     preprocessor: Option<PathBuf>,
     preprocessor_globs: Override,
+    preprocessor_cache: Option<PreprocessorCache>,
     search_zip: bool,
+    search_archives: bool,
+    archive_globs: Override,
+    archive_types: Types,
     binary_implicit: BinaryDetection,
     binary_explicit: BinaryDetection,
+    detect_encoding: bool,
+    label: Option<PathBuf>,
 }
 
 impl Default for Config {
     fn default() -> Config {
         Config {
             json_stats: false,
+            all_match: None,
+            pattern_matchers: vec![],
             preprocessor: None,
             preprocessor_globs: Override::empty(),
+            preprocessor_cache: None,
             search_zip: false,
+            search_archives: false,
+            archive_globs: Override::empty(),
+            archive_types: Types::empty(),
             binary_implicit: BinaryDetection::none(),
             binary_explicit: BinaryDetection::none(),
+            detect_encoding: false,
+            label: None,
         }
     }
 }
@@ -107,6 +137,29 @@ impl SearchWorkerBuilder {
         self
     }
 
+    /// Require every pattern to match before reporting anything, either on
+    /// the same line or somewhere in the same file.
+    ///
+    /// The individual pattern matchers must also be provided via
+    /// `pattern_matchers` for this to have an effect.
+    pub fn all_match(
+        &mut self,
+        kind: Option<AllMatchKind>,
+    ) -> &mut SearchWorkerBuilder {
+        self.config.all_match = kind;
+        self
+    }
+
+    /// Set the matchers for the individual patterns given, used to enforce
+    /// the `all_match` setting.
+    pub fn pattern_matchers(
+        &mut self,
+        matchers: Vec<PatternMatcher>,
+    ) -> &mut SearchWorkerBuilder {
+        self.config.pattern_matchers = matchers;
+        self
+    }
+
     /// Set the path to a preprocessor command.
     ///
     /// When this is set, instead of searching files directly, the given
@@ -136,6 +189,19 @@ impl SearchWorkerBuilder {
         self
     }
 
+    /// Set a cache for preprocessor output.
+    ///
+    /// When set, the output of the preprocessor command is stored in the
+    /// cache and reused on subsequent searches, as long as the underlying
+    /// file has not changed.
+    pub fn preprocessor_cache(
+        &mut self,
+        cache: Option<PreprocessorCache>,
+    ) -> &mut SearchWorkerBuilder {
+        self.config.preprocessor_cache = cache;
+        self
+    }
+
     /// Enable the decompression and searching of common compressed files.
     ///
     /// When enabled, if a particular file path is recognized as a compressed
@@ -148,6 +214,52 @@ impl SearchWorkerBuilder {
         self
     }
 
+    /// Set the matcher used to look up the decompression command for each
+    /// file path searched with -z/--search-zip.
+    ///
+    /// By default, a matcher with the standard set of rules is used.
+    pub fn decompression_matcher(
+        &mut self,
+        matcher: cli::DecompressionMatcher,
+    ) -> &mut SearchWorkerBuilder {
+        self.decomp_builder.matcher(matcher);
+        self
+    }
+
+    /// Enable the searching of archive files such as zip and tar files.
+    ///
+    /// When enabled, if a particular file path is recognized as an archive,
+    /// then each of its file entries is searched under a virtual path like
+    /// `archive.zip!/path/inner.txt`.
+    ///
+    /// Note that if a preprocessor command is set, then it overrides this
+    /// setting.
+    pub fn search_archives(&mut self, yes: bool) -> &mut SearchWorkerBuilder {
+        self.config.search_archives = yes;
+        self
+    }
+
+    /// Set the glob overrides to apply to the entry names inside archives.
+    ///
+    /// Entries whose names are ignored by the given overrides are not
+    /// searched.
+    pub fn archive_globs(
+        &mut self,
+        globs: Override,
+    ) -> &mut SearchWorkerBuilder {
+        self.config.archive_globs = globs;
+        self
+    }
+
+    /// Set the file type matcher to apply to the entry names inside
+    /// archives.
+    ///
+    /// Entries whose names are ignored by the given types are not searched.
+    pub fn archive_types(&mut self, types: Types) -> &mut SearchWorkerBuilder {
+        self.config.archive_types = types;
+        self
+    }
+
     /// Set the binary detection that should be used when searching files
     /// found via a recursive directory search.
     ///
@@ -178,6 +290,29 @@ impl SearchWorkerBuilder {
         self.config.binary_explicit = detection;
         self
     }
+
+    /// When enabled, the encoding of each file is detected from a prefix of
+    /// its contents before searching, and the file is transcoded when the
+    /// detected encoding calls for it. Files with a BOM are unaffected,
+    /// since BOM sniffing already picks the correct encoding for them.
+    ///
+    /// This is disabled by default.
+    pub fn detect_encoding(&mut self, yes: bool) -> &mut SearchWorkerBuilder {
+        self.config.detect_encoding = yes;
+        self
+    }
+
+    /// Set the path to display for searches of stdin.
+    ///
+    /// By default, searches of stdin are reported with the special `<stdin>`
+    /// path.
+    pub fn label(
+        &mut self,
+        label: Option<PathBuf>,
+    ) -> &mut SearchWorkerBuilder {
+        self.config.label = label;
+        self
+    }
 }
 
 /// The result of executing a search.
@@ -215,6 +350,20 @@ pub enum PatternMatcher {
     PCRE2(PCRE2RegexMatcher),
 }
 
+impl PatternMatcher {
+    /// Returns true if and only if this matcher matches anywhere in the
+    /// given haystack. Errors are treated as non-matches.
+    fn is_match(&self, haystack: &[u8]) -> bool {
+        use self::PatternMatcher::*;
+
+        match *self {
+            RustRegex(ref m) => m.is_match(haystack).unwrap_or(false),
+            #[cfg(feature = "pcre2")]
+            PCRE2(ref m) => m.is_match(haystack).unwrap_or(false),
+        }
+    }
+}
+
 /// The printer used by a search worker.
 ///
 /// The `W` type parameter refers to the type of the underlying writer.
@@ -334,15 +483,67 @@ impl<W: WriteColor> SearchWorker<W> {
         log::trace!("{}: binary detection: {:?}", path.display(), bin);
 
         self.searcher.set_binary_detection(bin);
-        if subject.is_stdin() {
+        // Encoding detection needs a re-readable source whose raw bytes are
+        // what gets searched, so skip stdin, preprocessors and archives.
+        if self.config.detect_encoding
+            && !subject.is_stdin()
+            && !subject.is_stream()
+            && !self.should_preprocess(path)
+            && !self.should_search_archive(path)
+            && !self.should_decompress(path)
+        {
+            let encoding = detect_file_encoding(path);
+            log::trace!(
+                "{}: detected encoding: {:?}",
+                path.display(),
+                encoding,
+            );
+            self.searcher.set_encoding(encoding);
+        }
+        if self.should_check_file_wide(path, subject) {
+            if !self.has_all_patterns(path)? {
+                // The "empty" stats here are important: callers aggregating
+                // statistics expect every search to produce them.
+                return Ok(SearchResult {
+                    has_match: false,
+                    stats: Some(Stats::new()),
+                });
+            }
+        }
+        let start = Instant::now();
+        let mut result = if subject.is_stdin() {
+            let label = self.config.label.clone();
+            let path = label.as_deref().unwrap_or(path);
             self.search_reader(path, &mut io::stdin().lock())
         } else if self.should_preprocess(path) {
             self.search_preprocessor(path)
+        } else if self.should_search_archive(path) {
+            self.search_archive(path)
         } else if self.should_decompress(path) {
             self.search_decompress(path)
+        } else if subject.is_stream() {
+            self.search_stream(path)
         } else {
             self.search_path(path)
+        };
+        if let Ok(ref mut result) = result {
+            log::trace!(
+                "{}: search finished in {:?} (found match: {})",
+                path.display(),
+                start.elapsed(),
+                result.has_match,
+            );
+            // Per-file counts only show up in the JSON summary message, so
+            // don't bother recording them for human readable statistics.
+            if self.config.json_stats {
+                if let Some(ref mut stats) = result.stats {
+                    if stats.matches() > 0 {
+                        stats.add_file_matches(path, stats.matches());
+                    }
+                }
+            }
         }
+        result
     }
 
     /// Return a mutable reference to the underlying printer.
@@ -368,6 +569,60 @@ impl<W: WriteColor> SearchWorker<W> {
         }
     }
 
+    /// Returns true if and only if the file-wide `--all-match=file` check
+    /// should run for the given subject.
+    ///
+    /// The check requires re-readable contents, so it does not apply to
+    /// stdin, preprocessor output or archive entries.
+    fn should_check_file_wide(&self, path: &Path, subject: &Subject) -> bool {
+        self.config.all_match == Some(AllMatchKind::File)
+            && self.config.pattern_matchers.len() > 1
+            && !subject.is_stdin()
+            && !subject.is_stream()
+            && !self.should_preprocess(path)
+            && !self.should_search_archive(path)
+    }
+
+    /// Returns true if and only if every pattern matches somewhere in the
+    /// contents of the given file path, taking decompression into account.
+    ///
+    /// This implements the pre-pass for `--all-match=file`.
+    fn has_all_patterns(&mut self, path: &Path) -> io::Result<bool> {
+        use self::PatternMatcher::*;
+
+        let matchers = self.config.pattern_matchers.clone();
+        for matcher in matchers.iter() {
+            let mut sink = HasMatchSink::default();
+            if self.should_decompress(path) {
+                let mut rdr = self.decomp_builder.build(path)?;
+                match *matcher {
+                    RustRegex(ref m) => {
+                        self.searcher.search_reader(m, &mut rdr, &mut sink)?
+                    }
+                    #[cfg(feature = "pcre2")]
+                    PCRE2(ref m) => {
+                        self.searcher.search_reader(m, &mut rdr, &mut sink)?
+                    }
+                }
+                rdr.close()?;
+            } else {
+                match *matcher {
+                    RustRegex(ref m) => {
+                        self.searcher.search_path(m, path, &mut sink)?
+                    }
+                    #[cfg(feature = "pcre2")]
+                    PCRE2(ref m) => {
+                        self.searcher.search_path(m, path, &mut sink)?
+                    }
+                }
+            }
+            if !sink.has_match {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
     /// Returns true if and only if the given file path should be
     /// decompressed before searching.
     fn should_decompress(&self, path: &Path) -> bool {
@@ -377,6 +632,12 @@ impl<W: WriteColor> SearchWorker<W> {
         self.decomp_builder.get_matcher().has_command(path)
     }
 
+    /// Returns true if and only if the given file path should be searched as
+    /// an archive.
+    fn should_search_archive(&self, path: &Path) -> bool {
+        self.config.search_archives && cli::ArchiveReader::is_archive(path)
+    }
+
     /// Returns true if and only if the given file path should be run through
     /// the preprocessor.
     fn should_preprocess(&self, path: &Path) -> bool {
@@ -395,6 +656,24 @@ impl<W: WriteColor> SearchWorker<W> {
         &mut self,
         path: &Path,
     ) -> io::Result<SearchResult> {
+        if let Some(ref cache) = self.config.preprocessor_cache {
+            let bin = self.config.preprocessor.as_ref().unwrap();
+            match cache.entry(bin, path) {
+                Ok(entry) => {
+                    return self.search_preprocessor_cached(path, &entry)
+                }
+                Err(err) => {
+                    // An unusable cache entry (e.g., the file vanished
+                    // between walking and searching) is no reason to fail
+                    // the search, so fall through to the uncached path.
+                    log::debug!(
+                        "{}: could not use preprocessor cache: {}",
+                        path.display(),
+                        err,
+                    );
+                }
+            }
+        }
         let bin = self.config.preprocessor.as_ref().unwrap();
         let mut cmd = Command::new(bin);
         cmd.arg(path).stdin(Stdio::from(File::open(path)?));
@@ -420,6 +699,99 @@ impl<W: WriteColor> SearchWorker<W> {
         Ok(search_result)
     }
 
+    /// Like `search_preprocessor`, but reads the preprocessor's output from
+    /// the given cache entry, running the preprocessor (and filling the
+    /// entry) only when the entry does not exist yet.
+    fn search_preprocessor_cached(
+        &mut self,
+        path: &Path,
+        entry: &Path,
+    ) -> io::Result<SearchResult> {
+        if !entry.exists() {
+            let bin = self.config.preprocessor.clone().unwrap();
+            let mut cmd = Command::new(&bin);
+            cmd.arg(path).stdin(Stdio::from(File::open(path)?));
+
+            let mut rdr =
+                self.command_builder.build(&mut cmd).map_err(|err| {
+                    io::Error::new(
+                        io::ErrorKind::Other,
+                        format!(
+                            "preprocessor command could not start: \
+                             '{:?}': {}",
+                            cmd, err,
+                        ),
+                    )
+                })?;
+            let cache = self.config.preprocessor_cache.clone().unwrap();
+            let store_result = cache.store(entry, &mut rdr);
+            let close_result = rdr.close();
+            store_result.map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("preprocessor cache write failed: {}", err),
+                )
+            })?;
+            close_result?;
+        }
+        let mut rdr = File::open(entry)?;
+        self.search_reader(path, &mut rdr)
+    }
+
+    /// Search each file entry in the archive at the given file path. Every
+    /// entry is searched as if it were a file of its own, under a virtual
+    /// path that reflects the nesting, e.g., `archive.zip!/path/inner.txt`.
+    ///
+    /// Glob overrides and file type filters are applied to the entry names,
+    /// so that, e.g., `--type rust` only searches Rust files inside the
+    /// archive.
+    fn search_archive(&mut self, path: &Path) -> io::Result<SearchResult> {
+        let mut aggregate = SearchResult::default();
+        // We can't call methods on `self` while it is mutably borrowed by
+        // the closure below, so pull out what we need first.
+        let (config, searcher, printer, matcher) = (
+            &self.config,
+            &mut self.searcher,
+            &mut self.printer,
+            &self.matcher,
+        );
+        let archive = cli::ArchiveReader::new(path)?;
+        archive.for_each_entry(|name, rdr| {
+            if !config.archive_globs.is_empty()
+                && config.archive_globs.matched(name, false).is_ignore()
+            {
+                return Ok(());
+            }
+            if config.archive_types.matched(name, false).is_ignore() {
+                return Ok(());
+            }
+            let vpath = archive.virtual_path(name);
+            let all_line = all_match_line_matchers(config);
+            let result = {
+                use self::PatternMatcher::*;
+
+                match *matcher {
+                    RustRegex(ref m) => search_reader(
+                        m, searcher, printer, &vpath, rdr, all_line,
+                    ),
+                    #[cfg(feature = "pcre2")]
+                    PCRE2(ref m) => search_reader(
+                        m, searcher, printer, &vpath, rdr, all_line,
+                    ),
+                }
+            }?;
+            aggregate.has_match = aggregate.has_match || result.has_match;
+            if let Some(stats) = result.stats {
+                match aggregate.stats {
+                    Some(ref mut total) => *total += stats,
+                    None => aggregate.stats = Some(stats),
+                }
+            }
+            Ok(())
+        })?;
+        Ok(aggregate)
+    }
+
     /// Attempt to decompress the data at the given file path and search the
     /// result. If the given file path isn't recognized as a compressed file,
     /// then search it without doing any decompression.
@@ -432,16 +804,50 @@ impl<W: WriteColor> SearchWorker<W> {
         Ok(search_result)
     }
 
+    /// Search the contents of the given non-regular file, such as a named
+    /// pipe or a character device, by streaming them.
+    ///
+    /// This avoids memory maps and other file-length heuristics, which
+    /// misbehave on such files. Notably, this makes named pipe arguments
+    /// work on Windows (e.g., from PowerShell process substitution), where
+    /// they cannot be memory mapped.
+    fn search_stream(&mut self, path: &Path) -> io::Result<SearchResult> {
+        log::trace!("{}: searching as a stream", path.display());
+        let mut file = std::fs::File::open(path)?;
+        self.search_reader(path, &mut file)
+    }
+
     /// Search the contents of the given file path.
     fn search_path(&mut self, path: &Path) -> io::Result<SearchResult> {
         use self::PatternMatcher::*;
 
+        // When a byte budget is in effect, note files truncated by it so
+        // that missing matches aren't a surprise.
+        if let Some(limit) = self.searcher.max_bytes_searched() {
+            if path.metadata().map_or(false, |md| md.len() > limit) {
+                message!(
+                    "{}: only the first {} bytes were searched",
+                    path.display(),
+                    limit,
+                );
+            }
+        }
+        let all_line = all_match_line_matchers(&self.config);
         let (searcher, printer) = (&mut self.searcher, &mut self.printer);
-        match self.matcher {
-            RustRegex(ref m) => search_path(m, searcher, printer, path),
+        let result = match self.matcher {
+            RustRegex(ref m) => {
+                search_path(m, searcher, printer, path, all_line)
+            }
             #[cfg(feature = "pcre2")]
-            PCRE2(ref m) => search_path(m, searcher, printer, path),
+            PCRE2(ref m) => search_path(m, searcher, printer, path, all_line),
+        };
+        if self.searcher.timed_out() {
+            message!(
+                "{}: search timed out; results may be incomplete",
+                path.display(),
+            );
         }
+        result
     }
 
     /// Executes a search on the given reader, which may or may not correspond
@@ -460,12 +866,24 @@ impl<W: WriteColor> SearchWorker<W> {
     ) -> io::Result<SearchResult> {
         use self::PatternMatcher::*;
 
+        let all_line = all_match_line_matchers(&self.config);
         let (searcher, printer) = (&mut self.searcher, &mut self.printer);
-        match self.matcher {
-            RustRegex(ref m) => search_reader(m, searcher, printer, path, rdr),
+        let result = match self.matcher {
+            RustRegex(ref m) => {
+                search_reader(m, searcher, printer, path, rdr, all_line)
+            }
             #[cfg(feature = "pcre2")]
-            PCRE2(ref m) => search_reader(m, searcher, printer, path, rdr),
+            PCRE2(ref m) => {
+                search_reader(m, searcher, printer, path, rdr, all_line)
+            }
+        };
+        if self.searcher.timed_out() {
+            message!(
+                "{}: search timed out; results may be incomplete",
+                path.display(),
+            );
         }
+        result
     }
 }
 
@@ -476,11 +894,12 @@ fn search_path<M: Matcher, W: WriteColor>(
     searcher: &mut Searcher,
     printer: &mut Printer<W>,
     path: &Path,
+    all_line: Option<&[PatternMatcher]>,
 ) -> io::Result<SearchResult> {
     match *printer {
         Printer::Standard(ref mut p) => {
             let mut sink = p.sink_with_path(&matcher, path);
-            searcher.search_path(&matcher, path, &mut sink)?;
+            do_search_path(&matcher, searcher, path, &mut sink, all_line)?;
             Ok(SearchResult {
                 has_match: sink.has_match(),
                 stats: sink.stats().map(|s| s.clone()),
@@ -488,7 +907,7 @@ fn search_path<M: Matcher, W: WriteColor>(
         }
         Printer::Summary(ref mut p) => {
             let mut sink = p.sink_with_path(&matcher, path);
-            searcher.search_path(&matcher, path, &mut sink)?;
+            do_search_path(&matcher, searcher, path, &mut sink, all_line)?;
             Ok(SearchResult {
                 has_match: sink.has_match(),
                 stats: sink.stats().map(|s| s.clone()),
@@ -496,7 +915,7 @@ fn search_path<M: Matcher, W: WriteColor>(
         }
         Printer::JSON(ref mut p) => {
             let mut sink = p.sink_with_path(&matcher, path);
-            searcher.search_path(&matcher, path, &mut sink)?;
+            do_search_path(&matcher, searcher, path, &mut sink, all_line)?;
             Ok(SearchResult {
                 has_match: sink.has_match(),
                 stats: Some(sink.stats().clone()),
@@ -513,11 +932,14 @@ fn search_reader<M: Matcher, R: io::Read, W: WriteColor>(
     printer: &mut Printer<W>,
     path: &Path,
     mut rdr: R,
+    all_line: Option<&[PatternMatcher]>,
 ) -> io::Result<SearchResult> {
     match *printer {
         Printer::Standard(ref mut p) => {
             let mut sink = p.sink_with_path(&matcher, path);
-            searcher.search_reader(&matcher, &mut rdr, &mut sink)?;
+            do_search_reader(
+                &matcher, searcher, &mut rdr, &mut sink, all_line,
+            )?;
             Ok(SearchResult {
                 has_match: sink.has_match(),
                 stats: sink.stats().map(|s| s.clone()),
@@ -525,7 +947,9 @@ fn search_reader<M: Matcher, R: io::Read, W: WriteColor>(
         }
         Printer::Summary(ref mut p) => {
             let mut sink = p.sink_with_path(&matcher, path);
-            searcher.search_reader(&matcher, &mut rdr, &mut sink)?;
+            do_search_reader(
+                &matcher, searcher, &mut rdr, &mut sink, all_line,
+            )?;
             Ok(SearchResult {
                 has_match: sink.has_match(),
                 stats: sink.stats().map(|s| s.clone()),
@@ -533,7 +957,9 @@ fn search_reader<M: Matcher, R: io::Read, W: WriteColor>(
         }
         Printer::JSON(ref mut p) => {
             let mut sink = p.sink_with_path(&matcher, path);
-            searcher.search_reader(&matcher, &mut rdr, &mut sink)?;
+            do_search_reader(
+                &matcher, searcher, &mut rdr, &mut sink, all_line,
+            )?;
             Ok(SearchResult {
                 has_match: sink.has_match(),
                 stats: Some(sink.stats().clone()),
@@ -542,6 +968,149 @@ fn search_reader<M: Matcher, R: io::Read, W: WriteColor>(
     }
 }
 
+/// Execute a search of the given file path into the given sink, suppressing
+/// matched lines that do not match every one of the given matchers.
+fn do_search_path<M: Matcher, S: Sink>(
+    matcher: M,
+    searcher: &mut Searcher,
+    path: &Path,
+    mut sink: S,
+    all_line: Option<&[PatternMatcher]>,
+) -> Result<(), S::Error> {
+    match all_line {
+        None => searcher.search_path(matcher, path, sink),
+        Some(matchers) => searcher.search_path(
+            matcher,
+            path,
+            AllMatchSink { sink: &mut sink, matchers },
+        ),
+    }
+}
+
+/// Execute a search of the given reader into the given sink, suppressing
+/// matched lines that do not match every one of the given matchers.
+fn do_search_reader<M: Matcher, R: io::Read, S: Sink>(
+    matcher: M,
+    searcher: &mut Searcher,
+    rdr: R,
+    mut sink: S,
+    all_line: Option<&[PatternMatcher]>,
+) -> Result<(), S::Error> {
+    match all_line {
+        None => searcher.search_reader(matcher, rdr, sink),
+        Some(matchers) => searcher.search_reader(
+            matcher,
+            rdr,
+            AllMatchSink { sink: &mut sink, matchers },
+        ),
+    }
+}
+
+/// Return the matchers that every matched line must satisfy, if line-wise
+/// `--all-match` filtering is in effect.
+fn all_match_line_matchers(config: &Config) -> Option<&[PatternMatcher]> {
+    if config.all_match == Some(AllMatchKind::Line)
+        && config.pattern_matchers.len() > 1
+    {
+        Some(&config.pattern_matchers)
+    } else {
+        None
+    }
+}
+
+/// A sink that records whether a search found anything at all and stops the
+/// search as soon as it does.
+#[derive(Debug, Default)]
+struct HasMatchSink {
+    has_match: bool,
+}
+
+impl Sink for HasMatchSink {
+    type Error = io::Error;
+
+    fn matched(
+        &mut self,
+        _searcher: &Searcher,
+        _mat: &SinkMatch<'_>,
+    ) -> io::Result<bool> {
+        self.has_match = true;
+        Ok(false)
+    }
+}
+
+/// A sink adapter that only forwards matched lines that match every one of
+/// the given matchers, implementing line-wise `--all-match` filtering.
+#[derive(Debug)]
+struct AllMatchSink<'a, S> {
+    sink: S,
+    matchers: &'a [PatternMatcher],
+}
+
+impl<'a, S: Sink> Sink for AllMatchSink<'a, S> {
+    type Error = S::Error;
+
+    fn matched(
+        &mut self,
+        searcher: &Searcher,
+        mat: &SinkMatch<'_>,
+    ) -> Result<bool, S::Error> {
+        if !self.matchers.iter().all(|m| m.is_match(mat.bytes())) {
+            return Ok(true);
+        }
+        self.sink.matched(searcher, mat)
+    }
+
+    fn context(
+        &mut self,
+        searcher: &Searcher,
+        context: &SinkContext<'_>,
+    ) -> Result<bool, S::Error> {
+        self.sink.context(searcher, context)
+    }
+
+    fn context_break(
+        &mut self,
+        searcher: &Searcher,
+    ) -> Result<bool, S::Error> {
+        self.sink.context_break(searcher)
+    }
+
+    fn binary_data(
+        &mut self,
+        searcher: &Searcher,
+        binary_byte_offset: u64,
+    ) -> Result<bool, S::Error> {
+        self.sink.binary_data(searcher, binary_byte_offset)
+    }
+
+    fn begin(&mut self, searcher: &Searcher) -> Result<bool, S::Error> {
+        self.sink.begin(searcher)
+    }
+
+    fn finish(
+        &mut self,
+        searcher: &Searcher,
+        finish: &SinkFinish,
+    ) -> Result<(), S::Error> {
+        self.sink.finish(searcher, finish)
+    }
+}
+
+/// Detect the encoding of the file at the given path by examining a small
+/// prefix of its contents.
+///
+/// `None` is returned when no transcoding is necessary or when the prefix
+/// could not be read. In the latter case, the search itself will report the
+/// underlying error.
+fn detect_file_encoding(path: &Path) -> Option<Encoding> {
+    use std::io::Read;
+
+    let file = File::open(path).ok()?;
+    let mut data = vec![];
+    file.take(8192).read_to_end(&mut data).ok()?;
+    Encoding::detect(&data)
+}
+
 /// Return the given duration as fractional seconds.
 fn fractional_seconds(duration: Duration) -> f64 {
     (duration.as_secs() as f64) + (duration.subsec_nanos() as f64 * 1e-9)
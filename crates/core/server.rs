@@ -0,0 +1,190 @@
+/*!
+Implements ripgrep's server mode for editor integrations.
+
+In server mode, ripgrep reads search requests as JSON objects from stdin,
+one per line, and streams the results of each request as JSON messages to
+stdout. The walker configuration and compiled file type definitions from the
+command line are reused for every request, which amortizes startup cost
+across many queries.
+*/
+
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use serde_json as json;
+use serde_json::json;
+
+use crate::args::Args;
+use crate::Result;
+
+/// A single search request, decoded from one line of stdin.
+#[derive(Debug)]
+struct Request {
+    /// An opaque identifier echoed back in every message produced by this
+    /// request.
+    id: json::Value,
+    /// The patterns to search for.
+    patterns: Vec<String>,
+    /// The paths to search. When empty, the paths from the command line are
+    /// used.
+    paths: Vec<PathBuf>,
+}
+
+/// Run the server until stdin is exhausted.
+///
+/// Returns true if and only if any request found a match.
+pub fn server(args: &Args) -> Result<bool> {
+    let mut matched = false;
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (id, request) = match parse_request(&line) {
+            Ok(request) => (request.id.clone(), Ok(request)),
+            Err((id, msg)) => (id, Err(msg)),
+        };
+        let request = match request {
+            Ok(request) => request,
+            Err(msg) => {
+                send(&json!({"type": "error", "id": id, "message": msg}))?;
+                continue;
+            }
+        };
+        send(&json!({"type": "query_begin", "id": id}))?;
+        match run_request(args, &request) {
+            Ok(request_matched) => {
+                matched = matched || request_matched;
+                send(&json!({
+                    "type": "query_end",
+                    "id": id,
+                    "matched": request_matched,
+                }))?;
+            }
+            Err(err) => {
+                send(&json!({
+                    "type": "error",
+                    "id": id,
+                    "message": err.to_string(),
+                }))?;
+            }
+        }
+    }
+    Ok(matched)
+}
+
+/// Decode a request from the given line. On failure, returns the request id
+/// (when one could be extracted) along with an error message.
+fn parse_request(
+    line: &str,
+) -> std::result::Result<Request, (json::Value, String)> {
+    let value: json::Value = match json::from_str(line) {
+        Ok(value) => value,
+        Err(err) => {
+            return Err((json::Value::Null, format!("invalid JSON: {}", err)))
+        }
+    };
+    let id = value.get("id").cloned().unwrap_or(json::Value::Null);
+    let mut patterns = vec![];
+    if let Some(pattern) = value.get("pattern") {
+        match pattern.as_str() {
+            Some(pattern) => patterns.push(pattern.to_string()),
+            None => {
+                return Err((id, "'pattern' must be a string".to_string()))
+            }
+        }
+    }
+    if let Some(values) = value.get("patterns") {
+        let values = match values.as_array() {
+            Some(values) => values,
+            None => {
+                return Err((id, "'patterns' must be an array".to_string()))
+            }
+        };
+        for value in values {
+            match value.as_str() {
+                Some(pattern) => patterns.push(pattern.to_string()),
+                None => {
+                    return Err((
+                        id,
+                        "'patterns' must contain only strings".to_string(),
+                    ))
+                }
+            }
+        }
+    }
+    if patterns.is_empty() {
+        return Err((
+            id,
+            "request must contain 'pattern' or 'patterns'".to_string(),
+        ));
+    }
+    let mut paths = vec![];
+    if let Some(values) = value.get("paths") {
+        let values = match values.as_array() {
+            Some(values) => values,
+            None => return Err((id, "'paths' must be an array".to_string())),
+        };
+        for value in values {
+            match value.as_str() {
+                Some(path) => paths.push(PathBuf::from(path)),
+                None => {
+                    return Err((
+                        id,
+                        "'paths' must contain only strings".to_string(),
+                    ))
+                }
+            }
+        }
+    }
+    Ok(Request { id, patterns, paths })
+}
+
+/// Execute a search for the given request, streaming results to stdout.
+///
+/// Returns true if and only if the search found a match.
+fn run_request(args: &Args, request: &Request) -> Result<bool> {
+    let subject_builder = args.subject_builder();
+    let mut worker =
+        args.search_worker_for_patterns(&request.patterns, args.stdout())?;
+    let mut matched = false;
+
+    let walkers: Vec<_> = if request.paths.is_empty() {
+        vec![args.walker()?]
+    } else {
+        request
+            .paths
+            .iter()
+            .map(|path| args.walker_for(path))
+            .collect::<Result<_>>()?
+    };
+    for walker in walkers {
+        for result in walker {
+            let subject = match subject_builder.build_from_result(result) {
+                None => continue,
+                Some(subject) => subject,
+            };
+            let search_result = match worker.search(&subject) {
+                Ok(search_result) => search_result,
+                Err(err) => {
+                    err_file_message!(subject.path(), err);
+                    continue;
+                }
+            };
+            matched = matched || search_result.has_match();
+        }
+    }
+    worker.printer().get_mut().flush()?;
+    Ok(matched)
+}
+
+/// Write a single JSON message to stdout, terminated by a line ending.
+fn send(message: &json::Value) -> Result<()> {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    json::to_writer(&mut stdout, message)?;
+    stdout.write_all(b"\n")?;
+    stdout.flush()?;
+    Ok(())
+}
@@ -1,4 +1,6 @@
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 use ignore::{self, DirEntry};
 use log;
@@ -7,11 +9,13 @@ use log;
 #[derive(Clone, Debug)]
 struct Config {
     strip_dot_prefix: bool,
+    dedup: bool,
+    allow_special: bool,
 }
 
 impl Default for Config {
     fn default() -> Config {
-        Config { strip_dot_prefix: false }
+        Config { strip_dot_prefix: false, dedup: false, allow_special: false }
     }
 }
 
@@ -19,12 +23,31 @@ impl Default for Config {
 #[derive(Clone, Debug)]
 pub struct SubjectBuilder {
     config: Config,
+    /// The files seen so far when duplicate suppression is enabled. This is
+    /// shared between clones, so that parallel workers using the same
+    /// builder suppress duplicates across threads.
+    seen: Arc<Mutex<HashSet<FileId>>>,
+}
+
+/// A key that identifies a file regardless of how many paths lead to it.
+#[derive(Debug, Eq, Hash, PartialEq)]
+enum FileId {
+    /// The device and inode numbers, which identify a file across hard
+    /// links. (Unix only.)
+    #[cfg(unix)]
+    Inode { dev: u64, ino: u64 },
+    /// The canonical path, with all symlinks and relative components
+    /// resolved.
+    Canonical(PathBuf),
 }
 
 impl SubjectBuilder {
     /// Return a new subject builder with a default configuration.
     pub fn new() -> SubjectBuilder {
-        SubjectBuilder { config: Config::default() }
+        SubjectBuilder {
+            config: Config::default(),
+            seen: Arc::new(Mutex::new(HashSet::new())),
+        }
     }
 
     /// Create a new subject from a possibly missing directory entry.
@@ -39,7 +62,11 @@ impl SubjectBuilder {
         match result {
             Ok(dent) => self.build(dent),
             Err(err) => {
-                err_message!("{}", err);
+                err_class_message!(
+                    crate::messages::ignore_error_class(&err),
+                    "{}",
+                    err
+                );
                 None
             }
         }
@@ -56,16 +83,26 @@ impl SubjectBuilder {
             ignore_message!("{}", ignore_err);
         }
         // If this entry was explicitly provided by an end user, then we always
-        // want to search it.
+        // want to search it. The exception is special files (FIFOs, sockets
+        // and devices), since reading them can block indefinitely or have
+        // side effects. Those need to be allowed explicitly.
         if subj.is_explicit() {
-            return Some(subj);
+            if subj.is_stream() && !self.config.allow_special {
+                message!(
+                    "ignoring {}: not a regular file \
+                     (use --allow-special-files to search it)",
+                    subj.path().display(),
+                );
+                return None;
+            }
+            return self.dedup_subject(subj);
         }
         // At this point, we only want to search something if it's explicitly a
         // file. This omits symlinks. (If ripgrep was configured to follow
         // symlinks, then they have already been followed by the directory
         // traversal.)
         if subj.is_file() {
-            return Some(subj);
+            return self.dedup_subject(subj);
         }
         // We got nothing. Emit a debug message, but only if this isn't a
         // directory. Otherwise, emitting messages for directories is just
@@ -82,6 +119,48 @@ impl SubjectBuilder {
         None
     }
 
+    /// Apply duplicate suppression to an otherwise searchable subject.
+    ///
+    /// When duplicate suppression is disabled, the subject is returned
+    /// unchanged. Otherwise, the subject is returned only the first time its
+    /// underlying file is seen, so that hard links, repeated search roots
+    /// and symlinked paths don't produce the same results more than once.
+    fn dedup_subject(&self, subj: Subject) -> Option<Subject> {
+        if !self.config.dedup || subj.is_stdin() {
+            return Some(subj);
+        }
+        let id = match subj.file_id() {
+            Some(id) => id,
+            // If the file can't be identified, search it rather than risk
+            // silently dropping results.
+            None => return Some(subj),
+        };
+        if self.seen.lock().unwrap().insert(id) {
+            Some(subj)
+        } else {
+            log::debug!(
+                "ignoring {}: duplicate of a file already searched",
+                subj.path().display(),
+            );
+            None
+        }
+    }
+
+    /// When enabled, special files such as FIFOs, sockets and devices that
+    /// are explicitly given as arguments are searched as streams. Otherwise,
+    /// they are skipped with a message.
+    pub fn allow_special_files(&mut self, yes: bool) -> &mut SubjectBuilder {
+        self.config.allow_special = yes;
+        self
+    }
+
+    /// When enabled, a file that is reachable through multiple paths is
+    /// searched only once.
+    pub fn dedup(&mut self, yes: bool) -> &mut SubjectBuilder {
+        self.config.dedup = yes;
+        self
+    }
+
     /// When enabled, if the subject's file path starts with `./` then it is
     /// stripped.
     ///
@@ -157,4 +236,45 @@ impl Subject {
     fn is_file(&self) -> bool {
         self.dent.file_type().map_or(false, |ft| ft.is_file())
     }
+
+    /// Returns true if and only if this subject should be read as a stream
+    /// rather than as a regular file.
+    ///
+    /// This is the case for explicitly provided arguments that are neither
+    /// regular files nor directories, such as named pipes or character
+    /// devices (e.g., from shell process substitution).
+    pub fn is_stream(&self) -> bool {
+        if self.is_stdin() || !self.is_explicit() {
+            return false;
+        }
+        // The common case: an explicitly provided regular file. Don't pay
+        // for an extra stat call for it.
+        if let Some(ft) = self.dent.file_type() {
+            if ft.is_file() {
+                return false;
+            }
+        }
+        // Follow symlinks, so that a link to a regular file isn't mistaken
+        // for a stream.
+        match std::fs::metadata(self.path()) {
+            Ok(md) => !md.file_type().is_file() && !md.file_type().is_dir(),
+            Err(_) => false,
+        }
+    }
+
+    /// Return a key that identifies this subject's underlying file, if one
+    /// could be determined.
+    fn file_id(&self) -> Option<FileId> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            // Use std's metadata rather than the directory entry's, since
+            // the former follows symlinks.
+            if let Ok(md) = self.dent.path().metadata() {
+                return Some(FileId::Inode { dev: md.dev(), ino: md.ino() });
+            }
+        }
+        std::fs::canonicalize(self.dent.path()).ok().map(FileId::Canonical)
+    }
 }
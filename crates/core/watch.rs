@@ -0,0 +1,152 @@
+/*!
+Implements ripgrep's watch mode.
+
+After the initial search completes, watch mode keeps the process running,
+watches the searched roots for changes and re-searches files as they are
+created or modified, printing only the results for the affected files. The
+process runs until it is interrupted.
+*/
+
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use notify::{RecursiveMode, Watcher};
+
+use crate::args::Args;
+use crate::Result;
+
+/// How long to keep draining file system events after the first one arrives.
+/// Editors commonly produce a flurry of events for a single save, and this
+/// coalesces them into one re-search.
+const DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// Watch the configured search roots and re-search files as they change.
+///
+/// This only returns if the watcher could not be set up or the event stream
+/// is severed. Otherwise, it blocks indefinitely.
+pub fn watch(args: &Args) -> Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })?;
+    for path in args.paths() {
+        let mode = if path.is_dir() {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        watcher.watch(path, mode)?;
+    }
+
+    let cwd = std::env::current_dir()?;
+    let stdout_id = stdout_file_id();
+    let subject_builder = args.subject_builder();
+    let mut worker = args.search_worker(args.stdout())?;
+    loop {
+        let event = match rx.recv() {
+            // The watcher is gone, so there will never be another event.
+            Err(_) => return Ok(()),
+            Ok(event) => event,
+        };
+        let mut changed = BTreeSet::new();
+        collect_changed(event, &mut changed);
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            collect_changed(event, &mut changed);
+        }
+        for path in changed {
+            // If stdout is redirected to a file inside a watched directory,
+            // then our own output triggers change events, which would put us
+            // in an infinite feedback loop. Never re-search that file.
+            if let Some(stdout_id) = stdout_id {
+                if file_id(&path) == Some(stdout_id) {
+                    continue;
+                }
+            }
+            // Events report absolute paths, but results read better (and
+            // match the initial search) when shown relative to the current
+            // directory.
+            let path = match path.strip_prefix(&cwd) {
+                Ok(stripped) => stripped.to_path_buf(),
+                Err(_) => path,
+            };
+            // Re-walk the changed path so that ignore and type filters are
+            // applied to it, and so that a newly created directory has its
+            // contents searched.
+            let walker = match args.walker_for(&path) {
+                Ok(walker) => walker,
+                Err(err) => {
+                    err_message!("{}: {}", path.display(), err);
+                    continue;
+                }
+            };
+            for result in walker {
+                let subject = match subject_builder.build_from_result(result) {
+                    None => continue,
+                    Some(subject) => subject,
+                };
+                if let Err(err) = worker.search(&subject) {
+                    err_file_message!(subject.path(), err);
+                }
+            }
+            let _ = worker.printer().get_mut().flush();
+        }
+    }
+}
+
+/// Returns an identifier for the regular file that stdout is connected to,
+/// if there is one.
+#[cfg(unix)]
+fn stdout_file_id() -> Option<(u64, u64)> {
+    // The metadata call traverses the link, yielding the metadata of
+    // whatever file stdout is actually connected to.
+    file_id(std::path::Path::new("/dev/stdout"))
+}
+
+/// Returns an identifier for the regular file at the given path, if it is
+/// one.
+#[cfg(unix)]
+fn file_id(path: &std::path::Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    let md = std::fs::metadata(path).ok()?;
+    if md.is_file() {
+        Some((md.dev(), md.ino()))
+    } else {
+        None
+    }
+}
+
+/// Stdout identification is only used to break feedback loops on Unix.
+#[cfg(not(unix))]
+fn stdout_file_id() -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(not(unix))]
+fn file_id(_path: &std::path::Path) -> Option<(u64, u64)> {
+    None
+}
+
+/// Add the paths of the given event to `dst` if the event indicates that
+/// their contents may have changed.
+fn collect_changed(
+    event: notify::Result<notify::Event>,
+    dst: &mut BTreeSet<PathBuf>,
+) {
+    let event = match event {
+        Ok(event) => event,
+        Err(err) => {
+            err_message!("error watching for changes: {}", err);
+            return;
+        }
+    };
+    match event.kind {
+        notify::EventKind::Create(_) | notify::EventKind::Modify(_) => {
+            dst.extend(event.paths);
+        }
+        _ => {}
+    }
+}
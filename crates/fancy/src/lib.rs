@@ -0,0 +1,18 @@
+/*!
+An implementation of `grep-matcher`'s `Matcher` trait for
+[fancy-regex](https://docs.rs/fancy-regex).
+
+fancy-regex supports look-around and backreferences by falling back to a
+backtracking VM, while delegating everything else to Rust's regex crate.
+This makes it a useful alternative to PCRE2 for patterns that need
+`(?<=...)` and friends, particularly in environments where building PCRE2
+is difficult or undesirable.
+*/
+
+#![deny(missing_docs)]
+
+pub use crate::error::{Error, ErrorKind};
+pub use crate::matcher::{RegexCaptures, RegexMatcher, RegexMatcherBuilder};
+
+mod error;
+mod matcher;
@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+
+use fancy_regex::{BytesMode, Regex, RegexBuilder};
+use grep_matcher::{Captures, Match, Matcher};
+
+use crate::error::Error;
+
+/// A builder for configuring the compilation of a fancy-regex regex.
+#[derive(Clone, Debug)]
+pub struct RegexMatcherBuilder {
+    case_insensitive: bool,
+    case_smart: bool,
+    multi_line: bool,
+    dot_matches_new_line: bool,
+    crlf: bool,
+    unicode: bool,
+    word: bool,
+    fixed_strings: bool,
+    whole_line: bool,
+    backtrack_limit: Option<usize>,
+}
+
+impl RegexMatcherBuilder {
+    /// Create a new matcher builder with a default configuration.
+    pub fn new() -> RegexMatcherBuilder {
+        RegexMatcherBuilder {
+            case_insensitive: false,
+            case_smart: false,
+            multi_line: false,
+            dot_matches_new_line: false,
+            crlf: false,
+            unicode: true,
+            word: false,
+            fixed_strings: false,
+            whole_line: false,
+            backtrack_limit: None,
+        }
+    }
+
+    /// Compile the given pattern into a fancy-regex matcher using the current
+    /// configuration.
+    ///
+    /// If there was a problem compiling the pattern, then an error is
+    /// returned.
+    pub fn build(&self, pattern: &str) -> Result<RegexMatcher, Error> {
+        self.build_many(&[pattern])
+    }
+
+    /// Compile all of the given patterns into a single regex that matches when
+    /// at least one of the patterns matches.
+    ///
+    /// If there was a problem building the regex, then an error is returned.
+    pub fn build_many<P: AsRef<str>>(
+        &self,
+        patterns: &[P],
+    ) -> Result<RegexMatcher, Error> {
+        let mut pats = Vec::with_capacity(patterns.len());
+        for p in patterns.iter() {
+            pats.push(if self.fixed_strings {
+                format!("(?:{})", fancy_regex::escape(p.as_ref()))
+            } else {
+                format!("(?:{})", p.as_ref())
+            });
+        }
+        let mut singlepat = pats.join("|");
+        if self.whole_line {
+            singlepat = format!(r"(?m:^)(?:{})(?m:$)", singlepat);
+        } else if self.word {
+            // We make this option exclusive with whole_line because when
+            // whole_line is enabled, all matches necessary fall on word
+            // boundaries. So this extra goop is strictly redundant.
+            singlepat = format!(r"(?<!\w)(?:{})(?!\w)", singlepat);
+        }
+        log::trace!("final regex: {:?}", singlepat);
+
+        let mut builder = RegexBuilder::new(&singlepat);
+        builder
+            .case_insensitive(
+                self.case_insensitive
+                    || (self.case_smart
+                        && !has_uppercase_literal(&singlepat)),
+            )
+            .multi_line(self.multi_line)
+            .dot_matches_new_line(self.dot_matches_new_line)
+            .crlf(self.crlf)
+            // We always use a bytes mode since haystacks given to the matcher
+            // are arbitrary bytes and are not guaranteed to be valid UTF-8.
+            .bytes_mode(if self.unicode {
+                BytesMode::UnicodeBytes
+            } else {
+                BytesMode::Ascii
+            });
+        if let Some(limit) = self.backtrack_limit {
+            builder.backtrack_limit(limit);
+        }
+        builder.build().map_err(Error::regex).map(|regex| {
+            let mut names = HashMap::new();
+            for (i, name) in regex.capture_names().enumerate() {
+                if let Some(name) = name {
+                    names.insert(name.to_string(), i);
+                }
+            }
+            RegexMatcher { regex, names }
+        })
+    }
+
+    /// Enables case insensitive matching.
+    ///
+    /// This option corresponds to the `i` flag.
+    pub fn case_insensitive(&mut self, yes: bool) -> &mut RegexMatcherBuilder {
+        self.case_insensitive = yes;
+        self
+    }
+
+    /// Whether to enable "smart case" or not.
+    ///
+    /// When smart case is enabled, the builder will automatically enable
+    /// case insensitive matching based on how the pattern is written. Namely,
+    /// case insensitive mode is enabled when both of the following things
+    /// are believed to be true:
+    ///
+    /// 1. The pattern contains at least one literal character. For example,
+    ///    `a\w` contains a literal (`a`) but `\w` does not.
+    /// 2. Of the literals in the pattern, none of them are considered to be
+    ///    uppercase according to Unicode. For example, `foo\pL` has no
+    ///    uppercase literals but `Foo\pL` does.
+    ///
+    /// Note that the implementation of this is not perfect. Namely, `\p{Ll}`
+    /// will prevent case insensitive matching even though it is part of a meta
+    /// sequence. This bug will probably never be fixed.
+    pub fn case_smart(&mut self, yes: bool) -> &mut RegexMatcherBuilder {
+        self.case_smart = yes;
+        self
+    }
+
+    /// Enable multiline matching mode.
+    ///
+    /// When enabled, the `^` and `$` anchors will match both at the beginning
+    /// and end of a subject string, in addition to matching at the start of
+    /// a line and the end of a line. When disabled, the `^` and `$` anchors
+    /// will only match at the beginning and end of a subject string.
+    ///
+    /// This option corresponds to the `m` flag.
+    pub fn multi_line(&mut self, yes: bool) -> &mut RegexMatcherBuilder {
+        self.multi_line = yes;
+        self
+    }
+
+    /// Enables "dot all" matching.
+    ///
+    /// When enabled, the `.` metacharacter in the pattern matches any
+    /// character, include `\n`. When disabled (the default), `.` will match
+    /// any character except for `\n`.
+    ///
+    /// This option corresponds to the `s` flag.
+    pub fn dot_matches_new_line(
+        &mut self,
+        yes: bool,
+    ) -> &mut RegexMatcherBuilder {
+        self.dot_matches_new_line = yes;
+        self
+    }
+
+    /// Enable matching of CRLF as a line terminator.
+    ///
+    /// When enabled, anchors such as `^` and `$` will match any of the
+    /// following as a line terminator: `\r`, `\n` or `\r\n`.
+    ///
+    /// This is disabled by default, in which case, only `\n` is recognized as
+    /// a line terminator.
+    pub fn crlf(&mut self, yes: bool) -> &mut RegexMatcherBuilder {
+        self.crlf = yes;
+        self
+    }
+
+    /// Enable Unicode matching mode.
+    ///
+    /// When enabled, the following patterns become Unicode aware: `\b`, `\B`,
+    /// `\d`, `\D`, `\s`, `\S`, `\w`, `\W`. When disabled, these patterns
+    /// match their ASCII definitions and `.` matches any byte.
+    ///
+    /// This is enabled by default.
+    pub fn unicode(&mut self, yes: bool) -> &mut RegexMatcherBuilder {
+        self.unicode = yes;
+        self
+    }
+
+    /// Require that all matches occur on word boundaries.
+    ///
+    /// Enabling this option is subtly different than putting `\b` assertions
+    /// on both sides of your pattern. In particular, a `\b` assertion requires
+    /// that one side of it match a word character while the other match a
+    /// non-word character. This option, in contrast, merely requires that
+    /// one side match a non-word character.
+    ///
+    /// For example, `\b-2\b` will not match `foo -2 bar` since `-` is not a
+    /// word character. However, `-2` with this `word` option enabled will
+    /// match the `-2` in `foo -2 bar`.
+    pub fn word(&mut self, yes: bool) -> &mut RegexMatcherBuilder {
+        self.word = yes;
+        self
+    }
+
+    /// Whether the patterns should be treated as literal strings or not. When
+    /// this is active, all characters, including ones that would normally be
+    /// special regex meta characters, are matched literally.
+    pub fn fixed_strings(&mut self, yes: bool) -> &mut RegexMatcherBuilder {
+        self.fixed_strings = yes;
+        self
+    }
+
+    /// Whether each pattern should match the entire line or not. This is
+    /// equivalent to surrounding the pattern with `(?m:^)` and `(?m:$)`.
+    pub fn whole_line(&mut self, yes: bool) -> &mut RegexMatcherBuilder {
+        self.whole_line = yes;
+        self
+    }
+
+    /// Set the limit on the number of backtracking steps permitted during a
+    /// single search.
+    ///
+    /// This only applies to patterns that use the backtracking VM, i.e.,
+    /// patterns that make use of look-around or backreferences. When the
+    /// limit is exceeded, the search returns an error.
+    ///
+    /// By default, fancy-regex's own default limit is used.
+    pub fn backtrack_limit(
+        &mut self,
+        limit: Option<usize>,
+    ) -> &mut RegexMatcherBuilder {
+        self.backtrack_limit = limit;
+        self
+    }
+}
+
+/// An implementation of the `Matcher` trait using fancy-regex.
+///
+/// Patterns that use look-around or backreferences are executed on a
+/// backtracking VM, while all other patterns are delegated to Rust's regex
+/// crate and run at full speed.
+#[derive(Clone, Debug)]
+pub struct RegexMatcher {
+    regex: Regex,
+    names: HashMap<String, usize>,
+}
+
+impl RegexMatcher {
+    /// Create a new matcher from the given pattern using the default
+    /// configuration.
+    pub fn new(pattern: &str) -> Result<RegexMatcher, Error> {
+        RegexMatcherBuilder::new().build(pattern)
+    }
+}
+
+impl Matcher for RegexMatcher {
+    type Captures = RegexCaptures;
+    type Error = Error;
+
+    fn find_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+    ) -> Result<Option<Match>, Error> {
+        Ok(self
+            .regex
+            .find_from_pos(haystack, at)
+            .map_err(Error::regex)?
+            .map(|m| Match::new(m.start(), m.end())))
+    }
+
+    fn new_captures(&self) -> Result<RegexCaptures, Error> {
+        Ok(RegexCaptures { spans: vec![None; self.regex.captures_len()] })
+    }
+
+    fn capture_count(&self) -> usize {
+        self.regex.captures_len()
+    }
+
+    fn capture_index(&self, name: &str) -> Option<usize> {
+        self.names.get(name).map(|i| *i)
+    }
+
+    fn captures_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+        caps: &mut RegexCaptures,
+    ) -> Result<bool, Error> {
+        let fancy_caps = self
+            .regex
+            .captures_from_pos(haystack, at)
+            .map_err(Error::regex)?;
+        match fancy_caps {
+            None => {
+                for span in caps.spans.iter_mut() {
+                    *span = None;
+                }
+                Ok(false)
+            }
+            Some(fancy_caps) => {
+                for (i, span) in caps.spans.iter_mut().enumerate() {
+                    *span = fancy_caps
+                        .get(i)
+                        .map(|m| Match::new(m.start(), m.end()));
+                }
+                Ok(true)
+            }
+        }
+    }
+}
+
+/// Represents the match offsets of each capturing group in a match.
+///
+/// The first, or `0`th capture group, always corresponds to the entire match
+/// and is guaranteed to be present when a match occurs. The next capture
+/// group, at index `1`, corresponds to the first capturing group in the regex,
+/// ordered by the position at which the left opening parenthesis occurs.
+///
+/// Note that not all capturing groups are guaranteed to be present in a match.
+/// For example, in the regex, `(?P<foo>\w)|(?P<bar>\W)`, only one of `foo`
+/// or `bar` will ever be set in any given match.
+///
+/// In order to access a capture group by name, you'll need to first find the
+/// index of the group using the corresponding matcher's `capture_index`
+/// method, and then use that index with `RegexCaptures::get`.
+#[derive(Clone, Debug)]
+pub struct RegexCaptures {
+    /// The spans of each capture group, copied out of the regex engine's
+    /// capture state after each search.
+    spans: Vec<Option<Match>>,
+}
+
+impl Captures for RegexCaptures {
+    fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    fn get(&self, i: usize) -> Option<Match> {
+        self.spans.get(i).copied().flatten()
+    }
+}
+
+/// Determine whether the pattern contains an uppercase character which should
+/// negate the effect of the smart-case option.
+///
+/// Ideally we would be able to check the AST in order to correctly handle
+/// things like '\p{Ll}' and '\p{Lu}' (which should be treated as explicitly
+/// cased), but fancy-regex doesn't expose enough details for that kind of
+/// analysis. For now, our 'good enough' solution is to simply perform a
+/// semi-naïve scan of the input pattern and ignore all characters following
+/// a '\'. This at least lets us support the most common cases, like 'foo\w'
+/// and 'foo\S', in an intuitive manner.
+fn has_uppercase_literal(pattern: &str) -> bool {
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c.is_uppercase() {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grep_matcher::{Captures, Match, Matcher};
+
+    // Test that look-around works, since it's the reason this crate exists.
+    #[test]
+    fn look_around() {
+        let matcher = RegexMatcher::new(r"(?<=foo)\d+").unwrap();
+        assert_eq!(
+            Some(Match::new(3, 6)),
+            matcher.find(b"foo123").unwrap(),
+        );
+        assert_eq!(None, matcher.find(b"bar123").unwrap());
+    }
+
+    // Test that backreferences work.
+    #[test]
+    fn backreference() {
+        let matcher = RegexMatcher::new(r"(\w+) \1").unwrap();
+        assert_eq!(
+            Some(Match::new(0, 7)),
+            matcher.find(b"foo foo bar").unwrap(),
+        );
+        assert_eq!(None, matcher.find(b"foo bar").unwrap());
+    }
+
+    // Test that enabling word matches does the right thing and demonstrate
+    // the difference between it and surrounding the regex in `\b`.
+    #[test]
+    fn word() {
+        let matcher =
+            RegexMatcherBuilder::new().word(true).build(r"-2").unwrap();
+        assert!(matcher.is_match(b"abc -2 foo").unwrap());
+
+        let matcher =
+            RegexMatcherBuilder::new().word(false).build(r"\b-2\b").unwrap();
+        assert!(!matcher.is_match(b"abc -2 foo").unwrap());
+    }
+
+    // Test that the captures API reports offsets and names correctly.
+    #[test]
+    fn captures() {
+        let matcher = RegexMatcher::new(r"(?P<a>\w+) (?<=\w )(\d+)").unwrap();
+        assert_eq!(3, matcher.capture_count());
+        assert_eq!(Some(1), matcher.capture_index("a"));
+
+        let mut caps = matcher.new_captures().unwrap();
+        assert!(matcher.captures(b"foo 123", &mut caps).unwrap());
+        assert_eq!(caps.get(0), Some(Match::new(0, 7)));
+        assert_eq!(caps.get(1), Some(Match::new(0, 3)));
+        assert_eq!(caps.get(2), Some(Match::new(4, 7)));
+    }
+
+    // Test that smart case works.
+    #[test]
+    fn case_smart() {
+        let matcher =
+            RegexMatcherBuilder::new().case_smart(true).build(r"abc").unwrap();
+        assert!(matcher.is_match(b"ABC").unwrap());
+
+        let matcher =
+            RegexMatcherBuilder::new().case_smart(true).build(r"aBc").unwrap();
+        assert!(!matcher.is_match(b"ABC").unwrap());
+    }
+}
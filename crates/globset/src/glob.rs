@@ -1,6 +1,7 @@
 use std::fmt;
 use std::hash;
 use std::iter;
+use std::mem;
 use std::ops::{Deref, DerefMut};
 use std::path::{is_separator, Path};
 use std::str;
@@ -199,22 +200,22 @@ pub struct GlobBuilder<'a> {
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-struct GlobOptions {
+pub(crate) struct GlobOptions {
     /// Whether to match case insensitively.
-    case_insensitive: bool,
+    pub(crate) case_insensitive: bool,
     /// Whether to require a literal separator to match a separator in a file
     /// path. e.g., when enabled, `*` won't match `/`.
-    literal_separator: bool,
+    pub(crate) literal_separator: bool,
     /// Whether or not to use `\` to escape special characters.
     /// e.g., when enabled, `\*` will match a literal `*`.
-    backslash_escape: bool,
+    pub(crate) backslash_escape: bool,
     /// Whether or not an empty case in an alternate will be removed.
     /// e.g., when enabled, `{,a}` will match "" and "a".
-    empty_alternates: bool,
+    pub(crate) empty_alternates: bool,
 }
 
 impl GlobOptions {
-    fn default() -> GlobOptions {
+    pub(crate) fn default() -> GlobOptions {
         GlobOptions {
             case_insensitive: false,
             literal_separator: false,
@@ -278,6 +279,91 @@ impl Glob {
         GlobStrategic { strategy: strategy, re: re }
     }
 
+    /// Returns the options this glob was built with.
+    #[cfg(feature = "serde1")]
+    pub(crate) fn options(&self) -> &GlobOptions {
+        &self.opts
+    }
+
+    /// Returns a copy of this pattern with case insensitive matching set as
+    /// given, with its regular expression and match strategy re-derived
+    /// accordingly. All other options are preserved.
+    pub(crate) fn with_case_insensitive(&self, yes: bool) -> Glob {
+        let mut opts = self.opts;
+        opts.case_insensitive = yes;
+        Glob {
+            glob: self.glob.clone(),
+            re: self.tokens.to_regex_with(&opts),
+            opts,
+            tokens: self.tokens.clone(),
+        }
+    }
+
+    /// Returns true if this pattern could match some path strictly under
+    /// the directory given by the prefix components.
+    ///
+    /// This is a conservative feasibility test: it returns false only when
+    /// the leading literal components of this pattern provably conflict
+    /// with the prefix. e.g., `src/**/*.rs` cannot match anything under
+    /// `tests`.
+    pub(crate) fn can_match_under(&self, prefix: &[&[u8]]) -> bool {
+        let (components, exhaustive) = self.literal_prefix_components();
+        for (glob, path) in components.iter().zip(prefix) {
+            if !self.component_eq(glob.as_bytes(), path) {
+                return false;
+            }
+        }
+        // An entirely literal pattern matches exactly one path, which lies
+        // under the prefix only if it has more components.
+        !exhaustive || components.len() > prefix.len()
+    }
+
+    /// Returns the leading literal path components of this pattern, stopping
+    /// at the first wildcard, class or alternation. The boolean is true when
+    /// the entire pattern is literal.
+    fn literal_prefix_components(&self) -> (Vec<String>, bool) {
+        let mut components = vec![];
+        let mut cur = String::new();
+        for token in self.tokens.iter() {
+            match *token {
+                Token::Literal('/') => {
+                    components.push(mem::take(&mut cur));
+                }
+                Token::Literal(ch) => {
+                    cur.push(ch);
+                }
+                // A recursive suffix consumes the separator that precedes
+                // it, so the component accumulated so far is complete.
+                Token::RecursiveSuffix | Token::RecursiveZeroOrMore => {
+                    components.push(cur);
+                    return (components, false);
+                }
+                // Any other token may extend the current component, so it
+                // cannot be used to prove a conflict.
+                _ => return (components, false),
+            }
+        }
+        components.push(cur);
+        (components, true)
+    }
+
+    /// Returns true if the given literal component of this pattern could
+    /// match the given path component, taking case insensitivity into
+    /// account. Since case folding in the compiled regex is Unicode aware,
+    /// this errs on the side of reporting a match for non-ASCII components.
+    fn component_eq(&self, glob: &[u8], path: &[u8]) -> bool {
+        if glob == path {
+            return true;
+        }
+        if !self.opts.case_insensitive {
+            return false;
+        }
+        if !glob.is_ascii() || !path.is_ascii() {
+            return true;
+        }
+        glob.eq_ignore_ascii_case(path)
+    }
+
     /// Returns the original glob pattern used to build this pattern.
     pub fn glob(&self) -> &str {
         &self.glob
@@ -301,6 +387,39 @@ impl Glob {
         &self.re
     }
 
+    /// Returns the regex for each alternative of the first brace alternation
+    /// in this glob, in the order the alternatives appear in the pattern.
+    ///
+    /// Each regex matches exactly what the whole glob matches when that
+    /// alternative is chosen. A `None` entry corresponds to an empty
+    /// alternative that can never match (i.e., when empty alternates are
+    /// disabled). If this glob has no brace alternation at all, then `None`
+    /// is returned.
+    pub(crate) fn alternative_regexes(&self) -> Option<Vec<Option<String>>> {
+        let pos = self
+            .tokens
+            .iter()
+            .position(|tok| matches!(*tok, Token::Alternates(_)))?;
+        let mut alts = match self.tokens[pos] {
+            Token::Alternates(ref alts) => alts.clone(),
+            _ => unreachable!(),
+        };
+        // The parser collects alternates by popping its stack, so they are
+        // stored in reverse of the order they appear in the pattern.
+        alts.reverse();
+        let mut regexes = vec![];
+        for alt in alts {
+            if alt.is_empty() && !self.opts.empty_alternates {
+                regexes.push(None);
+                continue;
+            }
+            let mut tokens = self.tokens.clone();
+            tokens.0.splice(pos..pos + 1, alt.0);
+            regexes.push(Some(tokens.to_regex_with(&self.opts)));
+        }
+        Some(regexes)
+    }
+
     /// Returns the pattern as a literal if and only if the pattern must match
     /// an entire path exactly.
     ///
@@ -115,8 +115,7 @@ use aho_corasick::AhoCorasick;
 use bstr::{ByteSlice, ByteVec, B};
 use regex::bytes::{Regex, RegexBuilder, RegexSet};
 
-use crate::glob::MatchStrategy;
-pub use crate::glob::{Glob, GlobBuilder, GlobMatcher};
+pub use crate::glob::{Glob, GlobBuilder, GlobMatcher, MatchStrategy};
 use crate::pathutil::{file_name, file_name_ext, normalize_path};
 
 mod glob;
@@ -287,13 +286,14 @@ type Fnv = hash::BuildHasherDefault<fnv::FnvHasher>;
 pub struct GlobSet {
     len: usize,
     strats: Vec<GlobSetMatchStrategy>,
+    globs: Vec<Glob>,
 }
 
 impl GlobSet {
     /// Create an empty `GlobSet`. An empty set matches nothing.
     #[inline]
     pub fn empty() -> GlobSet {
-        GlobSet { len: 0, strats: vec![] }
+        GlobSet { len: 0, strats: vec![], globs: vec![] }
     }
 
     /// Returns true if this set is empty, and therefore matches nothing.
@@ -388,9 +388,74 @@ impl GlobSet {
         into.dedup();
     }
 
+    /// Returns true if any glob in this set could match some path strictly
+    /// under the given directory prefix.
+    ///
+    /// This is a conservative feasibility test meant for pruning entire
+    /// subtrees while walking a directory hierarchy: `false` guarantees that
+    /// no path under `prefix` can match any glob in this set, while `true`
+    /// makes no promise that some path actually does. Only the leading
+    /// literal components of each glob are consulted, so globs that begin
+    /// with a wildcard (e.g., `*.rs` or `**/foo`) are compatible with every
+    /// prefix.
+    ///
+    /// The prefix should be expressed relative to the same point as the
+    /// paths given to `is_match`. An empty set cannot match anything, so it
+    /// reports `false` for every prefix.
+    pub fn can_match_under<P: AsRef<Path>>(&self, prefix: P) -> bool {
+        let prefix = Candidate::new(prefix.as_ref());
+        let mut path: &[u8] = &prefix.path;
+        while path.last() == Some(&b'/') {
+            path = &path[..path.len() - 1];
+        }
+        let components: Vec<&[u8]> = if path.is_empty() {
+            vec![]
+        } else {
+            path.split(|&b| b == b'/').collect()
+        };
+        self.globs.iter().any(|glob| glob.can_match_under(&components))
+    }
+
+    /// Returns details about every glob pattern that matches the given path.
+    ///
+    /// In addition to the sequence number of each matching glob, the details
+    /// include the strategy that was used to match it and, for globs with a
+    /// brace alternation, which alternative of the alternation matched. Only
+    /// the first alternation of a glob is considered.
+    ///
+    /// Computing details is slower than `matches`, so this is intended for
+    /// tooling that explains why a path was matched rather than for use on
+    /// hot paths.
+    pub fn matches_with_details<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Vec<GlobMatchDetails> {
+        self.matches_candidate_with_details(&Candidate::new(path.as_ref()))
+    }
+
+    /// Returns details about every glob pattern that matches the given path.
+    ///
+    /// This takes a Candidate as input, which can be used to amortize the
+    /// cost of preparing a path for matching.
+    pub fn matches_candidate_with_details(
+        &self,
+        path: &Candidate<'_>,
+    ) -> Vec<GlobMatchDetails> {
+        let mut details = vec![];
+        for index in self.matches_candidate(path) {
+            let glob = &self.globs[index];
+            details.push(GlobMatchDetails {
+                index,
+                strategy: MatchStrategy::new(glob),
+                alternative: matched_alternative(glob, path),
+            });
+        }
+        details
+    }
+
     fn new(pats: &[Glob]) -> Result<GlobSet, Error> {
         if pats.is_empty() {
-            return Ok(GlobSet { len: 0, strats: vec![] });
+            return Ok(GlobSet::empty());
         }
         let mut lits = LiteralStrategy::new();
         let mut base_lits = BasenameLiteralStrategy::new();
@@ -452,6 +517,7 @@ impl GlobSet {
                 ),
                 GlobSetMatchStrategy::Regex(regexes.regex_set()?),
             ],
+            globs: pats.to_vec(),
         })
     }
 }
@@ -468,6 +534,7 @@ impl Default for GlobSet {
 #[derive(Clone, Debug)]
 pub struct GlobSetBuilder {
     pats: Vec<Glob>,
+    case_insensitive: bool,
 }
 
 impl GlobSetBuilder {
@@ -475,14 +542,23 @@ impl GlobSetBuilder {
     /// patterns. Once all patterns have been added, `build` should be called
     /// to produce a `GlobSet`, which can then be used for matching.
     pub fn new() -> GlobSetBuilder {
-        GlobSetBuilder { pats: vec![] }
+        GlobSetBuilder { pats: vec![], case_insensitive: false }
     }
 
     /// Builds a new matcher from all of the glob patterns added so far.
     ///
     /// Once a matcher is built, no new patterns can be added to it.
     pub fn build(&self) -> Result<GlobSet, Error> {
-        GlobSet::new(&self.pats)
+        if self.case_insensitive {
+            let pats: Vec<Glob> = self
+                .pats
+                .iter()
+                .map(|pat| pat.with_case_insensitive(true))
+                .collect();
+            GlobSet::new(&pats)
+        } else {
+            GlobSet::new(&self.pats)
+        }
     }
 
     /// Add a new pattern to this set.
@@ -490,6 +566,67 @@ impl GlobSetBuilder {
         self.pats.push(pat);
         self
     }
+
+    /// Toggle whether all patterns in this set match case insensitively.
+    ///
+    /// When enabled, every pattern added to this set matches case
+    /// insensitively when the set is built, regardless of how the individual
+    /// `Glob`s were configured. When disabled (the default), each pattern's
+    /// own setting, as given by
+    /// [`GlobBuilder::case_insensitive`](struct.GlobBuilder.html#method.case_insensitive),
+    /// is respected.
+    pub fn case_insensitive(&mut self, yes: bool) -> &mut GlobSetBuilder {
+        self.case_insensitive = yes;
+        self
+    }
+}
+
+/// Details about a single glob in a set that matched a path.
+///
+/// This is produced by
+/// [`GlobSet::matches_with_details`](struct.GlobSet.html#method.matches_with_details).
+#[derive(Clone, Debug)]
+pub struct GlobMatchDetails {
+    index: usize,
+    strategy: MatchStrategy,
+    alternative: Option<usize>,
+}
+
+impl GlobMatchDetails {
+    /// The sequence number of the glob that matched.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The strategy that was used to match the glob, e.g., a literal
+    /// comparison, an extension lookup or a full regex search.
+    pub fn strategy(&self) -> &MatchStrategy {
+        &self.strategy
+    }
+
+    /// The position of the alternative of the glob's first brace alternation
+    /// that matched, if the glob has one. For example, matching `a.rs`
+    /// against `*.{go,rs}` reports alternative `1`.
+    pub fn alternative(&self) -> Option<usize> {
+        self.alternative
+    }
+}
+
+/// Returns the position of the first alternative of the glob's first brace
+/// alternation that matches the given path, if the glob has an alternation.
+fn matched_alternative(glob: &Glob, path: &Candidate<'_>) -> Option<usize> {
+    for (i, re) in glob.alternative_regexes()?.into_iter().enumerate() {
+        let re = match re {
+            None => continue,
+            Some(re) => re,
+        };
+        if let Ok(re) = new_regex(&re) {
+            if re.is_match(&path.path) {
+                return Some(i);
+            }
+        }
+    }
+    None
 }
 
 /// A candidate path for matching.
@@ -929,6 +1066,106 @@ mod tests {
         assert_eq!(2, matches[1]);
     }
 
+    #[test]
+    fn set_details_works() {
+        use crate::glob::MatchStrategy;
+
+        let mut builder = GlobSetBuilder::new();
+        builder.add(Glob::new("*.{go,rs}").unwrap());
+        builder.add(Glob::new("src/lib.rs").unwrap());
+        builder.add(Glob::new("src/**/*.rs").unwrap());
+        let set = builder.build().unwrap();
+
+        let details = set.matches_with_details("src/lib.rs");
+        assert_eq!(3, details.len());
+
+        assert_eq!(0, details[0].index());
+        assert_eq!(&MatchStrategy::Regex, details[0].strategy());
+        assert_eq!(Some(1), details[0].alternative());
+
+        assert_eq!(1, details[1].index());
+        assert_eq!(
+            &MatchStrategy::Literal("src/lib.rs".to_string()),
+            details[1].strategy(),
+        );
+        assert_eq!(None, details[1].alternative());
+
+        assert_eq!(2, details[2].index());
+        assert_eq!(
+            &MatchStrategy::RequiredExtension(".rs".to_string()),
+            details[2].strategy(),
+        );
+        assert_eq!(None, details[2].alternative());
+
+        let details = set.matches_with_details("main.go");
+        assert_eq!(1, details.len());
+        assert_eq!(0, details[0].index());
+        assert_eq!(Some(0), details[0].alternative());
+    }
+
+    #[test]
+    fn set_case_insensitive_works() {
+        let mut builder = GlobSetBuilder::new();
+        builder.add(Glob::new("*.rs").unwrap());
+        builder.add(Glob::new("src/lib.rs").unwrap());
+        builder.add(Glob::new("Makefile").unwrap());
+        builder.case_insensitive(true);
+        let set = builder.build().unwrap();
+
+        assert!(set.is_match("foo.RS"));
+        assert!(set.is_match("SRC/LIB.RS"));
+        assert!(set.is_match("makefile"));
+        assert!(!set.is_match("foo.c"));
+
+        let matches = set.matches("Src/Lib.rs");
+        assert_eq!(2, matches.len());
+        assert_eq!(0, matches[0]);
+        assert_eq!(1, matches[1]);
+
+        // The default leaves each pattern's own setting alone.
+        builder.case_insensitive(false);
+        let set = builder.build().unwrap();
+        assert!(!set.is_match("foo.RS"));
+        assert!(set.is_match("foo.rs"));
+    }
+
+    #[test]
+    fn set_can_match_under_works() {
+        let mut builder = GlobSetBuilder::new();
+        builder.add(Glob::new("src/**/*.rs").unwrap());
+        builder.add(Glob::new("docs/*.md").unwrap());
+        builder.add(Glob::new("Cargo.toml").unwrap());
+        let set = builder.build().unwrap();
+
+        assert!(set.can_match_under(""));
+        assert!(set.can_match_under("src"));
+        assert!(set.can_match_under("src/grep"));
+        assert!(set.can_match_under("docs/"));
+        assert!(!set.can_match_under("tests"));
+        assert!(!set.can_match_under("benchsuite/runs"));
+
+        // A fully literal glob matches exactly one path, so nothing under
+        // that path (or under a sibling) can match.
+        assert!(!set.can_match_under("Cargo.toml"));
+
+        // Globs that begin with a wildcard are compatible with everything.
+        let mut builder = GlobSetBuilder::new();
+        builder.add(Glob::new("**/*.rs").unwrap());
+        let set = builder.build().unwrap();
+        assert!(set.can_match_under("anything/at/all"));
+
+        // Case insensitivity is respected.
+        let mut builder = GlobSetBuilder::new();
+        builder.add(Glob::new("src/**").unwrap());
+        builder.case_insensitive(true);
+        let set = builder.build().unwrap();
+        assert!(set.can_match_under("SRC"));
+        assert!(!set.can_match_under("TESTS"));
+
+        // An empty set matches nothing at all.
+        assert!(!GlobSet::empty().can_match_under("src"));
+    }
+
     #[test]
     fn empty_set_works() {
         let set = GlobSetBuilder::new().build().unwrap();
@@ -1,9 +1,11 @@
 use serde::{
-    de::{Error, SeqAccess, Visitor},
+    de::{Error, MapAccess, SeqAccess, Visitor},
+    ser::{SerializeMap, SerializeSeq},
     {Deserialize, Deserializer, Serialize, Serializer},
 };
 
-use crate::{Glob, GlobSet, GlobSetBuilder};
+use crate::glob::GlobOptions;
+use crate::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
 
 impl Serialize for Glob {
     fn serialize<S: Serializer>(
@@ -42,6 +44,147 @@ impl<'de> Deserialize<'de> for Glob {
     }
 }
 
+/// The option field names accepted in the map form of a glob entry.
+const GLOB_ENTRY_FIELDS: &[&str] = &[
+    "glob",
+    "case_insensitive",
+    "literal_separator",
+    "backslash_escape",
+    "empty_alternates",
+];
+
+/// Serializes a glob in a glob set, including its options.
+///
+/// A glob built with default options serializes as a plain pattern string.
+/// Otherwise, it serializes as a map of the pattern and its options, so that
+/// the options survive a round trip.
+struct GlobEntryRef<'a>(&'a Glob);
+
+impl<'a> Serialize for GlobEntryRef<'a> {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let opts = self.0.options();
+        if *opts == GlobOptions::default() {
+            return serializer.serialize_str(self.0.glob());
+        }
+        let mut map = serializer.serialize_map(Some(5))?;
+        map.serialize_entry("glob", self.0.glob())?;
+        map.serialize_entry("case_insensitive", &opts.case_insensitive)?;
+        map.serialize_entry("literal_separator", &opts.literal_separator)?;
+        map.serialize_entry("backslash_escape", &opts.backslash_escape)?;
+        map.serialize_entry("empty_alternates", &opts.empty_alternates)?;
+        map.end()
+    }
+}
+
+/// Deserializes a glob in a glob set, either from a plain pattern string or
+/// from the map form produced by `GlobEntryRef`.
+struct GlobEntry(Glob);
+
+struct GlobEntryVisitor;
+
+impl<'de> Visitor<'de> for GlobEntryVisitor {
+    type Value = GlobEntry;
+
+    fn expecting(
+        &self,
+        formatter: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        formatter.write_str("a glob pattern or a map of a glob and options")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        Glob::new(v).map(GlobEntry).map_err(serde::de::Error::custom)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut glob: Option<String> = None;
+        let mut case_insensitive: Option<bool> = None;
+        let mut literal_separator: Option<bool> = None;
+        let mut backslash_escape: Option<bool> = None;
+        let mut empty_alternates: Option<bool> = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match &*key {
+                "glob" => glob = Some(map.next_value()?),
+                "case_insensitive" => {
+                    case_insensitive = Some(map.next_value()?)
+                }
+                "literal_separator" => {
+                    literal_separator = Some(map.next_value()?)
+                }
+                "backslash_escape" => {
+                    backslash_escape = Some(map.next_value()?)
+                }
+                "empty_alternates" => {
+                    empty_alternates = Some(map.next_value()?)
+                }
+                unknown => {
+                    return Err(A::Error::unknown_field(
+                        unknown,
+                        GLOB_ENTRY_FIELDS,
+                    ));
+                }
+            }
+        }
+        let glob = glob.ok_or_else(|| A::Error::missing_field("glob"))?;
+        let mut builder = GlobBuilder::new(&glob);
+        if let Some(yes) = case_insensitive {
+            builder.case_insensitive(yes);
+        }
+        if let Some(yes) = literal_separator {
+            builder.literal_separator(yes);
+        }
+        if let Some(yes) = backslash_escape {
+            builder.backslash_escape(yes);
+        }
+        if let Some(yes) = empty_alternates {
+            builder.empty_alternates(yes);
+        }
+        builder.build().map(GlobEntry).map_err(serde::de::Error::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for GlobEntry {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(GlobEntryVisitor)
+    }
+}
+
+/// Serializes a sequence of globs as glob entries.
+struct GlobEntries<'a>(&'a [Glob]);
+
+impl<'a> Serialize for GlobEntries<'a> {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for glob in self.0 {
+            seq.serialize_element(&GlobEntryRef(glob))?;
+        }
+        seq.end()
+    }
+}
+
+impl Serialize for GlobSet {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        GlobEntries(&self.globs).serialize(serializer)
+    }
+}
+
 struct GlobSetVisitor;
 
 impl<'de> Visitor<'de> for GlobSetVisitor {
@@ -59,7 +202,7 @@ impl<'de> Visitor<'de> for GlobSetVisitor {
         A: SeqAccess<'de>,
     {
         let mut builder = GlobSetBuilder::new();
-        while let Some(glob) = seq.next_element()? {
+        while let Some(GlobEntry(glob)) = seq.next_element()? {
             builder.add(glob);
         }
         builder.build().map_err(serde::de::Error::custom)
@@ -74,11 +217,72 @@ impl<'de> Deserialize<'de> for GlobSet {
     }
 }
 
+impl Serialize for GlobSetBuilder {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("patterns", &GlobEntries(&self.pats))?;
+        map.serialize_entry("case_insensitive", &self.case_insensitive)?;
+        map.end()
+    }
+}
+
+struct GlobSetBuilderVisitor;
+
+impl<'de> Visitor<'de> for GlobSetBuilderVisitor {
+    type Value = GlobSetBuilder;
+
+    fn expecting(
+        &self,
+        formatter: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        formatter.write_str("a map of glob patterns and options")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut builder = GlobSetBuilder::new();
+        while let Some(key) = map.next_key::<String>()? {
+            match &*key {
+                "patterns" => {
+                    for GlobEntry(glob) in
+                        map.next_value::<Vec<GlobEntry>>()?
+                    {
+                        builder.add(glob);
+                    }
+                }
+                "case_insensitive" => {
+                    builder.case_insensitive(map.next_value()?);
+                }
+                unknown => {
+                    return Err(A::Error::unknown_field(
+                        unknown,
+                        &["patterns", "case_insensitive"],
+                    ));
+                }
+            }
+        }
+        Ok(builder)
+    }
+}
+
+impl<'de> Deserialize<'de> for GlobSetBuilder {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(GlobSetBuilderVisitor)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
 
-    use crate::{Glob, GlobSet};
+    use crate::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
 
     #[test]
     fn glob_deserialize_borrowed() {
@@ -125,4 +329,51 @@ mod tests {
         assert!(set.is_match("src/lib.rs"));
         assert!(!set.is_match("Cargo.lock"));
     }
+
+    #[test]
+    fn glob_set_deserialize_with_options() {
+        let j = r#" [
+            {"glob": "*.md", "case_insensitive": true},
+            "src/**/*.rs"
+        ] "#;
+        let set: GlobSet = serde_json::from_str(j).unwrap();
+        assert!(set.is_match("README.MD"));
+        assert!(set.is_match("src/lib.rs"));
+        assert!(!set.is_match("SRC/LIB.RS"));
+    }
+
+    #[test]
+    fn glob_set_deserialize_unknown_option() {
+        let j = r#" [{"glob": "*.md", "invert": true}] "#;
+        assert!(serde_json::from_str::<GlobSet>(j).is_err());
+    }
+
+    #[test]
+    fn glob_set_round_trip() {
+        let mut builder = GlobSetBuilder::new();
+        builder.add(Glob::new("src/**/*.rs").unwrap());
+        builder.add(
+            GlobBuilder::new("*.md").case_insensitive(true).build().unwrap(),
+        );
+        let set = builder.build().unwrap();
+
+        let ser = serde_json::to_string(&set).unwrap();
+        let de: GlobSet = serde_json::from_str(&ser).unwrap();
+        assert!(de.is_match("src/lib.rs"));
+        assert!(de.is_match("README.MD"));
+        assert!(!de.is_match("Cargo.lock"));
+    }
+
+    #[test]
+    fn glob_set_builder_round_trip() {
+        let mut builder = GlobSetBuilder::new();
+        builder.add(Glob::new("*.rs").unwrap());
+        builder.case_insensitive(true);
+
+        let ser = serde_json::to_string(&builder).unwrap();
+        let de: GlobSetBuilder = serde_json::from_str(&ser).unwrap();
+        let set = de.build().unwrap();
+        assert!(set.is_match("LIB.RS"));
+        assert!(!set.is_match("lib.py"));
+    }
 }
@@ -0,0 +1,283 @@
+/*!
+Defines runtime selection of a regex engine.
+
+ripgrep is built against several regex engines: Rust's regex crate, PCRE2
+and fancy-regex, with the latter two behind Cargo features. The
+[`matcher_for`](fn.matcher_for.html) function in this module hides that
+plumbing: it picks an engine based on the pattern and on which engines were
+compiled in, and returns a boxed [`DynMatcher`](struct.DynMatcher.html)
+that can be handed to a searcher like any other matcher. This saves
+downstream code from sprinkling `cfg(feature = "pcre2")` branches at every
+place a matcher is built.
+*/
+
+use std::fmt;
+
+use crate::matcher::{ByteSet, LineTerminator, Match, Matcher, NoCaptures};
+use crate::regex::RegexMatcherBuilder;
+use crate::search::Result;
+
+/// The regex engine to use for a pattern.
+///
+/// The `PCRE2` and `Fancy` variants always exist, but building a matcher
+/// with them fails at runtime when the corresponding Cargo feature was not
+/// enabled. This keeps `cfg` conditionals out of calling code.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EngineChoice {
+    /// Choose an engine based on the pattern.
+    ///
+    /// A pattern without any regex meta characters uses the literal engine.
+    /// Everything else uses Rust's regex engine, falling back to PCRE2 or
+    /// fancy-regex (in that order, when available) for patterns that Rust's
+    /// engine rejects, such as those with look-around or backreferences.
+    Auto,
+    /// Rust's regex engine.
+    RustRegex,
+    /// Search for the pattern as a literal string, without interpreting any
+    /// regex meta characters.
+    Literal,
+    /// The PCRE2 engine. Requires the `pcre2` feature.
+    PCRE2,
+    /// The fancy-regex engine. Requires the `fancy` feature.
+    Fancy,
+}
+
+/// Build a matcher for the given pattern using the given engine choice.
+///
+/// The returned matcher does not expose capturing groups; callers that need
+/// captures should build a concrete engine's matcher directly.
+///
+/// # Example
+///
+/// ```
+/// use grep::{matcher_for, EngineChoice};
+///
+/// # fn example() -> grep::search::Result<()> {
+/// let matcher = matcher_for(r"foo.bar", EngineChoice::Auto)?;
+/// assert_eq!("rust", matcher.engine());
+/// let matcher = matcher_for("foo.bar", EngineChoice::Literal)?;
+/// assert_eq!("literal", matcher.engine());
+/// # Ok(()) }
+/// # example().unwrap();
+/// ```
+pub fn matcher_for(
+    pattern: &str,
+    choice: EngineChoice,
+) -> Result<DynMatcher> {
+    match choice {
+        EngineChoice::Auto => {
+            if is_literal(pattern) {
+                return build_literal(pattern);
+            }
+            match build_rust(pattern) {
+                Ok(matcher) => Ok(matcher),
+                Err(err) => build_fallback(pattern, err),
+            }
+        }
+        EngineChoice::RustRegex => build_rust(pattern),
+        EngineChoice::Literal => build_literal(pattern),
+        EngineChoice::PCRE2 => build_pcre2(pattern),
+        EngineChoice::Fancy => build_fancy(pattern),
+    }
+}
+
+/// A matcher whose regex engine is chosen at runtime.
+///
+/// This wraps any of the engines' matchers behind one concrete type
+/// implementing the `Matcher` trait, so that it can be stored and passed
+/// around without generics. Capturing groups are not supported; matching
+/// behaves as if the pattern had no groups.
+pub struct DynMatcher {
+    imp: Box<dyn DynMatcherImp + Send + Sync>,
+    engine: &'static str,
+}
+
+impl DynMatcher {
+    /// Wrap the given matcher, erasing its concrete type.
+    ///
+    /// `engine` is a short human readable name for the underlying engine,
+    /// as reported by the `engine` method.
+    pub fn new<M>(matcher: M, engine: &'static str) -> DynMatcher
+    where
+        M: Matcher + Send + Sync + 'static,
+    {
+        DynMatcher { imp: Box::new(matcher), engine }
+    }
+
+    /// The name of the underlying engine, e.g., `rust`, `literal`, `pcre2`
+    /// or `fancy`.
+    pub fn engine(&self) -> &'static str {
+        self.engine
+    }
+}
+
+impl fmt::Debug for DynMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynMatcher").field("engine", &self.engine).finish()
+    }
+}
+
+impl Matcher for DynMatcher {
+    type Captures = NoCaptures;
+    type Error = String;
+
+    fn find_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+    ) -> std::result::Result<Option<Match>, String> {
+        self.imp.dyn_find_at(haystack, at)
+    }
+
+    fn new_captures(&self) -> std::result::Result<NoCaptures, String> {
+        Ok(NoCaptures::new())
+    }
+
+    fn line_terminator(&self) -> Option<LineTerminator> {
+        self.imp.dyn_line_terminator()
+    }
+
+    fn non_matching_bytes(&self) -> Option<&ByteSet> {
+        self.imp.dyn_non_matching_bytes()
+    }
+}
+
+/// An object safe subset of the `Matcher` trait.
+///
+/// The `Matcher` trait itself has generic methods and associated types, so
+/// it cannot be boxed directly. This trait captures just what `DynMatcher`
+/// needs to forward, with errors erased to strings.
+trait DynMatcherImp {
+    fn dyn_find_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+    ) -> std::result::Result<Option<Match>, String>;
+
+    fn dyn_line_terminator(&self) -> Option<LineTerminator>;
+
+    fn dyn_non_matching_bytes(&self) -> Option<&ByteSet>;
+}
+
+impl<M: Matcher> DynMatcherImp for M {
+    fn dyn_find_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+    ) -> std::result::Result<Option<Match>, String> {
+        self.find_at(haystack, at).map_err(|err| err.to_string())
+    }
+
+    fn dyn_line_terminator(&self) -> Option<LineTerminator> {
+        self.line_terminator()
+    }
+
+    fn dyn_non_matching_bytes(&self) -> Option<&ByteSet> {
+        self.non_matching_bytes()
+    }
+}
+
+/// Returns true if the given character is reserved by regex syntax.
+///
+/// This is the same set reserved by the regex crate, which includes a few
+/// characters reserved for future use.
+fn is_meta_character(c: char) -> bool {
+    matches!(
+        c,
+        '\\' | '.'
+            | '+'
+            | '*'
+            | '?'
+            | '('
+            | ')'
+            | '|'
+            | '['
+            | ']'
+            | '{'
+            | '}'
+            | '^'
+            | '$'
+            | '#'
+            | '&'
+            | '-'
+            | '~'
+    )
+}
+
+/// Returns true if the given pattern has no regex meta characters and so
+/// matches itself literally.
+fn is_literal(pattern: &str) -> bool {
+    !pattern.chars().any(is_meta_character)
+}
+
+/// Escape the given pattern so that a regex engine matches it literally.
+fn escape(pattern: &str) -> String {
+    let mut escaped = String::with_capacity(pattern.len());
+    for c in pattern.chars() {
+        if is_meta_character(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn build_rust(pattern: &str) -> Result<DynMatcher> {
+    let matcher = RegexMatcherBuilder::new().build(pattern)?;
+    Ok(DynMatcher::new(matcher, "rust"))
+}
+
+fn build_literal(pattern: &str) -> Result<DynMatcher> {
+    let matcher =
+        RegexMatcherBuilder::new().build_literals(&[escape(pattern)])?;
+    Ok(DynMatcher::new(matcher, "literal"))
+}
+
+#[cfg(feature = "pcre2")]
+fn build_pcre2(pattern: &str) -> Result<DynMatcher> {
+    let matcher = crate::pcre2::RegexMatcherBuilder::new().build(pattern)?;
+    Ok(DynMatcher::new(matcher, "pcre2"))
+}
+
+#[cfg(not(feature = "pcre2"))]
+fn build_pcre2(_pattern: &str) -> Result<DynMatcher> {
+    Err(crate::ErrorKind::UnavailableEngine {
+        engine: "PCRE2",
+        feature: "pcre2",
+    }
+    .into())
+}
+
+#[cfg(feature = "fancy")]
+fn build_fancy(pattern: &str) -> Result<DynMatcher> {
+    let matcher = crate::fancy::RegexMatcherBuilder::new().build(pattern)?;
+    Ok(DynMatcher::new(matcher, "fancy"))
+}
+
+#[cfg(not(feature = "fancy"))]
+fn build_fancy(_pattern: &str) -> Result<DynMatcher> {
+    Err(crate::ErrorKind::UnavailableEngine {
+        engine: "fancy-regex",
+        feature: "fancy",
+    }
+    .into())
+}
+
+/// Fall back to an engine that supports more syntax than Rust's regex
+/// engine, or report the original error when no fallback is available.
+fn build_fallback(
+    pattern: &str,
+    err: crate::Error,
+) -> Result<DynMatcher> {
+    if cfg!(feature = "pcre2") {
+        if let Ok(matcher) = build_pcre2(pattern) {
+            return Ok(matcher);
+        }
+    }
+    if cfg!(feature = "fancy") {
+        if let Ok(matcher) = build_fancy(pattern) {
+            return Ok(matcher);
+        }
+    }
+    Err(err)
+}
@@ -0,0 +1,170 @@
+/*!
+Defines a unified error type for the grep crates.
+
+Each of the constituent crates defines its own error type: compiling a
+pattern, configuring a searcher, walking a directory tree, parsing a color
+specification and plain I/O all fail differently. The
+[`Error`](struct.Error.html) type in this module wraps all of them behind a
+single type with a [`kind`](struct.Error.html#method.kind) accessor and a
+`source` chain, so embedders only need to handle one error type.
+*/
+
+use std::error;
+use std::fmt;
+use std::io;
+
+/// An error that can occur in any of the grep crates.
+#[derive(Debug)]
+pub struct Error {
+    kind: ErrorKind,
+}
+
+impl Error {
+    /// Return the kind of this error.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+}
+
+/// The underlying kind of an [`Error`](struct.Error.html).
+#[derive(Debug)]
+pub enum ErrorKind {
+    /// An error compiling a pattern with Rust's regex engine.
+    Regex(crate::regex::Error),
+    /// An error compiling a pattern with the PCRE2 engine.
+    #[cfg(feature = "pcre2")]
+    Pcre2(crate::pcre2::Error),
+    /// An error compiling a pattern with the fancy-regex engine.
+    #[cfg(feature = "fancy")]
+    Fancy(crate::fancy::Error),
+    /// A non-sensical searcher configuration.
+    SearcherConfig(crate::searcher::ConfigError),
+    /// An error walking a directory tree or parsing an ignore rule.
+    Ignore(ignore::Error),
+    /// An invalid color specification.
+    Color(crate::printer::ColorError),
+    /// An invalid hyperlink format.
+    HyperlinkFormat(crate::printer::HyperlinkFormatError),
+    /// An I/O error.
+    Io(io::Error),
+    /// A regex engine that was requested at runtime but not compiled in.
+    UnavailableEngine {
+        /// The name of the engine, e.g., `PCRE2`.
+        engine: &'static str,
+        /// The Cargo feature that enables the engine, e.g., `pcre2`.
+        feature: &'static str,
+    },
+    /// An unstructured error message.
+    Msg(String),
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self.kind {
+            ErrorKind::Regex(ref err) => Some(err),
+            #[cfg(feature = "pcre2")]
+            ErrorKind::Pcre2(ref err) => Some(err),
+            #[cfg(feature = "fancy")]
+            ErrorKind::Fancy(ref err) => Some(err),
+            ErrorKind::SearcherConfig(ref err) => Some(err),
+            ErrorKind::Ignore(ref err) => Some(err),
+            ErrorKind::Color(ref err) => Some(err),
+            ErrorKind::HyperlinkFormat(ref err) => Some(err),
+            ErrorKind::Io(ref err) => Some(err),
+            ErrorKind::UnavailableEngine { .. } => None,
+            ErrorKind::Msg(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::Regex(ref err) => err.fmt(f),
+            #[cfg(feature = "pcre2")]
+            ErrorKind::Pcre2(ref err) => err.fmt(f),
+            #[cfg(feature = "fancy")]
+            ErrorKind::Fancy(ref err) => err.fmt(f),
+            ErrorKind::SearcherConfig(ref err) => err.fmt(f),
+            ErrorKind::Ignore(ref err) => err.fmt(f),
+            ErrorKind::Color(ref err) => err.fmt(f),
+            ErrorKind::HyperlinkFormat(ref err) => err.fmt(f),
+            ErrorKind::Io(ref err) => err.fmt(f),
+            ErrorKind::UnavailableEngine { engine, feature } => write!(
+                f,
+                "{} is not available (this was compiled without the \
+                 '{}' feature)",
+                engine, feature,
+            ),
+            ErrorKind::Msg(ref msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Error {
+        Error { kind }
+    }
+}
+
+impl From<crate::regex::Error> for Error {
+    fn from(err: crate::regex::Error) -> Error {
+        Error { kind: ErrorKind::Regex(err) }
+    }
+}
+
+#[cfg(feature = "pcre2")]
+impl From<crate::pcre2::Error> for Error {
+    fn from(err: crate::pcre2::Error) -> Error {
+        Error { kind: ErrorKind::Pcre2(err) }
+    }
+}
+
+#[cfg(feature = "fancy")]
+impl From<crate::fancy::Error> for Error {
+    fn from(err: crate::fancy::Error) -> Error {
+        Error { kind: ErrorKind::Fancy(err) }
+    }
+}
+
+impl From<crate::searcher::ConfigError> for Error {
+    fn from(err: crate::searcher::ConfigError) -> Error {
+        Error { kind: ErrorKind::SearcherConfig(err) }
+    }
+}
+
+impl From<ignore::Error> for Error {
+    fn from(err: ignore::Error) -> Error {
+        Error { kind: ErrorKind::Ignore(err) }
+    }
+}
+
+impl From<crate::printer::ColorError> for Error {
+    fn from(err: crate::printer::ColorError) -> Error {
+        Error { kind: ErrorKind::Color(err) }
+    }
+}
+
+impl From<crate::printer::HyperlinkFormatError> for Error {
+    fn from(err: crate::printer::HyperlinkFormatError) -> Error {
+        Error { kind: ErrorKind::HyperlinkFormat(err) }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error { kind: ErrorKind::Io(err) }
+    }
+}
+
+impl From<String> for Error {
+    fn from(msg: String) -> Error {
+        Error { kind: ErrorKind::Msg(msg) }
+    }
+}
+
+impl From<&str> for Error {
+    fn from(msg: &str) -> Error {
+        Error { kind: ErrorKind::Msg(msg.to_string()) }
+    }
+}
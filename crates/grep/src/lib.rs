@@ -2,9 +2,12 @@
 ripgrep, as a library.
 
 This library is intended to provide a high level facade to the crates that
-make up ripgrep's core searching routines. However, there is no high level
-documentation available yet guiding users on how to fit all of the pieces
-together.
+make up ripgrep's core searching routines. The
+[`SearchBuilder`](struct.SearchBuilder.html) in this crate covers the most
+common use cases: it compiles patterns, traverses directories while
+respecting gitignore rules and reports matches to a callback or to one of
+the printers. For anything it doesn't cover, the constituent crates are
+re-exported as modules and can be combined by hand.
 
 Every public API item in the constituent crates is documented, but examples
 are sparse.
@@ -13,9 +16,22 @@ A cookbook and a guide are planned.
 */
 
 pub extern crate grep_cli as cli;
+#[cfg(feature = "fancy")]
+pub extern crate grep_fancy as fancy;
 pub extern crate grep_matcher as matcher;
 #[cfg(feature = "pcre2")]
 pub extern crate grep_pcre2 as pcre2;
 pub extern crate grep_printer as printer;
 pub extern crate grep_regex as regex;
 pub extern crate grep_searcher as searcher;
+
+pub use crate::engine::{matcher_for, DynMatcher, EngineChoice};
+pub use crate::error::{Error, ErrorKind};
+pub use crate::search::{
+    CancellationToken, SearchBuilder, SearchMatch, SearchPrinter,
+};
+
+pub mod engine;
+pub mod error;
+pub mod results;
+pub mod search;
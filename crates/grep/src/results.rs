@@ -0,0 +1,202 @@
+/*!
+Defines owned, structured search results and collectors for them.
+
+The searcher reports results through the `Sink` trait, whose callbacks
+borrow from the searcher's internal buffers. For the common case of just
+wanting a `Vec` of matches, this module provides a
+[`Collector`](struct.Collector.html) sink that copies each matching line
+into an owned [`LineMatch`](struct.LineMatch.html), including the spans of
+the individual matches within the line, and groups them into a
+[`FileMatches`](struct.FileMatches.html).
+
+When even wiring a searcher is more than needed,
+[`SearchBuilder::collect`](../struct.SearchBuilder.html#method.collect)
+runs a whole search and returns these types directly.
+*/
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::matcher::{Match, Matcher};
+use crate::searcher::{Searcher, Sink, SinkError, SinkMatch};
+
+/// The structured matches found in one file.
+#[derive(Clone, Debug)]
+pub struct FileMatches {
+    path: Option<PathBuf>,
+    matches: Vec<LineMatch>,
+}
+
+impl FileMatches {
+    /// The path of the file searched, if one was given to the collector.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// The matching lines, in the order they appear in the file.
+    pub fn matches(&self) -> &[LineMatch] {
+        &self.matches
+    }
+
+    /// The number of matching lines.
+    pub fn len(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// Returns true if no lines matched.
+    pub fn is_empty(&self) -> bool {
+        self.matches.is_empty()
+    }
+
+    /// Consume this value, returning the matching lines.
+    pub fn into_matches(self) -> Vec<LineMatch> {
+        self.matches
+    }
+}
+
+impl IntoIterator for FileMatches {
+    type Item = LineMatch;
+    type IntoIter = std::vec::IntoIter<LineMatch>;
+
+    fn into_iter(self) -> std::vec::IntoIter<LineMatch> {
+        self.matches.into_iter()
+    }
+}
+
+/// A single matching line, owned and independent of any searcher.
+#[derive(Clone, Debug)]
+pub struct LineMatch {
+    line_number: Option<u64>,
+    byte_offset: u64,
+    spans: Vec<Match>,
+    text: Vec<u8>,
+}
+
+impl LineMatch {
+    /// The 1-based line number of this line, if line numbers were enabled
+    /// on the searcher.
+    pub fn line_number(&self) -> Option<u64> {
+        self.line_number
+    }
+
+    /// The absolute byte offset of the start of this line within the file.
+    pub fn byte_offset(&self) -> u64 {
+        self.byte_offset
+    }
+
+    /// The spans of the individual matches within `text`, in order.
+    pub fn spans(&self) -> &[Match] {
+        &self.spans
+    }
+
+    /// The contents of the line, without its line terminator.
+    ///
+    /// This may contain arbitrary bytes; use `String::from_utf8_lossy` or
+    /// similar to display it.
+    pub fn text(&self) -> &[u8] {
+        &self.text
+    }
+}
+
+/// A sink that collects matches into owned, structured results.
+///
+/// A collector wraps the matcher used by the search so that it can record
+/// the spans of the matches within each line. After a search, the results
+/// are extracted with `take` or `into_file_matches`.
+///
+/// # Example
+///
+/// ```
+/// use grep::regex::RegexMatcher;
+/// use grep::results::Collector;
+/// use grep::searcher::Searcher;
+///
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let matcher = RegexMatcher::new(r"ma\w+")?;
+/// let mut collector = Collector::new(&matcher);
+/// Searcher::new().search_slice(
+///     &matcher,
+///     b"fn main() {}\nnope\n",
+///     &mut collector,
+/// )?;
+/// let results = collector.into_file_matches();
+/// assert_eq!(1, results.len());
+/// assert_eq!(b"fn main() {}", results.matches()[0].text());
+/// assert_eq!(3..7, {
+///     let span = results.matches()[0].spans()[0];
+///     span.start()..span.end()
+/// });
+/// # Ok(()) }
+/// # example().unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Collector<M> {
+    matcher: M,
+    path: Option<PathBuf>,
+    matches: Vec<LineMatch>,
+}
+
+impl<M: Matcher> Collector<M> {
+    /// Create a new collector using the given matcher to locate match
+    /// spans.
+    ///
+    /// The matcher should be the same one given to the searcher, and is
+    /// typically a cheap reference or clone of it.
+    pub fn new(matcher: M) -> Collector<M> {
+        Collector { matcher, path: None, matches: vec![] }
+    }
+
+    /// Create a new collector that records the given path in its results.
+    pub fn with_path<P: Into<PathBuf>>(
+        matcher: M,
+        path: P,
+    ) -> Collector<M> {
+        Collector { matcher, path: Some(path.into()), matches: vec![] }
+    }
+
+    /// Return the results collected so far, resetting this collector so
+    /// that it can be reused for another file.
+    pub fn take(&mut self) -> FileMatches {
+        FileMatches {
+            path: self.path.clone(),
+            matches: std::mem::take(&mut self.matches),
+        }
+    }
+
+    /// Consume this collector, returning the collected results.
+    pub fn into_file_matches(self) -> FileMatches {
+        FileMatches { path: self.path, matches: self.matches }
+    }
+}
+
+impl<M: Matcher> Sink for Collector<M> {
+    type Error = io::Error;
+
+    fn matched(
+        &mut self,
+        _searcher: &Searcher,
+        mat: &SinkMatch<'_>,
+    ) -> Result<bool, io::Error> {
+        let mut text = mat.bytes();
+        if text.last() == Some(&b'\n') {
+            text = &text[..text.len() - 1];
+        }
+        if text.last() == Some(&b'\r') {
+            text = &text[..text.len() - 1];
+        }
+        let mut spans = vec![];
+        self.matcher
+            .find_iter(text, |span| {
+                spans.push(span);
+                true
+            })
+            .map_err(io::Error::error_message)?;
+        self.matches.push(LineMatch {
+            line_number: mat.line_number(),
+            byte_offset: mat.absolute_byte_offset(),
+            spans,
+            text: text.to_vec(),
+        });
+        Ok(true)
+    }
+}
@@ -0,0 +1,644 @@
+/*!
+Defines a high level facade for running searches.
+
+The [`SearchBuilder`](struct.SearchBuilder.html) in this module wires a
+matcher, a recursive directory walker, a searcher and (optionally) a printer
+together, so that common searches can be expressed in a few lines instead of
+assembling the constituent crates by hand. It intentionally exposes only the
+most common options; callers that need more control should drop down to the
+`matcher`, `searcher` and `printer` modules directly.
+*/
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ignore::overrides::OverrideBuilder;
+use ignore::{WalkBuilder, WalkState};
+use termcolor::WriteColor;
+
+use crate::printer::{Standard, Summary, JSON};
+use crate::results::{Collector, FileMatches};
+use crate::regex::{RegexMatcher, RegexMatcherBuilder};
+use crate::searcher::{
+    BinaryDetection, Searcher, SearcherBuilder, Sink, SinkMatch,
+};
+
+/// The result type used by searches run through the facade.
+pub type Result<T> = std::result::Result<T, crate::Error>;
+
+/// The configuration of a search, as assembled by a `SearchBuilder`.
+#[derive(Clone, Debug)]
+struct Config {
+    patterns: Vec<String>,
+    paths: Vec<PathBuf>,
+    globs: Vec<String>,
+    case_insensitive: bool,
+    case_smart: bool,
+    before_context: usize,
+    after_context: usize,
+    threads: usize,
+    line_number: bool,
+    cancel: Option<CancellationToken>,
+    timeout: Option<Duration>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            patterns: vec![],
+            paths: vec![],
+            globs: vec![],
+            case_insensitive: false,
+            case_smart: false,
+            before_context: 0,
+            after_context: 0,
+            threads: 0,
+            line_number: true,
+            cancel: None,
+            timeout: None,
+        }
+    }
+}
+
+/// A handle for cancelling searches in progress.
+///
+/// A token is handed to a search via
+/// [`SearchBuilder::cancellation_token`](struct.SearchBuilder.html#method.cancellation_token)
+/// and may be cloned freely; all clones share the same state. Cancelling is
+/// one-way: once cancelled, a token stays cancelled, and any search using it
+/// stops promptly and returns the results found so far.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create a new token that has not been cancelled.
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Cancel all searches using this token.
+    ///
+    /// This may be called from any thread, e.g., from a signal handler or a
+    /// user interface thread while a search runs elsewhere.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns true if `cancel` has been called on this token or any of its
+    /// clones.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// The shared flag, in the form the walker and searcher builders accept.
+    fn as_arc(&self) -> Arc<AtomicBool> {
+        self.0.clone()
+    }
+}
+
+/// A builder for running complete searches.
+///
+/// This is a convenience facade over the constituent crates: it compiles the
+/// given patterns into a matcher, recursively traverses the given paths while
+/// respecting gitignore rules, and reports matching lines either to a
+/// callback or to one of the printers in the `printer` module. Binary files
+/// are skipped, mirroring the command line tool's default behavior.
+///
+/// # Example
+///
+/// ```no_run
+/// use grep::SearchBuilder;
+///
+/// # fn example() -> grep::search::Result<()> {
+/// let mut matches = vec![];
+/// SearchBuilder::new()
+///     .pattern(r"fn\s+main")
+///     .glob("*.rs")
+///     .path("./src")
+///     .search(|m| {
+///         matches.push(m.path().to_path_buf());
+///         true
+///     })?;
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SearchBuilder {
+    config: Config,
+}
+
+impl SearchBuilder {
+    /// Create a new builder with a default configuration.
+    pub fn new() -> SearchBuilder {
+        SearchBuilder::default()
+    }
+
+    /// Add a pattern to search for.
+    ///
+    /// Patterns use the same regex syntax as the command line tool. A line
+    /// matches when any of the added patterns match it. At least one pattern
+    /// must be added before running a search.
+    pub fn pattern<S: Into<String>>(&mut self, pattern: S) -> &mut Self {
+        self.config.patterns.push(pattern.into());
+        self
+    }
+
+    /// Add a file or directory to search.
+    ///
+    /// Directories are traversed recursively, respecting gitignore rules and
+    /// skipping hidden entries, just like the command line tool. When no
+    /// path is added, the current working directory is searched.
+    pub fn path<P: Into<PathBuf>>(&mut self, path: P) -> &mut Self {
+        self.config.paths.push(path.into());
+        self
+    }
+
+    /// Add a glob that file paths must match to be searched.
+    ///
+    /// Globs use gitignore syntax and may be negated with a leading `!`.
+    /// When at least one non-negated glob is added, only paths matching one
+    /// of them are searched.
+    pub fn glob<S: Into<String>>(&mut self, glob: S) -> &mut Self {
+        self.config.globs.push(glob.into());
+        self
+    }
+
+    /// Whether to match patterns case insensitively.
+    ///
+    /// This is disabled by default and overrides `smart_case` when enabled.
+    pub fn case_insensitive(&mut self, yes: bool) -> &mut Self {
+        self.config.case_insensitive = yes;
+        self
+    }
+
+    /// Whether to match case insensitively only when no pattern contains an
+    /// uppercase literal.
+    ///
+    /// This is disabled by default.
+    pub fn smart_case(&mut self, yes: bool) -> &mut Self {
+        self.config.case_smart = yes;
+        self
+    }
+
+    /// The number of lines to include before each match.
+    ///
+    /// Context lines are only reported when driving a printer; the match
+    /// callback receives matching lines only.
+    pub fn before_context(&mut self, lines: usize) -> &mut Self {
+        self.config.before_context = lines;
+        self
+    }
+
+    /// The number of lines to include after each match.
+    ///
+    /// Context lines are only reported when driving a printer; the match
+    /// callback receives matching lines only.
+    pub fn after_context(&mut self, lines: usize) -> &mut Self {
+        self.config.after_context = lines;
+        self
+    }
+
+    /// The approximate number of threads to use.
+    ///
+    /// A value of `0` (the default) chooses a thread count using heuristics.
+    /// Threads only apply to callback searches; driving a printer serializes
+    /// the output and so always searches on a single thread.
+    pub fn threads(&mut self, threads: usize) -> &mut Self {
+        self.config.threads = threads;
+        self
+    }
+
+    /// Whether to compute line numbers.
+    ///
+    /// This is enabled by default.
+    pub fn line_number(&mut self, yes: bool) -> &mut Self {
+        self.config.line_number = yes;
+        self
+    }
+
+    /// Set a cancellation token for searches run by this builder.
+    ///
+    /// The token is propagated to the directory walker and to every
+    /// searcher, so cancelling it (typically from another thread) stops an
+    /// in-progress search promptly, even in the middle of a large file. A
+    /// cancelled search returns normally with the results found so far.
+    pub fn cancellation_token(
+        &mut self,
+        token: CancellationToken,
+    ) -> &mut Self {
+        self.config.cancel = Some(token);
+        self
+    }
+
+    /// Set a wall clock limit on each search run by this builder.
+    ///
+    /// The limit is best effort: it is checked between files and
+    /// periodically within each file, so a search may overshoot it
+    /// somewhat. When the limit is reached, the search stops and returns
+    /// the results found so far.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.config.timeout = Some(timeout);
+        self
+    }
+
+    /// Run the search, calling `on_match` for each matching line.
+    ///
+    /// The callback should return `true` to continue the search, or `false`
+    /// to stop it. When searching with multiple threads, the callback is
+    /// synchronized, so it may be called from several threads but never
+    /// concurrently.
+    ///
+    /// Returns `true` if at least one match was found. Files that cannot be
+    /// read are skipped.
+    pub fn search<F>(&self, mut on_match: F) -> Result<bool>
+    where
+        F: FnMut(SearchMatch) -> bool + Send,
+    {
+        let matcher = self.matcher()?;
+        let searcher = self.searcher();
+        let walker = self.walker()?;
+        let threads = self.threads_heuristic();
+        let deadline = self.deadline();
+        if threads <= 1 {
+            let mut searcher = searcher;
+            let mut found = false;
+            for result in walker.build() {
+                if stopped(&self.config.cancel, deadline) {
+                    break;
+                }
+                let entry = match result {
+                    Ok(entry) => entry,
+                    Err(_) => continue,
+                };
+                if !entry.file_type().map_or(false, |ft| ft.is_file()) {
+                    continue;
+                }
+                let mut sink = CallbackSink {
+                    path: entry.path(),
+                    on_match: &mut on_match,
+                    found: &mut found,
+                    stop: false,
+                };
+                let _ = searcher.search_path(
+                    &matcher,
+                    entry.path(),
+                    &mut sink,
+                );
+                if sink.stop {
+                    break;
+                }
+            }
+            return Ok(found);
+        }
+        let on_match = Mutex::new(&mut on_match);
+        let found = AtomicBool::new(false);
+        let stop = AtomicBool::new(false);
+        let mut walker = walker;
+        walker.threads(threads);
+        walker.build_parallel().run(|| {
+            let matcher = matcher.clone();
+            let mut searcher = searcher.clone();
+            let cancel = self.config.cancel.clone();
+            let (on_match, found, stop) = (&on_match, &found, &stop);
+            Box::new(move |result| {
+                if stopped(&cancel, deadline) {
+                    return WalkState::Quit;
+                }
+                let entry = match result {
+                    Ok(entry) => entry,
+                    Err(_) => return WalkState::Continue,
+                };
+                if !entry.file_type().map_or(false, |ft| ft.is_file()) {
+                    return WalkState::Continue;
+                }
+                let mut sink = SyncCallbackSink {
+                    path: entry.path(),
+                    on_match,
+                    found,
+                    stop: false,
+                };
+                let _ = searcher.search_path(
+                    &matcher,
+                    entry.path(),
+                    &mut sink,
+                );
+                if sink.stop {
+                    stop.store(true, Ordering::SeqCst);
+                    WalkState::Quit
+                } else {
+                    WalkState::Continue
+                }
+            })
+        });
+        Ok(found.load(Ordering::SeqCst))
+    }
+
+    /// Run the search, printing results with the given printer.
+    ///
+    /// Any of the printers in the `printer` module may be given. Since a
+    /// printer writes to a single destination, this search always runs on a
+    /// single thread.
+    ///
+    /// Returns `true` if at least one match was found. Files that cannot be
+    /// read are skipped.
+    pub fn search_with_printer<P: SearchPrinter>(
+        &self,
+        printer: &mut P,
+    ) -> Result<bool> {
+        let matcher = self.matcher()?;
+        let mut searcher = self.searcher();
+        let walker = self.walker()?;
+        let deadline = self.deadline();
+        let mut found = false;
+        for result in walker.build() {
+            if stopped(&self.config.cancel, deadline) {
+                break;
+            }
+            let entry = match result {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+            if !entry.file_type().map_or(false, |ft| ft.is_file()) {
+                continue;
+            }
+            match printer.search_path(&matcher, &mut searcher, entry.path()) {
+                Ok(has_match) => found = found || has_match,
+                Err(err) if err.kind() == io::ErrorKind::BrokenPipe => {
+                    break;
+                }
+                Err(_) => continue,
+            }
+        }
+        Ok(found)
+    }
+
+    /// Run the search, collecting the results into owned, structured
+    /// values.
+    ///
+    /// One [`FileMatches`](results/struct.FileMatches.html) is returned per
+    /// file with at least one match, in the order the files were visited.
+    /// Files that cannot be read are skipped. Since the results are
+    /// accumulated in memory, this search always runs on a single thread.
+    pub fn collect(&self) -> Result<Vec<FileMatches>> {
+        let matcher = self.matcher()?;
+        let mut searcher = self.searcher();
+        let walker = self.walker()?;
+        let deadline = self.deadline();
+        let mut results = vec![];
+        for result in walker.build() {
+            if stopped(&self.config.cancel, deadline) {
+                break;
+            }
+            let entry = match result {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+            if !entry.file_type().map_or(false, |ft| ft.is_file()) {
+                continue;
+            }
+            let mut collector =
+                Collector::with_path(&matcher, entry.path());
+            if searcher
+                .search_path(&matcher, entry.path(), &mut collector)
+                .is_err()
+            {
+                continue;
+            }
+            let file_matches = collector.into_file_matches();
+            if !file_matches.is_empty() {
+                results.push(file_matches);
+            }
+        }
+        Ok(results)
+    }
+
+    /// Compile the configured patterns into a matcher.
+    fn matcher(&self) -> Result<RegexMatcher> {
+        if self.config.patterns.is_empty() {
+            return Err(From::from("no patterns given"));
+        }
+        let matcher = RegexMatcherBuilder::new()
+            .case_insensitive(self.config.case_insensitive)
+            .case_smart(self.config.case_smart)
+            .build_many(&self.config.patterns)?;
+        Ok(matcher)
+    }
+
+    /// Build a searcher from this configuration.
+    fn searcher(&self) -> Searcher {
+        SearcherBuilder::new()
+            .binary_detection(BinaryDetection::quit(b'\x00'))
+            .line_number(self.config.line_number)
+            .before_context(self.config.before_context)
+            .after_context(self.config.after_context)
+            .cancel_token(self.config.cancel.as_ref().map(|t| t.as_arc()))
+            .timeout(self.config.timeout)
+            .build()
+    }
+
+    /// Build a directory walker over the configured paths.
+    fn walker(&self) -> Result<WalkBuilder> {
+        let first = match self.config.paths.first() {
+            None => Path::new("./"),
+            Some(path) => path,
+        };
+        let mut builder = WalkBuilder::new(first);
+        for path in self.config.paths.iter().skip(1) {
+            builder.add(path);
+        }
+        builder.cancel_token(self.config.cancel.as_ref().map(|t| t.as_arc()));
+        if !self.config.globs.is_empty() {
+            let root = std::env::current_dir()?;
+            let mut overrides = OverrideBuilder::new(root);
+            for glob in &self.config.globs {
+                overrides.add(glob)?;
+            }
+            builder.overrides(overrides.build()?);
+        }
+        Ok(builder)
+    }
+
+    /// The number of threads to use for a callback search.
+    fn threads_heuristic(&self) -> usize {
+        if self.config.threads != 0 {
+            return self.config.threads;
+        }
+        std::thread::available_parallelism().map_or(1, |n| n.get().min(12))
+    }
+
+    /// The instant at which a search starting now must stop, if a timeout
+    /// is configured.
+    fn deadline(&self) -> Option<Instant> {
+        self.config.timeout.map(|timeout| Instant::now() + timeout)
+    }
+}
+
+/// Returns true if a search should stop, either because its cancellation
+/// token was set or because its deadline has passed.
+fn stopped(
+    cancel: &Option<CancellationToken>,
+    deadline: Option<Instant>,
+) -> bool {
+    cancel.as_ref().map_or(false, |token| token.is_cancelled())
+        || deadline.map_or(false, |deadline| Instant::now() >= deadline)
+}
+
+/// A single matching line reported to a search callback.
+#[derive(Clone, Debug)]
+pub struct SearchMatch {
+    path: PathBuf,
+    line_number: Option<u64>,
+    byte_offset: u64,
+    bytes: Vec<u8>,
+}
+
+impl SearchMatch {
+    /// The path of the file containing this match.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The line number of the first line in this match, if computed.
+    pub fn line_number(&self) -> Option<u64> {
+        self.line_number
+    }
+
+    /// The absolute byte offset of the start of this match's line within its
+    /// file.
+    pub fn byte_offset(&self) -> u64 {
+        self.byte_offset
+    }
+
+    /// The contents of the matching line, without its line terminator.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl SearchMatch {
+    fn new(path: &Path, mat: &SinkMatch<'_>) -> SearchMatch {
+        let mut bytes = mat.bytes();
+        if bytes.last() == Some(&b'\n') {
+            bytes = &bytes[..bytes.len() - 1];
+        }
+        if bytes.last() == Some(&b'\r') {
+            bytes = &bytes[..bytes.len() - 1];
+        }
+        SearchMatch {
+            path: path.to_path_buf(),
+            line_number: mat.line_number(),
+            byte_offset: mat.absolute_byte_offset(),
+            bytes: bytes.to_vec(),
+        }
+    }
+}
+
+/// A sink that forwards matches to a caller provided callback.
+struct CallbackSink<'a, F> {
+    path: &'a Path,
+    on_match: &'a mut F,
+    found: &'a mut bool,
+    stop: bool,
+}
+
+impl<'a, F: FnMut(SearchMatch) -> bool> Sink for CallbackSink<'a, F> {
+    type Error = io::Error;
+
+    fn matched(
+        &mut self,
+        _searcher: &Searcher,
+        mat: &SinkMatch<'_>,
+    ) -> std::result::Result<bool, io::Error> {
+        *self.found = true;
+        if !(self.on_match)(SearchMatch::new(self.path, mat)) {
+            self.stop = true;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+}
+
+/// Like `CallbackSink`, but synchronizes calls to the callback so that it
+/// can be shared among search threads.
+struct SyncCallbackSink<'a, 'b, F> {
+    path: &'b Path,
+    on_match: &'a Mutex<&'a mut F>,
+    found: &'a AtomicBool,
+    stop: bool,
+}
+
+impl<'a, 'b, F: FnMut(SearchMatch) -> bool> Sink
+    for SyncCallbackSink<'a, 'b, F>
+{
+    type Error = io::Error;
+
+    fn matched(
+        &mut self,
+        _searcher: &Searcher,
+        mat: &SinkMatch<'_>,
+    ) -> std::result::Result<bool, io::Error> {
+        self.found.store(true, Ordering::SeqCst);
+        let mut on_match = self.on_match.lock().unwrap();
+        if !(on_match)(SearchMatch::new(self.path, mat)) {
+            self.stop = true;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+}
+
+/// A printer that can be driven by `SearchBuilder::search_with_printer`.
+///
+/// This is implemented for each of the printers in the `printer` module, so
+/// callers normally never need to implement it themselves.
+pub trait SearchPrinter {
+    /// Search the file at the given path, printing any results, and return
+    /// whether a match was found.
+    fn search_path(
+        &mut self,
+        matcher: &RegexMatcher,
+        searcher: &mut Searcher,
+        path: &Path,
+    ) -> io::Result<bool>;
+}
+
+impl<W: WriteColor> SearchPrinter for Standard<W> {
+    fn search_path(
+        &mut self,
+        matcher: &RegexMatcher,
+        searcher: &mut Searcher,
+        path: &Path,
+    ) -> io::Result<bool> {
+        let mut sink = self.sink_with_path(matcher, path);
+        searcher.search_path(matcher, path, &mut sink)?;
+        Ok(sink.has_match())
+    }
+}
+
+impl<W: WriteColor> SearchPrinter for Summary<W> {
+    fn search_path(
+        &mut self,
+        matcher: &RegexMatcher,
+        searcher: &mut Searcher,
+        path: &Path,
+    ) -> io::Result<bool> {
+        let mut sink = self.sink_with_path(matcher, path);
+        searcher.search_path(matcher, path, &mut sink)?;
+        Ok(sink.has_match())
+    }
+}
+
+impl<W: io::Write> SearchPrinter for JSON<W> {
+    fn search_path(
+        &mut self,
+        matcher: &RegexMatcher,
+        searcher: &mut Searcher,
+        path: &Path,
+    ) -> io::Result<bool> {
+        let mut sink = self.sink_with_path(matcher, path);
+        searcher.search_path(matcher, path, &mut sink)?;
+        Ok(sink.has_match())
+    }
+}
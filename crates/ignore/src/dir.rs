@@ -22,7 +22,7 @@ use std::sync::{Arc, RwLock};
 
 use crate::gitignore::{self, Gitignore, GitignoreBuilder};
 use crate::overrides::{self, Override};
-use crate::pathutil::{is_hidden, strip_prefix};
+use crate::pathutil::{is_hidden, is_hidden_path, strip_prefix};
 use crate::types::{self, Types};
 use crate::walk::DirEntry;
 use crate::{Error, Match, PartialErrorBuilder};
@@ -58,6 +58,43 @@ impl<'a> IgnoreMatch<'a> {
     fn hidden() -> IgnoreMatch<'static> {
         IgnoreMatch(IgnoreMatchInner::Hidden)
     }
+
+    /// Returns a human readable description of the rule that produced this
+    /// match, e.g., the glob and the ignore file it came from.
+    pub fn describe(&self) -> String {
+        match self.0 {
+            IgnoreMatchInner::Override(ref glob) => match glob.glob() {
+                None => "no override glob matched".to_string(),
+                Some(glob) => {
+                    format!("override glob '{}'", glob.original())
+                }
+            },
+            IgnoreMatchInner::Gitignore(glob) => match glob.from() {
+                None => format!("glob '{}'", glob.original()),
+                Some(from) => format!(
+                    "glob '{}' from {}",
+                    glob.original(),
+                    from.display()
+                ),
+            },
+            IgnoreMatchInner::Types(ref glob) => match glob.file_type_def() {
+                None => "no selected file type matched".to_string(),
+                Some(def) => format!("file type '{}'", def.name()),
+            },
+            IgnoreMatchInner::Hidden => {
+                "the rule for hidden files and directories".to_string()
+            }
+        }
+    }
+
+    /// Returns the file type definition that produced this match, if the
+    /// match came from a file type matcher.
+    pub(crate) fn file_type_def(&self) -> Option<&types::FileTypeDef> {
+        match self.0 {
+            IgnoreMatchInner::Types(ref glob) => glob.file_type_def(),
+            _ => None,
+        }
+    }
 }
 
 /// Options for the ignore matcher, shared between the matcher itself and the
@@ -153,6 +190,15 @@ impl Ignore {
         self.0.parent.clone()
     }
 
+    /// Invalidate the cache of compiled per-directory matchers.
+    ///
+    /// Directories visited after this call will have their ignore files
+    /// re-read from disk. Matchers built at construction time (such as the
+    /// global gitignore matcher) are unaffected.
+    pub(crate) fn invalidate_caches(&self) {
+        self.0.compiled.write().unwrap().clear();
+    }
+
     /// Create a new `Ignore` matcher with the parent directories of `dir`.
     ///
     /// Note that this can only be called on an `Ignore` matcher with no
@@ -346,6 +392,22 @@ impl Ignore {
         m
     }
 
+    /// Like `matched_dir_entry`, but works with a bare path instead.
+    ///
+    /// This is principally useful for explaining how a path would be
+    /// treated without running a full directory traversal.
+    pub fn matched_path<'a>(
+        &'a self,
+        path: &Path,
+        is_dir: bool,
+    ) -> Match<IgnoreMatch<'a>> {
+        let m = self.matched(path, is_dir);
+        if m.is_none() && self.0.opts.hidden && is_hidden_path(path) {
+            return Match::Ignore(IgnoreMatch::hidden());
+        }
+        m
+    }
+
     /// Returns a match indicating whether the given file path should be
     /// ignored or not.
     ///
@@ -497,6 +559,117 @@ impl Ignore {
             .or(m_explicit)
     }
 
+    /// Returns every ignore glob that matches the given path, ordered from
+    /// highest precedence to lowest. That is, the first glob returned is the
+    /// glob whose effect applies, and corresponds to the glob reported by
+    /// `matched` (when an ignore file produced the match).
+    ///
+    /// While `matched` only reports the highest precedence match, this
+    /// includes globs whose effect was overridden, e.g., a whitelist glob in
+    /// a child directory's ignore file overriding an ignore glob in a parent
+    /// directory's ignore file. This is principally useful for explaining
+    /// why a path is or isn't ignored. Note that only ignore files are
+    /// consulted; override and file type matchers are not represented in the
+    /// sequence returned.
+    pub fn explain<'a>(
+        &'a self,
+        path: &Path,
+        is_dir: bool,
+    ) -> Vec<&'a gitignore::Glob> {
+        // Strip a leading ./ for the same reason `matched` does.
+        let mut path = path;
+        if let Some(p) = strip_prefix("./", path) {
+            path = p;
+        }
+        let (mut v_custom_ignore, mut v_ignore, mut v_gi, mut v_gi_exclude) =
+            (vec![], vec![], vec![], vec![]);
+        let any_git =
+            !self.0.opts.require_git || self.parents().any(|ig| ig.0.has_git);
+        let mut saw_git = false;
+        for ig in self.parents().take_while(|ig| !ig.0.is_absolute_parent) {
+            v_custom_ignore.extend(
+                ig.0.custom_ignore_matcher
+                    .matched_all(path, is_dir)
+                    .into_iter()
+                    .rev(),
+            );
+            v_ignore.extend(
+                ig.0.ignore_matcher
+                    .matched_all(path, is_dir)
+                    .into_iter()
+                    .rev(),
+            );
+            if any_git && !saw_git {
+                v_gi.extend(
+                    ig.0.git_ignore_matcher
+                        .matched_all(path, is_dir)
+                        .into_iter()
+                        .rev(),
+                );
+                v_gi_exclude.extend(
+                    ig.0.git_exclude_matcher
+                        .matched_all(path, is_dir)
+                        .into_iter()
+                        .rev(),
+                );
+            }
+            saw_git = saw_git || ig.0.has_git;
+        }
+        if self.0.opts.parents {
+            if let Some(abs_parent_path) = self.absolute_base() {
+                let path = abs_parent_path.join(path);
+                for ig in
+                    self.parents().skip_while(|ig| !ig.0.is_absolute_parent)
+                {
+                    v_custom_ignore.extend(
+                        ig.0.custom_ignore_matcher
+                            .matched_all(&path, is_dir)
+                            .into_iter()
+                            .rev(),
+                    );
+                    v_ignore.extend(
+                        ig.0.ignore_matcher
+                            .matched_all(&path, is_dir)
+                            .into_iter()
+                            .rev(),
+                    );
+                    if any_git && !saw_git {
+                        v_gi.extend(
+                            ig.0.git_ignore_matcher
+                                .matched_all(&path, is_dir)
+                                .into_iter()
+                                .rev(),
+                        );
+                        v_gi_exclude.extend(
+                            ig.0.git_exclude_matcher
+                                .matched_all(&path, is_dir)
+                                .into_iter()
+                                .rev(),
+                        );
+                    }
+                    saw_git = saw_git || ig.0.has_git;
+                }
+            }
+        }
+        let mut globs = v_custom_ignore;
+        globs.extend(v_ignore);
+        globs.extend(v_gi);
+        globs.extend(v_gi_exclude);
+        if any_git {
+            globs.extend(
+                self.0
+                    .git_global_matcher
+                    .matched_all(path, is_dir)
+                    .into_iter()
+                    .rev(),
+            );
+        }
+        for gi in self.0.explicit_ignores.iter().rev() {
+            globs.extend(gi.matched_all(path, is_dir).into_iter().rev());
+        }
+        globs
+    }
+
     /// Returns an iterator over parent ignore matchers, including this one.
     pub fn parents(&self) -> Parents<'_> {
         Parents(Some(self))
@@ -541,6 +714,9 @@ pub struct IgnoreBuilder {
     explicit_ignores: Vec<Gitignore>,
     /// Ignore files in addition to .ignore.
     custom_ignore_filenames: Vec<OsString>,
+    /// An explicit path to a global gitignore file, overriding git config
+    /// discovery (default is None).
+    git_global_path: Option<PathBuf>,
     /// Ignore config.
     opts: IgnoreOptions,
 }
@@ -557,6 +733,7 @@ impl IgnoreBuilder {
             types: Arc::new(Types::empty()),
             explicit_ignores: vec![],
             custom_ignore_filenames: vec![],
+            git_global_path: None,
             opts: IgnoreOptions {
                 hidden: true,
                 ignore: true,
@@ -577,6 +754,21 @@ impl IgnoreBuilder {
     pub fn build(&self) -> Ignore {
         let git_global_matcher = if !self.opts.git_global {
             Gitignore::empty()
+        } else if let Some(ref path) = self.git_global_path {
+            let mut builder = GitignoreBuilder::new("");
+            builder
+                .case_insensitive(self.opts.ignore_case_insensitive)
+                .unwrap();
+            if let Some(err) = builder.add(path) {
+                log::debug!("{}", err);
+            }
+            match builder.build() {
+                Ok(gi) => gi,
+                Err(err) => {
+                    log::debug!("{}", err);
+                    Gitignore::empty()
+                }
+            }
         } else {
             let mut builder = GitignoreBuilder::new("");
             builder
@@ -694,6 +886,23 @@ impl IgnoreBuilder {
         self
     }
 
+    /// Set an explicit path to a global gitignore file.
+    ///
+    /// When set, this path is used instead of discovering the global
+    /// gitignore file via git configuration (e.g., `core.excludesFile`).
+    /// The file is re-read each time a matcher is built, so callers may
+    /// rebuild to pick up changes to it. Setting this to `None` restores
+    /// the default discovery behavior.
+    ///
+    /// This has no effect when the global gitignore matcher is disabled.
+    pub fn git_global_path(
+        &mut self,
+        path: Option<PathBuf>,
+    ) -> &mut IgnoreBuilder {
+        self.git_global_path = path;
+        self
+    }
+
     /// Enables reading `.gitignore` files.
     ///
     /// `.gitignore` files have match semantics as described in the `gitignore`
@@ -1141,6 +1350,34 @@ mod tests {
         assert!(ig2.matched("src/foo", false).is_ignore());
     }
 
+    // Tests that explain reports every matching glob with its provenance,
+    // not just the highest precedence match.
+    #[test]
+    fn explain() {
+        let td = tmpdir();
+        mkdirp(td.path().join(".git"));
+        mkdirp(td.path().join("sub"));
+        wfile(td.path().join(".gitignore"), "# comment\nfoo");
+        wfile(td.path().join("sub/.gitignore"), "!foo");
+
+        let ig0 = IgnoreBuilder::new().build();
+        let (ig1, err) = ig0.add_child(td.path());
+        assert!(err.is_none());
+        let (ig2, err) = ig1.add_child(td.path().join("sub"));
+        assert!(err.is_none());
+
+        assert!(ig2.matched("foo", false).is_whitelist());
+        let globs = ig2.explain(Path::new("foo"), false);
+        assert_eq!(2, globs.len());
+        // The highest precedence glob comes first and agrees with `matched`.
+        assert!(globs[0].is_whitelist());
+        assert_eq!(Some(&*td.path().join("sub/.gitignore")), globs[0].from());
+        assert_eq!(Some(1), globs[0].line());
+        assert!(!globs[1].is_whitelist());
+        assert_eq!(Some(&*td.path().join(".gitignore")), globs[1].from());
+        assert_eq!(Some(2), globs[1].line());
+    }
+
     #[test]
     fn git_info_exclude_in_linked_worktree() {
         let td = tmpdir();
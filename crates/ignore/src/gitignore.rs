@@ -30,6 +30,8 @@ use crate::{Error, Match, PartialErrorBuilder};
 pub struct Glob {
     /// The file path that this glob was extracted from.
     from: Option<PathBuf>,
+    /// The line number in the file that this glob was extracted from.
+    line: Option<u64>,
     /// The original glob string.
     original: String,
     /// The actual glob string used to convert to a regex.
@@ -46,6 +48,13 @@ impl Glob {
         self.from.as_ref().map(|p| &**p)
     }
 
+    /// Returns the line number in the file that defined this glob, where
+    /// the first line is `1`. This is only present when the glob was read
+    /// from a file.
+    pub fn line(&self) -> Option<u64> {
+        self.line
+    }
+
     /// The original glob as it was defined in a gitignore file.
     pub fn original(&self) -> &str {
         &self.original
@@ -239,6 +248,37 @@ impl Gitignore {
         Match::None
     }
 
+    /// Returns every glob in this matcher that matches the given path, in
+    /// ascending order of precedence. That is, the last glob returned is the
+    /// glob whose effect applies, and is the same glob reported by `matched`.
+    ///
+    /// This is principally useful for explaining why a path is or isn't
+    /// ignored, since it includes globs whose effect was overridden by a
+    /// later glob.
+    ///
+    /// `is_dir` should be true if the path refers to a directory and false
+    /// otherwise. The given path is matched relative to the path given when
+    /// building the matcher, just as with `matched`.
+    pub fn matched_all<P: AsRef<Path>>(
+        &self,
+        path: P,
+        is_dir: bool,
+    ) -> Vec<&Glob> {
+        if self.is_empty() {
+            return vec![];
+        }
+        let path = self.strip(path.as_ref());
+        let _matches = self.matches.as_ref().unwrap().get_or_default();
+        let mut matches = _matches.borrow_mut();
+        let candidate = Candidate::new(path);
+        self.set.matches_candidate_into(&candidate, &mut *matches);
+        matches
+            .iter()
+            .map(|&i| &self.globs[i])
+            .filter(|glob| !glob.is_only_dir() || is_dir)
+            .collect()
+    }
+
     /// Like matched, but takes a path that has already been stripped.
     fn matched_stripped<P: AsRef<Path>>(
         &self,
@@ -400,8 +440,13 @@ impl GitignoreBuilder {
                     break;
                 }
             };
+            let nglobs = self.globs.len();
             if let Err(err) = self.add_line(Some(path.to_path_buf()), &line) {
                 errs.push(err.tagged(path, lineno));
+            } else if self.globs.len() > nglobs {
+                // add_line doesn't add a glob for comments and empty lines,
+                // so only record a line number when a glob was added.
+                self.globs.last_mut().unwrap().line = Some(lineno);
             }
         }
         errs.into_error_option()
@@ -449,6 +494,7 @@ impl GitignoreBuilder {
         }
         let mut glob = Glob {
             from: from,
+            line: None,
             original: line.to_string(),
             actual: String::new(),
             is_whitelist: false,
@@ -791,4 +837,15 @@ mod tests {
     not_ignored!(cs2, ROOT, "*.html", "foo.HTML");
     not_ignored!(cs3, ROOT, "*.html", "foo.htm");
     not_ignored!(cs4, ROOT, "*.html", "foo.HTM");
+
+    #[test]
+    fn matched_all() {
+        let gi = gi_from_str(ROOT, "*.rs\n!lib.rs");
+        let globs = gi.matched_all("lib.rs", false);
+        assert_eq!(2, globs.len());
+        assert_eq!("*.rs", globs[0].original());
+        assert_eq!("!lib.rs", globs[1].original());
+        assert!(globs[1].is_whitelist());
+        assert!(gi.matched_all("main.c", false).is_empty());
+    }
 }
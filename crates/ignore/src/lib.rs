@@ -52,8 +52,8 @@ use std::io;
 use std::path::{Path, PathBuf};
 
 pub use crate::walk::{
-    DirEntry, ParallelVisitor, ParallelVisitorBuilder, Walk, WalkBuilder,
-    WalkParallel, WalkState,
+    DirEntry, Explainer, Explanation, IgnoreStatus, ParallelVisitor,
+    ParallelVisitorBuilder, Walk, WalkBuilder, WalkParallel, WalkState,
 };
 
 mod default_types;
@@ -35,6 +35,18 @@ impl<'a> Glob<'a> {
     fn unmatched() -> Glob<'a> {
         Glob(GlobInner::UnmatchedIgnore)
     }
+
+    /// Returns the glob that matched, if one exists.
+    ///
+    /// Note that a path can be ignored without any particular glob
+    /// matching it: when at least one whitelist override is present, any
+    /// file path that matches no override at all is ignored.
+    pub fn glob(&self) -> Option<&gitignore::Glob> {
+        match self.0 {
+            GlobInner::UnmatchedIgnore => None,
+            GlobInner::Matched(glob) => Some(glob),
+        }
+    }
 }
 
 /// Manages a set of overrides provided explicitly by the end user.
@@ -57,6 +57,19 @@ pub fn is_hidden(dent: &DirEntry) -> bool {
     }
 }
 
+/// Returns true if and only if this path is considered to be hidden.
+///
+/// Unlike `is_hidden`, this works on a bare path instead of a directory
+/// entry, and therefore only ever inspects the base name of the path. In
+/// particular, on Windows, file attributes are not consulted.
+pub fn is_hidden_path(path: &Path) -> bool {
+    if let Some(name) = file_name(path) {
+        name.to_str().map(|s| s.starts_with(".")).unwrap_or(false)
+    } else {
+        false
+    }
+}
+
 /// Strip `prefix` from the `path` and return the remainder.
 ///
 /// If `path` doesn't have a prefix `prefix`, then return `None`.
@@ -485,6 +485,29 @@ impl TypesBuilder {
         }
     }
 
+    /// Add file type definitions from the file at the given path.
+    ///
+    /// Each line must be a definition in the format accepted by `add_def`.
+    /// Empty lines and lines starting with `#` are ignored.
+    pub fn add_def_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<(), Error> {
+        let path = path.as_ref();
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) => return Err(Error::Io(err).with_path(path)),
+        };
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            self.add_def(line).map_err(|err| err.with_path(path))?;
+        }
+        Ok(())
+    }
+
     /// Add a set of default file type definitions.
     pub fn add_defaults(&mut self) -> &mut TypesBuilder {
         static MSG: &'static str = "adding a default type should never fail";
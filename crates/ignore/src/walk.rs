@@ -7,17 +7,17 @@ use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 use std::vec;
 
 use same_file::Handle;
 use walkdir::{self, WalkDir};
 
 use crate::dir::{Ignore, IgnoreBuilder};
-use crate::gitignore::GitignoreBuilder;
+use crate::gitignore::{GitignoreBuilder, Glob};
 use crate::overrides::Override;
-use crate::types::Types;
-use crate::{Error, PartialErrorBuilder};
+use crate::types::{FileTypeDef, Types};
+use crate::{Error, Match, PartialErrorBuilder};
 
 /// A directory entry with a possible error attached.
 ///
@@ -27,6 +27,8 @@ use crate::{Error, PartialErrorBuilder};
 pub struct DirEntry {
     dent: DirEntryInner,
     err: Option<Error>,
+    type_def: Option<FileTypeDef>,
+    ignore_status: IgnoreStatus,
 }
 
 impl DirEntry {
@@ -97,21 +99,94 @@ impl DirEntry {
         self.err.as_ref()
     }
 
+    /// Returns the file type definition that admitted this entry, if one
+    /// exists.
+    ///
+    /// This is only present when file type filtering is enabled via
+    /// [`WalkBuilder::types`](struct.WalkBuilder.html#method.types) and this
+    /// entry was whitelisted by one of the selected file types. In all other
+    /// cases, including when the entry was admitted for a reason other than
+    /// its file type (e.g., an explicitly provided path), this returns
+    /// `None`.
+    pub fn file_type_def(&self) -> Option<&FileTypeDef> {
+        self.type_def.as_ref()
+    }
+
+    /// Returns the aggregate ignore status of this entry.
+    ///
+    /// The status reflects the highest precedence ignore rule that matched
+    /// this entry, if any. Entries with an `Ignored` status are only yielded
+    /// when
+    /// [`WalkBuilder::report_ignored`](struct.WalkBuilder.html#method.report_ignored)
+    /// is enabled; otherwise, ignored entries are skipped entirely and never
+    /// observed by callers.
+    pub fn ignore_status(&self) -> &IgnoreStatus {
+        &self.ignore_status
+    }
+
     /// Returns true if and only if this entry points to a directory.
     pub(crate) fn is_dir(&self) -> bool {
         self.dent.is_dir()
     }
 
     fn new_stdin() -> DirEntry {
-        DirEntry { dent: DirEntryInner::Stdin, err: None }
+        DirEntry {
+            dent: DirEntryInner::Stdin,
+            err: None,
+            type_def: None,
+            ignore_status: IgnoreStatus::NotMatched,
+        }
     }
 
     fn new_walkdir(dent: walkdir::DirEntry, err: Option<Error>) -> DirEntry {
-        DirEntry { dent: DirEntryInner::Walkdir(dent), err: err }
+        DirEntry {
+            dent: DirEntryInner::Walkdir(dent),
+            err,
+            type_def: None,
+            ignore_status: IgnoreStatus::NotMatched,
+        }
     }
 
     fn new_raw(dent: DirEntryRaw, err: Option<Error>) -> DirEntry {
-        DirEntry { dent: DirEntryInner::Raw(dent), err: err }
+        DirEntry {
+            dent: DirEntryInner::Raw(dent),
+            err,
+            type_def: None,
+            ignore_status: IgnoreStatus::NotMatched,
+        }
+    }
+}
+
+/// The aggregate ignore status of a directory entry.
+///
+/// This describes the result of applying all of the ignore rules configured
+/// on a walker to a single entry. Only the highest precedence rule is
+/// represented; use [`WalkBuilder::explain`](struct.WalkBuilder.html#method.explain)
+/// to see every rule that matched a path.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum IgnoreStatus {
+    /// The entry was ignored. The string is a human readable description of
+    /// the rule that ignored it, e.g., the glob and the ignore file it came
+    /// from.
+    Ignored(String),
+    /// The entry was explicitly whitelisted, e.g., by a `!` glob in an
+    /// ignore file or by a selected file type.
+    Whitelisted,
+    /// No ignore rule matched the entry.
+    NotMatched,
+}
+
+impl IgnoreStatus {
+    /// Returns true if and only if this status corresponds to an entry that
+    /// was ignored.
+    pub fn is_ignored(&self) -> bool {
+        matches!(*self, IgnoreStatus::Ignored(_))
+    }
+
+    /// Returns true if and only if this status corresponds to an entry that
+    /// was explicitly whitelisted.
+    pub fn is_whitelisted(&self) -> bool {
+        *self == IgnoreStatus::Whitelisted
     }
 }
 
@@ -484,12 +559,17 @@ pub struct WalkBuilder {
     ig_builder: IgnoreBuilder,
     max_depth: Option<usize>,
     max_filesize: Option<u64>,
+    modified_after: Option<SystemTime>,
+    modified_before: Option<SystemTime>,
     follow_links: bool,
     same_file_system: bool,
     sorter: Option<Sorter>,
     threads: usize,
     skip: Option<Arc<Handle>>,
     filter: Option<Filter>,
+    cancel: Option<Arc<AtomicBool>>,
+    ordered: bool,
+    report_ignored: bool,
 }
 
 #[derive(Clone)]
@@ -510,9 +590,14 @@ impl fmt::Debug for WalkBuilder {
             .field("ig_builder", &self.ig_builder)
             .field("max_depth", &self.max_depth)
             .field("max_filesize", &self.max_filesize)
+            .field("modified_after", &self.modified_after)
+            .field("modified_before", &self.modified_before)
             .field("follow_links", &self.follow_links)
             .field("threads", &self.threads)
             .field("skip", &self.skip)
+            .field("cancel", &self.cancel)
+            .field("ordered", &self.ordered)
+            .field("report_ignored", &self.report_ignored)
             .finish()
     }
 }
@@ -530,12 +615,17 @@ impl WalkBuilder {
             ig_builder: IgnoreBuilder::new(),
             max_depth: None,
             max_filesize: None,
+            modified_after: None,
+            modified_before: None,
             follow_links: false,
             same_file_system: false,
             sorter: None,
             threads: 0,
             skip: None,
             filter: None,
+            cancel: None,
+            ordered: false,
+            report_ignored: false,
         }
     }
 
@@ -583,8 +673,12 @@ impl WalkBuilder {
             ig_root: ig_root.clone(),
             ig: ig_root.clone(),
             max_filesize: self.max_filesize,
+            modified_after: self.modified_after,
+            modified_before: self.modified_before,
             skip: self.skip.clone(),
             filter: self.filter.clone(),
+            cancel: self.cancel.clone(),
+            report_ignored: self.report_ignored,
         }
     }
 
@@ -599,14 +693,46 @@ impl WalkBuilder {
             ig_root: self.ig_builder.build(),
             max_depth: self.max_depth,
             max_filesize: self.max_filesize,
+            modified_after: self.modified_after,
+            modified_before: self.modified_before,
             follow_links: self.follow_links,
             same_file_system: self.same_file_system,
             threads: self.threads,
             skip: self.skip.clone(),
             filter: self.filter.clone(),
+            cancel: self.cancel.clone(),
+            ordered: self.ordered,
+            report_ignored: self.report_ignored,
         }
     }
 
+    /// Returns every ignore glob that matches the given path according to
+    /// the ignore rules configured on this builder, ordered from highest
+    /// precedence to lowest.
+    ///
+    /// This is useful for explaining why a path is or isn't ignored without
+    /// running a full traversal. A traversal only applies the highest
+    /// precedence match, but this includes globs whose effect was
+    /// overridden, e.g., a whitelist glob in a child directory's ignore file
+    /// overriding an ignore glob in a parent directory's ignore file. Each
+    /// glob returned reports the ignore file it was defined in, its line
+    /// number and whether it was an ignore or whitelist glob.
+    ///
+    /// `is_dir` should be true if the path refers to a directory and false
+    /// otherwise. Note that only ignore files are consulted; override and
+    /// file type matchers are not represented in the sequence returned.
+    /// I/O errors that occur while reading ignore files are ignored, which
+    /// is consistent with how they are treated during traversal.
+    pub fn explain<P: AsRef<Path>>(&self, path: P, is_dir: bool) -> Vec<Glob> {
+        let path = path.as_ref();
+        let mut ig = self.ig_builder.build();
+        if let Some(parent) = path.parent() {
+            ig = ig.add_parents(parent).0;
+            ig = ig.add_child(parent).0;
+        }
+        ig.explain(path, is_dir).into_iter().cloned().collect()
+    }
+
     /// Add a file path to the iterator.
     ///
     /// Each additional file path added is traversed recursively. This should
@@ -637,6 +763,24 @@ impl WalkBuilder {
         self
     }
 
+    /// Whether to ignore files last modified at or before the given time.
+    pub fn modified_after(
+        &mut self,
+        time: Option<SystemTime>,
+    ) -> &mut WalkBuilder {
+        self.modified_after = time;
+        self
+    }
+
+    /// Whether to ignore files last modified at or after the given time.
+    pub fn modified_before(
+        &mut self,
+        time: Option<SystemTime>,
+    ) -> &mut WalkBuilder {
+        self.modified_before = time;
+        self
+    }
+
     /// The number of threads to use for traversal.
     ///
     /// Note that this only has an effect when using `build_parallel`.
@@ -772,6 +916,25 @@ impl WalkBuilder {
         self
     }
 
+    /// Set an explicit path to a global gitignore file.
+    ///
+    /// When set, this path is used instead of discovering the global
+    /// gitignore file via git's `core.excludesFile` config option. The file
+    /// is re-read each time `build` or `build_parallel` is called, so
+    /// long-running processes can pick up changes to it by rebuilding the
+    /// walker. Setting this to `None` restores the default discovery
+    /// behavior.
+    ///
+    /// This has no effect when reading a global gitignore file is disabled
+    /// via `git_global`.
+    pub fn git_global_path(
+        &mut self,
+        path: Option<PathBuf>,
+    ) -> &mut WalkBuilder {
+        self.ig_builder.git_global_path(path);
+        self
+    }
+
     /// Enables reading `.gitignore` files.
     ///
     /// `.gitignore` files have match semantics as described in the `gitignore`
@@ -902,6 +1065,192 @@ impl WalkBuilder {
         self.filter = Some(Filter(Arc::new(filter)));
         self
     }
+
+    /// Set a cancellation token for the walkers built by this builder.
+    ///
+    /// The token is checked before each directory entry is yielded. Once it
+    /// has been set to `true` (typically from a signal handler or another
+    /// thread), the walk winds down as quickly as possible: `Walk` stops
+    /// yielding entries and every thread of a `WalkParallel` quits at its
+    /// next opportunity.
+    ///
+    /// By default, no token is set.
+    pub fn cancel_token(
+        &mut self,
+        token: Option<Arc<AtomicBool>>,
+    ) -> &mut WalkBuilder {
+        self.cancel = token;
+        self
+    }
+
+    /// Deliver entries from the parallel walker in deterministic order.
+    ///
+    /// When enabled, the walker returned by `build_parallel` yields entries
+    /// to its visitor in the same order that the single threaded walker
+    /// would. Directory traversal still happens on multiple threads, but
+    /// every entry is tagged with its position in depth first traversal
+    /// order and held in a reorder buffer until the traversal finishes. The
+    /// buffered entries are then replayed, in order, to a single visitor
+    /// built from the caller's builder.
+    ///
+    /// `WalkState::Skip` and `WalkState::Quit` are honored during the
+    /// replay, although the I/O for a skipped subtree may have already
+    /// happened by the time the visitor asks for it to be skipped. Note
+    /// also that comparators set by `sort_by_file_path` or
+    /// `sort_by_file_name` only apply to the single threaded walker;
+    /// ordered mode reproduces the operating system's directory entry
+    /// order.
+    ///
+    /// This has no effect on the single threaded walker. It is disabled by
+    /// default.
+    pub fn ordered(&mut self, yes: bool) -> &mut WalkBuilder {
+        self.ordered = yes;
+        self
+    }
+
+    /// Yield entries that would otherwise be skipped by ignore rules.
+    ///
+    /// When enabled, entries matched by an ignore rule are yielded rather
+    /// than skipped, with
+    /// [`DirEntry::ignore_status`](struct.DirEntry.html#method.ignore_status)
+    /// reporting the rule that matched them. This is useful for building
+    /// tooling that shows what a traversal *would* skip and why, without
+    /// changing the ignore configuration.
+    ///
+    /// An ignored directory is yielded but not descended into, since
+    /// everything beneath it would be ignored by the same rule. Entries
+    /// skipped for reasons other than ignore rules (e.g., a maximum file
+    /// size or a `filter_entry` predicate) are still skipped.
+    ///
+    /// This is disabled by default.
+    pub fn report_ignored(&mut self, yes: bool) -> &mut WalkBuilder {
+        self.report_ignored = yes;
+        self
+    }
+
+    /// Build an explainer that reports why the walker would or wouldn't
+    /// yield a particular path.
+    ///
+    /// The explainer shares this builder's configuration, so the decisions
+    /// it reports are the same ones the walker itself would make.
+    pub fn build_explainer(&self) -> Explainer {
+        Explainer {
+            ig_root: self.ig_builder.build(),
+            max_filesize: self.max_filesize,
+        }
+    }
+}
+
+/// Explains why a particular path would or wouldn't be yielded by a walker.
+///
+/// An explainer is created from a `WalkBuilder` via its `build_explainer`
+/// method and evaluates the same override globs, ignore files, file type
+/// filters and hidden file rules as the corresponding walker, without
+/// running a directory traversal.
+#[derive(Clone, Debug)]
+pub struct Explainer {
+    ig_root: Ignore,
+    max_filesize: Option<u64>,
+}
+
+impl Explainer {
+    /// Returns the chain of decisions that apply to the given path.
+    ///
+    /// The path is interpreted just as if the walker had encountered it
+    /// during a traversal starting at the current working directory (or,
+    /// for an absolute path, at the root of the file system). Each of the
+    /// path's ancestors is checked from the top down, since the walker
+    /// prunes an ignored directory without ever visiting its contents. The
+    /// chain therefore ends early at the first ignored ancestor.
+    ///
+    /// An empty chain means no rule matched the path or any of its
+    /// ancestors, i.e., the walker would yield it.
+    pub fn explain<P: AsRef<Path>>(&self, path: P) -> Vec<Explanation> {
+        let path = path.as_ref();
+        let mut chain: Vec<&Path> =
+            path.ancestors().filter(|p| !p.as_os_str().is_empty()).collect();
+        chain.reverse();
+        let mut steps = vec![];
+        let mut ig = match chain.first() {
+            None => return steps,
+            Some(first) => self.ig_root.add_parents(first).0,
+        };
+        for (i, p) in chain.iter().enumerate() {
+            let is_last = i + 1 == chain.len();
+            let is_dir = if is_last { path.is_dir() } else { true };
+            match ig.matched_path(p, is_dir) {
+                Match::None => {}
+                Match::Ignore(m) => {
+                    steps.push(Explanation {
+                        path: p.to_path_buf(),
+                        ignored: true,
+                        reason: m.describe(),
+                    });
+                    // An ignored directory is pruned, so rules that might
+                    // apply below it are never consulted.
+                    if !is_last {
+                        return steps;
+                    }
+                }
+                Match::Whitelist(m) => {
+                    steps.push(Explanation {
+                        path: p.to_path_buf(),
+                        ignored: false,
+                        reason: m.describe(),
+                    });
+                }
+            }
+            if is_last {
+                if let (Some(max), Ok(md)) =
+                    (self.max_filesize, path.metadata())
+                {
+                    if !md.is_dir() && md.len() > max {
+                        steps.push(Explanation {
+                            path: p.to_path_buf(),
+                            ignored: true,
+                            reason: format!(
+                                "file size {} exceeds the limit of {}",
+                                md.len(),
+                                max
+                            ),
+                        });
+                    }
+                }
+            } else {
+                ig = ig.add_child(p).0;
+            }
+        }
+        steps
+    }
+}
+
+/// A single step in the decision chain reported by an `Explainer`.
+///
+/// Each step pairs the explained path, or one of the directories above it,
+/// with the highest precedence rule that matched it.
+#[derive(Clone, Debug)]
+pub struct Explanation {
+    path: PathBuf,
+    ignored: bool,
+    reason: String,
+}
+
+impl Explanation {
+    /// The path this step applies to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns true if the matched rule ignores the path, or false if it
+    /// whitelists it.
+    pub fn is_ignore(&self) -> bool {
+        self.ignored
+    }
+
+    /// A human readable description of the rule that matched.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
 }
 
 /// Walk is a recursive directory iterator over file paths in one or more
@@ -916,8 +1265,12 @@ pub struct Walk {
     ig_root: Ignore,
     ig: Ignore,
     max_filesize: Option<u64>,
+    modified_after: Option<SystemTime>,
+    modified_before: Option<SystemTime>,
     skip: Option<Arc<Handle>>,
     filter: Option<Filter>,
+    cancel: Option<Arc<AtomicBool>>,
+    report_ignored: bool,
 }
 
 impl Walk {
@@ -930,7 +1283,17 @@ impl Walk {
         WalkBuilder::new(path).build()
     }
 
-    fn skip_entry(&self, ent: &DirEntry) -> Result<bool, Error> {
+    /// Invalidate the cache of compiled per-directory ignore matchers.
+    ///
+    /// Directories yielded after this call will have their ignore files
+    /// re-read from disk. Matchers built when the walker was built (such as
+    /// the global gitignore matcher) are not re-read; rebuild the walker to
+    /// refresh those.
+    pub fn invalidate_caches(&self) {
+        self.ig_root.invalidate_caches();
+    }
+
+    fn skip_entry(&self, ent: &mut DirEntry) -> Result<bool, Error> {
         if ent.depth() == 0 {
             return Ok(false);
         }
@@ -943,7 +1306,7 @@ impl Walk {
         // when particular filesystem operations occurred. Users of this system
         // who ensured correct file-type filters were being used could still
         // get unnecessary file access resulting in large downloads.
-        if should_skip_entry(&self.ig, ent) {
+        if should_skip_entry(&self.ig, ent, self.report_ignored) {
             return Ok(true);
         }
         if let Some(ref stdout) = self.skip {
@@ -958,6 +1321,18 @@ impl Walk {
                 &ent.metadata().ok(),
             ));
         }
+        if (self.modified_after.is_some() || self.modified_before.is_some())
+            && !ent.is_dir()
+        {
+            if skip_mtime(
+                self.modified_after,
+                self.modified_before,
+                ent.path(),
+                &ent.metadata().ok(),
+            ) {
+                return Ok(true);
+            }
+        }
         if let Some(Filter(filter)) = &self.filter {
             if !filter(ent) {
                 return Ok(true);
@@ -973,6 +1348,11 @@ impl Iterator for Walk {
     #[inline(always)]
     fn next(&mut self) -> Option<Result<DirEntry, Error>> {
         loop {
+            if let Some(ref cancel) = self.cancel {
+                if cancel.load(Ordering::SeqCst) {
+                    return None;
+                }
+            }
             let ev = match self.it.as_mut().and_then(|it| it.next()) {
                 Some(ev) => ev,
                 None => {
@@ -1006,7 +1386,7 @@ impl Iterator for Walk {
                 }
                 Ok(WalkEvent::Dir(ent)) => {
                     let mut ent = DirEntry::new_walkdir(ent, None);
-                    let should_skip = match self.skip_entry(&ent) {
+                    let should_skip = match self.skip_entry(&mut ent) {
                         Err(err) => return Some(Err(err)),
                         Ok(should_skip) => should_skip,
                     };
@@ -1019,14 +1399,24 @@ impl Iterator for Walk {
                         self.ig = igtmp;
                         continue;
                     }
+                    if ent.ignore_status().is_ignored() {
+                        // An ignored directory is only present when
+                        // reporting ignored entries. Yield it, but don't
+                        // descend into it, since everything beneath it is
+                        // ignored by the same rule.
+                        self.it.as_mut().unwrap().it.skip_current_dir();
+                        let (igtmp, _) = self.ig.add_child(ent.path());
+                        self.ig = igtmp;
+                        return Some(Ok(ent));
+                    }
                     let (igtmp, err) = self.ig.add_child(ent.path());
                     self.ig = igtmp;
                     ent.err = err;
                     return Some(Ok(ent));
                 }
                 Ok(WalkEvent::File(ent)) => {
-                    let ent = DirEntry::new_walkdir(ent, None);
-                    let should_skip = match self.skip_entry(&ent) {
+                    let mut ent = DirEntry::new_walkdir(ent, None);
+                    let should_skip = match self.skip_entry(&mut ent) {
                         Err(err) => return Some(Err(err)),
                         Ok(should_skip) => should_skip,
                     };
@@ -1178,6 +1568,25 @@ impl<'s> ParallelVisitor for FnVisitorImp<'s> {
     }
 }
 
+/// The visitor builder used internally by ordered mode. Workers push their
+/// results on to the shared reorder buffer instead of calling a visitor, so
+/// the per-thread visitors do nothing.
+struct OrderedBuilder;
+
+impl<'s> ParallelVisitorBuilder<'s> for OrderedBuilder {
+    fn build(&mut self) -> Box<dyn ParallelVisitor + 's> {
+        Box::new(OrderedVisitor)
+    }
+}
+
+struct OrderedVisitor;
+
+impl ParallelVisitor for OrderedVisitor {
+    fn visit(&mut self, _: Result<DirEntry, Error>) -> WalkState {
+        WalkState::Continue
+    }
+}
+
 /// WalkParallel is a parallel recursive directory iterator over files paths
 /// in one or more directories.
 ///
@@ -1190,14 +1599,27 @@ pub struct WalkParallel {
     paths: vec::IntoIter<PathBuf>,
     ig_root: Ignore,
     max_filesize: Option<u64>,
+    modified_after: Option<SystemTime>,
+    modified_before: Option<SystemTime>,
     max_depth: Option<usize>,
     follow_links: bool,
     same_file_system: bool,
     threads: usize,
     skip: Option<Arc<Handle>>,
     filter: Option<Filter>,
+    cancel: Option<Arc<AtomicBool>>,
+    ordered: bool,
+    report_ignored: bool,
 }
 
+/// The reorder buffer used by ordered mode.
+///
+/// Each entry (or error) is tagged with its position in depth first
+/// traversal order: the sequence of child indices leading from a root path
+/// to the entry. Sorting the buffer lexicographically by these tags yields
+/// the order in which the single threaded walker reports entries.
+type OrderedBuffer = Vec<(Vec<usize>, Result<DirEntry, Error>)>;
+
 impl WalkParallel {
     /// Execute the parallel recursive directory iterator. `mkf` is called
     /// for each thread used for iteration. The function produced by `mkf`
@@ -1226,7 +1648,48 @@ impl WalkParallel {
     /// visitor runs on only one thread, this build-up can be done without
     /// synchronization. Then, once traversal is complete, all of the results
     /// can be merged together into a single data structure.
-    pub fn visit(mut self, builder: &mut dyn ParallelVisitorBuilder<'_>) {
+    pub fn visit(self, builder: &mut dyn ParallelVisitorBuilder<'_>) {
+        if self.ordered {
+            self.visit_ordered(builder)
+        } else {
+            self.visit_impl(builder, None)
+        }
+    }
+
+    /// Runs the traversal with a reorder buffer and then replays the
+    /// buffered entries, in depth first traversal order, to a single
+    /// visitor built from the given builder.
+    fn visit_ordered(self, builder: &mut dyn ParallelVisitorBuilder<'_>) {
+        let buf = Arc::new(Mutex::new(vec![]));
+        self.visit_impl(&mut OrderedBuilder, Some(&buf));
+        // All of the workers have finished, so nothing else is writing to
+        // the buffer at this point.
+        let mut entries = std::mem::take(&mut *buf.lock().unwrap());
+        entries.sort_by(|e1, e2| e1.0.cmp(&e2.0));
+
+        let mut visitor = builder.build();
+        let mut skipping: Option<Vec<usize>> = None;
+        for (key, result) in entries {
+            if let Some(ref prefix) = skipping {
+                if key.len() > prefix.len() && key[..prefix.len()] == **prefix
+                {
+                    continue;
+                }
+                skipping = None;
+            }
+            match visitor.visit(result) {
+                WalkState::Continue => {}
+                WalkState::Skip => skipping = Some(key),
+                WalkState::Quit => return,
+            }
+        }
+    }
+
+    fn visit_impl(
+        mut self,
+        builder: &mut dyn ParallelVisitorBuilder<'_>,
+        collect: Option<&Arc<Mutex<OrderedBuffer>>>,
+    ) {
         let threads = self.threads();
         let stack = Arc::new(Mutex::new(vec![]));
         {
@@ -1237,7 +1700,7 @@ impl WalkParallel {
             // Send the initial set of root paths to the pool of workers. Note
             // that we only send directories. For files, we send to them the
             // callback directly.
-            for path in paths {
+            for (root_index, path) in paths.enumerate() {
                 let (dent, root_device) = if path == Path::new("-") {
                     (DirEntry::new_stdin(), None)
                 } else {
@@ -1248,8 +1711,16 @@ impl WalkParallel {
                             Ok(root_device) => Some(root_device),
                             Err(err) => {
                                 let err = Error::Io(err).with_path(path);
-                                if visitor.visit(Err(err)).is_quit() {
-                                    return;
+                                match collect {
+                                    Some(buf) => buf
+                                        .lock()
+                                        .unwrap()
+                                        .push((vec![root_index], Err(err))),
+                                    None => {
+                                        if visitor.visit(Err(err)).is_quit() {
+                                            return;
+                                        }
+                                    }
                                 }
                                 continue;
                             }
@@ -1260,8 +1731,16 @@ impl WalkParallel {
                             (DirEntry::new_raw(dent, None), root_device)
                         }
                         Err(err) => {
-                            if visitor.visit(Err(err)).is_quit() {
-                                return;
+                            match collect {
+                                Some(buf) => buf
+                                    .lock()
+                                    .unwrap()
+                                    .push((vec![root_index], Err(err))),
+                                None => {
+                                    if visitor.visit(Err(err)).is_quit() {
+                                        return;
+                                    }
+                                }
                             }
                             continue;
                         }
@@ -1271,6 +1750,7 @@ impl WalkParallel {
                     dent: dent,
                     ignore: self.ig_root.clone(),
                     root_device: root_device,
+                    key: collect.map(|_| vec![root_index]),
                 }));
             }
             // ... but there's no need to start workers if we don't need them.
@@ -1292,9 +1772,14 @@ impl WalkParallel {
                     num_pending: num_pending.clone(),
                     max_depth: self.max_depth,
                     max_filesize: self.max_filesize,
+                    modified_after: self.modified_after,
+                    modified_before: self.modified_before,
                     follow_links: self.follow_links,
                     skip: self.skip.clone(),
                     filter: self.filter.clone(),
+                    cancel: self.cancel.clone(),
+                    collect: collect.cloned(),
+                    report_ignored: self.report_ignored,
                 };
                 handles.push(s.spawn(|| worker.run()));
             }
@@ -1335,6 +1820,9 @@ struct Work {
     /// The root device number. When present, only files with the same device
     /// number should be considered.
     root_device: Option<u64>,
+    /// The entry's position in depth first traversal order. This is only
+    /// present in ordered mode.
+    key: Option<Vec<usize>>,
 }
 
 impl Work {
@@ -1413,6 +1901,10 @@ struct Worker<'s> {
     /// The maximum size a searched file can be (in bytes). If a file exceeds
     /// this size it will be skipped.
     max_filesize: Option<u64>,
+    /// If set, files last modified at or before this time are skipped.
+    modified_after: Option<SystemTime>,
+    /// If set, files last modified at or after this time are skipped.
+    modified_before: Option<SystemTime>,
     /// Whether to follow symbolic links or not. When this is enabled, loop
     /// detection is performed.
     follow_links: bool,
@@ -1422,6 +1914,17 @@ struct Worker<'s> {
     /// A predicate applied to dir entries. If true, the entry and all
     /// children will be skipped.
     filter: Option<Filter>,
+    /// A caller provided cancellation token. When set, it is treated just
+    /// like the internal `quit_now` flag, except that it can be flipped
+    /// from outside the traversal, e.g., by a signal handler.
+    cancel: Option<Arc<AtomicBool>>,
+    /// The shared reorder buffer, if the traversal is running in ordered
+    /// mode. When present, results are pushed on to the buffer along with
+    /// their traversal sequence instead of being given to the visitor.
+    collect: Option<Arc<Mutex<OrderedBuffer>>>,
+    /// Whether to yield ignored entries (tagged with their ignore status)
+    /// instead of skipping them.
+    report_ignored: bool,
 }
 
 impl<'s> Worker<'s> {
@@ -1439,13 +1942,14 @@ impl<'s> Worker<'s> {
     }
 
     fn run_one(&mut self, mut work: Work) -> WalkState {
+        let key = work.key.take();
         // If the work is not a directory, then we can just execute the
         // caller's callback immediately and move on.
         if work.is_symlink() || !work.is_dir() {
-            return self.visitor.visit(Ok(work.dent));
+            return self.visit_item(&key, Ok(work.dent));
         }
         if let Some(err) = work.add_parents() {
-            let state = self.visitor.visit(Err(err));
+            let state = self.visit_item(&key, Err(err));
             if state.is_quit() {
                 return state;
             }
@@ -1456,7 +1960,7 @@ impl<'s> Worker<'s> {
                 Ok(true) => true,
                 Ok(false) => false,
                 Err(err) => {
-                    let state = self.visitor.visit(Err(err));
+                    let state = self.visit_item(&key, Err(err));
                     if state.is_quit() {
                         return state;
                     }
@@ -1466,6 +1970,10 @@ impl<'s> Worker<'s> {
         } else {
             true
         };
+        // An ignored directory is only present when reporting ignored
+        // entries. Yield it, but don't descend into it, since everything
+        // beneath it is ignored by the same rule.
+        let descend = descend && !work.dent.ignore_status().is_ignored();
 
         // Try to read the directory first before we transfer ownership
         // to the provided closure. Do not unwrap it immediately, though,
@@ -1475,7 +1983,7 @@ impl<'s> Worker<'s> {
         // entry before passing the error value.
         let readdir = work.read_dir();
         let depth = work.dent.depth();
-        let state = self.visitor.visit(Ok(work.dent));
+        let state = self.visit_item(&key, Ok(work.dent));
         if !state.is_continue() {
             return state;
         }
@@ -1486,18 +1994,26 @@ impl<'s> Worker<'s> {
         let readdir = match readdir {
             Ok(readdir) => readdir,
             Err(err) => {
-                return self.visitor.visit(Err(err));
+                return self.visit_item(&key, Err(err));
             }
         };
 
         if self.max_depth.map_or(false, |max| depth >= max) {
             return WalkState::Skip;
         }
+        let mut child_index = 0;
         for result in readdir {
+            let child_key = key.as_ref().map(|key| {
+                let mut key = key.clone();
+                key.push(child_index);
+                key
+            });
+            child_index += 1;
             let state = self.generate_work(
                 &work.ignore,
                 depth + 1,
                 work.root_device,
+                child_key,
                 result,
             );
             if state.is_quit() {
@@ -1518,27 +2034,30 @@ impl<'s> Worker<'s> {
     /// caller's callback.
     ///
     /// `ig` is the `Ignore` matcher for the parent directory. `depth` should
-    /// be the depth of this entry. `result` should be the item yielded by
-    /// a directory iterator.
+    /// be the depth of this entry. `key`, if present, is the entry's
+    /// position in depth first traversal order. `result` should be the item
+    /// yielded by a directory iterator.
     fn generate_work(
         &mut self,
         ig: &Ignore,
         depth: usize,
         root_device: Option<u64>,
+        key: Option<Vec<usize>>,
         result: Result<fs::DirEntry, io::Error>,
     ) -> WalkState {
         let fs_dent = match result {
             Ok(fs_dent) => fs_dent,
             Err(err) => {
-                return self
-                    .visitor
-                    .visit(Err(Error::from(err).with_depth(depth)));
+                return self.visit_item(
+                    &key,
+                    Err(Error::from(err).with_depth(depth)),
+                );
             }
         };
         let mut dent = match DirEntryRaw::from_entry(depth, &fs_dent) {
             Ok(dent) => DirEntry::new_raw(dent, None),
             Err(err) => {
-                return self.visitor.visit(Err(err));
+                return self.visit_item(&key, Err(err));
             }
         };
         let is_symlink = dent.file_type().map_or(false, |ft| ft.is_symlink());
@@ -1547,24 +2066,24 @@ impl<'s> Worker<'s> {
             dent = match DirEntryRaw::from_path(depth, path, true) {
                 Ok(dent) => DirEntry::new_raw(dent, None),
                 Err(err) => {
-                    return self.visitor.visit(Err(err));
+                    return self.visit_item(&key, Err(err));
                 }
             };
             if dent.is_dir() {
                 if let Err(err) = check_symlink_loop(ig, dent.path(), depth) {
-                    return self.visitor.visit(Err(err));
+                    return self.visit_item(&key, Err(err));
                 }
             }
         }
         // N.B. See analogous call in the single-threaded implementation about
         // why it's important for this to come before the checks below.
-        if should_skip_entry(ig, &dent) {
+        if should_skip_entry(ig, &mut dent, self.report_ignored) {
             return WalkState::Continue;
         }
         if let Some(ref stdout) = self.skip {
             let is_stdout = match path_equals(&dent, stdout) {
                 Ok(is_stdout) => is_stdout,
-                Err(err) => return self.visitor.visit(Err(err)),
+                Err(err) => return self.visit_item(&key, Err(err)),
             };
             if is_stdout {
                 return WalkState::Continue;
@@ -1580,18 +2099,52 @@ impl<'s> Worker<'s> {
             } else {
                 false
             };
+        let should_skip_mtime = if (self.modified_after.is_some()
+            || self.modified_before.is_some())
+            && !dent.is_dir()
+        {
+            skip_mtime(
+                self.modified_after,
+                self.modified_before,
+                dent.path(),
+                &dent.metadata().ok(),
+            )
+        } else {
+            false
+        };
         let should_skip_filtered =
             if let Some(Filter(predicate)) = &self.filter {
                 !predicate(&dent)
             } else {
                 false
             };
-        if !should_skip_filesize && !should_skip_filtered {
-            self.send(Work { dent, ignore: ig.clone(), root_device });
+        if !should_skip_filesize && !should_skip_mtime && !should_skip_filtered
+        {
+            self.send(Work { dent, ignore: ig.clone(), root_device, key });
         }
         WalkState::Continue
     }
 
+    /// Delivers a single traversal result.
+    ///
+    /// In ordered mode, the result is pushed on to the shared reorder
+    /// buffer along with the given traversal sequence. Otherwise, the
+    /// caller's visitor is called directly.
+    fn visit_item(
+        &mut self,
+        key: &Option<Vec<usize>>,
+        result: Result<DirEntry, Error>,
+    ) -> WalkState {
+        match self.collect {
+            None => self.visitor.visit(result),
+            Some(ref buf) => {
+                let key = key.clone().unwrap_or_default();
+                buf.lock().unwrap().push((key, result));
+                WalkState::Continue
+            }
+        }
+    }
+
     /// Returns the next directory to descend into.
     ///
     /// If all work has been exhausted, then this returns None. The worker
@@ -1655,6 +2208,7 @@ impl<'s> Worker<'s> {
     /// Returns true if this worker should quit immediately.
     fn is_quit_now(&self) -> bool {
         self.quit_now.load(Ordering::SeqCst)
+            || self.cancel.as_ref().map_or(false, |t| t.load(Ordering::SeqCst))
     }
 
     /// Returns the number of pending jobs.
@@ -1734,17 +2288,64 @@ fn skip_filesize(
     }
 }
 
-fn should_skip_entry(ig: &Ignore, dent: &DirEntry) -> bool {
+// Before calling this function, make sure that you ensure that is really
+// necessary as the arguments imply a file stat.
+fn skip_mtime(
+    after: Option<SystemTime>,
+    before: Option<SystemTime>,
+    path: &Path,
+    ent: &Option<Metadata>,
+) -> bool {
+    let mtime = match *ent {
+        Some(ref md) => md.modified().ok(),
+        None => None,
+    };
+
+    if let Some(mtime) = mtime {
+        let too_old = after.map_or(false, |after| mtime <= after);
+        let too_new = before.map_or(false, |before| mtime >= before);
+        if too_old || too_new {
+            log::debug!(
+                "ignoring {}: modified at {:?}",
+                path.display(),
+                mtime
+            );
+            true
+        } else {
+            false
+        }
+    } else {
+        false
+    }
+}
+
+fn should_skip_entry(
+    ig: &Ignore,
+    dent: &mut DirEntry,
+    report_ignored: bool,
+) -> bool {
     let m = ig.matched_dir_entry(dent);
     if m.is_ignore() {
         log::debug!("ignoring {}: {:?}", dent.path().display(), m);
-        true
-    } else if m.is_whitelist() {
+        if report_ignored {
+            if let Match::Ignore(ref im) = m {
+                dent.ignore_status = IgnoreStatus::Ignored(im.describe());
+            }
+            return false;
+        }
+        return true;
+    }
+    if m.is_whitelist() {
         log::debug!("whitelisting {}: {:?}", dent.path().display(), m);
-        false
-    } else {
-        false
+        dent.ignore_status = IgnoreStatus::Whitelisted;
     }
+    // Record the file type definition that admitted this entry, if any, so
+    // that callers can retrieve it via DirEntry::file_type_def.
+    dent.type_def = match m {
+        Match::Whitelist(ref im) => im.file_type_def().cloned(),
+        _ => None,
+    };
+    false
 }
 
 /// Returns a handle to stdout for filtering search.
@@ -1849,8 +2450,9 @@ mod tests {
     use std::io::Write;
     use std::path::Path;
     use std::sync::{Arc, Mutex};
+    use std::time::{Duration, SystemTime};
 
-    use super::{DirEntry, WalkBuilder, WalkState};
+    use super::{DirEntry, IgnoreStatus, WalkBuilder, WalkState};
     use crate::tests::TempDir;
 
     fn wfile<P: AsRef<Path>>(path: P, contents: &str) {
@@ -1962,6 +2564,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn file_type_def() {
+        use crate::types::TypesBuilder;
+
+        let td = tmpdir();
+        wfile(td.path().join("main.rs"), "");
+        wfile(td.path().join("notes.txt"), "");
+
+        let mut tbuilder = TypesBuilder::new();
+        tbuilder.add_defaults();
+        tbuilder.select("rust");
+        let mut builder = WalkBuilder::new(td.path());
+        builder.types(tbuilder.build().unwrap());
+
+        let mut got = vec![];
+        for result in builder.build() {
+            let dent = result.unwrap();
+            if dent.path() == td.path() {
+                continue;
+            }
+            got.push((
+                dent.file_name().to_string_lossy().into_owned(),
+                dent.file_type_def().map(|def| def.name().to_string()),
+            ));
+        }
+        assert_eq!(
+            vec![("main.rs".to_string(), Some("rust".to_string()))],
+            got
+        );
+    }
+
     #[test]
     fn custom_ignore() {
         let td = tmpdir();
@@ -2016,6 +2649,66 @@ mod tests {
         );
     }
 
+    #[test]
+    fn report_ignored() {
+        let td = tmpdir();
+        mkdirp(td.path().join(".git"));
+        mkdirp(td.path().join("dir"));
+        wfile(td.path().join(".gitignore"), "foo\ndir/\n!bar\n");
+        wfile(td.path().join("foo"), "");
+        wfile(td.path().join("bar"), "");
+        wfile(td.path().join("quux"), "");
+        wfile(td.path().join("dir/baz"), "");
+
+        let mut builder = WalkBuilder::new(td.path());
+        assert_paths(td.path(), &builder, &["bar", "quux"]);
+
+        // With reporting enabled, ignored entries are yielded, but ignored
+        // directories are not descended into.
+        builder.report_ignored(true);
+        assert_paths(
+            td.path(),
+            &builder,
+            &[".git", ".gitignore", "bar", "dir", "foo", "quux"],
+        );
+        for result in builder.build() {
+            let dent = result.unwrap();
+            let path = dent.path().strip_prefix(td.path()).unwrap();
+            match path.to_str().unwrap() {
+                "foo" => match *dent.ignore_status() {
+                    IgnoreStatus::Ignored(ref desc) => {
+                        assert!(desc.contains("glob 'foo'"));
+                    }
+                    ref status => panic!("unexpected status: {:?}", status),
+                },
+                "dir" => assert!(dent.ignore_status().is_ignored()),
+                "bar" => assert!(dent.ignore_status().is_whitelisted()),
+                "quux" => {
+                    assert_eq!(*dent.ignore_status(), IgnoreStatus::NotMatched)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn git_global_path() {
+        let td = tmpdir();
+        mkdirp(td.path().join(".git"));
+        wfile(td.path().join("global-ignore"), "foo");
+        wfile(td.path().join("foo"), "");
+        wfile(td.path().join("bar"), "");
+
+        let mut builder = WalkBuilder::new(td.path());
+        builder.git_global_path(Some(td.path().join("global-ignore")));
+        assert_paths(td.path(), &builder, &["bar", "global-ignore"]);
+
+        // The global gitignore file is re-read on every build, so changes
+        // to it are picked up by rebuilding the walker.
+        wfile(td.path().join("global-ignore"), "bar");
+        assert_paths(td.path(), &builder, &["foo", "global-ignore"]);
+    }
+
     #[test]
     fn explicit_ignore() {
         let td = tmpdir();
@@ -2124,6 +2817,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn modified_time() {
+        let td = tmpdir();
+        mkdirp(td.path().join("a"));
+        wfile(td.path().join("foo"), "");
+        wfile(td.path().join("a/bar"), "");
+
+        let hour = Duration::from_secs(3600);
+        let now = SystemTime::now();
+        let mut builder = WalkBuilder::new(td.path());
+        assert_paths(td.path(), &builder, &["a", "foo", "a/bar"]);
+        assert_paths(
+            td.path(),
+            builder.modified_after(Some(now - hour)),
+            &["a", "foo", "a/bar"],
+        );
+        assert_paths(
+            td.path(),
+            builder.modified_after(Some(now + hour)),
+            &["a"],
+        );
+        builder.modified_after(None);
+        assert_paths(
+            td.path(),
+            builder.modified_before(Some(now + hour)),
+            &["a", "foo", "a/bar"],
+        );
+        assert_paths(
+            td.path(),
+            builder.modified_before(Some(now - hour)),
+            &["a"],
+        );
+    }
+
     #[cfg(unix)] // because symlinks on windows are weird
     #[test]
     fn symlinks() {
@@ -2248,4 +2975,46 @@ mod tests {
             &["x", "x/y", "x/y/foo"],
         );
     }
+
+    #[test]
+    fn ordered_matches_single_threaded() {
+        let td = tmpdir();
+        mkdirp(td.path().join("a/b/c"));
+        mkdirp(td.path().join("x/y"));
+        wfile(td.path().join("a/b/foo"), "");
+        wfile(td.path().join("a/b/c/bar"), "");
+        wfile(td.path().join("x/y/foo"), "");
+
+        let mut builder = WalkBuilder::new(td.path());
+        builder.threads(4).ordered(true);
+
+        // Note the absence of sorting: ordered mode must reproduce the
+        // exact sequence of the single threaded walker.
+        let mut serial = vec![];
+        for result in builder.build() {
+            let dent = result.unwrap();
+            let path = dent.path().strip_prefix(td.path()).unwrap();
+            if path.as_os_str().is_empty() {
+                continue;
+            }
+            serial.push(normal_path(path.to_str().unwrap()));
+        }
+
+        let paths = Arc::new(Mutex::new(vec![]));
+        builder.build_parallel().run(|| {
+            let paths = paths.clone();
+            let prefix = td.path().to_path_buf();
+            Box::new(move |result| {
+                let dent = result.unwrap();
+                let path = dent.path().strip_prefix(&prefix).unwrap();
+                if !path.as_os_str().is_empty() {
+                    let path = normal_path(path.to_str().unwrap());
+                    paths.lock().unwrap().push(path);
+                }
+                WalkState::Continue
+            })
+        });
+        let parallel = paths.lock().unwrap().to_vec();
+        assert_eq!(serial, parallel);
+    }
 }
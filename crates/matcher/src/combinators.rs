@@ -0,0 +1,285 @@
+/*!
+Provides combinators for composing matchers.
+
+The combinators in this module wrap one or more existing implementations of
+[`Matcher`](../trait.Matcher.html) and combine their results:
+
+* [`AllOf`](struct.AllOf.html) reports the matches of its first matcher,
+  but only where every other matcher also matches.
+* [`AnyOf`](struct.AnyOf.html) reports the leftmost match among all of its
+  matchers.
+* [`Not`](struct.Not.html) matches exactly when its matcher does not.
+
+When every wrapped matcher is line oriented (that is, agrees on a line
+terminator that never appears in a match), the combinators remain line
+oriented: `AllOf` confines its confirmation of the other matchers to the
+line containing a candidate match, and all combinators report the common
+line terminator so that callers can keep using line oriented optimizations.
+Otherwise, combination happens over the entire haystack given.
+
+Combinators do not support capturing groups; their `new_captures` routines
+yield [`NoCaptures`](../struct.NoCaptures.html).
+*/
+
+use std::cmp;
+
+use crate::{ByteSet, LineTerminator, Match, Matcher, NoCaptures};
+
+/// A matcher that reports the matches of its first matcher, but only where
+/// every other matcher also matches.
+///
+/// When a common line terminator is known, the other matchers must match
+/// within the line containing a candidate match. Otherwise, they must match
+/// somewhere in the haystack being searched.
+///
+/// An `AllOf` with no matchers never matches.
+#[derive(Clone, Debug)]
+pub struct AllOf<M> {
+    matchers: Vec<M>,
+    line_term: Option<LineTerminator>,
+    non_matching: Option<ByteSet>,
+}
+
+impl<M: Matcher> AllOf<M> {
+    /// Create a new matcher that intersects the matches of all of the given
+    /// matchers.
+    pub fn new(matchers: Vec<M>) -> AllOf<M> {
+        let line_term = common_line_terminator(&matchers);
+        // Reported matches come from the first matcher alone, so its
+        // non-matching bytes are non-matching for the intersection too.
+        let non_matching =
+            matchers.first().and_then(|m| m.non_matching_bytes().cloned());
+        AllOf { matchers, line_term, non_matching }
+    }
+}
+
+impl<M: Matcher> Matcher for AllOf<M> {
+    type Captures = NoCaptures;
+    type Error = M::Error;
+
+    fn find_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+    ) -> Result<Option<Match>, M::Error> {
+        let (first, rest) = match self.matchers.split_first() {
+            None => return Ok(None),
+            Some(split) => split,
+        };
+        let mut at = at;
+        while let Some(m) = first.find_at(haystack, at)? {
+            let scope = match self.line_term {
+                Some(line_term) => line_bounds(haystack, m, line_term),
+                None => Match::new(0, haystack.len()),
+            };
+            let mut confirmed = true;
+            for matcher in rest {
+                if !matcher.is_match(&haystack[scope])? {
+                    confirmed = false;
+                    break;
+                }
+            }
+            if confirmed {
+                return Ok(Some(m));
+            }
+            // Resume after the scope that failed confirmation, taking care
+            // to always make progress even on empty matches.
+            let next = cmp::max(m.end(), scope.end());
+            at = if next > at { next } else { at + 1 };
+            if at > haystack.len() {
+                break;
+            }
+        }
+        Ok(None)
+    }
+
+    fn new_captures(&self) -> Result<NoCaptures, M::Error> {
+        Ok(NoCaptures::new())
+    }
+
+    fn is_match(&self, haystack: &[u8]) -> Result<bool, M::Error> {
+        if self.matchers.is_empty() {
+            return Ok(false);
+        }
+        for matcher in &self.matchers {
+            if !matcher.is_match(haystack)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn non_matching_bytes(&self) -> Option<&ByteSet> {
+        self.non_matching.as_ref()
+    }
+
+    fn line_terminator(&self) -> Option<LineTerminator> {
+        self.line_term
+    }
+}
+
+/// A matcher that reports the leftmost match among all of its matchers.
+///
+/// When two matchers produce matches with the same starting position, the
+/// match of the matcher given first is reported.
+///
+/// An `AnyOf` with no matchers never matches.
+#[derive(Clone, Debug)]
+pub struct AnyOf<M> {
+    matchers: Vec<M>,
+    line_term: Option<LineTerminator>,
+    non_matching: Option<ByteSet>,
+}
+
+impl<M: Matcher> AnyOf<M> {
+    /// Create a new matcher that unions the matches of all of the given
+    /// matchers.
+    pub fn new(matchers: Vec<M>) -> AnyOf<M> {
+        let line_term = common_line_terminator(&matchers);
+        let non_matching = intersect_non_matching_bytes(&matchers);
+        AnyOf { matchers, line_term, non_matching }
+    }
+}
+
+impl<M: Matcher> Matcher for AnyOf<M> {
+    type Captures = NoCaptures;
+    type Error = M::Error;
+
+    fn find_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+    ) -> Result<Option<Match>, M::Error> {
+        let mut best: Option<Match> = None;
+        for matcher in &self.matchers {
+            let m = match matcher.find_at(haystack, at)? {
+                None => continue,
+                Some(m) => m,
+            };
+            if best.map_or(true, |b| m.start() < b.start()) {
+                best = Some(m);
+            }
+        }
+        Ok(best)
+    }
+
+    fn new_captures(&self) -> Result<NoCaptures, M::Error> {
+        Ok(NoCaptures::new())
+    }
+
+    fn is_match(&self, haystack: &[u8]) -> Result<bool, M::Error> {
+        for matcher in &self.matchers {
+            if matcher.is_match(haystack)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn non_matching_bytes(&self) -> Option<&ByteSet> {
+        self.non_matching.as_ref()
+    }
+
+    fn line_terminator(&self) -> Option<LineTerminator> {
+        self.line_term
+    }
+}
+
+/// A matcher that matches exactly when its matcher does not.
+///
+/// Since there is no span to report for the absence of a match, a `Not`
+/// reports an empty match at the starting position of the search. This
+/// makes it most useful for composition (e.g., inside an
+/// [`AllOf`](struct.AllOf.html) to subtract matches) and for `is_match`,
+/// rather than for reporting spans on its own.
+#[derive(Clone, Debug)]
+pub struct Not<M> {
+    matcher: M,
+    non_matching: ByteSet,
+}
+
+impl<M: Matcher> Not<M> {
+    /// Create a new matcher that inverts the given matcher.
+    pub fn new(matcher: M) -> Not<M> {
+        Not { matcher, non_matching: ByteSet::full() }
+    }
+}
+
+impl<M: Matcher> Matcher for Not<M> {
+    type Captures = NoCaptures;
+    type Error = M::Error;
+
+    fn find_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+    ) -> Result<Option<Match>, M::Error> {
+        if self.matcher.find_at(haystack, at)?.is_some() {
+            Ok(None)
+        } else {
+            Ok(Some(Match::zero(at)))
+        }
+    }
+
+    fn new_captures(&self) -> Result<NoCaptures, M::Error> {
+        Ok(NoCaptures::new())
+    }
+
+    fn is_match(&self, haystack: &[u8]) -> Result<bool, M::Error> {
+        Ok(!self.matcher.is_match(haystack)?)
+    }
+
+    fn non_matching_bytes(&self) -> Option<&ByteSet> {
+        // Every match reported is empty, so no byte ever appears in one.
+        Some(&self.non_matching)
+    }
+
+    fn line_terminator(&self) -> Option<LineTerminator> {
+        // An empty match can never contain a line terminator, so the inner
+        // matcher's line orientation carries over.
+        self.matcher.line_terminator()
+    }
+}
+
+/// Return the line terminator shared by all of the given matchers, if there
+/// is one.
+fn common_line_terminator<M: Matcher>(
+    matchers: &[M],
+) -> Option<LineTerminator> {
+    let mut matchers = matchers.iter();
+    let line_term = matchers.next()?.line_terminator()?;
+    for matcher in matchers {
+        if matcher.line_terminator() != Some(line_term) {
+            return None;
+        }
+    }
+    Some(line_term)
+}
+
+/// Return the set of bytes that are non-matching for every one of the given
+/// matchers, or `None` if any matcher cannot provide a set.
+fn intersect_non_matching_bytes<M: Matcher>(
+    matchers: &[M],
+) -> Option<ByteSet> {
+    let mut set = ByteSet::full();
+    for matcher in matchers {
+        let theirs = matcher.non_matching_bytes()?;
+        for byte in 0..=255 {
+            if !theirs.contains(byte) {
+                set.remove(byte);
+            }
+        }
+    }
+    Some(set)
+}
+
+/// Return the bounds of the line containing the given match, including its
+/// line terminator when present.
+fn line_bounds(haystack: &[u8], m: Match, line_term: LineTerminator) -> Match {
+    let byte = line_term.as_byte();
+    let start = memchr::memrchr(byte, &haystack[..m.start()])
+        .map_or(0, |offset| offset + 1);
+    let end = memchr::memchr(byte, &haystack[m.end()..])
+        .map_or(haystack.len(), |offset| m.end() + offset + 1);
+    Match::new(start, end)
+}
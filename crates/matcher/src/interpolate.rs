@@ -1,6 +1,6 @@
 use std::str;
 
-use memchr::memchr;
+use memchr::memchr2;
 
 /// Interpolate capture references in `replacement` and write the interpolation
 /// result to `dst`. References in `replacement` take the form of $N or $name,
@@ -12,6 +12,12 @@ use memchr::memchr;
 /// of a capture group reference and is expected to resolve the index to its
 /// corresponding matched text. If no such match exists, then `append` should
 /// not write anything to its given buffer.
+///
+/// The case of interpolated text can be changed. `\U` starts converting
+/// everything that follows to uppercase and `\L` to lowercase, until a `\E`
+/// (or the end of the replacement) is seen. A single capture reference may
+/// also be converted by attaching a transform to its brace form, e.g.,
+/// `${1:upper}` or `${name:lower}`. Case conversion is limited to ASCII.
 pub fn interpolate<A, N>(
     mut replacement: &[u8],
     mut append: A,
@@ -21,14 +27,29 @@ pub fn interpolate<A, N>(
     A: FnMut(usize, &mut Vec<u8>),
     N: FnMut(&str) -> Option<usize>,
 {
+    let mut transform = None;
     while !replacement.is_empty() {
-        match memchr(b'$', replacement) {
+        match memchr2(b'$', b'\\', replacement) {
             None => break,
             Some(i) => {
-                dst.extend(&replacement[..i]);
+                extend_transformed(dst, &replacement[..i], transform);
                 replacement = &replacement[i..];
             }
         }
+        if replacement[0] == b'\\' {
+            match replacement.get(1) {
+                Some(&b'U') => transform = Some(Transform::Upper),
+                Some(&b'L') => transform = Some(Transform::Lower),
+                Some(&b'E') => transform = None,
+                _ => {
+                    dst.push(b'\\');
+                    replacement = &replacement[1..];
+                    continue;
+                }
+            }
+            replacement = &replacement[2..];
+            continue;
+        }
         if replacement.get(1).map_or(false, |&b| b == b'$') {
             dst.push(b'$');
             replacement = &replacement[2..];
@@ -44,16 +65,67 @@ pub fn interpolate<A, N>(
             }
         };
         replacement = &replacement[cap_ref.end..];
+        let transform = cap_ref.transform.or(transform);
         match cap_ref.cap {
-            Ref::Number(i) => append(i, dst),
+            Ref::Number(i) => {
+                append_transformed(dst, i, transform, &mut append)
+            }
             Ref::Named(name) => {
                 if let Some(i) = name_to_index(name) {
-                    append(i, dst);
+                    append_transformed(dst, i, transform, &mut append);
                 }
             }
         }
     }
-    dst.extend(replacement);
+    extend_transformed(dst, replacement, transform);
+}
+
+/// A case transform to apply to interpolated text.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Transform {
+    Upper,
+    Lower,
+}
+
+impl Transform {
+    /// Apply this transform to the given byte.
+    fn apply(&self, b: u8) -> u8 {
+        match *self {
+            Transform::Upper => b.to_ascii_uppercase(),
+            Transform::Lower => b.to_ascii_lowercase(),
+        }
+    }
+}
+
+/// Extend `dst` with the given bytes, transformed if a transform is active.
+fn extend_transformed(
+    dst: &mut Vec<u8>,
+    bytes: &[u8],
+    transform: Option<Transform>,
+) {
+    match transform {
+        None => dst.extend(bytes),
+        Some(t) => dst.extend(bytes.iter().map(|&b| t.apply(b))),
+    }
+}
+
+/// Append the text of the capture group at index `i` to `dst`, transformed
+/// if a transform is active.
+fn append_transformed<A: FnMut(usize, &mut Vec<u8>)>(
+    dst: &mut Vec<u8>,
+    i: usize,
+    transform: Option<Transform>,
+    append: &mut A,
+) {
+    let t = match transform {
+        None => return append(i, dst),
+        Some(t) => t,
+    };
+    let start = dst.len();
+    append(i, dst);
+    for b in dst[start..].iter_mut() {
+        *b = t.apply(*b);
+    }
 }
 
 /// `CaptureRef` represents a reference to a capture group inside some text.
@@ -65,6 +137,7 @@ pub fn interpolate<A, N>(
 struct CaptureRef<'a> {
     cap: Ref<'a>,
     end: usize,
+    transform: Option<Transform>,
 }
 
 /// A reference to a capture group in some text.
@@ -116,11 +189,20 @@ fn find_cap_ref(replacement: &[u8]) -> Option<CaptureRef<'_>> {
     // from &[u8].
     let cap = str::from_utf8(&replacement[i..cap_end])
         .expect("valid UTF-8 capture name");
+    let mut transform = None;
     if brace {
-        if !replacement.get(cap_end).map_or(false, |&b| b == b'}') {
+        let rest = &replacement[cap_end..];
+        if rest.starts_with(b":upper}") {
+            transform = Some(Transform::Upper);
+            cap_end += 7;
+        } else if rest.starts_with(b":lower}") {
+            transform = Some(Transform::Lower);
+            cap_end += 7;
+        } else if rest.starts_with(b"}") {
+            cap_end += 1;
+        } else {
             return None;
         }
-        cap_end += 1;
     }
     Some(CaptureRef {
         cap: match cap.parse::<u32>() {
@@ -128,6 +210,7 @@ fn find_cap_ref(replacement: &[u8]) -> Option<CaptureRef<'_>> {
             Err(_) => Ref::Named(cap),
         },
         end: cap_end,
+        transform,
     })
 }
 
@@ -141,7 +224,7 @@ fn is_valid_cap_letter(b: &u8) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use super::{find_cap_ref, interpolate, CaptureRef};
+    use super::{find_cap_ref, interpolate, CaptureRef, Transform};
 
     macro_rules! find {
         ($name:ident, $text:expr) => {
@@ -160,7 +243,18 @@ mod tests {
 
     macro_rules! c {
         ($name_or_number:expr, $pos:expr) => {
-            CaptureRef { cap: $name_or_number.into(), end: $pos }
+            CaptureRef {
+                cap: $name_or_number.into(),
+                end: $pos,
+                transform: None,
+            }
+        };
+        ($name_or_number:expr, $pos:expr, $transform:expr) => {
+            CaptureRef {
+                cap: $name_or_number.into(),
+                end: $pos,
+                transform: Some($transform),
+            }
         };
     }
 
@@ -177,6 +271,11 @@ mod tests {
     find!(find_cap_ref11, "$");
     find!(find_cap_ref12, " ");
     find!(find_cap_ref13, "");
+    find!(find_cap_ref14, "${foo:upper}", c!("foo", 12, Transform::Upper));
+    find!(find_cap_ref15, "${1:lower}a", c!(1, 10, Transform::Lower));
+    find!(find_cap_ref16, "${foo:title}");
+    find!(find_cap_ref17, "${foo:upper");
+    find!(find_cap_ref18, "$foo:upper", c!("foo", 4));
 
     // A convenience routine for using interpolate's unwieldy but flexible API.
     fn interpolate_string(
@@ -325,4 +424,62 @@ mod tests {
         "test ${a} test",
         "test  test",
     );
+
+    interp!(
+        interp_upper1,
+        vec![("foo", 2)],
+        vec!["", "", "xxx"],
+        "test ${foo:upper} test",
+        "test XXX test",
+    );
+
+    interp!(
+        interp_upper2,
+        vec![("foo", 2)],
+        vec!["", "", "xXx"],
+        r"a \U$foo test",
+        "a XXX TEST",
+    );
+
+    interp!(
+        interp_upper3,
+        vec![("foo", 2)],
+        vec!["", "", "xxx"],
+        r"a \U$foo\E test",
+        "a XXX test",
+    );
+
+    interp!(
+        interp_lower1,
+        vec![("foo", 2)],
+        vec!["", "", "XXX"],
+        "test ${foo:lower} test",
+        "test xxx test",
+    );
+
+    interp!(
+        interp_lower2,
+        vec![("foo", 2)],
+        vec!["", "", "XXX"],
+        r"\L$foo TEST\E test",
+        "xxx test test",
+    );
+
+    // A per-reference transform overrides the active block transform.
+    interp!(
+        interp_transform_override,
+        vec![("foo", 2)],
+        vec!["", "", "xxx"],
+        r"\L${foo:upper} abc\E",
+        "XXX abc",
+    );
+
+    // Unrecognized escapes and transforms are passed through literally.
+    interp!(
+        interp_transform_literal1,
+        vec![("foo", 2)],
+        vec!["", "", "xxx"],
+        r"\n ${foo:title}",
+        r"\n ${foo:title}",
+    );
 }
@@ -45,6 +45,8 @@ use std::u64;
 
 use crate::interpolate::interpolate;
 
+pub mod combinators;
+
 mod interpolate;
 
 /// The type of a match.
@@ -427,6 +429,13 @@ pub trait Captures {
     ///
     /// To write a literal `$` use `$$`.
     ///
+    /// The case of interpolated text can be changed. `\U` starts converting
+    /// everything that follows to uppercase and `\L` to lowercase, until a
+    /// `\E` (or the end of the replacement) is seen. A single capture group
+    /// may also be converted by attaching a transform to its brace form,
+    /// e.g., `${1:upper}` or `${name:lower}`. Case conversion is limited to
+    /// ASCII.
+    ///
     /// Note that the capture group match indices are resolved by slicing
     /// the given `haystack`. Generally, this means that `haystack` should be
     /// the same slice that was searched to get the current capture group
@@ -601,6 +610,16 @@ pub trait Matcher {
         None
     }
 
+    /// Maps the given capture group index to its corresponding capture group
+    /// name, if the group exists and has a name. Index `0` corresponds to
+    /// the overall match, which never has a name.
+    ///
+    /// By default, capturing groups are not supported, so this always returns
+    /// `None`.
+    fn capture_name(&self, _index: usize) -> Option<&str> {
+        None
+    }
+
     /// Returns the start and end byte range of the first match in `haystack`.
     /// If no match exists, then `None` is returned.
     ///
@@ -610,6 +629,68 @@ pub trait Matcher {
         self.find_at(haystack, 0)
     }
 
+    /// Returns the start and end byte range of the last match in `haystack`.
+    /// If no match exists, then `None` is returned.
+    ///
+    /// The "last" match is the final match that would be reported by
+    /// iterating over successive non-overlapping matches in `haystack`. In
+    /// particular, this means the match returned here is always a member of
+    /// the sequence of matches reported by `find_iter`.
+    ///
+    /// By default, this is implemented by scanning the entire haystack and
+    /// discarding all but the final match. Implementations may be able to do
+    /// better, e.g., by scanning the haystack in reverse.
+    fn find_last(
+        &self,
+        haystack: &[u8],
+    ) -> Result<Option<Match>, Self::Error> {
+        self.find_last_at(haystack, 0)
+    }
+
+    /// Returns the start and end byte range of the last match in `haystack`
+    /// starting at `at`, where the byte offsets are relative to that start
+    /// of `haystack` (and not `at`). If no match exists, then `None` is
+    /// returned.
+    ///
+    /// The significance of the starting point is that it takes the
+    /// surrounding context into consideration. For example, the `\A` anchor
+    /// can only match when `at == 0`.
+    fn find_last_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+    ) -> Result<Option<Match>, Self::Error> {
+        let mut last = None;
+        self.find_iter_at(haystack, at, |m| {
+            last = Some(m);
+            true
+        })?;
+        Ok(last)
+    }
+
+    /// Returns the start and end byte range of the leftmost-longest match in
+    /// `haystack` starting at `at`, where the byte offsets are relative to
+    /// that start of `haystack` (and not `at`). If no match exists, then
+    /// `None` is returned.
+    ///
+    /// Leftmost-longest semantics correspond to POSIX regular expression
+    /// matching: among all matches beginning at the leftmost matching
+    /// position, the longest one is reported, regardless of the order of
+    /// alternations in the pattern. For example, the pattern `sam|samwise`
+    /// reports `samwise` in the haystack `samwise`, where as leftmost-first
+    /// semantics report `sam`.
+    ///
+    /// By default, this falls back to `find_at`, which reports
+    /// leftmost-first matches. Implementations whose underlying regex engine
+    /// can report leftmost-longest matches should override this.
+    fn find_longest_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+    ) -> Result<Option<Match>, Self::Error> {
+        self.find_at(haystack, at)
+    }
+
     /// Executes the given function over successive non-overlapping matches
     /// in `haystack`. If no match exists, then the given function is never
     /// called. If the function returns `false`, then iteration stops.
@@ -624,6 +705,22 @@ pub trait Matcher {
         self.find_iter_at(haystack, 0, matched)
     }
 
+    /// Returns the total number of successive non-overlapping matches in
+    /// `haystack`.
+    ///
+    /// By default, this is implemented by iterating over every match via
+    /// `find_iter` and counting them. Implementations whose underlying
+    /// regex engine can count matches without resolving their offsets
+    /// should override this.
+    fn count_matches(&self, haystack: &[u8]) -> Result<u64, Self::Error> {
+        let mut count = 0;
+        self.find_iter(haystack, |_| {
+            count += 1;
+            true
+        })?;
+        Ok(count)
+    }
+
     /// Executes the given function over successive non-overlapping matches
     /// in `haystack`. If no match exists, then the given function is never
     /// called. If the function returns `false`, then iteration stops.
@@ -1052,6 +1149,27 @@ pub trait Matcher {
         None
     }
 
+    /// Returns the index of the pattern that produced the match at the given
+    /// span of the haystack, if the matcher tracks which of its patterns
+    /// matched.
+    ///
+    /// Matchers are frequently built from several patterns, e.g., one for
+    /// each `-e/--regexp` flag given to a grep-like tool. Implementations
+    /// that can attribute a match to one of those patterns may do so here,
+    /// where the index corresponds to the position of the pattern as it was
+    /// given to the matcher's constructor. Implementations that are built
+    /// from a single pattern, or that cannot make the attribution, should
+    /// return `None`.
+    ///
+    /// By default, this returns `None`.
+    fn pattern_index(
+        &self,
+        _haystack: &[u8],
+        _span: Match,
+    ) -> Result<Option<usize>, Self::Error> {
+        Ok(None)
+    }
+
     /// Return one of the following: a confirmed line match, a candidate line
     /// match (which may be a false positive) or no match at all (which **must
     /// not** be a false negative). When reporting a confirmed or candidate
@@ -1127,6 +1245,10 @@ impl<'a, M: Matcher> Matcher for &'a M {
         (*self).capture_index(name)
     }
 
+    fn capture_name(&self, index: usize) -> Option<&str> {
+        (*self).capture_name(index)
+    }
+
     fn capture_count(&self) -> usize {
         (*self).capture_count()
     }
@@ -1135,6 +1257,29 @@ impl<'a, M: Matcher> Matcher for &'a M {
         (*self).find(haystack)
     }
 
+    fn find_last(
+        &self,
+        haystack: &[u8],
+    ) -> Result<Option<Match>, Self::Error> {
+        (*self).find_last(haystack)
+    }
+
+    fn find_last_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+    ) -> Result<Option<Match>, Self::Error> {
+        (*self).find_last_at(haystack, at)
+    }
+
+    fn find_longest_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+    ) -> Result<Option<Match>, Self::Error> {
+        (*self).find_longest_at(haystack, at)
+    }
+
     fn find_iter<F>(
         &self,
         haystack: &[u8],
@@ -1146,6 +1291,10 @@ impl<'a, M: Matcher> Matcher for &'a M {
         (*self).find_iter(haystack, matched)
     }
 
+    fn count_matches(&self, haystack: &[u8]) -> Result<u64, Self::Error> {
+        (*self).count_matches(haystack)
+    }
+
     fn find_iter_at<F>(
         &self,
         haystack: &[u8],
@@ -1313,6 +1462,14 @@ impl<'a, M: Matcher> Matcher for &'a M {
         (*self).line_terminator()
     }
 
+    fn pattern_index(
+        &self,
+        haystack: &[u8],
+        span: Match,
+    ) -> Result<Option<usize>, Self::Error> {
+        (*self).pattern_index(haystack, span)
+    }
+
     fn find_candidate_line(
         &self,
         haystack: &[u8],
@@ -0,0 +1,92 @@
+use grep_matcher::combinators::{AllOf, AnyOf, Not};
+use grep_matcher::{LineTerminator, Match, Matcher, NoCaptures, NoError};
+use regex::bytes::Regex;
+
+use crate::util::RegexMatcherNoCaps;
+
+fn matcher(pattern: &str) -> RegexMatcherNoCaps {
+    RegexMatcherNoCaps(Regex::new(pattern).unwrap())
+}
+
+/// A matcher that advertises itself as line oriented, so that combinators
+/// confine their work to individual lines.
+#[derive(Debug)]
+struct LineRegexMatcher(RegexMatcherNoCaps);
+
+fn line_matcher(pattern: &str) -> LineRegexMatcher {
+    LineRegexMatcher(matcher(pattern))
+}
+
+impl Matcher for LineRegexMatcher {
+    type Captures = NoCaptures;
+    type Error = NoError;
+
+    fn find_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+    ) -> Result<Option<Match>, NoError> {
+        self.0.find_at(haystack, at)
+    }
+
+    fn new_captures(&self) -> Result<NoCaptures, NoError> {
+        self.0.new_captures()
+    }
+
+    fn line_terminator(&self) -> Option<LineTerminator> {
+        Some(LineTerminator::byte(b'\n'))
+    }
+}
+
+#[test]
+fn any_of_leftmost() {
+    let m = AnyOf::new(vec![matcher("bcd"), matcher("ab")]);
+    assert_eq!(Some(Match::new(0, 2)), m.find(b"abcdef").unwrap());
+    assert!(m.is_match(b"xbcdx").unwrap());
+    assert!(!m.is_match(b"xyz").unwrap());
+}
+
+#[test]
+fn any_of_empty_never_matches() {
+    let m = AnyOf::<RegexMatcherNoCaps>::new(vec![]);
+    assert_eq!(None, m.find(b"anything").unwrap());
+    assert!(!m.is_match(b"anything").unwrap());
+}
+
+#[test]
+fn all_of_whole_haystack() {
+    let m = AllOf::new(vec![matcher("foo"), matcher("bar")]);
+    assert_eq!(Some(Match::new(0, 3)), m.find(b"foo bar").unwrap());
+    assert_eq!(None, m.find(b"foo baz").unwrap());
+    assert!(m.is_match(b"bar foo").unwrap());
+}
+
+#[test]
+fn all_of_line_oriented() {
+    let m = AllOf::new(vec![line_matcher("foo"), line_matcher("bar")]);
+    assert_eq!(
+        Some(LineTerminator::byte(b'\n')),
+        Matcher::line_terminator(&m),
+    );
+    // "foo" appears on two lines, but only the second contains "bar".
+    let haystack = b"foo baz\nbar foo\n";
+    assert_eq!(Some(Match::new(12, 15)), m.find(haystack).unwrap());
+    // Confirmation happens per line, so matchers on different lines do not
+    // intersect.
+    assert_eq!(None, m.find(b"foo\nbar\n").unwrap());
+}
+
+#[test]
+fn all_of_mixed_line_orientation_is_not_line_oriented() {
+    let m = AllOf::new(vec![matcher("foo"), matcher("bar")]);
+    assert_eq!(None, Matcher::line_terminator(&m));
+}
+
+#[test]
+fn not_inverts() {
+    let m = Not::new(matcher("foo"));
+    assert!(m.is_match(b"bar").unwrap());
+    assert!(!m.is_match(b"foo").unwrap());
+    assert_eq!(Some(Match::zero(0)), m.find(b"bar").unwrap());
+    assert_eq!(None, m.find(b"foo").unwrap());
+}
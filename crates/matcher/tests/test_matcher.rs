@@ -21,6 +21,13 @@ fn find() {
     assert_eq!(matcher.find(b" homer simpson ").unwrap(), Some(m(1, 14)));
 }
 
+#[test]
+fn find_last() {
+    let matcher = matcher(r"(\w+)\s+(\w+)");
+    assert_eq!(matcher.find_last(b"aa bb cc dd").unwrap(), Some(m(6, 11)));
+    assert_eq!(matcher.find_last(b"  ").unwrap(), None);
+}
+
 #[test]
 fn find_iter() {
     let matcher = matcher(r"(\w+)\s+(\w+)");
@@ -44,6 +51,13 @@ fn find_iter() {
     assert_eq!(matches, vec![m(0, 5)]);
 }
 
+#[test]
+fn count_matches() {
+    let matcher = matcher(r"(\w+)\s+(\w+)");
+    assert_eq!(matcher.count_matches(b"aa bb cc dd").unwrap(), 2);
+    assert_eq!(matcher.count_matches(b"  ").unwrap(), 0);
+}
+
 #[test]
 fn try_find_iter() {
     #[derive(Clone, Debug, Eq, PartialEq)]
@@ -1,3 +1,4 @@
 mod util;
 
+mod test_combinators;
 mod test_matcher;
@@ -16,6 +16,10 @@ impl Error {
         Error { kind: ErrorKind::Regex(err.to_string()) }
     }
 
+    pub(crate) fn config(msg: String) -> Error {
+        Error { kind: ErrorKind::Config(msg) }
+    }
+
     /// Return the kind of this error.
     pub fn kind(&self) -> &ErrorKind {
         &self.kind
@@ -31,6 +35,9 @@ pub enum ErrorKind {
     ///
     /// The string here is the underlying error converted to a string.
     Regex(String),
+    /// An error that occurred because the matcher was configured in a way
+    /// that PCRE2 cannot support.
+    Config(String),
     /// Hints that destructuring should not be exhaustive.
     ///
     /// This enum may grow additional variants, so this makes sure clients
@@ -44,6 +51,7 @@ impl error::Error for Error {
     fn description(&self) -> &str {
         match self.kind {
             ErrorKind::Regex(_) => "regex error",
+            ErrorKind::Config(_) => "configuration error",
             ErrorKind::__Nonexhaustive => unreachable!(),
         }
     }
@@ -53,6 +61,7 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self.kind {
             ErrorKind::Regex(ref s) => write!(f, "{}", s),
+            ErrorKind::Config(ref s) => write!(f, "{}", s),
             ErrorKind::__Nonexhaustive => unreachable!(),
         }
     }
@@ -6,8 +6,15 @@ An implementation of `grep-matcher`'s `Matcher` trait for
 #![deny(missing_docs)]
 
 pub use crate::error::{Error, ErrorKind};
-pub use crate::matcher::{RegexCaptures, RegexMatcher, RegexMatcherBuilder};
+pub use crate::matcher::{
+    Newline, RegexCaptures, RegexMatcher, RegexMatcherBuilder,
+};
+pub use crate::multi::RegexSet;
+pub use crate::stream::StreamSearcher;
 pub use pcre2::{is_jit_available, version};
 
 mod error;
 mod matcher;
+mod multi;
+mod stream;
+mod substitute;
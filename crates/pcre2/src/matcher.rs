@@ -1,10 +1,45 @@
 use std::collections::HashMap;
 
-use grep_matcher::{Captures, Match, Matcher};
+use grep_matcher::{Captures, LineTerminator, Match, Matcher};
 use pcre2::bytes::{CaptureLocations, Regex, RegexBuilder};
 
 use crate::error::Error;
 
+/// The newline convention used by PCRE2 when matching line anchors and `.`.
+///
+/// This controls what PCRE2 considers a line ending. It is distinct from
+/// enabling multi-line mode, which controls whether `^` and `$` match at
+/// line boundaries at all.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Newline {
+    /// Only `\n` ends a line.
+    Lf,
+    /// Only `\r` ends a line.
+    Cr,
+    /// Only `\r\n` ends a line.
+    CrLf,
+    /// Any of `\r`, `\n` or `\r\n` ends a line.
+    AnyCrLf,
+    /// Any Unicode line ending sequence ends a line.
+    Any,
+    /// Only `NUL` (`\0`) ends a line.
+    Nul,
+}
+
+impl Newline {
+    /// Returns the start-of-pattern control verb for this convention.
+    fn verb(&self) -> &'static str {
+        match *self {
+            Newline::Lf => "(*LF)",
+            Newline::Cr => "(*CR)",
+            Newline::CrLf => "(*CRLF)",
+            Newline::AnyCrLf => "(*ANYCRLF)",
+            Newline::Any => "(*ANY)",
+            Newline::Nul => "(*NUL)",
+        }
+    }
+}
+
 /// A builder for configuring the compilation of a PCRE2 regex.
 #[derive(Clone, Debug)]
 pub struct RegexMatcherBuilder {
@@ -13,6 +48,10 @@ pub struct RegexMatcherBuilder {
     word: bool,
     fixed_strings: bool,
     whole_line: bool,
+    match_limit: Option<u32>,
+    depth_limit: Option<u32>,
+    newline: Option<Newline>,
+    newline_error: Option<String>,
 }
 
 impl RegexMatcherBuilder {
@@ -24,6 +63,10 @@ impl RegexMatcherBuilder {
             word: false,
             fixed_strings: false,
             whole_line: false,
+            match_limit: None,
+            depth_limit: None,
+            newline: None,
+            newline_error: None,
         }
     }
 
@@ -44,6 +87,9 @@ impl RegexMatcherBuilder {
         &self,
         patterns: &[P],
     ) -> Result<RegexMatcher, Error> {
+        if let Some(ref msg) = self.newline_error {
+            return Err(Error::config(msg.clone()));
+        }
         let mut builder = self.builder.clone();
         let mut pats = Vec::with_capacity(patterns.len());
         for p in patterns.iter() {
@@ -65,6 +111,22 @@ impl RegexMatcherBuilder {
             // boundaries. So this extra goop is strictly redundant.
             singlepat = format!(r"(?<!\w)(?:{})(?!\w)", singlepat);
         }
+        // The limits are communicated to PCRE2 via in-pattern control verbs,
+        // which must appear at the very start of the pattern. This is the
+        // only way to set them through the underlying bindings, which do not
+        // expose PCRE2's match context.
+        if let Some(limit) = self.depth_limit {
+            singlepat = format!("(*LIMIT_DEPTH={}){}", limit, singlepat);
+        }
+        if let Some(limit) = self.match_limit {
+            singlepat = format!("(*LIMIT_MATCH={}){}", limit, singlepat);
+        }
+        // Like the limits above, the newline convention is communicated via
+        // a start-of-pattern control verb, since the underlying bindings
+        // only expose a fixed LF-or-ANYCRLF choice at compile time.
+        if let Some(newline) = self.newline {
+            singlepat = format!("{}{}", newline.verb(), singlepat);
+        }
         log::trace!("final regex: {:?}", singlepat);
         builder.build(&singlepat).map_err(Error::regex).map(|regex| {
             let mut names = HashMap::new();
@@ -288,6 +350,111 @@ impl RegexMatcherBuilder {
         self.builder.max_jit_stack_size(bytes);
         self
     }
+
+    /// Set the newline convention used when matching.
+    ///
+    /// This determines what PCRE2 treats as a line ending for the purposes
+    /// of `^`, `$` (in multi-line mode) and `.`. For example, with
+    /// [`Newline::AnyCrLf`], `$` will match just before a `\r\n` sequence
+    /// and `.` will not match `\r`.
+    ///
+    /// When `None` is given (the default), PCRE2's build-time default
+    /// convention is used, which is almost always [`Newline::Lf`].
+    ///
+    /// Note that the `crlf` option also affects the newline convention. A
+    /// convention set here takes precedence over it.
+    pub fn newline(
+        &mut self,
+        newline: Option<Newline>,
+    ) -> &mut RegexMatcherBuilder {
+        self.newline = newline;
+        self.newline_error = None;
+        self
+    }
+
+    /// Set the newline convention from the searcher's line terminator.
+    ///
+    /// This is a convenience for keeping the matcher's idea of a line
+    /// ending consistent with the line terminator configured on a searcher.
+    /// A CRLF line terminator maps to [`Newline::AnyCrLf`], `\n` to
+    /// [`Newline::Lf`], `\r` to [`Newline::Cr`] and `NUL` to
+    /// [`Newline::Nul`].
+    ///
+    /// If the line terminator is some other byte, then building the matcher
+    /// will return an error, since PCRE2 has no corresponding newline
+    /// convention.
+    pub fn line_terminator(
+        &mut self,
+        line_term: Option<LineTerminator>,
+    ) -> &mut RegexMatcherBuilder {
+        let line_term = match line_term {
+            None => {
+                self.newline = None;
+                self.newline_error = None;
+                return self;
+            }
+            Some(line_term) => line_term,
+        };
+        self.newline_error = None;
+        self.newline = if line_term.is_crlf() {
+            Some(Newline::AnyCrLf)
+        } else {
+            match line_term.as_byte() {
+                b'\n' => Some(Newline::Lf),
+                b'\r' => Some(Newline::Cr),
+                b'\x00' => Some(Newline::Nul),
+                byte => {
+                    self.newline_error = Some(format!(
+                        "line terminator 0x{:02X} has no corresponding \
+                         PCRE2 newline convention",
+                        byte,
+                    ));
+                    None
+                }
+            }
+        };
+        self
+    }
+
+    /// Set the limit on the internal resource usage of a single match.
+    ///
+    /// This bounds the number of times PCRE2's internal match function may
+    /// be called during a single search, which roughly corresponds to the
+    /// amount of backtracking performed. When the limit is exceeded, the
+    /// search fails with an error instead of running away on pathological
+    /// patterns.
+    ///
+    /// Note that this can only lower PCRE2's default limit (normally ten
+    /// million); a value larger than the default has no effect.
+    ///
+    /// By default, no limit is set beyond PCRE2's own default.
+    pub fn match_limit(
+        &mut self,
+        limit: Option<u32>,
+    ) -> &mut RegexMatcherBuilder {
+        self.match_limit = limit;
+        self
+    }
+
+    /// Set the limit on the depth of PCRE2's backtracking.
+    ///
+    /// This bounds the amount of memory used for backtracking state during
+    /// a single search. Heavy look-behind patterns on large lines can hit
+    /// PCRE2's default depth limit and fail with an opaque resource error;
+    /// conversely, services may want to lower it to bound worst-case regex
+    /// execution.
+    ///
+    /// Note that this can only lower PCRE2's default limit; a value larger
+    /// than the default has no effect.
+    ///
+    /// By default, no limit is set beyond PCRE2's own default.
+    pub fn depth_limit(
+        &mut self,
+        limit: Option<u32>,
+    ) -> &mut RegexMatcherBuilder {
+        self.depth_limit = limit;
+        self
+    }
 }
 
 /// An implementation of the `Matcher` trait using PCRE2.
@@ -303,6 +470,54 @@ impl RegexMatcher {
     pub fn new(pattern: &str) -> Result<RegexMatcher, Error> {
         RegexMatcherBuilder::new().build(pattern)
     }
+
+    /// Replace every match in `haystack` with the expansion of the given
+    /// replacement template, appending the result to `dst`.
+    ///
+    /// In addition to the usual group references (`$0`, `$name`, `${name}`),
+    /// the template supports PCRE2's extended substitution syntax:
+    ///
+    /// * `\U` ... `\E` uppercases the enclosed text, and `\L` ... `\E`
+    ///   lowercases it.
+    /// * `\u` and `\l` upper/lowercase the next character.
+    /// * `${name:+then:else}` expands to `then` when the group participated
+    ///   in the match and `else` otherwise.
+    /// * `$$` and `\\` expand to a literal `$` and `\`, respectively.
+    ///
+    /// Case transformations are applied ASCII-only. Note that this is
+    /// implemented on top of this matcher's capture groups rather than
+    /// `pcre2_substitute`, since the underlying bindings do not expose the
+    /// latter. The observable behavior is the same for the syntax documented
+    /// here.
+    pub fn replace_all(
+        &self,
+        haystack: &[u8],
+        replacement: &[u8],
+        dst: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        let mut caps = self.new_captures()?;
+        let mut last = 0;
+        let mut at = 0;
+        while at <= haystack.len() {
+            if !self.captures_at(haystack, at, &mut caps)? {
+                break;
+            }
+            let m = caps.get(0).expect("capture group 0 always present");
+            dst.extend_from_slice(&haystack[last..m.start()]);
+            crate::substitute::expand(
+                &caps,
+                |name| self.capture_index(name),
+                haystack,
+                replacement,
+                dst,
+            );
+            last = m.end();
+            // Make progress on empty matches.
+            at = if m.start() == m.end() { m.end() + 1 } else { m.end() };
+        }
+        dst.extend_from_slice(&haystack[last..]);
+        Ok(())
+    }
 }
 
 impl Matcher for RegexMatcher {
@@ -333,6 +548,10 @@ impl Matcher for RegexMatcher {
         self.names.get(name).map(|i| *i)
     }
 
+    fn capture_name(&self, index: usize) -> Option<&str> {
+        self.regex.capture_names().get(index)?.as_deref()
+    }
+
     fn try_find_iter<F, E>(
         &self,
         haystack: &[u8],
@@ -442,6 +661,98 @@ mod tests {
         assert!(!matcher.is_match(b"abc -2 foo").unwrap());
     }
 
+    // Test the extended substitution syntax supported by replace_all.
+    #[test]
+    fn replace_all() {
+        fn replace(pattern: &str, haystack: &str, template: &str) -> String {
+            let matcher = RegexMatcher::new(pattern).unwrap();
+            let mut dst = vec![];
+            matcher
+                .replace_all(
+                    haystack.as_bytes(),
+                    template.as_bytes(),
+                    &mut dst,
+                )
+                .unwrap();
+            String::from_utf8(dst).unwrap()
+        }
+
+        // Plain group references.
+        assert_eq!(replace(r"(\w+)=(\w+)", "a=1 b=2", "$2=$1"), "1=a 2=b",);
+        assert_eq!(
+            replace(r"(?P<k>\w+)=(?P<v>\w+)", "a=1", "${v}=${k}"),
+            "1=a",
+        );
+        // Case transforms.
+        assert_eq!(replace(r"\w+", "foo bar", r"\U$0\E!"), "FOO! BAR!");
+        assert_eq!(replace(r"\w+", "FOO", r"\L$0\E"), "foo");
+        assert_eq!(replace(r"\w+", "foo bar", r"\u$0"), "Foo Bar");
+        // Conditional substitution.
+        assert_eq!(
+            replace(r"(?P<a>x)?y", "xy y", "${a:+has-x:no-x}"),
+            "has-x no-x",
+        );
+        assert_eq!(replace(r"(?P<a>x)?y", "y", "${a:+has-x}"), "");
+        // Literal escapes.
+        assert_eq!(replace(r"a", "a", r"$$ \\"), r"$ \");
+        // A trailing backslash is emitted literally instead of looping
+        // forever.
+        assert_eq!(replace(r"\w+", "hello world", r"x\"), r"x\ x\");
+    }
+
+    // Test that a match limit causes pathological searches to fail with an
+    // error instead of running away.
+    #[test]
+    fn match_limit() {
+        let haystack = format!("{}b", "a".repeat(20));
+
+        let matcher = RegexMatcherBuilder::new().build(r"(a+)+$").unwrap();
+        assert!(matcher.find(haystack.as_bytes()).is_ok());
+
+        let matcher = RegexMatcherBuilder::new()
+            .match_limit(Some(100))
+            .build(r"(a+)+$")
+            .unwrap();
+        assert!(matcher.find(haystack.as_bytes()).is_err());
+    }
+
+    // Test that newline conventions are honored when matching.
+    #[test]
+    fn newline() {
+        use super::Newline;
+        use grep_matcher::LineTerminator;
+
+        // With the ANYCRLF convention, `$` matches before `\r\n`.
+        let matcher = RegexMatcherBuilder::new()
+            .multi_line(true)
+            .newline(Some(Newline::AnyCrLf))
+            .build(r"abc$")
+            .unwrap();
+        assert!(matcher.is_match(b"abc\r\n").unwrap());
+
+        // With the LF convention, it doesn't.
+        let matcher = RegexMatcherBuilder::new()
+            .multi_line(true)
+            .newline(Some(Newline::Lf))
+            .build(r"abc$")
+            .unwrap();
+        assert!(!matcher.is_match(b"abc\r\n").unwrap());
+
+        // The searcher's line terminator maps to the right convention.
+        let matcher = RegexMatcherBuilder::new()
+            .multi_line(true)
+            .line_terminator(Some(LineTerminator::crlf()))
+            .build(r"abc$")
+            .unwrap();
+        assert!(matcher.is_match(b"abc\r\n").unwrap());
+
+        // An unsupported line terminator is an error at build time.
+        assert!(RegexMatcherBuilder::new()
+            .line_terminator(Some(LineTerminator::byte(b'\x7F')))
+            .build(r"abc")
+            .is_err());
+    }
+
     // Test that enabling CRLF permits `$` to match at the end of a line.
     #[test]
     fn line_terminator_crlf() {
@@ -0,0 +1,171 @@
+use grep_matcher::{Match, Matcher};
+
+use crate::error::Error;
+use crate::matcher::{RegexMatcher, RegexMatcherBuilder};
+
+/// A set of PCRE2 regexes, compiled separately, with stable pattern IDs.
+///
+/// Unlike joining patterns into a single alternation, a set preserves the
+/// identity of each pattern: searches report which pattern matched. Pattern
+/// IDs correspond to the order in which patterns were given when building
+/// the set.
+///
+/// PCRE2 has no native multi-pattern engine, so searches iterate over each
+/// compiled pattern. This makes a set more expensive than an alternation,
+/// in proportion to the number of patterns.
+#[derive(Clone, Debug)]
+pub struct RegexSet {
+    matchers: Vec<RegexMatcher>,
+}
+
+impl RegexSet {
+    /// Compile each of the given patterns with the given builder's
+    /// configuration and assemble them into a set.
+    ///
+    /// If compiling any one pattern fails, then an error is returned.
+    pub fn new<P: AsRef<str>>(
+        builder: &RegexMatcherBuilder,
+        patterns: &[P],
+    ) -> Result<RegexSet, Error> {
+        let mut matchers = Vec::with_capacity(patterns.len());
+        for p in patterns.iter() {
+            matchers.push(builder.build(p.as_ref())?);
+        }
+        Ok(RegexSet { matchers })
+    }
+
+    /// Returns the number of patterns in this set.
+    pub fn len(&self) -> usize {
+        self.matchers.len()
+    }
+
+    /// Returns true if this set contains no patterns.
+    pub fn is_empty(&self) -> bool {
+        self.matchers.is_empty()
+    }
+
+    /// Returns the matcher for the pattern with the given ID.
+    ///
+    /// This panics when the ID is out of bounds.
+    pub fn matcher(&self, id: usize) -> &RegexMatcher {
+        &self.matchers[id]
+    }
+
+    /// Returns true if any pattern in this set matches the given haystack.
+    pub fn is_match(&self, haystack: &[u8]) -> Result<bool, Error> {
+        for m in self.matchers.iter() {
+            if m.is_match(haystack)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Returns the IDs of every pattern in this set that matches the given
+    /// haystack, in ascending order.
+    pub fn matches(&self, haystack: &[u8]) -> Result<Vec<usize>, Error> {
+        let mut ids = vec![];
+        for (id, m) in self.matchers.iter().enumerate() {
+            if m.is_match(haystack)? {
+                ids.push(id);
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Returns the leftmost match in the given haystack along with the ID
+    /// of the pattern that produced it. Ties on the starting position are
+    /// broken by preferring the pattern with the smallest ID.
+    pub fn find(
+        &self,
+        haystack: &[u8],
+    ) -> Result<Option<(usize, Match)>, Error> {
+        self.find_at(haystack, 0)
+    }
+
+    /// Like `find`, but begins the search at the given position.
+    pub fn find_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+    ) -> Result<Option<(usize, Match)>, Error> {
+        let mut best: Option<(usize, Match)> = None;
+        for (id, matcher) in self.matchers.iter().enumerate() {
+            if let Some(m) = matcher.find_at(haystack, at)? {
+                match best {
+                    Some((_, prev)) if prev.start() <= m.start() => {}
+                    _ => best = Some((id, m)),
+                }
+            }
+        }
+        Ok(best)
+    }
+
+    /// Executes the given function over successive non-overlapping matches
+    /// in `haystack`, along with the ID of the pattern that produced each
+    /// match. If the function returns `false`, then iteration stops.
+    pub fn find_iter<F>(
+        &self,
+        haystack: &[u8],
+        mut matched: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(usize, Match) -> bool,
+    {
+        let mut at = 0;
+        while at <= haystack.len() {
+            let (id, m) = match self.find_at(haystack, at)? {
+                None => break,
+                Some(x) => x,
+            };
+            if !matched(id, m) {
+                break;
+            }
+            // Make progress on empty matches.
+            at = if m.start() == m.end() { m.end() + 1 } else { m.end() };
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use grep_matcher::Match;
+
+    use super::RegexSet;
+    use crate::matcher::RegexMatcherBuilder;
+
+    fn set(patterns: &[&str]) -> RegexSet {
+        RegexSet::new(&RegexMatcherBuilder::new(), patterns).unwrap()
+    }
+
+    #[test]
+    fn matches() {
+        let set = set(&[r"\d+", r"[a-z]+", r"XYZ"]);
+        assert_eq!(set.matches(b"abc 123").unwrap(), vec![0, 1]);
+        assert_eq!(set.matches(b"???").unwrap(), vec![]);
+        assert!(set.is_match(b"123").unwrap());
+        assert!(!set.is_match(b"!!!").unwrap());
+    }
+
+    #[test]
+    fn find_with_ids() {
+        let set = set(&[r"\d+", r"[a-z]+"]);
+        assert_eq!(set.find(b"abc 123").unwrap(), Some((1, Match::new(0, 3))),);
+
+        let mut results = vec![];
+        set.find_iter(b"abc 123 def", |id, m| {
+            results.push((id, m));
+            true
+        })
+        .unwrap();
+        assert_eq!(
+            results,
+            vec![
+                (1, Match::new(0, 3)),
+                (0, Match::new(4, 7)),
+                (1, Match::new(8, 11)),
+            ]
+        );
+    }
+}
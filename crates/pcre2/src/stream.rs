@@ -0,0 +1,209 @@
+/*!
+Provides a chunked (streaming) search interface on top of a PCRE2 matcher.
+
+PCRE2 itself supports partial matching, but the underlying bindings do not
+expose the relevant match-time options. Instead, streaming is implemented
+here by buffering just enough of the haystack across chunk boundaries:
+
+* A match that ends flush at the end of the buffered data might extend
+  further once more input arrives, so it is not reported until either more
+  data is pushed or the stream is finished.
+* After each scan, a bounded amount of trailing context is retained so that
+  matches straddling a chunk boundary are still found. The bound prevents
+  the buffer from growing to the size of the whole haystack.
+*/
+
+use grep_matcher::Matcher;
+
+use crate::error::Error;
+use crate::matcher::RegexMatcher;
+
+/// The default number of bytes of trailing context retained across chunk
+/// boundaries.
+const DEFAULT_MAX_PENDING: usize = 64 * (1 << 10);
+
+/// A streaming searcher for executing a PCRE2 search over a haystack that
+/// arrives in chunks.
+///
+/// Matches are reported through a caller supplied closure with their
+/// absolute byte offsets in the overall stream. Matches that straddle chunk
+/// boundaries are found as long as they are no longer than the configured
+/// maximum pending buffer size.
+#[derive(Clone, Debug)]
+pub struct StreamSearcher {
+    matcher: RegexMatcher,
+    /// Buffered input that has not yet been fully consumed by a scan.
+    buf: Vec<u8>,
+    /// The absolute offset in the stream of the first byte in `buf`.
+    base: u64,
+    /// The maximum number of trailing bytes retained across chunk
+    /// boundaries, excluding a match in progress.
+    max_pending: usize,
+    /// Set when the caller's closure requested that the search stop.
+    done: bool,
+}
+
+impl StreamSearcher {
+    /// Create a new streaming searcher for the given matcher.
+    pub fn new(matcher: RegexMatcher) -> StreamSearcher {
+        StreamSearcher {
+            matcher,
+            buf: vec![],
+            base: 0,
+            max_pending: DEFAULT_MAX_PENDING,
+            done: false,
+        }
+    }
+
+    /// Set the maximum number of trailing bytes retained across chunk
+    /// boundaries.
+    ///
+    /// A match that straddles a chunk boundary is only guaranteed to be
+    /// found when the portion of it in the earlier chunks is no longer than
+    /// this. Larger values make longer straddling matches findable at the
+    /// cost of more buffering.
+    ///
+    /// This defaults to `64KB`.
+    pub fn max_pending(&mut self, bytes: usize) -> &mut StreamSearcher {
+        self.max_pending = bytes;
+        self
+    }
+
+    /// Push the next chunk of the haystack into the searcher.
+    ///
+    /// `matched` is called for each match found, with the absolute starting
+    /// offset of the match in the stream and the matched bytes. If it
+    /// returns `false`, then the search stops and all subsequent pushes are
+    /// no-ops.
+    pub fn push<F>(
+        &mut self,
+        chunk: &[u8],
+        mut matched: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(u64, &[u8]) -> bool,
+    {
+        if self.done {
+            return Ok(());
+        }
+        self.buf.extend_from_slice(chunk);
+        self.scan(false, &mut matched)
+    }
+
+    /// Signal the end of the haystack and flush any pending matches.
+    ///
+    /// After this returns, the searcher is reset and can be reused for a
+    /// new stream.
+    pub fn finish<F>(&mut self, mut matched: F) -> Result<(), Error>
+    where
+        F: FnMut(u64, &[u8]) -> bool,
+    {
+        if !self.done {
+            self.scan(true, &mut matched)?;
+        }
+        self.buf.clear();
+        self.base = 0;
+        self.done = false;
+        Ok(())
+    }
+
+    fn scan<F>(&mut self, eof: bool, matched: &mut F) -> Result<(), Error>
+    where
+        F: FnMut(u64, &[u8]) -> bool,
+    {
+        let mut at = 0;
+        // The start of a match that may still extend with more input, and
+        // therefore must be retained in full.
+        let mut partial = None;
+        while at <= self.buf.len() {
+            let m = match self.matcher.find_at(&self.buf, at)? {
+                None => break,
+                Some(m) => m,
+            };
+            if !eof && m.end() == self.buf.len() {
+                // The match runs flush against the end of our buffered
+                // data, so it might keep going once more input arrives.
+                // Hold off on reporting it.
+                partial = Some(m.start());
+                break;
+            }
+            if !matched(
+                self.base + m.start() as u64,
+                &self.buf[m.start()..m.end()],
+            ) {
+                self.done = true;
+                break;
+            }
+            // Make progress on empty matches.
+            at = if m.start() == m.end() { m.end() + 1 } else { m.end() };
+        }
+        // Drop consumed input, but retain bounded trailing context (or the
+        // entirety of a potential match in progress) so that matches
+        // straddling the next chunk boundary can still be found.
+        let keep_from = match partial {
+            Some(start) => start,
+            None if eof || self.done => self.buf.len(),
+            None => std::cmp::max(
+                at,
+                self.buf.len() - self.max_pending.min(self.buf.len()),
+            ),
+        };
+        self.base += keep_from as u64;
+        self.buf.drain(..keep_from);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StreamSearcher;
+    use crate::matcher::RegexMatcher;
+
+    fn search(pattern: &str, chunks: &[&str]) -> Vec<(u64, String)> {
+        let mut searcher =
+            StreamSearcher::new(RegexMatcher::new(pattern).unwrap());
+        let mut results = vec![];
+        for chunk in chunks {
+            searcher
+                .push(chunk.as_bytes(), |start, bytes| {
+                    results
+                        .push((start, String::from_utf8_lossy(bytes).into()));
+                    true
+                })
+                .unwrap();
+        }
+        searcher
+            .finish(|start, bytes| {
+                results.push((start, String::from_utf8_lossy(bytes).into()));
+                true
+            })
+            .unwrap();
+        results
+    }
+
+    // A multi-line match that straddles a chunk boundary is still found,
+    // with correct absolute offsets.
+    #[test]
+    fn straddling_match() {
+        let results = search(r"foo\nbar", &["xx foo\nb", "ar yy"]);
+        assert_eq!(results, vec![(3, "foo\nbar".to_string())]);
+    }
+
+    // A match that ends flush at a chunk boundary is deferred so that it
+    // can grow to its proper length.
+    #[test]
+    fn deferred_match() {
+        let results = search(r"ab+", &["xa", "b", "bb yz"]);
+        assert_eq!(results, vec![(1, "abbb".to_string())]);
+    }
+
+    // Matches entirely within chunks are reported as the stream advances.
+    #[test]
+    fn multiple_chunks() {
+        let results = search(r"\w+@\w+", &["a@b ", "ignored ", "c@d "]);
+        assert_eq!(
+            results,
+            vec![(0, "a@b".to_string()), (12, "c@d".to_string())]
+        );
+    }
+}
@@ -0,0 +1,210 @@
+/*!
+Provides routines for expanding PCRE2-style replacement templates.
+
+This implements the parts of PCRE2's extended substitution syntax
+(`PCRE2_SUBSTITUTE_EXTENDED`) that are useful for search-and-replace
+workflows, on top of the capture groups reported by a matcher. The
+underlying PCRE2 bindings do not expose `pcre2_substitute`, so the
+expansion is done here in Rust instead.
+*/
+
+use grep_matcher::Captures;
+
+/// The case transformation state while expanding a template.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Case {
+    /// Copy bytes through unchanged.
+    None,
+    /// Uppercase everything until `\E`.
+    Upper,
+    /// Lowercase everything until `\E`.
+    Lower,
+    /// Uppercase the next character only.
+    UpperNext,
+    /// Lowercase the next character only.
+    LowerNext,
+}
+
+/// Expand the given replacement template for a single match.
+///
+/// Capture group references (`$0`, `$name`, `${name}`) are resolved via
+/// `caps` and `name_to_index`, with the corresponding text drawn from
+/// `haystack`. Group references to groups that did not participate in the
+/// match expand to the empty string.
+///
+/// In addition to plain group references, the following extended syntax is
+/// supported:
+///
+/// * `\U` ... `\E`: uppercase the enclosed text.
+/// * `\L` ... `\E`: lowercase the enclosed text.
+/// * `\u` and `\l`: upper/lowercase the next character.
+/// * `${name:+then:else}`: expand to `then` when the group participated in
+///   the match, and `else` otherwise.
+/// * `$$` and `\\` expand to a literal `$` and `\`, respectively.
+///
+/// Case transformations are applied ASCII-only.
+pub(crate) fn expand<C, F>(
+    caps: &C,
+    name_to_index: F,
+    haystack: &[u8],
+    mut replacement: &[u8],
+    dst: &mut Vec<u8>,
+) where
+    C: Captures,
+    F: Fn(&str) -> Option<usize>,
+{
+    let mut case = Case::None;
+    while !replacement.is_empty() {
+        match replacement[0] {
+            b'\\' => {
+                match replacement.get(1) {
+                    None => {
+                        // A trailing backslash is emitted literally.
+                        push(dst, &mut case, b"\\");
+                        replacement = &replacement[1..];
+                        continue;
+                    }
+                    Some(&b'U') => case = Case::Upper,
+                    Some(&b'L') => case = Case::Lower,
+                    Some(&b'u') => case = Case::UpperNext,
+                    Some(&b'l') => case = Case::LowerNext,
+                    Some(&b'E') => case = Case::None,
+                    Some(&byte) => push(dst, &mut case, &[byte]),
+                }
+                replacement = &replacement[2..];
+            }
+            b'$' if replacement.get(1) == Some(&b'$') => {
+                push(dst, &mut case, b"$");
+                replacement = &replacement[2..];
+            }
+            b'$' => {
+                let (expanded, rest) = expand_group_ref(
+                    caps,
+                    &name_to_index,
+                    haystack,
+                    &replacement[1..],
+                );
+                match expanded {
+                    Some(bytes) => push(dst, &mut case, &bytes),
+                    None => push(dst, &mut case, b"$"),
+                }
+                replacement = rest;
+            }
+            _ => {
+                let end = replacement
+                    .iter()
+                    .position(|&b| b == b'$' || b == b'\\')
+                    .unwrap_or(replacement.len());
+                push(dst, &mut case, &replacement[..end]);
+                replacement = &replacement[end..];
+            }
+        }
+    }
+}
+
+/// Expand a group reference beginning immediately after a `$`.
+///
+/// On success, returns the expanded bytes along with the rest of the
+/// replacement following the reference. When no valid reference exists,
+/// `None` is returned and the replacement is returned unchanged (so the
+/// caller can emit a literal `$`).
+fn expand_group_ref<'r, C, F>(
+    caps: &C,
+    name_to_index: &F,
+    haystack: &[u8],
+    replacement: &'r [u8],
+) -> (Option<Vec<u8>>, &'r [u8])
+where
+    C: Captures,
+    F: Fn(&str) -> Option<usize>,
+{
+    if replacement.first() == Some(&b'{') {
+        let close = match replacement.iter().position(|&b| b == b'}') {
+            None => return (None, replacement),
+            Some(i) => i,
+        };
+        let inner = &replacement[1..close];
+        let rest = &replacement[close + 1..];
+        // `${name:+then:else}` is a conditional substitution on whether the
+        // group participated in the match.
+        if let Some(colon) = find_subslice(inner, b":+") {
+            let (name, branches) = (&inner[..colon], &inner[colon + 2..]);
+            let (then, otherwise) =
+                match branches.iter().position(|&b| b == b':') {
+                    None => (branches, &[][..]),
+                    Some(i) => (&branches[..i], &branches[i + 1..]),
+                };
+            let chosen = match group_match(caps, name_to_index, name) {
+                Some(_) => then,
+                None => otherwise,
+            };
+            return (Some(chosen.to_vec()), rest);
+        }
+        let expanded = group_match(caps, name_to_index, inner)
+            .map(|m| haystack[m].to_vec())
+            .unwrap_or_default();
+        return (Some(expanded), rest);
+    }
+    let end = replacement
+        .iter()
+        .position(|&b| !b.is_ascii_alphanumeric() && b != b'_')
+        .unwrap_or(replacement.len());
+    if end == 0 {
+        return (None, replacement);
+    }
+    let (name, rest) = (&replacement[..end], &replacement[end..]);
+    let expanded = group_match(caps, name_to_index, name)
+        .map(|m| haystack[m].to_vec())
+        .unwrap_or_default();
+    (Some(expanded), rest)
+}
+
+/// Resolve a group given by name or number to its match, if the group
+/// exists and participated in the match.
+fn group_match<C, F>(
+    caps: &C,
+    name_to_index: &F,
+    name: &[u8],
+) -> Option<grep_matcher::Match>
+where
+    C: Captures,
+    F: Fn(&str) -> Option<usize>,
+{
+    let name = std::str::from_utf8(name).ok()?;
+    let index = match name.parse::<usize>() {
+        Ok(i) => i,
+        Err(_) => name_to_index(name)?,
+    };
+    caps.get(index)
+}
+
+/// Append `bytes` to `dst`, applying the current case transformation.
+fn push(dst: &mut Vec<u8>, case: &mut Case, bytes: &[u8]) {
+    match *case {
+        Case::None => dst.extend_from_slice(bytes),
+        Case::Upper => {
+            dst.extend(bytes.iter().map(|b| b.to_ascii_uppercase()));
+        }
+        Case::Lower => {
+            dst.extend(bytes.iter().map(|b| b.to_ascii_lowercase()));
+        }
+        Case::UpperNext | Case::LowerNext => {
+            let upper = *case == Case::UpperNext;
+            if let Some((&first, rest)) = bytes.split_first() {
+                *case = Case::None;
+                dst.push(if upper {
+                    first.to_ascii_uppercase()
+                } else {
+                    first.to_ascii_lowercase()
+                });
+                dst.extend_from_slice(rest);
+            }
+        }
+    }
+}
+
+/// Returns the starting position of the first occurrence of `needle` in
+/// `haystack`, if one exists.
+fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
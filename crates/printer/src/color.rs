@@ -79,7 +79,8 @@ impl fmt::Display for ColorError {
                 f,
                 "unrecognized style attribute '{}'. Choose from: \
                      nobold, bold, nointense, intense, nounderline, \
-                     underline.",
+                     underline, noitalic, italic, nodimmed, dimmed, \
+                     nostrikethrough, strikethrough.",
                 name,
             ),
             ColorError::InvalidFormat(ref original) => write!(
@@ -132,10 +133,12 @@ pub struct ColorSpecs {
 /// as `x` (for 256-bit colors) or `x,x,x` (for 24-bit true color), where
 /// `x` is a number between 0 and 255 inclusive. `x` may be given as a normal
 /// decimal number of a hexadecimal number, where the latter is prefixed by
-/// `0x`.
+/// `0x`. The special color `none` resets the foreground or background to the
+/// terminal's default, without touching any other settings.
 ///
 /// Valid style instructions are `nobold`, `bold`, `intense`, `nointense`,
-/// `underline`, `nounderline`.
+/// `underline`, `nounderline`, `italic`, `noitalic`, `dimmed`, `nodimmed`,
+/// `strikethrough`, `nostrikethrough`.
 ///
 /// ## Example
 ///
@@ -183,8 +186,8 @@ impl UserColorSpec {
 #[derive(Clone, Debug, Eq, PartialEq)]
 enum SpecValue {
     None,
-    Fg(Color),
-    Bg(Color),
+    Fg(Option<Color>),
+    Bg(Option<Color>),
     Style(Style),
 }
 
@@ -215,6 +218,12 @@ enum Style {
     NoIntense,
     Underline,
     NoUnderline,
+    Italic,
+    NoItalic,
+    Dimmed,
+    NoDimmed,
+    Strikethrough,
+    NoStrikethrough,
 }
 
 impl ColorSpecs {
@@ -276,10 +285,10 @@ impl SpecValue {
         match *self {
             SpecValue::None => cspec.clear(),
             SpecValue::Fg(ref color) => {
-                cspec.set_fg(Some(color.clone()));
+                cspec.set_fg(color.clone());
             }
             SpecValue::Bg(ref color) => {
-                cspec.set_bg(Some(color.clone()));
+                cspec.set_bg(color.clone());
             }
             SpecValue::Style(ref style) => match *style {
                 Style::Bold => {
@@ -300,6 +309,24 @@ impl SpecValue {
                 Style::NoUnderline => {
                     cspec.set_underline(false);
                 }
+                Style::Italic => {
+                    cspec.set_italic(true);
+                }
+                Style::NoItalic => {
+                    cspec.set_italic(false);
+                }
+                Style::Dimmed => {
+                    cspec.set_dimmed(true);
+                }
+                Style::NoDimmed => {
+                    cspec.set_dimmed(false);
+                }
+                Style::Strikethrough => {
+                    cspec.set_strikethrough(true);
+                }
+                Style::NoStrikethrough => {
+                    cspec.set_strikethrough(false);
+                }
             },
         }
     }
@@ -329,22 +356,29 @@ impl FromStr for UserColorSpec {
                 if pieces.len() < 3 {
                     return Err(ColorError::InvalidFormat(s.to_string()));
                 }
-                let color: Color =
-                    pieces[2].parse().map_err(ColorError::from_parse_error)?;
+                let color = parse_color(pieces[2])?;
                 Ok(UserColorSpec { ty: otype, value: SpecValue::Fg(color) })
             }
             SpecType::Bg => {
                 if pieces.len() < 3 {
                     return Err(ColorError::InvalidFormat(s.to_string()));
                 }
-                let color: Color =
-                    pieces[2].parse().map_err(ColorError::from_parse_error)?;
+                let color = parse_color(pieces[2])?;
                 Ok(UserColorSpec { ty: otype, value: SpecValue::Bg(color) })
             }
         }
     }
 }
 
+/// Parse a color value, where the special value `none` resets the color to
+/// the terminal's default.
+fn parse_color(s: &str) -> Result<Option<Color>, ColorError> {
+    if s.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    s.parse().map(Some).map_err(ColorError::from_parse_error)
+}
+
 impl FromStr for OutType {
     type Err = ColorError;
 
@@ -384,6 +418,12 @@ impl FromStr for Style {
             "nointense" => Ok(Style::NoIntense),
             "underline" => Ok(Style::Underline),
             "nounderline" => Ok(Style::NoUnderline),
+            "italic" => Ok(Style::Italic),
+            "noitalic" => Ok(Style::NoItalic),
+            "dimmed" => Ok(Style::Dimmed),
+            "nodimmed" => Ok(Style::NoDimmed),
+            "strikethrough" => Ok(Style::Strikethrough),
+            "nostrikethrough" => Ok(Style::NoStrikethrough),
             _ => Err(ColorError::UnrecognizedStyle(s.to_string())),
         }
     }
@@ -0,0 +1,233 @@
+use std::error;
+use std::fmt;
+
+/// The format of a hyperlink emitted around file paths in search results.
+///
+/// A format is a template for the URL of each link. It may contain the
+/// variables `{host}`, `{path}` and `{line}`, which are replaced by the
+/// machine's hostname, the absolute path to the file being printed and the
+/// line number of the first match on the printed line, respectively. The
+/// rendered URL is wrapped around the printed path using the OSC 8 terminal
+/// escape sequence, which many terminal emulators turn into a clickable
+/// link.
+///
+/// The default format is empty, which disables hyperlinks entirely.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct HyperlinkFormat {
+    parts: Vec<Part>,
+}
+
+/// A single piece of a parsed hyperlink format.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Part {
+    /// Literal text from the template.
+    Text(Vec<u8>),
+    /// The `{host}` variable.
+    Host,
+    /// The `{path}` variable.
+    Path,
+    /// The `{line}` variable.
+    Line,
+}
+
+impl HyperlinkFormat {
+    /// Create a format that emits no hyperlinks.
+    pub fn none() -> HyperlinkFormat {
+        HyperlinkFormat::default()
+    }
+
+    /// Returns true if and only if this format emits no hyperlinks.
+    pub fn is_none(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    /// Parse a hyperlink format template.
+    ///
+    /// The aliases `none` and `default` expand to the empty format and
+    /// `file://{host}{path}`, respectively. Any other value is treated as a
+    /// template, which must contain the `{path}` variable.
+    pub fn parse(
+        template: &str,
+    ) -> Result<HyperlinkFormat, HyperlinkFormatError> {
+        let template = match template {
+            "none" => return Ok(HyperlinkFormat::none()),
+            "default" | "file" => "file://{host}{path}",
+            template => template,
+        };
+        let mut parts = vec![];
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            if !rest[..start].is_empty() {
+                parts.push(Part::Text(rest[..start].as_bytes().to_vec()));
+            }
+            let end = match rest[start..].find('}') {
+                Some(i) => start + i,
+                None => {
+                    return Err(HyperlinkFormatError::new(format!(
+                        "invalid hyperlink format {:?}: unclosed variable",
+                        template,
+                    )));
+                }
+            };
+            let part = match &rest[start + 1..end] {
+                "host" => Part::Host,
+                "path" => Part::Path,
+                "line" => Part::Line,
+                unknown => {
+                    return Err(HyperlinkFormatError::new(format!(
+                        "invalid hyperlink format {:?}: unknown variable \
+                         {{{}}} (expected host, path or line)",
+                        template, unknown,
+                    )));
+                }
+            };
+            parts.push(part);
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.as_bytes().to_vec()));
+        }
+        let format = HyperlinkFormat { parts };
+        if !format.parts.contains(&Part::Path) {
+            return Err(HyperlinkFormatError::new(format!(
+                "invalid hyperlink format {:?}: missing the {{path}} \
+                 variable",
+                template,
+            )));
+        }
+        Ok(format)
+    }
+
+    /// Render the URL for the given absolute path and line number.
+    pub(crate) fn render(&self, path: &[u8], line: Option<u64>) -> Vec<u8> {
+        let mut url = vec![];
+        for part in &self.parts {
+            match *part {
+                Part::Text(ref text) => url.extend_from_slice(text),
+                Part::Host => url.extend_from_slice(hostname().as_bytes()),
+                Part::Path => url.extend_from_slice(&percent_encode(path)),
+                Part::Line => {
+                    url.extend_from_slice(
+                        line.unwrap_or(1).to_string().as_bytes(),
+                    );
+                }
+            }
+        }
+        url
+    }
+}
+
+/// An error that occurs when parsing a hyperlink format template.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HyperlinkFormatError {
+    message: String,
+}
+
+impl HyperlinkFormatError {
+    fn new(message: String) -> HyperlinkFormatError {
+        HyperlinkFormatError { message }
+    }
+}
+
+impl error::Error for HyperlinkFormatError {}
+
+impl fmt::Display for HyperlinkFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// The escape sequence that closes a previously opened hyperlink.
+pub(crate) const CLOSE_SEQUENCE: &[u8] = b"\x1b]8;;\x1b\\";
+
+/// Returns the escape sequence that opens a hyperlink for the given URL.
+pub(crate) fn open_sequence(url: &[u8]) -> Vec<u8> {
+    let mut seq = b"\x1b]8;;".to_vec();
+    seq.extend_from_slice(url);
+    seq.extend_from_slice(b"\x1b\\");
+    seq
+}
+
+/// Return an absolute version of the given printed path, resolved against
+/// the current working directory, for use in rendered URLs.
+pub(crate) fn absolutize(path: &[u8]) -> Vec<u8> {
+    let path = path.strip_prefix(b"./").unwrap_or(path);
+    // Windows drive-letter paths are already absolute too.
+    if path.first() == Some(&b'/') || path.get(1) == Some(&b':') {
+        return path.to_vec();
+    }
+    let mut abs = match std::env::current_dir() {
+        Ok(cwd) => cwd.to_string_lossy().into_owned().into_bytes(),
+        Err(_) => vec![],
+    };
+    if abs.last() != Some(&b'/') {
+        abs.push(b'/');
+    }
+    abs.extend_from_slice(path);
+    abs
+}
+
+/// Percent-encode the given path for inclusion in a URL.
+///
+/// Path separators, colons (for Windows drive letters) and URL-unreserved
+/// bytes are passed through untouched. Everything else, including spaces and
+/// bytes that aren't valid UTF-8, is escaped as `%XX`.
+fn percent_encode(path: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(path.len());
+    for &b in path {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => encoded.push(b),
+            b'-' | b'.' | b'_' | b'~' | b'/' | b':' => encoded.push(b),
+            b => {
+                encoded.extend_from_slice(format!("%{:02X}", b).as_bytes());
+            }
+        }
+    }
+    encoded
+}
+
+/// Returns the name of this machine for the `{host}` variable.
+///
+/// Using the actual hostname in `file://` URLs lets terminals running over
+/// SSH distinguish remote paths from local ones. There is no portable
+/// hostname API in std, so this falls back to an empty string when the
+/// HOSTNAME environment variable is unset.
+fn hostname() -> String {
+    std::env::var("HOSTNAME").unwrap_or_else(|_| String::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_parse() {
+        assert!(HyperlinkFormat::parse("none").unwrap().is_none());
+        assert!(!HyperlinkFormat::parse("default").unwrap().is_none());
+        assert!(HyperlinkFormat::parse("file://{host}{path}").is_ok());
+        assert!(HyperlinkFormat::parse("vscode://file{path}:{line}").is_ok());
+
+        // A template without {path} or with unknown/unclosed variables is
+        // rejected.
+        assert!(HyperlinkFormat::parse("file://{host}").is_err());
+        assert!(HyperlinkFormat::parse("file://{pathological}").is_err());
+        assert!(HyperlinkFormat::parse("file://{path").is_err());
+    }
+
+    #[test]
+    fn format_render() {
+        let format = HyperlinkFormat::parse("x://{path}:{line}").unwrap();
+        assert_eq!(b"x:///a/b:5".to_vec(), format.render(b"/a/b", Some(5)));
+        assert_eq!(b"x:///a/b:1".to_vec(), format.render(b"/a/b", None));
+
+        // Bytes that aren't safe in URLs are percent-encoded.
+        assert_eq!(
+            b"x:///a%20dir/100%25.rs:1".to_vec(),
+            format.render(b"/a dir/100%.rs", None),
+        );
+        assert_eq!(
+            b"x:///caf%C3%A9:1".to_vec(),
+            format.render("/café".as_bytes(), None),
+        );
+    }
+}
@@ -2,7 +2,7 @@ use std::io::{self, Write};
 use std::path::Path;
 use std::time::Instant;
 
-use grep_matcher::{Match, Matcher};
+use grep_matcher::{Captures, Match, Matcher};
 use grep_searcher::{
     Searcher, Sink, SinkContext, SinkContextKind, SinkFinish, SinkMatch,
 };
@@ -23,11 +23,19 @@ struct Config {
     pretty: bool,
     max_matches: Option<u64>,
     always_begin_end: bool,
+    context_breaks: bool,
+    capture_groups: bool,
 }
 
 impl Default for Config {
     fn default() -> Config {
-        Config { pretty: false, max_matches: None, always_begin_end: false }
+        Config {
+            pretty: false,
+            max_matches: None,
+            always_begin_end: false,
+            context_breaks: false,
+            capture_groups: false,
+        }
     }
 }
 
@@ -60,6 +68,8 @@ impl JSONBuilder {
             config: self.config.clone(),
             wtr: CounterWriter::new(wtr),
             matches: vec![],
+            patterns: vec![],
+            capture_spans: vec![],
         }
     }
 
@@ -95,6 +105,29 @@ impl JSONBuilder {
         self.config.always_begin_end = yes;
         self
     }
+
+    /// When enabled, a `context_break` message is emitted between
+    /// discontiguous runs of search results, i.e., at the positions where a
+    /// grep-like printer would show a `--` separator. This permits consumers
+    /// to group contiguous `match` and `context` messages into hunks.
+    ///
+    /// This is disabled by default.
+    pub fn context_breaks(&mut self, yes: bool) -> &mut JSONBuilder {
+        self.config.context_breaks = yes;
+        self
+    }
+
+    /// When enabled, each `submatch` object includes a `captures` array
+    /// describing the capture groups that participated in the match, with
+    /// their names (when present) and offsets. This requires running the
+    /// matcher's capture machinery for every match, which may be slower
+    /// than standard searching.
+    ///
+    /// This is disabled by default.
+    pub fn capture_groups(&mut self, yes: bool) -> &mut JSONBuilder {
+        self.config.capture_groups = yes;
+        self
+    }
 }
 
 /// The JSON printer, which emits results in a JSON lines format.
@@ -285,6 +318,18 @@ impl JSONBuilder {
 ///   non-empty, for example, when searching reports inverted matches such that
 ///   the original matcher could match things in the contextual lines.
 ///
+/// #### Message: **context_break**
+///
+/// This message indicates a discontinuity between runs of match and context
+/// messages, and corresponds to the position at which the standard printer
+/// would emit its `--` separator. It is only emitted when break reporting is
+/// enabled via [`JSONBuilder::context_breaks`]. It has this field:
+///
+/// * **path** - An
+///   [arbitrary data object](#object-arbitrary-data)
+///   representing the file path corresponding to the search, if one is
+///   present. If no file path is available, then this field is `null`.
+///
 /// #### Object: **submatch**
 ///
 /// This object describes submatches found within `match` or `context`
@@ -305,6 +350,33 @@ impl JSONBuilder {
 ///   the `lines` field in the
 ///   [`match`](#message-match) or [`context`](#message-context)
 ///   messages.
+/// * **pattern** - The index of the pattern that produced this submatch,
+///   where the index corresponds to the order in which the patterns were
+///   given. This field is omitted when the underlying matcher does not track
+///   which of its patterns matched, e.g., when only one pattern was given.
+/// * **captures** - An array of
+///   [`capture group` objects](#object-capture-group)
+///   describing the capture groups that participated in this submatch. This
+///   field is only present when capture group reporting is enabled via
+///   [`JSONBuilder::capture_groups`].
+///
+/// #### Object: **capture group**
+///
+/// This object describes a single capture group that participated in a
+/// submatch. The overall match (capture group `0`) is not included, since it
+/// is already described by the submatch itself. It has these fields:
+///
+/// * **index** - The index of this capture group in the pattern, where
+///   index `1` corresponds to the first group.
+/// * **name** - The name of this capture group. This field is omitted when
+///   the group has no name.
+/// * **match** - An
+///   [arbitrary data object](#object-arbitrary-data)
+///   corresponding to the text captured by this group.
+/// * **start** - A byte offset indicating the start of this group, reported
+///   in terms of the parent submatch's data (i.e., the `lines` field).
+/// * **end** - A byte offset indicating the end of this group, reported in
+///   terms of the parent submatch's data (i.e., the `lines` field).
 ///
 /// #### Object: **stats**
 ///
@@ -449,6 +521,8 @@ pub struct JSON<W> {
     config: Config,
     wtr: CounterWriter<W>,
     matches: Vec<Match>,
+    patterns: Vec<Option<usize>>,
+    capture_spans: Vec<Vec<(usize, Match)>>,
 }
 
 impl<W: io::Write> JSON<W> {
@@ -613,6 +687,8 @@ impl<'p, 's, M: Matcher, W: io::Write> JSONSink<'p, 's, M, W> {
         range: std::ops::Range<usize>,
     ) -> io::Result<()> {
         self.json.matches.clear();
+        self.json.patterns.clear();
+        self.json.capture_spans.clear();
         // If printing requires knowing the location of each individual match,
         // then compute and stored those right now for use later. While this
         // adds an extra copy for storing the matches, we do amortize the
@@ -620,14 +696,47 @@ impl<'p, 's, M: Matcher, W: io::Write> JSONSink<'p, 's, M, W> {
         // the extent that it's easy to ensure that we never do more than
         // one search to find the matches.
         let matches = &mut self.json.matches;
+        let patterns = &mut self.json.patterns;
+        let capture_spans = &mut self.json.capture_spans;
+        let matcher = &self.matcher;
+        // Extracting capture groups is best effort: a matcher that cannot
+        // produce captures simply reports empty capture arrays.
+        let mut caps = if self.json.config.capture_groups {
+            matcher.new_captures().ok()
+        } else {
+            None
+        };
         find_iter_at_in_context(
             searcher,
-            &self.matcher,
+            matcher,
             bytes,
             range.clone(),
             |m| {
                 let (s, e) = (m.start() - range.start, m.end() - range.start);
                 matches.push(Match::new(s, e));
+                // Attributing the match to a pattern is best effort: a
+                // matcher that fails here would have already failed above.
+                patterns.push(matcher.pattern_index(bytes, m).unwrap_or(None));
+                if let Some(ref mut caps) = caps {
+                    let mut groups = vec![];
+                    if matcher
+                        .captures_at(bytes, m.start(), caps)
+                        .unwrap_or(false)
+                    {
+                        for i in 1..caps.len() {
+                            if let Some(sp) = caps.get(i) {
+                                groups.push((
+                                    i,
+                                    Match::new(
+                                        sp.start() - range.start,
+                                        sp.end() - range.start,
+                                    ),
+                                ));
+                            }
+                        }
+                    }
+                    capture_spans.push(groups);
+                }
                 true
             },
         )?;
@@ -637,6 +746,8 @@ impl<'p, 's, M: Matcher, W: io::Write> JSONSink<'p, 's, M, W> {
             && matches.last().unwrap().start() >= bytes.len()
         {
             matches.pop().unwrap();
+            patterns.pop().unwrap();
+            capture_spans.pop();
         }
         Ok(())
     }
@@ -712,7 +823,17 @@ impl<'p, 's, M: Matcher, W: io::Write> Sink for JSONSink<'p, 's, M, W> {
         self.stats.add_matches(self.json.matches.len() as u64);
         self.stats.add_matched_lines(mat.lines().count() as u64);
 
-        let submatches = SubMatches::new(mat.bytes(), &self.json.matches);
+        let captures = if self.json.config.capture_groups {
+            Some((&self.matcher, self.json.capture_spans.as_slice()))
+        } else {
+            None
+        };
+        let submatches = SubMatches::new(
+            mat.bytes(),
+            &self.json.matches,
+            &self.json.patterns,
+            captures,
+        );
         let msg = jsont::Message::Match(jsont::Match {
             path: self.path,
             lines: mat.bytes(),
@@ -731,6 +852,7 @@ impl<'p, 's, M: Matcher, W: io::Write> Sink for JSONSink<'p, 's, M, W> {
     ) -> Result<bool, io::Error> {
         self.write_begin_message()?;
         self.json.matches.clear();
+        self.json.patterns.clear();
 
         if ctx.kind() == &SinkContextKind::After {
             self.after_context_remaining =
@@ -738,7 +860,17 @@ impl<'p, 's, M: Matcher, W: io::Write> Sink for JSONSink<'p, 's, M, W> {
         }
         let submatches = if searcher.invert_match() {
             self.record_matches(searcher, ctx.bytes(), 0..ctx.bytes().len())?;
-            SubMatches::new(ctx.bytes(), &self.json.matches)
+            let captures = if self.json.config.capture_groups {
+                Some((&self.matcher, self.json.capture_spans.as_slice()))
+            } else {
+                None
+            };
+            SubMatches::new(
+                ctx.bytes(),
+                &self.json.matches,
+                &self.json.patterns,
+                captures,
+            )
         } else {
             SubMatches::empty()
         };
@@ -753,6 +885,25 @@ impl<'p, 's, M: Matcher, W: io::Write> Sink for JSONSink<'p, 's, M, W> {
         Ok(!self.should_quit())
     }
 
+    fn context_break(
+        &mut self,
+        _searcher: &Searcher,
+    ) -> Result<bool, io::Error> {
+        if !self.json.config.context_breaks {
+            return Ok(true);
+        }
+        // A break is only meaningful between runs of results, so there is
+        // nothing to emit before the first run has been printed.
+        if !self.begin_printed {
+            return Ok(true);
+        }
+        let msg = jsont::Message::ContextBreak(jsont::ContextBreak {
+            path: self.path,
+        });
+        self.json.write_message(&msg)?;
+        Ok(true)
+    }
+
     fn begin(&mut self, _searcher: &Searcher) -> Result<bool, io::Error> {
         self.json.wtr.reset_count();
         self.start_time = Instant::now();
@@ -811,22 +962,52 @@ enum SubMatches<'a> {
 
 impl<'a> SubMatches<'a> {
     /// Create a new set of match ranges from a set of matches and the
-    /// corresponding bytes that those matches apply to.
-    fn new(bytes: &'a [u8], matches: &[Match]) -> SubMatches<'a> {
+    /// corresponding bytes that those matches apply to. `patterns` gives the
+    /// pattern attribution for each match, when known.
+    fn new<M: Matcher>(
+        bytes: &'a [u8],
+        matches: &[Match],
+        patterns: &[Option<usize>],
+        captures: Option<(&'a M, &[Vec<(usize, Match)>])>,
+    ) -> SubMatches<'a> {
+        let pattern = |i: usize| patterns.get(i).copied().flatten();
+        let capture_groups = |i: usize| {
+            captures.map(|(matcher, spans)| {
+                spans
+                    .get(i)
+                    .map(|groups| {
+                        groups
+                            .iter()
+                            .map(|&(index, sp)| jsont::CaptureGroup {
+                                index,
+                                name: matcher.capture_name(index),
+                                m: &bytes[sp],
+                                start: sp.start(),
+                                end: sp.end(),
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_else(Vec::new)
+            })
+        };
         if matches.len() == 1 {
             let mat = matches[0];
             SubMatches::Small([jsont::SubMatch {
                 m: &bytes[mat],
                 start: mat.start(),
                 end: mat.end(),
+                pattern: pattern(0),
+                captures: capture_groups(0),
             }])
         } else {
             let mut match_ranges = vec![];
-            for &mat in matches {
+            for (i, &mat) in matches.iter().enumerate() {
                 match_ranges.push(jsont::SubMatch {
                     m: &bytes[mat],
                     start: mat.start(),
                     end: mat.end(),
+                    pattern: pattern(i),
+                    captures: capture_groups(i),
                 });
             }
             SubMatches::Big(match_ranges)
@@ -943,6 +1124,67 @@ e
         assert_eq!(got.lines().count(), 5);
     }
 
+    #[test]
+    fn context_break() {
+        let matcher = RegexMatcher::new(r"Watson").unwrap();
+        let mut printer =
+            JSONBuilder::new().context_breaks(true).build(vec![]);
+        SearcherBuilder::new()
+            .after_context(1)
+            .build()
+            .search_reader(&matcher, SHERLOCK, printer.sink(&matcher))
+            .unwrap();
+        let got = printer_contents(&mut printer);
+
+        // begin, match, context, context_break, match, context, end
+        assert_eq!(got.lines().count(), 7);
+        assert!(got
+            .lines()
+            .nth(3)
+            .unwrap()
+            .contains(r#""type":"context_break""#));
+
+        // Breaks are not emitted unless they're enabled.
+        let mut printer = JSONBuilder::new().build(vec![]);
+        SearcherBuilder::new()
+            .after_context(1)
+            .build()
+            .search_reader(&matcher, SHERLOCK, printer.sink(&matcher))
+            .unwrap();
+        let got = printer_contents(&mut printer);
+
+        assert_eq!(got.lines().count(), 6);
+        assert!(!got.contains("context_break"));
+    }
+
+    #[test]
+    fn capture_groups() {
+        let matcher =
+            RegexMatcher::new(r"(?P<title>\w+) (?P<surname>Watson)").unwrap();
+        let mut printer =
+            JSONBuilder::new().capture_groups(true).build(vec![]);
+        SearcherBuilder::new()
+            .build()
+            .search_reader(&matcher, SHERLOCK, printer.sink(&matcher))
+            .unwrap();
+        let got = printer_contents(&mut printer);
+
+        assert_eq!(got.lines().count(), 4);
+        let m1 = got.lines().nth(1).unwrap();
+        assert!(m1.contains(
+            r#""captures":[{"index":1,"name":"title","match":{"text":"Doctor"},"start":8,"end":14},{"index":2,"name":"surname","match":{"text":"Watson"},"start":15,"end":21}]"#
+        ));
+
+        // Capture groups are not reported unless they're enabled.
+        let mut printer = JSONBuilder::new().build(vec![]);
+        SearcherBuilder::new()
+            .build()
+            .search_reader(&matcher, SHERLOCK, printer.sink(&matcher))
+            .unwrap();
+        let got = printer_contents(&mut printer);
+        assert!(!got.contains("captures"));
+    }
+
     #[test]
     fn no_match() {
         let matcher = RegexMatcher::new(r"DOES NOT MATCH").unwrap();
@@ -23,6 +23,7 @@ pub enum Message<'a> {
     End(End<'a>),
     Match(Match<'a>),
     Context(Context<'a>),
+    ContextBreak(ContextBreak<'a>),
 }
 
 #[derive(Serialize)]
@@ -61,6 +62,12 @@ pub struct Context<'a> {
     pub submatches: &'a [SubMatch<'a>],
 }
 
+#[derive(Serialize)]
+pub struct ContextBreak<'a> {
+    #[serde(serialize_with = "ser_path")]
+    pub path: Option<&'a Path>,
+}
+
 #[derive(Serialize)]
 pub struct SubMatch<'a> {
     #[serde(rename = "match")]
@@ -68,6 +75,22 @@ pub struct SubMatch<'a> {
     pub m: &'a [u8],
     pub start: usize,
     pub end: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub captures: Option<Vec<CaptureGroup<'a>>>,
+}
+
+#[derive(Serialize)]
+pub struct CaptureGroup<'a> {
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<&'a str>,
+    #[serde(rename = "match")]
+    #[serde(serialize_with = "ser_bytes")]
+    pub m: &'a [u8],
+    pub start: usize,
+    pub end: usize,
 }
 
 /// Data represents things that look like strings, but may actually not be
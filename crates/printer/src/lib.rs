@@ -67,12 +67,15 @@ fn example() -> Result<(), Box<Error>> {
 pub use crate::color::{
     default_color_specs, ColorError, ColorSpecs, UserColorSpec,
 };
+pub use crate::hyperlink::{HyperlinkFormat, HyperlinkFormatError};
 #[cfg(feature = "serde1")]
 pub use crate::json::{JSONBuilder, JSONSink, JSON};
-pub use crate::standard::{Standard, StandardBuilder, StandardSink};
+pub use crate::standard::{
+    ColumnKind, Standard, StandardBuilder, StandardSink,
+};
 pub use crate::stats::Stats;
 pub use crate::summary::{Summary, SummaryBuilder, SummaryKind, SummarySink};
-pub use crate::util::PrinterPath;
+pub use crate::util::{PathTransform, PrinterPath};
 
 // The maximum number of bytes to execute a search to account for look-ahead.
 //
@@ -90,6 +93,7 @@ mod macros;
 
 mod color;
 mod counter;
+mod hyperlink;
 #[cfg(feature = "serde1")]
 mod json;
 #[cfg(feature = "serde1")]
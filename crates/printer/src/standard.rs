@@ -15,6 +15,7 @@ use termcolor::{ColorSpec, NoColor, WriteColor};
 
 use crate::color::ColorSpecs;
 use crate::counter::CounterWriter;
+use crate::hyperlink::{self, HyperlinkFormat};
 use crate::stats::Stats;
 use crate::util::{
     find_iter_at_in_context, trim_ascii_prefix, trim_line_terminator,
@@ -26,6 +27,30 @@ use crate::util::{
 /// This is manipulated by the StandardBuilder and then referenced by the
 /// actual implementation. Once a printer is build, the configuration is frozen
 /// and cannot changed.
+/// The unit in which column numbers are counted.
+///
+/// This is used in the
+/// [`StandardBuilder::column_kind`](struct.StandardBuilder.html#method.column_kind)
+/// configuration knob.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ColumnKind {
+    /// Count columns in bytes.
+    ///
+    /// This is the default, and is the fastest to compute.
+    Bytes,
+    /// Count columns in Unicode codepoints.
+    ///
+    /// Invalid UTF-8 is counted as if it were replaced by the Unicode
+    /// replacement codepoint, with one column per replacement.
+    Chars,
+    /// Count columns in display cells.
+    ///
+    /// This is like `Chars`, except tabs advance the column number to the
+    /// next multiple of eight, which matches how editors with a tab stop of
+    /// eight display them.
+    DisplayWidth,
+}
+
 #[derive(Debug, Clone)]
 struct Config {
     colors: ColorSpecs,
@@ -35,11 +60,13 @@ struct Config {
     only_matching: bool,
     per_match: bool,
     per_match_one_line: bool,
+    match_ranges: bool,
     replacement: Arc<Option<Vec<u8>>>,
     max_columns: Option<u64>,
     max_columns_preview: bool,
     max_matches: Option<u64>,
     column: bool,
+    column_kind: ColumnKind,
     byte_offset: bool,
     trim_ascii: bool,
     separator_search: Arc<Option<Vec<u8>>>,
@@ -48,6 +75,7 @@ struct Config {
     separator_field_context: Arc<Vec<u8>>,
     separator_path: Option<u8>,
     path_terminator: Option<u8>,
+    hyperlink_format: HyperlinkFormat,
 }
 
 impl Default for Config {
@@ -60,11 +88,13 @@ impl Default for Config {
             only_matching: false,
             per_match: false,
             per_match_one_line: false,
+            match_ranges: false,
             replacement: Arc::new(None),
             max_columns: None,
             max_columns_preview: false,
             max_matches: None,
             column: false,
+            column_kind: ColumnKind::Bytes,
             byte_offset: false,
             trim_ascii: false,
             separator_search: Arc::new(None),
@@ -73,6 +103,7 @@ impl Default for Config {
             separator_field_context: Arc::new(b"-".to_vec()),
             separator_path: None,
             path_terminator: None,
+            hyperlink_format: HyperlinkFormat::none(),
         }
     }
 }
@@ -254,6 +285,38 @@ impl StandardBuilder {
         self
     }
 
+    /// Print the end position of each match after its starting column.
+    ///
+    /// When enabled along with `per_match` and `column`, each match is
+    /// preceded by its end line number and end column in addition to its
+    /// start position, e.g., `path:line:column:endline:endcolumn:`. The end
+    /// column points at the position immediately following the last byte of
+    /// the match, matching the convention used by vim's quickfix ranges.
+    ///
+    /// This has no effect when line numbers are not available.
+    ///
+    /// This is disabled by default.
+    pub fn match_ranges(&mut self, yes: bool) -> &mut StandardBuilder {
+        self.config.match_ranges = yes;
+        self
+    }
+
+    /// Set the hyperlink format to use when printing file paths.
+    ///
+    /// When the format is not `none`, each printed path is wrapped in an
+    /// OSC 8 escape sequence whose URL is rendered from the format, which
+    /// turns paths into clickable links in terminal emulators that support
+    /// them.
+    ///
+    /// Hyperlinks are disabled by default.
+    pub fn hyperlink_format(
+        &mut self,
+        format: HyperlinkFormat,
+    ) -> &mut StandardBuilder {
+        self.config.hyperlink_format = format;
+        self
+    }
+
     /// Set the bytes that will be used to replace each occurrence of a match
     /// found.
     ///
@@ -324,6 +387,21 @@ impl StandardBuilder {
         self
     }
 
+    /// Set the unit in which column numbers are counted.
+    ///
+    /// Byte counting is cheap, but confuses editors when a line contains
+    /// tabs or multi-byte characters before the match. `ColumnKind::Chars`
+    /// counts codepoints instead, and `ColumnKind::DisplayWidth`
+    /// additionally expands tabs to a tab stop of eight.
+    ///
+    /// This has no effect unless column numbers are enabled via the
+    /// [`column`](#method.column) option. The default is
+    /// `ColumnKind::Bytes`.
+    pub fn column_kind(&mut self, kind: ColumnKind) -> &mut StandardBuilder {
+        self.config.column_kind = kind;
+        self
+    }
+
     /// Print the absolute byte offset of the beginning of each line printed.
     ///
     /// The absolute byte offset starts from the beginning of each search and
@@ -987,6 +1065,7 @@ impl<'a, M: Matcher, W: WriteColor> StandardImpl<'a, M, W> {
             self.sunk.absolute_byte_offset(),
             self.sunk.line_number(),
             None,
+            None,
         )?;
         self.write_line(self.sunk.bytes())
     }
@@ -1012,6 +1091,7 @@ impl<'a, M: Matcher, W: WriteColor> StandardImpl<'a, M, W> {
                 absolute_byte_offset,
                 self.sunk.line_number().map(|n| n + i as u64),
                 None,
+                None,
             )?;
             absolute_byte_offset += line.len() as u64;
 
@@ -1031,7 +1111,11 @@ impl<'a, M: Matcher, W: WriteColor> StandardImpl<'a, M, W> {
                 self.write_prelude(
                     self.sunk.absolute_byte_offset() + m.start() as u64,
                     self.sunk.line_number(),
-                    Some(m.start() as u64 + 1),
+                    Some((
+                        m.start() as u64 + 1,
+                        &self.sunk.bytes()[..m.start()],
+                    )),
+                    None,
                 )?;
 
                 let buf = &self.sunk.bytes()[m];
@@ -1039,18 +1123,30 @@ impl<'a, M: Matcher, W: WriteColor> StandardImpl<'a, M, W> {
             }
         } else if self.config().per_match {
             for &m in self.sunk.matches() {
+                let range = self.match_range(
+                    self.sunk.bytes(),
+                    0,
+                    m.end(),
+                    self.sunk.line_number(),
+                );
                 self.write_prelude(
                     self.sunk.absolute_byte_offset() + m.start() as u64,
                     self.sunk.line_number(),
-                    Some(m.start() as u64 + 1),
+                    Some((
+                        m.start() as u64 + 1,
+                        &self.sunk.bytes()[..m.start()],
+                    )),
+                    range,
                 )?;
                 self.write_colored_line(&[m], self.sunk.bytes())?;
             }
         } else {
+            let m = self.sunk.matches()[0];
             self.write_prelude(
                 self.sunk.absolute_byte_offset(),
                 self.sunk.line_number(),
-                Some(self.sunk.matches()[0].start() as u64 + 1),
+                Some((m.start() as u64 + 1, &self.sunk.bytes()[..m.start()])),
+                None,
             )?;
             self.write_colored_line(self.sunk.matches(), self.sunk.bytes())?;
         }
@@ -1078,7 +1174,11 @@ impl<'a, M: Matcher, W: WriteColor> StandardImpl<'a, M, W> {
             self.write_prelude(
                 self.sunk.absolute_byte_offset() + line.start() as u64,
                 self.sunk.line_number().map(|n| n + count),
-                Some(matches[0].start() as u64 + 1),
+                Some((
+                    matches[0].start() as u64 + 1,
+                    &bytes[..matches[0].start()],
+                )),
+                None,
             )?;
             count += 1;
             if self.exceeds_max_columns(&bytes[line]) {
@@ -1122,7 +1222,11 @@ impl<'a, M: Matcher, W: WriteColor> StandardImpl<'a, M, W> {
                     self.write_prelude(
                         self.sunk.absolute_byte_offset() + m.start() as u64,
                         self.sunk.line_number().map(|n| n + count),
-                        Some(m.start() as u64 + 1),
+                        Some((
+                            m.start() as u64 + 1,
+                            &bytes[start..cmp::max(start, m.start())],
+                        )),
+                        None,
                     )?;
 
                     let this_line = line.with_end(upto);
@@ -1157,10 +1261,21 @@ impl<'a, M: Matcher, W: WriteColor> StandardImpl<'a, M, W> {
                     count += 1;
                     continue;
                 }
+                let range = self.match_range(
+                    bytes,
+                    line.start(),
+                    m.end(),
+                    self.sunk.line_number().map(|n| n + count),
+                );
                 self.write_prelude(
                     self.sunk.absolute_byte_offset() + line.start() as u64,
                     self.sunk.line_number().map(|n| n + count),
-                    Some(m.start().saturating_sub(line.start()) as u64 + 1),
+                    Some((
+                        m.start().saturating_sub(line.start()) as u64 + 1,
+                        &bytes
+                            [line.start()..cmp::max(line.start(), m.start())],
+                    )),
+                    range,
                 )?;
                 count += 1;
                 if self.exceeds_max_columns(&bytes[line]) {
@@ -1207,7 +1322,8 @@ impl<'a, M: Matcher, W: WriteColor> StandardImpl<'a, M, W> {
         &self,
         absolute_byte_offset: u64,
         line_number: Option<u64>,
-        column: Option<u64>,
+        column: Option<(u64, &[u8])>,
+        range: Option<(u64, u64, &[u8])>,
     ) -> io::Result<()> {
         let sep = self.separator_field();
 
@@ -1217,9 +1333,13 @@ impl<'a, M: Matcher, W: WriteColor> StandardImpl<'a, M, W> {
         if let Some(n) = line_number {
             self.write_line_number(n, sep)?;
         }
-        if let Some(n) = column {
+        if let Some((n, prefix)) = column {
             if self.config().column {
-                self.write_column_number(n, sep)?;
+                self.write_column_number(n, prefix, sep)?;
+                if let Some((end_line, end_column, end_prefix)) = range {
+                    self.write_line_number(end_line, sep)?;
+                    self.write_column_number(end_column, end_prefix, sep)?;
+                }
             }
         }
         if self.config().byte_offset {
@@ -1228,6 +1348,36 @@ impl<'a, M: Matcher, W: WriteColor> StandardImpl<'a, M, W> {
         Ok(())
     }
 
+    /// Compute the end position of a match as a (line number, column) pair,
+    /// where the column points immediately after the last byte of the match.
+    /// `base` must be the offset at which the line containing the start of
+    /// the match begins in `bytes`, and `line_number` the number of that
+    /// line.
+    ///
+    /// This returns `None` unless end position printing is enabled and line
+    /// numbers are available.
+    fn match_range<'b>(
+        &self,
+        bytes: &'b [u8],
+        base: usize,
+        end: usize,
+        line_number: Option<u64>,
+    ) -> Option<(u64, u64, &'b [u8])> {
+        if !self.config().match_ranges {
+            return None;
+        }
+        let line_number = line_number?;
+        let line_term = self.searcher.line_terminator().as_byte();
+        let (mut lines, mut last) = (0, base);
+        for (i, &byte) in bytes[base..end].iter().enumerate() {
+            if byte == line_term {
+                lines += 1;
+                last = base + i + 1;
+            }
+        }
+        Some((line_number + lines, (end - last) as u64 + 1, &bytes[last..end]))
+    }
+
     #[inline(always)]
     fn write_line(&self, line: &[u8]) -> io::Result<()> {
         if self.exceeds_max_columns(line) {
@@ -1386,7 +1536,11 @@ impl<'a, M: Matcher, W: WriteColor> StandardImpl<'a, M, W> {
     /// terminator.)
     fn write_path_line(&self) -> io::Result<()> {
         if let Some(path) = self.path() {
+            let hyperlink = self.start_path_hyperlink(None)?;
             self.write_spec(self.config().colors.path(), path.as_bytes())?;
+            if hyperlink {
+                self.write(hyperlink::CLOSE_SEQUENCE)?;
+            }
             if let Some(term) = self.config().path_terminator {
                 self.write(&[term])?;
             } else {
@@ -1402,7 +1556,12 @@ impl<'a, M: Matcher, W: WriteColor> StandardImpl<'a, M, W> {
     /// the field separator.)
     fn write_path_field(&self, field_separator: &[u8]) -> io::Result<()> {
         if let Some(path) = self.path() {
+            let hyperlink =
+                self.start_path_hyperlink(self.sunk.line_number())?;
             self.write_spec(self.config().colors.path(), path.as_bytes())?;
+            if hyperlink {
+                self.write(hyperlink::CLOSE_SEQUENCE)?;
+            }
             if let Some(term) = self.config().path_terminator {
                 self.write(&[term])?;
             } else {
@@ -1412,6 +1571,26 @@ impl<'a, M: Matcher, W: WriteColor> StandardImpl<'a, M, W> {
         Ok(())
     }
 
+    /// If hyperlinks are enabled and this printer has a file path, then this
+    /// writes the escape sequence that opens a hyperlink around the path
+    /// about to be printed. Returns true if and only if a hyperlink was
+    /// opened, in which case the caller must close it after writing the
+    /// path.
+    fn start_path_hyperlink(&self, line: Option<u64>) -> io::Result<bool> {
+        let format = &self.config().hyperlink_format;
+        if format.is_none() {
+            return Ok(false);
+        }
+        let path = match self.path() {
+            None => return Ok(false),
+            Some(path) => path,
+        };
+        let abs = hyperlink::absolutize(path.as_bytes());
+        let url = format.render(&abs, line);
+        self.write(&hyperlink::open_sequence(&url))?;
+        Ok(true)
+    }
+
     fn write_search_prelude(&self) -> io::Result<()> {
         let this_search_written = self.wtr().borrow().count() > 0;
         if this_search_written {
@@ -1482,11 +1661,32 @@ impl<'a, M: Matcher, W: WriteColor> StandardImpl<'a, M, W> {
         Ok(())
     }
 
+    /// Write a column number in the configured unit.
+    ///
+    /// `line_prefix` must be the bytes of the line containing the position
+    /// being reported, from the start of that line up to the position. It is
+    /// only inspected when a unit other than bytes is configured.
     fn write_column_number(
         &self,
         column_number: u64,
+        line_prefix: &[u8],
         field_separator: &[u8],
     ) -> io::Result<()> {
+        let column_number = match self.config().column_kind {
+            ColumnKind::Bytes => column_number,
+            ColumnKind::Chars => line_prefix.chars().count() as u64 + 1,
+            ColumnKind::DisplayWidth => {
+                let mut width: u64 = 0;
+                for ch in line_prefix.chars() {
+                    if ch == '\t' {
+                        width += 8 - width % 8;
+                    } else {
+                        width += 1;
+                    }
+                }
+                width + 1
+            }
+        };
         let n = column_number.to_string();
         self.write_spec(self.config().colors.column(), n.as_bytes())?;
         self.write(field_separator)?;
@@ -1622,7 +1822,7 @@ mod tests {
     use grep_searcher::SearcherBuilder;
     use termcolor::{Ansi, NoColor};
 
-    use super::{ColorSpecs, Standard, StandardBuilder};
+    use super::{ColorSpecs, ColumnKind, Standard, StandardBuilder};
 
     const SHERLOCK: &'static str = "\
 For the Doctor Watsons of this world, as opposed to the Sherlock
@@ -3017,6 +3217,35 @@ Holmeses, success in the province of detective work must always
         assert_eq_printed!(expected, got);
     }
 
+    #[test]
+    fn column_kind() {
+        let matcher = RegexMatcher::new("bar").unwrap();
+        let haystack = "\tfoo \u{3b2} bar\n";
+        let kinds = [
+            (ColumnKind::Bytes, "1:9:\tfoo \u{3b2} bar\n"),
+            (ColumnKind::Chars, "1:8:\tfoo \u{3b2} bar\n"),
+            (ColumnKind::DisplayWidth, "1:15:\tfoo \u{3b2} bar\n"),
+        ];
+        for (kind, expected) in kinds {
+            let mut printer = StandardBuilder::new()
+                .column(true)
+                .column_kind(kind)
+                .build(NoColor::new(vec![]));
+            SearcherBuilder::new()
+                .line_number(true)
+                .build()
+                .search_reader(
+                    &matcher,
+                    haystack.as_bytes(),
+                    printer.sink(&matcher),
+                )
+                .unwrap();
+
+            let got = printer_contents(&mut printer);
+            assert_eq_printed!(expected, got);
+        }
+    }
+
     #[test]
     fn per_match_multi_line1() {
         let matcher =
@@ -3188,6 +3417,59 @@ Holmeses, success in the province of detective work must always
         assert_eq_printed!(expected, got);
     }
 
+    #[test]
+    fn per_match_match_ranges() {
+        let matcher = RegexMatcher::new(r"Sherlock").unwrap();
+        let mut printer = StandardBuilder::new()
+            .per_match(true)
+            .column(true)
+            .match_ranges(true)
+            .build(NoColor::new(vec![]));
+        SearcherBuilder::new()
+            .line_number(true)
+            .build()
+            .search_reader(
+                &matcher,
+                SHERLOCK.as_bytes(),
+                printer.sink(&matcher),
+            )
+            .unwrap();
+
+        let got = printer_contents(&mut printer);
+        let expected = "\
+1:57:1:65:For the Doctor Watsons of this world, as opposed to the Sherlock
+3:49:3:57:be, to a very large extent, the result of luck. Sherlock Holmes
+";
+        assert_eq_printed!(expected, got);
+    }
+
+    #[test]
+    fn per_match_match_ranges_multi_line() {
+        let matcher = RegexMatcher::new(r"(?s)Watsons.+?Holmeses").unwrap();
+        let mut printer = StandardBuilder::new()
+            .per_match(true)
+            .per_match_one_line(true)
+            .column(true)
+            .match_ranges(true)
+            .build(NoColor::new(vec![]));
+        SearcherBuilder::new()
+            .multi_line(true)
+            .line_number(true)
+            .build()
+            .search_reader(
+                &matcher,
+                SHERLOCK.as_bytes(),
+                printer.sink(&matcher),
+            )
+            .unwrap();
+
+        let got = printer_contents(&mut printer);
+        let expected = "\
+1:16:2:9:For the Doctor Watsons of this world, as opposed to the Sherlock
+";
+        assert_eq_printed!(expected, got);
+    }
+
     #[test]
     fn replacement_passthru() {
         let matcher = RegexMatcher::new(r"Sherlock|Doctor (\w+)").unwrap();
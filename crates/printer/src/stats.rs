@@ -1,4 +1,6 @@
+use std::collections::BTreeMap;
 use std::ops::{Add, AddAssign};
+use std::path::Path;
 use std::time::Duration;
 
 use crate::util::NiceDuration;
@@ -17,6 +19,11 @@ pub struct Stats {
     bytes_printed: u64,
     matched_lines: u64,
     matches: u64,
+    #[cfg_attr(
+        feature = "serde1",
+        serde(skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    matches_per_file: BTreeMap<String, u64>,
 }
 
 impl Add for Stats {
@@ -30,17 +37,9 @@ impl Add for Stats {
 impl<'a> Add<&'a Stats> for Stats {
     type Output = Stats;
 
-    fn add(self, rhs: &'a Stats) -> Stats {
-        Stats {
-            elapsed: NiceDuration(self.elapsed.0 + rhs.elapsed.0),
-            searches: self.searches + rhs.searches,
-            searches_with_match: self.searches_with_match
-                + rhs.searches_with_match,
-            bytes_searched: self.bytes_searched + rhs.bytes_searched,
-            bytes_printed: self.bytes_printed + rhs.bytes_printed,
-            matched_lines: self.matched_lines + rhs.matched_lines,
-            matches: self.matches + rhs.matches,
-        }
+    fn add(mut self, rhs: &'a Stats) -> Stats {
+        self += rhs;
+        self
     }
 }
 
@@ -59,6 +58,9 @@ impl<'a> AddAssign<&'a Stats> for Stats {
         self.bytes_printed += rhs.bytes_printed;
         self.matched_lines += rhs.matched_lines;
         self.matches += rhs.matches;
+        for (path, &matches) in rhs.matches_per_file.iter() {
+            *self.matches_per_file.entry(path.clone()).or_insert(0) += matches;
+        }
     }
 }
 
@@ -144,4 +146,21 @@ impl Stats {
     pub fn add_matches(&mut self, n: u64) {
         self.matches += n;
     }
+
+    /// Return the number of matches recorded for each file path.
+    ///
+    /// Per-file counts are never recorded by printers. They are only present
+    /// when a caller has explicitly recorded them via
+    /// [`add_file_matches`](Stats::add_file_matches).
+    pub fn matches_per_file(&self) -> &BTreeMap<String, u64> {
+        &self.matches_per_file
+    }
+
+    /// Add to the number of matches recorded for the given file path.
+    ///
+    /// Paths that are not valid UTF-8 are converted to a string lossily.
+    pub fn add_file_matches<P: AsRef<Path>>(&mut self, path: P, n: u64) {
+        let path = path.as_ref().to_string_lossy().into_owned();
+        *self.matches_per_file.entry(path).or_insert(0) += n;
+    }
 }
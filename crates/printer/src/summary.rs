@@ -62,6 +62,14 @@ pub enum SummaryKind {
     /// If the `path` setting is enabled, then the count is prefixed by the
     /// corresponding file path.
     CountMatches,
+    /// Show a count of the total number of matching lines, followed by a
+    /// count of the total number of matches (counting possibly many matches
+    /// on each line), separated by the field separator. Both counts are
+    /// computed in a single search.
+    ///
+    /// If the `path` setting is enabled, then the counts are prefixed by the
+    /// corresponding file path.
+    CountLinesAndMatches,
     /// Show only the file path if and only if a match was found.
     ///
     /// This ignores the `path` setting and always shows the file path. If no
@@ -92,7 +100,7 @@ impl SummaryKind {
 
         match *self {
             PathWithMatch | PathWithoutMatch => true,
-            Count | CountMatches | Quiet => false,
+            Count | CountMatches | CountLinesAndMatches | Quiet => false,
         }
     }
 
@@ -102,7 +110,7 @@ impl SummaryKind {
         use self::SummaryKind::*;
 
         match *self {
-            CountMatches => true,
+            CountMatches | CountLinesAndMatches => true,
             Count | PathWithMatch | PathWithoutMatch | Quiet => false,
         }
     }
@@ -114,7 +122,9 @@ impl SummaryKind {
 
         match *self {
             PathWithMatch | Quiet => true,
-            Count | CountMatches | PathWithoutMatch => false,
+            Count | CountMatches | CountLinesAndMatches | PathWithoutMatch => {
+                false
+            }
         }
     }
 }
@@ -261,9 +271,10 @@ impl SummaryBuilder {
 
     /// Exclude count-related summary results with no matches.
     ///
-    /// When enabled and the mode is either `Count` or `CountMatches`, then
-    /// results are not printed if no matches were found. Otherwise, every
-    /// search prints a result with a possibly `0` number of matches.
+    /// When enabled and the mode is one of `Count`, `CountMatches` or
+    /// `CountLinesAndMatches`, then results are not printed if no matches
+    /// were found. Otherwise, every search prints a result with a possibly
+    /// `0` number of matches.
     ///
     /// This is enabled by default.
     pub fn exclude_zero(&mut self, yes: bool) -> &mut SummaryBuilder {
@@ -271,8 +282,8 @@ impl SummaryBuilder {
         self
     }
 
-    /// Set the separator used between fields for the `Count` and
-    /// `CountMatches` modes.
+    /// Set the separator used between fields for the `Count`,
+    /// `CountMatches` and `CountLinesAndMatches` modes.
     ///
     /// By default, this is set to `:`.
     pub fn separator_field(&mut self, sep: Vec<u8>) -> &mut SummaryBuilder {
@@ -713,6 +724,18 @@ impl<'p, 's, M: Matcher, W: WriteColor> Sink for SummarySink<'p, 's, M, W> {
                     self.write_line_term(searcher)?;
                 }
             }
+            SummaryKind::CountLinesAndMatches => {
+                if show_count {
+                    let stats = self.stats.as_ref().expect(
+                        "CountLinesAndMatches should enable stats tracking",
+                    );
+                    self.write_path_field()?;
+                    self.write(self.match_count.to_string().as_bytes())?;
+                    self.write(&self.summary.config.separator_field)?;
+                    self.write(stats.matches().to_string().as_bytes())?;
+                    self.write_line_term(searcher)?;
+                }
+            }
             SummaryKind::PathWithMatch => {
                 if self.match_count > 0 {
                     self.write_path_line(searcher)?;
@@ -967,6 +990,25 @@ and exhibited clearly, with a label attached.
         assert_eq_printed!("sherlock:4\n", got);
     }
 
+    #[test]
+    fn count_lines_and_matches() {
+        let matcher = RegexMatcher::new(r"Watson|Sherlock").unwrap();
+        let mut printer = SummaryBuilder::new()
+            .kind(SummaryKind::CountLinesAndMatches)
+            .build_no_color(vec![]);
+        SearcherBuilder::new()
+            .build()
+            .search_reader(
+                &matcher,
+                SHERLOCK,
+                printer.sink_with_path(&matcher, "sherlock"),
+            )
+            .unwrap();
+
+        let got = printer_contents(&mut printer);
+        assert_eq_printed!("sherlock:3:4\n", got);
+    }
+
     #[test]
     fn path_with_match_found() {
         let matcher = RegexMatcher::new(r"Watson").unwrap();
@@ -278,6 +278,63 @@ impl<'a> Sunk<'a> {
 #[derive(Clone, Debug)]
 pub struct PrinterPath<'a>(Cow<'a, [u8]>);
 
+/// A transform to apply to a path before printing it.
+///
+/// Transforms that need the current working directory or the file system
+/// (e.g., to resolve symbolic links) fall back to printing the path as given
+/// when the relevant operation fails.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PathTransform {
+    /// Print the path as given.
+    None,
+    /// Print the path relative to the current working directory, when it is
+    /// an absolute path below it.
+    Relative,
+    /// Print the path as an absolute path, by joining it to the current
+    /// working directory when necessary.
+    Absolute,
+    /// Print the canonical form of the path, with all symbolic links and
+    /// relative components resolved.
+    Canonical,
+}
+
+impl PathTransform {
+    /// Apply this transform to the given path.
+    pub fn apply(self, path: &Path) -> Cow<'_, Path> {
+        match self {
+            PathTransform::None => Cow::Borrowed(path),
+            PathTransform::Relative => {
+                if !path.is_absolute() {
+                    return Cow::Borrowed(path);
+                }
+                let rel = std::env::current_dir()
+                    .ok()
+                    .and_then(|cwd| Some(path.strip_prefix(cwd).ok()?));
+                match rel {
+                    None => Cow::Borrowed(path),
+                    Some(rel) if rel.as_os_str().is_empty() => {
+                        Cow::Borrowed(Path::new("."))
+                    }
+                    Some(rel) => Cow::Owned(rel.to_path_buf()),
+                }
+            }
+            PathTransform::Absolute => {
+                if path.is_absolute() {
+                    return Cow::Borrowed(path);
+                }
+                match std::env::current_dir() {
+                    Ok(cwd) => Cow::Owned(cwd.join(path)),
+                    Err(_) => Cow::Borrowed(path),
+                }
+            }
+            PathTransform::Canonical => match path.canonicalize() {
+                Ok(path) => Cow::Owned(path),
+                Err(_) => Cow::Borrowed(path),
+            },
+        }
+    }
+}
+
 impl<'a> PrinterPath<'a> {
     /// Create a new path suitable for printing.
     pub fn new(path: &'a Path) -> PrinterPath<'a> {
@@ -297,6 +354,26 @@ impl<'a> PrinterPath<'a> {
         ppath
     }
 
+    /// Create a new printer path from the given path after applying the
+    /// given transform, and then replacing any separators, as with
+    /// `with_separator`.
+    pub fn with_transform(
+        path: &'a Path,
+        transform: PathTransform,
+        sep: Option<u8>,
+    ) -> PrinterPath<'a> {
+        let mut ppath = match transform.apply(path) {
+            Cow::Borrowed(path) => PrinterPath::new(path),
+            Cow::Owned(path) => PrinterPath(Cow::Owned(
+                Vec::from_path_lossy(&path).into_owned(),
+            )),
+        };
+        if let Some(sep) = sep {
+            ppath.replace_separator(sep);
+        }
+        ppath
+    }
+
     /// Replace the path separator in this path with the given separator
     /// and do it in place. On Windows, both `/` and `\` are treated as
     /// path separators that are both replaced by `new_sep`. In all other
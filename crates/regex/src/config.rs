@@ -1,6 +1,6 @@
 use {
     grep_matcher::{ByteSet, LineTerminator},
-    regex_automata::meta::Regex,
+    regex_automata::{meta::Regex, MatchKind},
     regex_syntax::{
         ast,
         hir::{self, Hir, HirKind},
@@ -30,6 +30,7 @@ pub(crate) struct Config {
     pub(crate) swap_greed: bool,
     pub(crate) ignore_whitespace: bool,
     pub(crate) unicode: bool,
+    pub(crate) ascii: bool,
     pub(crate) octal: bool,
     pub(crate) size_limit: usize,
     pub(crate) dfa_size_limit: usize,
@@ -37,8 +38,10 @@ pub(crate) struct Config {
     pub(crate) line_terminator: Option<LineTerminator>,
     pub(crate) crlf: bool,
     pub(crate) word: bool,
+    pub(crate) word_segmentation: bool,
     pub(crate) fixed_strings: bool,
     pub(crate) whole_line: bool,
+    pub(crate) pattern_indices: bool,
 }
 
 impl Default for Config {
@@ -51,6 +54,7 @@ impl Default for Config {
             swap_greed: false,
             ignore_whitespace: false,
             unicode: true,
+            ascii: false,
             octal: false,
             // These size limits are much bigger than what's in the regex
             // crate by default.
@@ -60,8 +64,10 @@ impl Default for Config {
             line_terminator: None,
             crlf: false,
             word: false,
+            word_segmentation: false,
             fixed_strings: false,
             whole_line: false,
+            pattern_indices: false,
         }
     }
 }
@@ -201,7 +207,7 @@ impl ConfiguredHIR {
                 .dot_matches_new_line(config.dot_matches_new_line)
                 .crlf(config.crlf)
                 .swap_greed(config.swap_greed)
-                .unicode(config.unicode)
+                .unicode(config.unicode && !config.ascii)
                 .build()
                 .translate(&pattern, &ast)
                 .map_err(Error::generic)?;
@@ -235,7 +241,25 @@ impl ConfiguredHIR {
 
     /// Convert this HIR to a regex that can be used for matching.
     pub(crate) fn to_regex(&self) -> Result<Regex, Error> {
+        self.to_regex_with(MatchKind::LeftmostFirst)
+    }
+
+    /// Convert this HIR to a regex with leftmost-longest ("POSIX") match
+    /// semantics instead of the default leftmost-first semantics.
+    ///
+    /// Note that an unanchored search with such a regex does *not* report
+    /// leftmost-longest matches, since the implicit unanchored prefix also
+    /// loses its priority. Callers should find the start of a match with the
+    /// regular leftmost-first regex and then run this one anchored at that
+    /// position to extend it to the longest match.
+    pub(crate) fn to_regex_longest(&self) -> Result<Regex, Error> {
+        self.to_regex_with(MatchKind::All)
+    }
+
+    /// Convert this HIR to a regex with the given match semantics.
+    fn to_regex_with(&self, kind: MatchKind) -> Result<Regex, Error> {
         let meta = Regex::config()
+            .match_kind(kind)
             .utf8_empty(false)
             .nfa_size_limit(Some(self.config.size_limit))
             // We don't expose a knob for this because the one-pass DFA is
@@ -259,6 +283,37 @@ impl ConfiguredHIR {
         non_matching_bytes(&self.hir)
     }
 
+    /// Returns true if this HIR is just a literal or an alternation of
+    /// literals.
+    pub(crate) fn is_literal(&self) -> bool {
+        match self.hir.kind() {
+            HirKind::Literal(_) => true,
+            HirKind::Alternation(ref alts) => alts
+                .iter()
+                .all(|hir| matches!(hir.kind(), HirKind::Literal(_))),
+            _ => false,
+        }
+    }
+
+    /// Returns true if every match of this HIR is required to begin at the
+    /// start of the haystack.
+    pub(crate) fn is_anchored_start(&self) -> bool {
+        self.hir.properties().look_set_prefix().contains(hir::Look::Start)
+    }
+
+    /// Returns true if every match of this HIR is required to end at the end
+    /// of the haystack.
+    pub(crate) fn is_anchored_end(&self) -> bool {
+        self.hir.properties().look_set_suffix().contains(hir::Look::End)
+    }
+
+    /// Returns the minimum length, in bytes, of any match of this HIR.
+    ///
+    /// When `None` is returned, the HIR cannot match anything at all.
+    pub(crate) fn min_match_len(&self) -> Option<usize> {
+        self.hir.properties().minimum_len()
+    }
+
     /// Returns the line terminator configured on this expression.
     ///
     /// When we have beginning/end anchors (NOT line anchors), the fast line
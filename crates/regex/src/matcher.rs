@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
 use {
     grep_matcher::{
@@ -6,8 +6,8 @@ use {
         NoError,
     },
     regex_automata::{
-        meta::Regex, util::captures::Captures as AutomataCaptures, Input,
-        PatternID,
+        meta::Regex, util::captures::Captures as AutomataCaptures, Anchored,
+        Input, PatternID,
     },
 };
 
@@ -15,7 +15,7 @@ use crate::{
     config::{Config, ConfiguredHIR},
     error::Error,
     literal::InnerLiterals,
-    word::WordMatcher,
+    word::{WordMatcher, WordSegmentationMatcher},
 };
 
 /// A builder for constructing a `Matcher` using regular expressions.
@@ -78,6 +78,22 @@ impl RegexMatcherBuilder {
         // simple, but the idea applies.)
         let fast_line_regex = InnerLiterals::new(chir, re).one_regex()?;
 
+        // When pattern tracking is enabled, compile each pattern on its own
+        // so that matches can be attributed back to the pattern that
+        // produced them. A single pattern needs no extra regexes since every
+        // match is trivially attributed to it.
+        let pattern_regexes =
+            if self.config.pattern_indices && patterns.len() > 1 {
+                let mut res = Vec::with_capacity(patterns.len());
+                for p in patterns.iter() {
+                    let chir = self.config.build_many(&[p.as_ref()])?;
+                    res.push(chir.to_regex()?);
+                }
+                res
+            } else {
+                vec![]
+            };
+
         // We override the line terminator in case the configured HIR doesn't
         // support it.
         let mut config = self.config.clone();
@@ -87,6 +103,8 @@ impl RegexMatcherBuilder {
             matcher,
             fast_line_regex,
             non_matching_bytes,
+            pattern_regexes,
+            longest_regex: Arc::new(OnceLock::new()),
         })
     }
 
@@ -187,6 +205,25 @@ impl RegexMatcherBuilder {
         self
     }
 
+    /// Whether to force ASCII-only matching semantics for the entire pattern.
+    ///
+    /// When enabled, character classes such as `\w`, `\d` and `\s` match
+    /// only their ASCII variants and case insensitive matching uses simple
+    /// ASCII case folding, regardless of whether the Unicode (`u`) flag is
+    /// set. This is equivalent to wrapping the entire pattern in `(?-u:...)`,
+    /// and trades Unicode correctness for faster compilation and smaller
+    /// DFAs. This is useful for searching data that is known to be pure
+    /// ASCII, such as most log files.
+    ///
+    /// Note that when this is enabled, patterns that explicitly require
+    /// Unicode mode (such as `\p{Greek}`) will fail to compile.
+    ///
+    /// This is disabled by default.
+    pub fn ascii(&mut self, yes: bool) -> &mut RegexMatcherBuilder {
+        self.config.ascii = yes;
+        self
+    }
+
     /// Whether to support octal syntax or not.
     ///
     /// Octal syntax is a little-known way of uttering Unicode codepoints in
@@ -335,6 +372,27 @@ impl RegexMatcherBuilder {
         self
     }
 
+    /// Require that all matches occur on word boundaries determined by
+    /// Unicode text segmentation (UAX #29).
+    ///
+    /// This is an alternative to the `word` option. While `word` requires
+    /// the characters surrounding a match to be in the `\W` class, this
+    /// option requires the start and end of each match to fall on the word
+    /// boundaries computed by the Unicode segmentation algorithm. The two
+    /// disagree in text where `\w`/`\W` draw the wrong boundaries. For
+    /// example, segmentation keeps the contraction `can't` together as a
+    /// single word, so `can` with this option enabled does not match inside
+    /// `can't`, while it does with `word`.
+    ///
+    /// When enabled, this option takes precedence over `word`.
+    pub fn word_segmentation(
+        &mut self,
+        yes: bool,
+    ) -> &mut RegexMatcherBuilder {
+        self.config.word_segmentation = yes;
+        self
+    }
+
     /// Whether the patterns should be treated as literal strings or not. When
     /// this is active, all characters, including ones that would normally be
     /// special regex meta characters, are matched literally.
@@ -349,6 +407,19 @@ impl RegexMatcherBuilder {
         self.config.whole_line = yes;
         self
     }
+
+    /// Whether to track which of the patterns given to `build_many` produced
+    /// each match.
+    ///
+    /// When enabled, matchers built from multiple patterns compile one
+    /// additional regex for each pattern, and the `Matcher` implementation's
+    /// `pattern_index` method reports the first pattern that matches within
+    /// the span of each reported match. This is disabled by default since it
+    /// adds construction cost proportional to the number of patterns.
+    pub fn pattern_indices(&mut self, yes: bool) -> &mut RegexMatcherBuilder {
+        self.config.pattern_indices = yes;
+        self
+    }
 }
 
 /// An implementation of the `Matcher` trait using Rust's standard regex
@@ -366,6 +437,16 @@ pub struct RegexMatcher {
     fast_line_regex: Option<Regex>,
     /// A set of bytes that will never appear in a match.
     non_matching_bytes: ByteSet,
+    /// One regex per input pattern, used to attribute matches back to the
+    /// pattern that produced them. This is empty unless pattern tracking was
+    /// enabled on the builder and more than one pattern was given.
+    pattern_regexes: Vec<Regex>,
+    /// A lazily built copy of the regex with leftmost-longest ("POSIX")
+    /// match semantics, used to service `find_longest_at`. It is behind an
+    /// `Arc` so that clones of this matcher share the compiled regex. The
+    /// inner `Option` records a failed build, in which case
+    /// `find_longest_at` falls back to leftmost-first matches.
+    longest_regex: Arc<OnceLock<Option<Regex>>>,
 }
 
 impl RegexMatcher {
@@ -390,6 +471,92 @@ impl RegexMatcher {
     pub fn new_line_matcher(pattern: &str) -> Result<RegexMatcher, Error> {
         RegexMatcherBuilder::new().line_terminator(Some(b'\n')).build(pattern)
     }
+
+    /// Returns true if the regex compiled by this matcher is just a literal
+    /// or an alternation of literals.
+    ///
+    /// Callers can use this to select a different strategy when the full
+    /// regex engine isn't needed, e.g., plain substring search.
+    ///
+    /// Note that the properties reported by this method and the other
+    /// analysis methods below describe the regex that was actually compiled.
+    /// That means they take builder transformations into account. For
+    /// example, a literal pattern stops being a literal when `word` matching
+    /// is enabled.
+    pub fn is_literal(&self) -> bool {
+        self.matcher.chir().is_literal()
+    }
+
+    /// Returns true if every match of this regex is required to begin at the
+    /// start of the haystack. That is, the regex begins with `\A`.
+    pub fn is_anchored_start(&self) -> bool {
+        self.matcher.chir().is_anchored_start()
+    }
+
+    /// Returns true if every match of this regex is required to end at the
+    /// end of the haystack. That is, the regex ends with `\z`.
+    pub fn is_anchored_end(&self) -> bool {
+        self.matcher.chir().is_anchored_end()
+    }
+
+    /// Returns true if this regex can match the empty string.
+    pub fn can_match_empty(&self) -> bool {
+        self.matcher.chir().min_match_len() == Some(0)
+    }
+
+    /// Returns the minimum length, in bytes, of any match of this regex.
+    ///
+    /// When `None` is returned, the regex cannot match anything at all.
+    pub fn min_match_len(&self) -> Option<usize> {
+        self.matcher.chir().min_match_len()
+    }
+
+    /// Returns the set of bytes that are guaranteed to never occur in any
+    /// match of this regex.
+    ///
+    /// This is the same set reported by the `Matcher` trait's
+    /// `non_matching_bytes` method, exposed here as an inherent method so
+    /// that callers can inspect it, e.g., for debugging why a search did or
+    /// didn't use the fast line-oriented path.
+    pub fn non_matching_bytes(&self) -> &ByteSet {
+        &self.non_matching_bytes
+    }
+
+    /// Returns the index of the first pattern that matches within the given
+    /// span of the haystack.
+    ///
+    /// The index corresponds to the position of the pattern in the slice
+    /// given to `RegexMatcherBuilder::build_many`. This returns `None` when
+    /// pattern tracking was not enabled on the builder, or when none of the
+    /// patterns match within the span on their own (which can happen, e.g.,
+    /// when word matching is enabled and the pattern relies on the
+    /// surrounding context).
+    pub fn pattern_index(
+        &self,
+        haystack: &[u8],
+        span: Match,
+    ) -> Option<usize> {
+        if !self.config.pattern_indices {
+            return None;
+        }
+        if self.pattern_regexes.is_empty() {
+            // Pattern tracking was enabled, but only one pattern was given.
+            return Some(0);
+        }
+        if span.end() > haystack.len() {
+            return None;
+        }
+        let slice = &haystack[span];
+        self.pattern_regexes.iter().position(|re| re.is_match(slice))
+    }
+
+    /// Return the lazily built leftmost-longest variant of this regex,
+    /// building it on first use. `None` is returned if the build failed.
+    fn longest_regex(&self) -> Option<&Regex> {
+        self.longest_regex
+            .get_or_init(|| self.matcher.chir().to_regex_longest().ok())
+            .as_ref()
+    }
 }
 
 /// An encapsulation of the type of matcher we use in `RegexMatcher`.
@@ -403,6 +570,9 @@ enum RegexMatcherImpl {
     /// `Matcher` to encapsulate its use of capture groups to make them
     /// invisible to the caller.
     Word(WordMatcher),
+    /// A matcher that only reports matches whose end points fall on word
+    /// boundaries determined by Unicode text segmentation (UAX #29).
+    WordSegmentation(WordSegmentationMatcher),
 }
 
 impl RegexMatcherImpl {
@@ -412,7 +582,11 @@ impl RegexMatcherImpl {
         // When whole_line is set, we don't use a word matcher even if word
         // matching was requested. Why? Because `(?m:^)(pat)(?m:$)` implies
         // word matching.
-        Ok(if chir.config().word && !chir.config().whole_line {
+        Ok(if chir.config().word_segmentation && !chir.config().whole_line {
+            RegexMatcherImpl::WordSegmentation(WordSegmentationMatcher::new(
+                chir,
+            )?)
+        } else if chir.config().word && !chir.config().whole_line {
             RegexMatcherImpl::Word(WordMatcher::new(chir)?)
         } else {
             if chir.config().whole_line {
@@ -426,6 +600,7 @@ impl RegexMatcherImpl {
     fn regex(&self) -> &Regex {
         match *self {
             RegexMatcherImpl::Word(ref x) => x.regex(),
+            RegexMatcherImpl::WordSegmentation(ref x) => x.regex(),
             RegexMatcherImpl::Standard(ref x) => &x.regex,
         }
     }
@@ -434,6 +609,7 @@ impl RegexMatcherImpl {
     fn chir(&self) -> &ConfiguredHIR {
         match *self {
             RegexMatcherImpl::Word(ref x) => x.chir(),
+            RegexMatcherImpl::WordSegmentation(ref x) => x.chir(),
             RegexMatcherImpl::Standard(ref x) => &x.chir,
         }
     }
@@ -455,6 +631,103 @@ impl Matcher for RegexMatcher {
         match self.matcher {
             Standard(ref m) => m.find_at(haystack, at),
             Word(ref m) => m.find_at(haystack, at),
+            WordSegmentation(ref m) => m.find_at(haystack, at),
+        }
+    }
+
+    fn find_longest_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+    ) -> Result<Option<Match>, NoError> {
+        use self::RegexMatcherImpl::*;
+
+        let m = match self.find_at(haystack, at)? {
+            None => return Ok(None),
+            Some(m) => m,
+        };
+        // The word matcher reports its match spans via capture groups that
+        // the leftmost-longest variant of the regex cannot reproduce, so it
+        // keeps its leftmost-first spans.
+        if let Word(_) | WordSegmentation(_) = self.matcher {
+            return Ok(Some(m));
+        }
+        let re = match self.longest_regex() {
+            None => return Ok(Some(m)),
+            Some(re) => re,
+        };
+        // Leftmost-first and leftmost-longest matches always begin at the
+        // same position, so anchor the leftmost-longest search at the start
+        // we already found to extend the match to its longest end. (An
+        // unanchored search with `MatchKind::All` would not work, since the
+        // implicit unanchored prefix loses its priority too and the search
+        // reports the last possible match.)
+        let input = Input::new(haystack)
+            .anchored(Anchored::Yes)
+            .span(m.start()..haystack.len());
+        Ok(match re.find(input) {
+            None => Some(m),
+            Some(lm) => Some(Match::new(lm.start(), lm.end())),
+        })
+    }
+
+    fn find_last_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+    ) -> Result<Option<Match>, NoError> {
+        use bstr::ByteSlice;
+
+        // The initial size of the window scanned at the end of the haystack.
+        // If no match is found in it, the window is grown exponentially.
+        const INITIAL_CHUNK_SIZE: usize = 64 * (1 << 10);
+
+        // When a line terminator is configured, this matcher guarantees that
+        // no match contains it. This permits scanning backward a chunk at a
+        // time: a window whose start falls immediately after a line
+        // terminator sees exactly the set of matches that start at or after
+        // that position. So the last match in the right-most window
+        // containing a match is the last match overall. Without that
+        // guarantee, a match could straddle any window boundary we pick, so
+        // we have no choice but to scan forward through everything.
+        let lineterm = match self.config.line_terminator {
+            None => {
+                let mut last = None;
+                self.find_iter_at(haystack, at, |m| {
+                    last = Some(m);
+                    true
+                })?;
+                return Ok(last);
+            }
+            // For CRLF, `as_byte` returns `\n`, which is the byte that
+            // actually terminates lines.
+            Some(lineterm) => lineterm.as_byte(),
+        };
+        let mut size = INITIAL_CHUNK_SIZE;
+        let mut window = haystack.len();
+        loop {
+            let guess = window.saturating_sub(size);
+            let start = if guess <= at {
+                at
+            } else {
+                match haystack[at..guess].rfind_byte(lineterm) {
+                    Some(i) => at + i + 1,
+                    None => at,
+                }
+            };
+            let mut last = None;
+            self.find_iter_at(haystack, start, |m| {
+                last = Some(m);
+                true
+            })?;
+            if last.is_some() {
+                return Ok(last);
+            }
+            if start == at {
+                return Ok(None);
+            }
+            window = start;
+            size = size.saturating_mul(2);
         }
     }
 
@@ -463,6 +736,7 @@ impl Matcher for RegexMatcher {
         match self.matcher {
             Standard(ref m) => m.new_captures(),
             Word(ref m) => m.new_captures(),
+            WordSegmentation(ref m) => m.new_captures(),
         }
     }
 
@@ -471,6 +745,7 @@ impl Matcher for RegexMatcher {
         match self.matcher {
             Standard(ref m) => m.capture_count(),
             Word(ref m) => m.capture_count(),
+            WordSegmentation(ref m) => m.capture_count(),
         }
     }
 
@@ -479,6 +754,16 @@ impl Matcher for RegexMatcher {
         match self.matcher {
             Standard(ref m) => m.capture_index(name),
             Word(ref m) => m.capture_index(name),
+            WordSegmentation(ref m) => m.capture_index(name),
+        }
+    }
+
+    fn capture_name(&self, index: usize) -> Option<&str> {
+        use self::RegexMatcherImpl::*;
+        match self.matcher {
+            Standard(ref m) => m.capture_name(index),
+            Word(ref m) => m.capture_name(index),
+            WordSegmentation(ref m) => m.capture_name(index),
         }
     }
 
@@ -487,6 +772,16 @@ impl Matcher for RegexMatcher {
         match self.matcher {
             Standard(ref m) => m.find(haystack),
             Word(ref m) => m.find(haystack),
+            WordSegmentation(ref m) => m.find(haystack),
+        }
+    }
+
+    fn count_matches(&self, haystack: &[u8]) -> Result<u64, NoError> {
+        use self::RegexMatcherImpl::*;
+        match self.matcher {
+            Standard(ref m) => m.count_matches(haystack),
+            Word(ref m) => m.count_matches(haystack),
+            WordSegmentation(ref m) => m.count_matches(haystack),
         }
     }
 
@@ -498,6 +793,7 @@ impl Matcher for RegexMatcher {
         match self.matcher {
             Standard(ref m) => m.find_iter(haystack, matched),
             Word(ref m) => m.find_iter(haystack, matched),
+            WordSegmentation(ref m) => m.find_iter(haystack, matched),
         }
     }
 
@@ -513,6 +809,7 @@ impl Matcher for RegexMatcher {
         match self.matcher {
             Standard(ref m) => m.try_find_iter(haystack, matched),
             Word(ref m) => m.try_find_iter(haystack, matched),
+            WordSegmentation(ref m) => m.try_find_iter(haystack, matched),
         }
     }
 
@@ -525,6 +822,7 @@ impl Matcher for RegexMatcher {
         match self.matcher {
             Standard(ref m) => m.captures(haystack, caps),
             Word(ref m) => m.captures(haystack, caps),
+            WordSegmentation(ref m) => m.captures(haystack, caps),
         }
     }
 
@@ -541,6 +839,9 @@ impl Matcher for RegexMatcher {
         match self.matcher {
             Standard(ref m) => m.captures_iter(haystack, caps, matched),
             Word(ref m) => m.captures_iter(haystack, caps, matched),
+            WordSegmentation(ref m) => {
+                m.captures_iter(haystack, caps, matched)
+            }
         }
     }
 
@@ -557,6 +858,9 @@ impl Matcher for RegexMatcher {
         match self.matcher {
             Standard(ref m) => m.try_captures_iter(haystack, caps, matched),
             Word(ref m) => m.try_captures_iter(haystack, caps, matched),
+            WordSegmentation(ref m) => {
+                m.try_captures_iter(haystack, caps, matched)
+            }
         }
     }
 
@@ -570,6 +874,7 @@ impl Matcher for RegexMatcher {
         match self.matcher {
             Standard(ref m) => m.captures_at(haystack, at, caps),
             Word(ref m) => m.captures_at(haystack, at, caps),
+            WordSegmentation(ref m) => m.captures_at(haystack, at, caps),
         }
     }
 
@@ -586,6 +891,7 @@ impl Matcher for RegexMatcher {
         match self.matcher {
             Standard(ref m) => m.replace(haystack, dst, append),
             Word(ref m) => m.replace(haystack, dst, append),
+            WordSegmentation(ref m) => m.replace(haystack, dst, append),
         }
     }
 
@@ -607,6 +913,9 @@ impl Matcher for RegexMatcher {
             Word(ref m) => {
                 m.replace_with_captures(haystack, caps, dst, append)
             }
+            WordSegmentation(ref m) => {
+                m.replace_with_captures(haystack, caps, dst, append)
+            }
         }
     }
 
@@ -615,6 +924,7 @@ impl Matcher for RegexMatcher {
         match self.matcher {
             Standard(ref m) => m.is_match(haystack),
             Word(ref m) => m.is_match(haystack),
+            WordSegmentation(ref m) => m.is_match(haystack),
         }
     }
 
@@ -627,6 +937,7 @@ impl Matcher for RegexMatcher {
         match self.matcher {
             Standard(ref m) => m.is_match_at(haystack, at),
             Word(ref m) => m.is_match_at(haystack, at),
+            WordSegmentation(ref m) => m.is_match_at(haystack, at),
         }
     }
 
@@ -638,6 +949,7 @@ impl Matcher for RegexMatcher {
         match self.matcher {
             Standard(ref m) => m.shortest_match(haystack),
             Word(ref m) => m.shortest_match(haystack),
+            WordSegmentation(ref m) => m.shortest_match(haystack),
         }
     }
 
@@ -650,6 +962,7 @@ impl Matcher for RegexMatcher {
         match self.matcher {
             Standard(ref m) => m.shortest_match_at(haystack, at),
             Word(ref m) => m.shortest_match_at(haystack, at),
+            WordSegmentation(ref m) => m.shortest_match_at(haystack, at),
         }
     }
 
@@ -661,6 +974,14 @@ impl Matcher for RegexMatcher {
         self.config.line_terminator
     }
 
+    fn pattern_index(
+        &self,
+        haystack: &[u8],
+        span: Match,
+    ) -> Result<Option<usize>, NoError> {
+        Ok(RegexMatcher::pattern_index(self, haystack, span))
+    }
+
     fn find_candidate_line(
         &self,
         haystack: &[u8],
@@ -714,6 +1035,13 @@ impl Matcher for StandardMatcher {
         Ok(self.regex.find(input).map(|m| Match::new(m.start(), m.end())))
     }
 
+    fn count_matches(&self, haystack: &[u8]) -> Result<u64, NoError> {
+        // This avoids the closure indirection of the default implementation
+        // and gives the regex engine the chance to use its fastest available
+        // iteration strategy, since the match offsets are discarded.
+        Ok(self.regex.find_iter(haystack).count() as u64)
+    }
+
     fn new_captures(&self) -> Result<RegexCaptures, NoError> {
         Ok(RegexCaptures::new(self.regex.create_captures()))
     }
@@ -726,6 +1054,10 @@ impl Matcher for StandardMatcher {
         self.regex.group_info().to_index(PatternID::ZERO, name)
     }
 
+    fn capture_name(&self, index: usize) -> Option<&str> {
+        self.regex.group_info().to_name(PatternID::ZERO, index)
+    }
+
     fn try_find_iter<F, E>(
         &self,
         haystack: &[u8],
@@ -756,6 +1088,22 @@ impl Matcher for StandardMatcher {
         Ok(caps.is_match())
     }
 
+    fn is_match_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+    ) -> Result<bool, NoError> {
+        // We explicitly use the regex engine's `is_match` instead of relying
+        // on the default implementation via `shortest_match_at`. This permits
+        // the underlying meta regex to select a match-only engine (e.g., a
+        // DFA with "earliest" semantics) that never touches the capture
+        // machinery or does a reverse scan to find the match start. This
+        // matters for workloads like `--files-with-matches` and `-q` that
+        // only ever need a yes/no answer.
+        let input = Input::new(haystack).span(at..haystack.len());
+        Ok(self.regex.is_match(input))
+    }
+
     fn shortest_match_at(
         &self,
         haystack: &[u8],
@@ -813,6 +1161,33 @@ impl Captures for RegexCaptures {
 }
 
 impl RegexCaptures {
+    /// Returns the spans of every capturing group in this match as a single
+    /// allocation.
+    ///
+    /// The span at index `0` always corresponds to the entire match. Groups
+    /// that did not participate in the match are represented by `None`. This
+    /// is useful for callers, such as printers, that want to copy all capture
+    /// data in one pass instead of issuing a `get` call per group.
+    pub fn slots(&self) -> Vec<Option<Match>> {
+        (0..self.len()).map(|i| self.get(i)).collect()
+    }
+
+    /// Returns the spans of every capturing group along with their names.
+    ///
+    /// This is like [`RegexCaptures::slots`], except each span is paired
+    /// with the name of its corresponding capture group, if one exists.
+    /// Groups are returned in index order, so the `i`th element of the
+    /// returned `Vec` corresponds to the group at index `i`.
+    pub fn to_vec(&self) -> Vec<(Option<&str>, Option<Match>)> {
+        self.caps
+            .group_info()
+            .pattern_names(PatternID::ZERO)
+            .skip(self.offset)
+            .enumerate()
+            .map(|(i, name)| (name, self.get(i)))
+            .collect()
+    }
+
     pub(crate) fn new(caps: AutomataCaptures) -> RegexCaptures {
         RegexCaptures::with_offset(caps, 0)
     }
@@ -847,6 +1222,88 @@ mod tests {
         assert!(!matcher.is_match(b"abc -2 foo").unwrap());
     }
 
+    // Test that the builder's Unicode word segmentation option wires up the
+    // segmentation based matcher. The detailed semantics are tested in the
+    // word module.
+    #[test]
+    fn word_segmentation() {
+        let matcher = RegexMatcherBuilder::new()
+            .word_segmentation(true)
+            .build(r"can")
+            .unwrap();
+        assert!(matcher.is_match(b"one can jump").unwrap());
+        // `\b` considers the boundary before `'` a word boundary, but
+        // Unicode segmentation keeps the contraction together.
+        assert!(!matcher.is_match(b"one can't jump").unwrap());
+
+        let matcher =
+            RegexMatcherBuilder::new().word(true).build(r"can").unwrap();
+        assert!(matcher.is_match(b"one can't jump").unwrap());
+    }
+
+    // Test that find_longest_at reports leftmost-longest matches where the
+    // standard search reports leftmost-first matches.
+    #[test]
+    fn find_longest() {
+        let matcher =
+            RegexMatcherBuilder::new().build(r"sam|samwise").unwrap();
+        let hay = &b"xxx samwise xxx"[..];
+        let m = matcher.find(hay).unwrap().unwrap();
+        assert_eq!(b"sam", &hay[m]);
+        let m = matcher.find_longest_at(hay, 0).unwrap().unwrap();
+        assert_eq!(b"samwise", &hay[m]);
+
+        // The starting position participates as with find_at.
+        assert_eq!(None, matcher.find_longest_at(hay, 8).unwrap());
+
+        // The word matcher doesn't support leftmost-longest matches and
+        // falls back to leftmost-first.
+        let matcher = RegexMatcherBuilder::new()
+            .word(true)
+            .build(r"foo|foo-bar")
+            .unwrap();
+        let hay = &b"foo-bar"[..];
+        let m = matcher.find_longest_at(hay, 0).unwrap().unwrap();
+        assert_eq!(b"foo", &hay[m]);
+    }
+
+    // Test that pattern tracking attributes each match to the pattern that
+    // produced it.
+    #[test]
+    fn pattern_index() {
+        let matcher = RegexMatcherBuilder::new()
+            .pattern_indices(true)
+            .build_many(&[r"foo\w+", r"bar"])
+            .unwrap();
+        let hay = &b"xxx barfly fooble xxx"[..];
+        let m = matcher.find(hay).unwrap().unwrap();
+        assert_eq!(b"bar", &hay[m]);
+        assert_eq!(Some(1), matcher.pattern_index(hay, m));
+        let m = matcher.find_at(hay, m.end()).unwrap().unwrap();
+        assert_eq!(b"fooble", &hay[m]);
+        assert_eq!(Some(0), matcher.pattern_index(hay, m));
+
+        // Without tracking enabled, no attribution is made.
+        let matcher = RegexMatcherBuilder::new()
+            .build_many(&[r"foo\w+", r"bar"])
+            .unwrap();
+        let m = matcher.find(hay).unwrap().unwrap();
+        assert_eq!(None, matcher.pattern_index(hay, m));
+    }
+
+    // Test that counting matches agrees with iterating over them, for both
+    // the standard fast path and the word matcher's default path.
+    #[test]
+    fn count_matches() {
+        let matcher = RegexMatcherBuilder::new().build(r"\w+").unwrap();
+        assert_eq!(4, matcher.count_matches(b"aa bb cc dd").unwrap());
+        assert_eq!(0, matcher.count_matches(b"  ").unwrap());
+
+        let matcher =
+            RegexMatcherBuilder::new().word(true).build(r"aa").unwrap();
+        assert_eq!(1, matcher.count_matches(b"aa aaa aa4").unwrap());
+    }
+
     // Test that enabling a line terminator prevents it from matching through
     // said line terminator.
     #[test]
@@ -899,6 +1356,98 @@ mod tests {
         assert!(matcher.is_match(b"abc\r\n").unwrap());
     }
 
+    // Test the pattern analysis methods.
+    #[test]
+    fn analysis() {
+        let matcher = RegexMatcherBuilder::new().build(r"foo").unwrap();
+        assert!(matcher.is_literal());
+        assert!(!matcher.is_anchored_start());
+        assert!(!matcher.is_anchored_end());
+        assert!(!matcher.can_match_empty());
+        assert_eq!(Some(3), matcher.min_match_len());
+
+        let matcher =
+            RegexMatcherBuilder::new().build_many(&["foo", "quux"]).unwrap();
+        assert!(matcher.is_literal());
+        assert_eq!(Some(3), matcher.min_match_len());
+
+        let matcher = RegexMatcherBuilder::new().build(r"\Aa*\z").unwrap();
+        assert!(!matcher.is_literal());
+        assert!(matcher.is_anchored_start());
+        assert!(matcher.is_anchored_end());
+        assert!(matcher.can_match_empty());
+        assert_eq!(Some(0), matcher.min_match_len());
+
+        // Builder transformations change the compiled regex, and the
+        // analysis reflects that.
+        let matcher =
+            RegexMatcherBuilder::new().word(true).build(r"foo").unwrap();
+        assert!(!matcher.is_literal());
+    }
+
+    // Test that find_last reports the final match, with and without the
+    // chunked reverse scan that a line terminator makes possible.
+    #[test]
+    fn find_last() {
+        use grep_matcher::Match;
+
+        // No line terminator: forward scan fallback.
+        let matcher = RegexMatcherBuilder::new().build(r"\w+").unwrap();
+        let m = matcher.find_last(b"foo bar baz").unwrap();
+        assert_eq!(m, Some(Match::new(8, 11)));
+        assert_eq!(None, matcher.find_last(b"!!!").unwrap());
+
+        // With a line terminator: chunked reverse scan.
+        let matcher = RegexMatcherBuilder::new()
+            .line_terminator(Some(b'\n'))
+            .build(r"ba\w")
+            .unwrap();
+        let hay = b"bar one\nbaz two\nnothing here\n";
+        assert_eq!(Some(Match::new(8, 11)), matcher.find_last(hay).unwrap());
+        assert_eq!(None, matcher.find_last(b"one\ntwo\n").unwrap());
+
+        // A big haystack whose only match falls outside the initial chunk.
+        let mut hay = b"bar\n".to_vec();
+        hay.extend(std::iter::repeat(b'\n').take(1 << 20));
+        assert_eq!(Some(Match::new(0, 3)), matcher.find_last(&hay).unwrap());
+    }
+
+    // Test that all capture spans can be retrieved in one pass, with group
+    // names alongside.
+    #[test]
+    fn captures_slots() {
+        use grep_matcher::{Captures, Match};
+
+        let matcher =
+            RegexMatcherBuilder::new().build(r"(?P<a>\w+) (\d+)").unwrap();
+        let mut caps = matcher.new_captures().unwrap();
+        assert!(matcher.captures(b"foo 123", &mut caps).unwrap());
+
+        let slots = caps.slots();
+        assert_eq!(slots.len(), caps.len());
+        assert_eq!(slots[0], Some(Match::new(0, 7)));
+        assert_eq!(slots[1], Some(Match::new(0, 3)));
+        assert_eq!(slots[2], Some(Match::new(4, 7)));
+
+        let all = caps.to_vec();
+        assert_eq!(all[0], (None, Some(Match::new(0, 7))));
+        assert_eq!(all[1], (Some("a"), Some(Match::new(0, 3))));
+        assert_eq!(all[2], (None, Some(Match::new(4, 7))));
+    }
+
+    // Test that ASCII mode restricts Unicode-aware classes to their ASCII
+    // definitions.
+    #[test]
+    fn ascii() {
+        let matcher = RegexMatcherBuilder::new().build(r"\w").unwrap();
+        assert!(matcher.is_match("δ".as_bytes()).unwrap());
+
+        let matcher =
+            RegexMatcherBuilder::new().ascii(true).build(r"\w").unwrap();
+        assert!(!matcher.is_match("δ".as_bytes()).unwrap());
+        assert!(matcher.is_match(b"a").unwrap());
+    }
+
     // Test that smart case works.
     #[test]
     fn case_smart() {
@@ -16,6 +16,13 @@ pub(crate) fn non_matching_bytes(expr: &Hir) -> ByteSet {
 /// Remove any bytes from the given set that can occur in a matched produced by
 /// the given expression.
 fn remove_matching_bytes(expr: &Hir, set: &mut ByteSet) {
+    // An expression that cannot match anything at all contributes no bytes
+    // to any match. This matters for alternations: a branch containing,
+    // e.g., an empty class can simply be skipped rather than poisoning the
+    // set with bytes from its other sub-expressions.
+    if cannot_match(expr) {
+        return;
+    }
     match *expr.kind() {
         HirKind::Empty
         | HirKind::Look(Look::WordAscii | Look::WordAsciiNegate)
@@ -55,7 +62,11 @@ fn remove_matching_bytes(expr: &Hir, set: &mut ByteSet) {
             }
         }
         HirKind::Repetition(ref x) => {
-            remove_matching_bytes(&x.sub, set);
+            // A repetition bounded at zero matches only the empty string, so
+            // the bytes of its sub-expression can never appear in a match.
+            if x.max != Some(0) {
+                remove_matching_bytes(&x.sub, set);
+            }
         }
         HirKind::Capture(ref x) => {
             remove_matching_bytes(&x.sub, set);
@@ -73,6 +84,23 @@ fn remove_matching_bytes(expr: &Hir, set: &mut ByteSet) {
     }
 }
 
+/// Returns true when the given expression is guaranteed to never match
+/// anything at all. This is a conservative syntactic check: a `false` return
+/// does not imply that the expression can match.
+fn cannot_match(expr: &Hir) -> bool {
+    match *expr.kind() {
+        HirKind::Class(hir::Class::Unicode(ref cls)) => {
+            cls.ranges().is_empty()
+        }
+        HirKind::Class(hir::Class::Bytes(ref cls)) => cls.ranges().is_empty(),
+        HirKind::Capture(ref x) => cannot_match(&x.sub),
+        HirKind::Repetition(ref x) => x.min > 0 && cannot_match(&x.sub),
+        HirKind::Concat(ref xs) => xs.iter().any(cannot_match),
+        HirKind::Alternation(ref xs) => xs.iter().all(cannot_match),
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use {grep_matcher::ByteSet, regex_syntax::ParserBuilder};
@@ -138,6 +166,26 @@ mod tests {
         assert_eq!(sparse(&extract(r"(?-u)\xFF")), sparse_except(&[0xFF]));
     }
 
+    #[test]
+    fn repetition() {
+        // A repetition bounded at zero can never contribute bytes to a
+        // match.
+        assert_eq!(sparse(&extract("ab{0}")), sparse_except(&[b'a']));
+        assert_eq!(sparse(&extract("ab{0,0}")), sparse_except(&[b'a']));
+        assert_eq!(sparse(&extract("ab{0,1}")), sparse_except(&[b'a', b'b']));
+        assert_eq!(sparse(&extract("ab*")), sparse_except(&[b'a', b'b']));
+    }
+
+    #[test]
+    fn alternation() {
+        assert_eq!(sparse(&extract("a|b")), sparse_except(&[b'a', b'b']));
+        // A branch that can't match anything contributes nothing.
+        assert_eq!(
+            sparse(&extract(r"a|(?-u:b[^\x00-\xFF])")),
+            sparse_except(&[b'a'])
+        );
+    }
+
     #[test]
     fn anchor() {
         // FIXME: The first four tests below should correspond to a full set
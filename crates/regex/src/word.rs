@@ -7,8 +7,8 @@ use std::{
 use {
     grep_matcher::{Match, Matcher, NoError},
     regex_automata::{
-        meta::Regex, util::captures::Captures, util::pool::Pool, Input,
-        PatternID,
+        meta::Regex, util::captures::Captures, util::pool::Pool, Anchored,
+        Input, PatternID,
     },
 };
 
@@ -211,6 +211,13 @@ impl Matcher for WordMatcher {
         self.names.get(name).map(|i| *i)
     }
 
+    fn capture_name(&self, index: usize) -> Option<&str> {
+        // Accounts for the `(re)` wrapping group, which hides the fact
+        // that the caller's groups are all shifted up by one.
+        let actual = index.checked_add(1)?;
+        self.regex.group_info().to_name(PatternID::ZERO, actual)
+    }
+
     fn captures_at(
         &self,
         haystack: &[u8],
@@ -223,14 +230,183 @@ impl Matcher for WordMatcher {
         Ok(caps.is_match())
     }
 
+    fn is_match_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+    ) -> Result<bool, NoError> {
+        // A match of the word-wrapped regex implies a word match of the
+        // original regex, so there is no need to resolve the offsets of the
+        // inner group here. That means we can use the regex engine's
+        // match-only search path and completely avoid both `fast_find` and
+        // the capture machinery.
+        let input = Input::new(haystack).span(at..haystack.len());
+        Ok(self.regex.is_match(input))
+    }
+
+    fn shortest_match_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+    ) -> Result<Option<usize>, NoError> {
+        // Like `is_match_at`, we search with the word-wrapped regex without
+        // extracting the inner group. The offset reported may therefore
+        // include the trailing non-word boundary character, but that's fine:
+        // `shortest_match` only guarantees an offset at which a match is
+        // known to exist, not the precise end of the proper match.
+        let input = Input::new(haystack).span(at..haystack.len());
+        Ok(self.regex.search_half(&input).map(|hm| hm.offset()))
+    }
+
     // We specifically do not implement other methods like find_iter or
     // captures_iter. Namely, the iter methods are guaranteed to be correct
     // by virtue of implementing find_at and captures_at above.
 }
 
+/// A matcher for "word match" semantics based on Unicode text segmentation.
+///
+/// While `WordMatcher` surrounds the pattern with `\W` classes, this matcher
+/// runs the original pattern unaltered and only reports matches whose start
+/// and end both fall on word boundaries, as determined by the UAX #29
+/// segmentation rules implemented in `bstr`. This matches whole words in
+/// text where `\w`/`\W` draw the wrong boundaries, e.g., around apostrophes
+/// in contractions like `can't`.
+#[derive(Clone, Debug)]
+pub(crate) struct WordSegmentationMatcher {
+    /// The regex compiled from the original pattern given by the caller.
+    regex: Regex,
+    /// The HIR that produced the regex above.
+    ///
+    /// We put this in an `Arc` because by the time it gets here, it won't
+    /// change. And because cloning and dropping an `Hir` is somewhat
+    /// expensive due to its deep recursive representation.
+    chir: Arc<ConfiguredHIR>,
+}
+
+impl WordSegmentationMatcher {
+    /// Create a new matcher from the given pattern that only produces
+    /// matches whose boundaries coincide with UAX #29 word boundaries.
+    pub(crate) fn new(
+        chir: ConfiguredHIR,
+    ) -> Result<WordSegmentationMatcher, Error> {
+        let chir = Arc::new(chir);
+        let regex = chir.to_regex()?;
+        Ok(WordSegmentationMatcher { regex, chir })
+    }
+
+    /// Return the underlying regex used by this matcher.
+    pub(crate) fn regex(&self) -> &Regex {
+        &self.regex
+    }
+
+    /// Return the underlying HIR for the regex used by this matcher.
+    pub(crate) fn chir(&self) -> &ConfiguredHIR {
+        &self.chir
+    }
+
+    /// Returns true when both end points of the given match fall on word
+    /// boundaries determined by Unicode text segmentation.
+    fn is_word_aligned(&self, haystack: &[u8], m: &Match) -> bool {
+        use bstr::ByteSlice;
+
+        // Segmentation boundaries never cross a line terminator, so only
+        // the lines containing the match need to be segmented.
+        let start =
+            haystack[..m.start()].rfind_byte(b'\n').map_or(0, |i| i + 1);
+        let end = haystack[m.end()..]
+            .find_byte(b'\n')
+            .map_or(haystack.len(), |i| m.end() + i);
+        let (target_start, target_end) = (m.start() - start, m.end() - start);
+        let mut start_aligned = target_start == 0;
+        let mut end_aligned = target_end == 0;
+        for (s, e, _) in haystack[start..end].words_with_break_indices() {
+            start_aligned = start_aligned || s == target_start;
+            end_aligned = end_aligned || e == target_end;
+            if e > target_end {
+                break;
+            }
+        }
+        start_aligned && end_aligned
+    }
+}
+
+impl Matcher for WordSegmentationMatcher {
+    type Captures = RegexCaptures;
+    type Error = NoError;
+
+    fn find_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+    ) -> Result<Option<Match>, NoError> {
+        // Look for candidate matches of the original pattern and keep the
+        // first one whose boundaries are word aligned. Advancing by one byte
+        // after a misaligned candidate is always correct, if not optimal,
+        // since a later match could begin inside the candidate.
+        let mut at = at;
+        loop {
+            let input = Input::new(haystack).span(at..haystack.len());
+            let m = match self.regex.find(input) {
+                None => return Ok(None),
+                Some(m) => Match::new(m.start(), m.end()),
+            };
+            if self.is_word_aligned(haystack, &m) {
+                return Ok(Some(m));
+            }
+            // The end of the haystack is always word aligned, so a
+            // misaligned match must end before it and there is room to
+            // advance.
+            at = m.start() + 1;
+        }
+    }
+
+    fn new_captures(&self) -> Result<RegexCaptures, NoError> {
+        Ok(RegexCaptures::new(self.regex.create_captures()))
+    }
+
+    fn capture_count(&self) -> usize {
+        self.regex.captures_len()
+    }
+
+    fn capture_index(&self, name: &str) -> Option<usize> {
+        self.regex.group_info().to_index(PatternID::ZERO, name)
+    }
+
+    fn capture_name(&self, index: usize) -> Option<&str> {
+        self.regex.group_info().to_name(PatternID::ZERO, index)
+    }
+
+    fn captures_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+        caps: &mut RegexCaptures,
+    ) -> Result<bool, NoError> {
+        // Find a word aligned match first and then re-run the capture
+        // machinery anchored at its start. A leftmost-first search from the
+        // same start always reproduces the same overall match.
+        let m = match self.find_at(haystack, at)? {
+            None => return Ok(false),
+            Some(m) => m,
+        };
+        let input = Input::new(haystack)
+            .anchored(Anchored::Yes)
+            .span(m.start()..haystack.len());
+        let caps = caps.captures_mut();
+        self.regex.search_captures(&input, caps);
+        Ok(caps.is_match())
+    }
+
+    // We do not implement the iterator methods, since the implementations
+    // derived from find_at and captures_at above are guaranteed to be
+    // correct. Similarly, is_match_at and shortest_match_at fall back to
+    // find_at: a match only counts when it is word aligned, which requires
+    // resolving its full extent anyway.
+}
+
 #[cfg(test)]
 mod tests {
-    use super::WordMatcher;
+    use super::{WordMatcher, WordSegmentationMatcher};
     use crate::config::Config;
     use grep_matcher::{Captures, Match, Matcher};
 
@@ -239,6 +415,18 @@ mod tests {
         WordMatcher::new(chir).unwrap()
     }
 
+    fn seg_matcher(pattern: &str) -> WordSegmentationMatcher {
+        let chir = Config::default().build_many(&[pattern]).unwrap();
+        WordSegmentationMatcher::new(chir).unwrap()
+    }
+
+    fn seg_find(pattern: &str, haystack: &str) -> Option<(usize, usize)> {
+        seg_matcher(pattern)
+            .find(haystack.as_bytes())
+            .unwrap()
+            .map(|m| (m.start(), m.end()))
+    }
+
     fn find(pattern: &str, haystack: &str) -> Option<(usize, usize)> {
         matcher(pattern)
             .find(haystack.as_bytes())
@@ -296,6 +484,35 @@ mod tests {
         assert_eq!(Some((0, 2)), find(r"-2", "-2"));
     }
 
+    // Test that Unicode word segmentation reports matches aligned with
+    // UAX #29 boundaries, and only those.
+    #[test]
+    fn various_segmentation() {
+        assert_eq!(Some((0, 3)), seg_find(r"foo", "foo"));
+        assert_eq!(Some((0, 3)), seg_find(r"foo", "foo("));
+        assert_eq!(Some((1, 4)), seg_find(r"foo", "!foo("));
+        assert_eq!(None, seg_find(r"foo", "!afoo("));
+        assert_eq!(None, seg_find(r"foo", "foo5"));
+        assert_eq!(Some((5, 8)), seg_find(r"foo", "foo5 foo"));
+        assert_eq!(Some((10, 13)), seg_find(r"foo", "foo5\nfoo5\nfoo"));
+
+        // Segmentation does not split a word at a non-ASCII letter the way
+        // the \w/\W classes do not either...
+        assert_eq!(None, seg_find(r"foo", "fooб"));
+        // ... but unlike \W wrapping, it keeps contractions and numbers
+        // like `32.3` together as single words.
+        assert_eq!(None, seg_find(r"can", "can't jump"));
+        assert_eq!(Some((0, 5)), seg_find(r"can't", "can't jump"));
+        assert_eq!(None, seg_find(r"32", "jump 32.3 feet"));
+        assert_eq!(Some((5, 9)), seg_find(r"[\d.]+", "jump 32.3 feet"));
+
+        // Punctuation segments on its own, so adjacent punctuation is fair
+        // game, just as it is for the \W based word matcher.
+        assert_eq!(Some((4, 6)), seg_find(r"-2", "abc -2 foo"));
+        assert_eq!(Some((1, 4)), seg_find(r"foo", "!foo!"));
+        assert_eq!(Some((1, 5)), seg_find(r"foo!", "!foo!"));
+    }
+
     // Test that the captures API also reports offsets correctly, just as
     // find does. This exercises a different path in the code since captures
     // are handled differently.
@@ -0,0 +1,42 @@
+/*!
+Detection of compressed files by sniffing their magic bytes.
+
+This module is only available when the `decompress` feature is enabled. It
+exists to support transparent decompression in
+[`Searcher::search_path_decompress`](crate::Searcher::search_path_decompress).
+*/
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// The compression formats that can be transparently decoded.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Compression {
+    /// The gzip format, including multi-member streams.
+    Gzip,
+    /// The Zstandard format.
+    Zstd,
+}
+
+/// Sniff the magic bytes at the beginning of the given file and return the
+/// compression format detected, if any.
+///
+/// The file's position is rewound to the start before returning, so the
+/// caller may read the file from the beginning regardless of the result.
+pub(crate) fn sniff(file: &mut File) -> io::Result<Option<Compression>> {
+    let mut magic = [0u8; 4];
+    let mut len = 0;
+    while len < magic.len() {
+        let n = file.read(&mut magic[len..])?;
+        if n == 0 {
+            break;
+        }
+        len += n;
+    }
+    file.seek(SeekFrom::Start(0))?;
+    Ok(match magic[..len] {
+        [0x1F, 0x8B, ..] => Some(Compression::Gzip),
+        [0x28, 0xB5, 0x2F, 0xFD] => Some(Compression::Zstd),
+        _ => None,
+    })
+}
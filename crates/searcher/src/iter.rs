@@ -0,0 +1,331 @@
+/*!
+Provides an iterator adapter over the results of a search.
+
+The `Sink` trait is push-based: a `Searcher` drives the search and calls
+into the sink for each result. This module inverts that control with
+[`SearchIter`](struct.SearchIter.html), which runs a search on a background
+thread and yields its results as a plain `Iterator` of
+[`SearchEvent`](enum.SearchEvent.html)s, so that search results can be
+consumed with a `for` loop or fed into pipelines that expect a pull-based
+source.
+*/
+
+use std::io;
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+
+use grep_matcher::Matcher;
+
+use crate::searcher::Searcher;
+use crate::sink::{
+    Sink, SinkContext, SinkContextKind, SinkError, SinkFinish, SinkMatch,
+};
+
+/// The number of events buffered between the search thread and the
+/// iterator before the search blocks waiting for the consumer.
+const EVENT_BUFFER: usize = 128;
+
+/// A single event produced by a search, with its data copied out of the
+/// searcher's internal buffers.
+#[derive(Clone, Debug)]
+pub enum SearchEvent {
+    /// A matching line (or lines, for multi-line searches).
+    Match {
+        /// The 1-based line number of the first line in this match, if
+        /// line numbers were enabled.
+        line_number: Option<u64>,
+        /// The absolute byte offset of the start of this match.
+        absolute_byte_offset: u64,
+        /// The matched bytes, including the line terminator.
+        bytes: Vec<u8>,
+    },
+    /// A contextual line near a match.
+    Context {
+        /// The type of context reported.
+        kind: SinkContextKind,
+        /// The 1-based line number of this line, if line numbers were
+        /// enabled.
+        line_number: Option<u64>,
+        /// The absolute byte offset of the start of this line.
+        absolute_byte_offset: u64,
+        /// The contextual bytes, including the line terminator.
+        bytes: Vec<u8>,
+    },
+    /// The search finished. This is always the last event yielded, unless
+    /// the search was stopped early or returned an error.
+    Finish(SinkFinish),
+}
+
+/// An iterator over the results of a search running on another thread.
+///
+/// The search starts when the iterator is created and runs ahead of the
+/// consumer by a bounded number of events. If the iterator is dropped
+/// before the search completes, the search stops at its next event.
+///
+/// Any error reported by the search is yielded as the final item of the
+/// iterator.
+///
+/// # Example
+///
+/// ```
+/// use grep_regex::RegexMatcher;
+/// use grep_searcher::iter::{SearchEvent, SearchIter};
+/// use grep_searcher::Searcher;
+///
+/// # fn main() { example().unwrap(); }
+/// fn example() -> Result<(), std::io::Error> {
+///     let matcher = RegexMatcher::new("Watson").unwrap();
+///     let haystack = b"Holmes\nDoctor Watson\n".to_vec();
+///     let iter = SearchIter::search_slice(
+///         Searcher::new(),
+///         matcher,
+///         haystack,
+///     );
+///     for result in iter {
+///         match result? {
+///             SearchEvent::Match { line_number, .. } => {
+///                 assert_eq!(Some(2), line_number);
+///             }
+///             SearchEvent::Context { .. } => unreachable!(),
+///             SearchEvent::Finish(_) => {}
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SearchIter {
+    rx: Option<mpsc::Receiver<SearchEvent>>,
+    handle: Option<thread::JoinHandle<Result<(), io::Error>>>,
+}
+
+impl SearchIter {
+    /// Start searching the file at the given path, yielding its results
+    /// as an iterator.
+    ///
+    /// Errors opening or reading the path are yielded as the final item
+    /// of the iterator.
+    pub fn search_path<M, P>(
+        searcher: Searcher,
+        matcher: M,
+        path: P,
+    ) -> SearchIter
+    where
+        M: Matcher + Send + 'static,
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+        SearchIter::spawn(move |sink| {
+            let mut searcher = searcher;
+            searcher.search_path(&matcher, &path, sink)
+        })
+    }
+
+    /// Start searching the given reader, yielding its results as an
+    /// iterator.
+    pub fn search_reader<M, R>(
+        searcher: Searcher,
+        matcher: M,
+        read_from: R,
+    ) -> SearchIter
+    where
+        M: Matcher + Send + 'static,
+        R: io::Read + Send + 'static,
+    {
+        SearchIter::spawn(move |sink| {
+            let mut searcher = searcher;
+            searcher.search_reader(&matcher, read_from, sink)
+        })
+    }
+
+    /// Start searching the given bytes, yielding the results as an
+    /// iterator.
+    ///
+    /// The bytes are owned so that the search can run on another thread;
+    /// callers with a borrowed slice can copy it with `to_vec`.
+    pub fn search_slice<M>(
+        searcher: Searcher,
+        matcher: M,
+        slice: Vec<u8>,
+    ) -> SearchIter
+    where
+        M: Matcher + Send + 'static,
+    {
+        SearchIter::spawn(move |sink| {
+            let mut searcher = searcher;
+            searcher.search_slice(&matcher, &slice, sink)
+        })
+    }
+
+    /// Spawn the search thread, connecting it to a new iterator with a
+    /// bounded channel.
+    fn spawn<F>(search: F) -> SearchIter
+    where
+        F: FnOnce(ChannelSink) -> Result<(), io::Error> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::sync_channel(EVENT_BUFFER);
+        let handle = thread::spawn(move || search(ChannelSink { tx }));
+        SearchIter { rx: Some(rx), handle: Some(handle) }
+    }
+}
+
+impl Iterator for SearchIter {
+    type Item = Result<SearchEvent, io::Error>;
+
+    fn next(&mut self) -> Option<Result<SearchEvent, io::Error>> {
+        if let Some(ref rx) = self.rx {
+            if let Ok(event) = rx.recv() {
+                return Some(Ok(event));
+            }
+        }
+        // The channel is closed, so the search is over. Join the thread
+        // and yield its error, if any, as the final item.
+        self.rx = None;
+        match self.handle.take() {
+            None => None,
+            Some(handle) => match handle.join() {
+                Ok(Ok(())) => None,
+                Ok(Err(err)) => Some(Err(err)),
+                Err(_) => Some(Err(io::Error::error_message(
+                    "search thread panicked",
+                ))),
+            },
+        }
+    }
+}
+
+impl Drop for SearchIter {
+    fn drop(&mut self) {
+        // Disconnect the channel so that the search stops at its next
+        // event, then wait for the thread to wind down.
+        self.rx = None;
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// A sink that forwards each result, copied into an owned event, over a
+/// channel.
+#[derive(Debug)]
+struct ChannelSink {
+    tx: mpsc::SyncSender<SearchEvent>,
+}
+
+impl ChannelSink {
+    /// Send the given event, returning false if the receiving iterator
+    /// has been dropped and the search should stop.
+    fn send(&self, event: SearchEvent) -> bool {
+        self.tx.send(event).is_ok()
+    }
+}
+
+impl Sink for ChannelSink {
+    type Error = io::Error;
+
+    fn matched(
+        &mut self,
+        _searcher: &Searcher,
+        mat: &SinkMatch<'_>,
+    ) -> Result<bool, io::Error> {
+        Ok(self.send(SearchEvent::Match {
+            line_number: mat.line_number(),
+            absolute_byte_offset: mat.absolute_byte_offset(),
+            bytes: mat.bytes().to_vec(),
+        }))
+    }
+
+    fn context(
+        &mut self,
+        _searcher: &Searcher,
+        ctx: &SinkContext<'_>,
+    ) -> Result<bool, io::Error> {
+        Ok(self.send(SearchEvent::Context {
+            kind: ctx.kind().clone(),
+            line_number: ctx.line_number(),
+            absolute_byte_offset: ctx.absolute_byte_offset(),
+            bytes: ctx.bytes().to_vec(),
+        }))
+    }
+
+    fn finish(
+        &mut self,
+        _searcher: &Searcher,
+        finish: &SinkFinish,
+    ) -> Result<(), io::Error> {
+        self.send(SearchEvent::Finish(finish.clone()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use grep_regex::RegexMatcher;
+
+    use super::*;
+    use crate::searcher::SearcherBuilder;
+
+    const SHERLOCK: &'static str = "\
+For the Doctor Watsons of this world, as opposed to the Sherlock
+Holmeses, success in the province of detective work must always
+be, to a very large extent, the result of luck. Sherlock Holmes
+can extract a clew from a wisp of straw or a flake of cigar ash;
+but Doctor Watson has to have it taken out for him and dusted,
+and exhibited clearly, with a label attached.
+";
+
+    #[test]
+    fn events_in_order() {
+        let matcher = RegexMatcher::new("Sherlock").unwrap();
+        let searcher = SearcherBuilder::new().after_context(1).build();
+        let iter = SearchIter::search_slice(
+            searcher,
+            matcher,
+            SHERLOCK.as_bytes().to_vec(),
+        );
+        let events: Vec<SearchEvent> =
+            iter.map(|result| result.unwrap()).collect();
+        assert_eq!(5, events.len());
+        let lines: Vec<Option<u64>> = events
+            .iter()
+            .map(|event| match *event {
+                SearchEvent::Match { line_number, .. } => line_number,
+                SearchEvent::Context { line_number, .. } => line_number,
+                SearchEvent::Finish(_) => None,
+            })
+            .collect();
+        assert_eq!(vec![Some(1), Some(2), Some(3), Some(4), None], lines,);
+        match events[4] {
+            SearchEvent::Finish(ref finish) => {
+                assert_eq!(SHERLOCK.len() as u64, finish.byte_count());
+            }
+            ref event => panic!("unexpected event: {:?}", event),
+        }
+    }
+
+    #[test]
+    fn drop_stops_search() {
+        let matcher = RegexMatcher::new("o").unwrap();
+        // A haystack with far more matches than the event buffer holds.
+        let haystack = "hello world\n".repeat(10_000).into_bytes();
+        let mut iter =
+            SearchIter::search_slice(Searcher::new(), matcher, haystack);
+        assert!(iter.next().is_some());
+        // Dropping the iterator must disconnect the search thread rather
+        // than deadlock waiting for the channel to drain.
+        drop(iter);
+    }
+
+    #[test]
+    fn path_error_is_last() {
+        let matcher = RegexMatcher::new("x").unwrap();
+        let mut iter = SearchIter::search_path(
+            Searcher::new(),
+            matcher,
+            "/does/not/exist",
+        );
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+}
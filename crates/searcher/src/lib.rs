@@ -97,8 +97,8 @@ searches stdin.
 
 pub use crate::lines::{LineIter, LineStep};
 pub use crate::searcher::{
-    BinaryDetection, ConfigError, Encoding, MmapChoice, Searcher,
-    SearcherBuilder,
+    BinaryDetection, CancellationToken, ConfigError, ContextStart, Encoding,
+    MmapAdvice, MmapChoice, Searcher, SearcherBuilder,
 };
 pub use crate::sink::sinks;
 pub use crate::sink::{
@@ -108,6 +108,9 @@ pub use crate::sink::{
 #[macro_use]
 mod macros;
 
+#[cfg(feature = "decompress")]
+mod decompress;
+pub mod iter;
 mod line_buffer;
 mod lines;
 mod searcher;
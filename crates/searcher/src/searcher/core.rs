@@ -30,8 +30,11 @@ pub struct Core<'s, M: 's, S> {
     last_line_counted: usize,
     last_line_visited: usize,
     after_context_left: usize,
+    after_context_active: bool,
+    partial_context_floor: usize,
     has_sunk: bool,
     has_matched: bool,
+    gap_lines: usize,
 }
 
 impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
@@ -56,8 +59,11 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
             last_line_counted: 0,
             last_line_visited: 0,
             after_context_left: 0,
+            after_context_active: false,
+            partial_context_floor: 0,
             has_sunk: false,
             has_matched: false,
+            gap_lines: 0,
         };
         if !core.searcher.multi_line_with_matcher(&core.matcher) {
             if core.is_line_by_line_fast() {
@@ -89,8 +95,9 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
         &mut self,
         buf: &[u8],
         range: &Range,
+        span: Option<&Range>,
     ) -> Result<bool, S::Error> {
-        self.sink_matched(buf, range)
+        self.sink_matched(buf, range, span)
     }
 
     pub fn binary_data(
@@ -128,7 +135,7 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
     }
 
     pub fn roll(&mut self, buf: &[u8]) -> usize {
-        let consumed = if self.config.max_context() == 0 {
+        let mut consumed = if self.config.max_context() == 0 {
             buf.len()
         } else {
             // It might seem like all we need to care about here is just
@@ -144,9 +151,20 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
             let consumed = cmp::max(context_start, self.last_line_visited);
             consumed
         };
+        if self.config.before_context_bytes > 0 {
+            // Retain enough of the buffer to report partial "before"
+            // context for a match at the start of the next fill.
+            consumed = cmp::min(
+                consumed,
+                buf.len().saturating_sub(self.config.before_context_bytes),
+            );
+        }
         self.count_lines(buf, consumed);
         self.absolute_byte_offset += consumed as u64;
         self.last_line_counted = 0;
+        self.partial_context_floor =
+            cmp::max(self.partial_context_floor, self.last_line_visited)
+                .saturating_sub(consumed);
         self.last_line_visited = 0;
         self.set_pos(buf.len() - consumed);
         consumed
@@ -167,6 +185,10 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
         };
         if let Some(i) = buf[*range].find_byte(binary_byte) {
             let offset = range.start() + i;
+            log::trace!(
+                "searcher core: binary data found at offset {}",
+                offset
+            );
             self.binary_byte_offset = Some(offset);
             if !self.binary_data(offset as u64)? {
                 return Ok(true);
@@ -182,19 +204,45 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
         buf: &[u8],
         upto: usize,
     ) -> Result<bool, S::Error> {
-        if self.config.before_context == 0 {
+        if self.config.before_context == 0
+            && self.config.context_start.is_none()
+        {
             return Ok(true);
         }
         let range = Range::new(self.last_line_visited, upto);
         if range.is_empty() {
             return Ok(true);
         }
-        let before_context_start = range.start()
-            + lines::preceding(
-                &buf[range],
+        let mut before_context_start = range.end();
+        if self.config.before_context > 0 {
+            before_context_start = range.start()
+                + lines::preceding(
+                    &buf[range],
+                    self.config.line_term.as_byte(),
+                    self.config.before_context - 1,
+                );
+        }
+        if let Some(ref context_start) = self.config.context_start {
+            // Extend the context up to the closest preceding line that
+            // starts a context block, or to the last line visited when no
+            // such line exists.
+            let mut block_start = range.start();
+            let mut stepper = LineStep::new(
                 self.config.line_term.as_byte(),
-                self.config.before_context - 1,
+                range.start(),
+                range.end(),
             );
+            while let Some(line) = stepper.next_match(buf) {
+                let slice = lines::without_terminator(
+                    &buf[line],
+                    self.config.line_term,
+                );
+                if context_start.is_start(slice) {
+                    block_start = line.start();
+                }
+            }
+            before_context_start = cmp::min(before_context_start, block_start);
+        }
 
         let range = Range::new(before_context_start, range.end());
         let mut stepper = LineStep::new(
@@ -218,7 +266,7 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
         buf: &[u8],
         upto: usize,
     ) -> Result<bool, S::Error> {
-        if self.after_context_left == 0 {
+        if self.after_context_left == 0 && !self.after_context_active {
             return Ok(true);
         }
         let range = Range::new(self.last_line_visited, upto);
@@ -228,10 +276,14 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
             range.end(),
         );
         while let Some(line) = stepper.next_match(buf) {
+            self.check_context_block_end(buf, &line);
+            if self.after_context_left == 0 && !self.after_context_active {
+                break;
+            }
             if !self.sink_after_context(buf, &line)? {
                 return Ok(false);
             }
-            if self.after_context_left == 0 {
+            if self.after_context_left == 0 && !self.after_context_active {
                 break;
             }
         }
@@ -267,6 +319,10 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
             range.end(),
         );
         while let Some(line) = stepper.next_match(buf) {
+            self.config.check_cancelled()?;
+            if self.config.interrupted() {
+                return Ok(false);
+            }
             let matched = {
                 // Stripping the line terminator is necessary to prevent some
                 // classes of regexes from matching the empty position *after*
@@ -288,20 +344,30 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
                 if !self.before_context_by_line(buf, line.start())? {
                     return Ok(false);
                 }
-                if !self.sink_matched(buf, &line)? {
-                    return Ok(false);
-                }
-            } else if self.after_context_left >= 1 {
-                if !self.sink_after_context(buf, &line)? {
+                if !self.sink_matched(buf, &line, None)? {
                     return Ok(false);
                 }
-            } else if self.config.passthru {
-                if !self.sink_other_context(buf, &line)? {
-                    return Ok(false);
+            } else {
+                self.check_context_block_end(buf, &line);
+                if self.after_context_left >= 1 || self.after_context_active {
+                    if !self.sink_after_context(buf, &line)? {
+                        return Ok(false);
+                    }
+                } else if self.config.passthru {
+                    if !self.sink_other_context(buf, &line)? {
+                        return Ok(false);
+                    }
                 }
             }
-            if self.config.stop_on_nonmatch && !success && self.has_matched {
-                return Ok(false);
+            if let Some(gap) = self.config.stop_gap() {
+                if success {
+                    self.gap_lines = 0;
+                } else if self.has_matched {
+                    self.gap_lines += 1;
+                    if self.gap_lines >= gap {
+                        return Ok(false);
+                    }
+                }
             }
         }
         Ok(true)
@@ -315,7 +381,11 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
 
         debug_assert!(!self.config.passthru);
         while !buf[self.pos()..].is_empty() {
-            if self.config.stop_on_nonmatch && self.has_matched {
+            self.config.check_cancelled()?;
+            if self.config.interrupted() {
+                return Ok(Stop);
+            }
+            if self.config.stop_gap().is_some() && self.has_matched {
                 return Ok(SwitchToSlow);
             }
             if self.config.invert_match {
@@ -333,7 +403,7 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
                     }
                 }
                 self.set_pos(line.end());
-                if !self.sink_matched(buf, &line)? {
+                if !self.sink_matched(buf, &line, None)? {
                     return Ok(Stop);
                 }
             } else {
@@ -382,7 +452,7 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
             invert_match.end(),
         );
         while let Some(line) = stepper.next_match(buf) {
-            if !self.sink_matched(buf, &line)? {
+            if !self.sink_matched(buf, &line, None)? {
                 return Ok(false);
             }
         }
@@ -450,6 +520,7 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
         &mut self,
         buf: &[u8],
         range: &Range,
+        span: Option<&Range>,
     ) -> Result<bool, S::Error> {
         if self.binary && self.detect_binary(buf, range)? {
             return Ok(false);
@@ -457,6 +528,9 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
         if !self.sink_break_context(range.start())? {
             return Ok(false);
         }
+        if !self.sink_partial_before_context(buf, range)? {
+            return Ok(false);
+        }
         self.count_lines(buf, range.start());
         let offset = self.absolute_byte_offset + range.start() as u64;
         let linebuf = &buf[*range];
@@ -469,6 +543,11 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
                 line_number: self.line_number,
                 buffer: buf,
                 bytes_range_in_buffer: range.start()..range.end(),
+                match_absolute_range: span.map(|s| {
+                    let start = self.absolute_byte_offset + s.start() as u64;
+                    let end = self.absolute_byte_offset + s.end() as u64;
+                    start..end
+                }),
             },
         )?;
         if !keepgoing {
@@ -476,7 +555,11 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
         }
         self.last_line_visited = range.end();
         self.after_context_left = self.config.after_context;
+        self.after_context_active = self.config.context_start.is_some();
         self.has_sunk = true;
+        if !self.sink_partial_after_context(buf, range)? {
+            return Ok(false);
+        }
         Ok(true)
     }
 
@@ -514,7 +597,7 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
         buf: &[u8],
         range: &Range,
     ) -> Result<bool, S::Error> {
-        assert!(self.after_context_left >= 1);
+        assert!(self.after_context_left >= 1 || self.after_context_active);
 
         if self.binary && self.detect_binary(buf, range)? {
             return Ok(false);
@@ -536,7 +619,9 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
             return Ok(false);
         }
         self.last_line_visited = range.end();
-        self.after_context_left -= 1;
+        if self.after_context_left > 0 {
+            self.after_context_left -= 1;
+        }
         self.has_sunk = true;
         Ok(true)
     }
@@ -570,13 +655,122 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
         Ok(true)
     }
 
+    /// Reports up to `before_context_bytes` bytes immediately preceding the
+    /// given match range as partial context.
+    ///
+    /// Bytes that were already reported, e.g., as part of a previous match
+    /// or a previous partial context, are clipped so that they are never
+    /// reported twice. The starting position is snapped forward to a UTF-8
+    /// boundary.
+    fn sink_partial_before_context(
+        &mut self,
+        buf: &[u8],
+        range: &Range,
+    ) -> Result<bool, S::Error> {
+        let limit = self.config.before_context_bytes;
+        if limit == 0 {
+            return Ok(true);
+        }
+        let lower = cmp::max(
+            cmp::max(self.partial_context_floor, self.last_line_visited),
+            range.start().saturating_sub(limit),
+        );
+        let mut start = lower;
+        while start < range.start() && is_utf8_continuation(buf[start]) {
+            start += 1;
+        }
+        if start >= range.start() {
+            return Ok(true);
+        }
+        self.count_lines(buf, start);
+        let offset = self.absolute_byte_offset + start as u64;
+        let keepgoing = self.sink.context(
+            &self.searcher,
+            &SinkContext {
+                #[cfg(test)]
+                line_term: self.config.line_term,
+                bytes: &buf[start..range.start()],
+                kind: SinkContextKind::Partial,
+                absolute_byte_offset: offset,
+                line_number: self.line_number,
+            },
+        )?;
+        if !keepgoing {
+            return Ok(false);
+        }
+        self.has_sunk = true;
+        Ok(true)
+    }
+
+    /// Reports up to `after_context_bytes` bytes immediately following the
+    /// given match range as partial context.
+    ///
+    /// The ending position is snapped backward to a UTF-8 boundary. Fewer
+    /// bytes than the limit may be reported when the match occurs near the
+    /// end of the current buffer.
+    fn sink_partial_after_context(
+        &mut self,
+        buf: &[u8],
+        range: &Range,
+    ) -> Result<bool, S::Error> {
+        let limit = self.config.after_context_bytes;
+        if limit == 0 {
+            return Ok(true);
+        }
+        let mut end = cmp::min(buf.len(), range.end().saturating_add(limit));
+        while end > range.end()
+            && end < buf.len()
+            && is_utf8_continuation(buf[end])
+        {
+            end -= 1;
+        }
+        if end <= range.end() {
+            return Ok(true);
+        }
+        self.count_lines(buf, range.end());
+        let offset = self.absolute_byte_offset + range.end() as u64;
+        let keepgoing = self.sink.context(
+            &self.searcher,
+            &SinkContext {
+                #[cfg(test)]
+                line_term: self.config.line_term,
+                bytes: &buf[range.end()..end],
+                kind: SinkContextKind::Partial,
+                absolute_byte_offset: offset,
+                line_number: self.line_number,
+            },
+        )?;
+        if !keepgoing {
+            return Ok(false);
+        }
+        self.partial_context_floor = end;
+        self.has_sunk = true;
+        Ok(true)
+    }
+
+    /// When context block tracking is enabled, stop reporting "after"
+    /// context if the given line starts a new block.
+    fn check_context_block_end(&mut self, buf: &[u8], range: &Range) {
+        if !self.after_context_active {
+            return;
+        }
+        if let Some(ref context_start) = self.config.context_start {
+            let slice =
+                lines::without_terminator(&buf[*range], self.config.line_term);
+            if context_start.is_start(slice) {
+                self.after_context_active = false;
+            }
+        }
+    }
+
     fn sink_break_context(
         &mut self,
         start_of_line: usize,
     ) -> Result<bool, S::Error> {
         let is_gap = self.last_line_visited < start_of_line;
-        let any_context =
-            self.config.before_context > 0 || self.config.after_context > 0;
+        let any_context = self.config.before_context > 0
+            || self.config.after_context > 0
+            || self.config.context_start.is_some();
 
         if !any_context || !self.has_sunk || !is_gap {
             Ok(true)
@@ -603,7 +797,12 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
         if self.config.passthru {
             return false;
         }
-        if self.config.stop_on_nonmatch && self.has_matched {
+        // Context block tracking needs to inspect every line, so the fast
+        // line-skipping strategy can't be used.
+        if self.config.context_start.is_some() {
+            return false;
+        }
+        if self.config.stop_gap().is_some() && self.has_matched {
             return false;
         }
         if let Some(line_term) = self.matcher.line_terminator() {
@@ -623,3 +822,8 @@ impl<'s, M: Matcher, S: Sink> Core<'s, M, S> {
         false
     }
 }
+
+/// Returns true if and only if the given byte is a UTF-8 continuation byte.
+fn is_utf8_continuation(b: u8) -> bool {
+    b & 0b1100_0000 == 0b1000_0000
+}
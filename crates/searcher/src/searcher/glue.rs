@@ -51,6 +51,11 @@ where
     fn fill(&mut self) -> Result<bool, S::Error> {
         assert!(self.rdr.buffer()[self.core.pos()..].is_empty());
 
+        self.config.check_cancelled()?;
+        if self.config.interrupted() {
+            return Ok(false);
+        }
+
         let already_binary = self.rdr.binary_byte_offset().is_some();
         let old_buf_len = self.rdr.buffer().len();
         let consumed = self.core.roll(self.rdr.buffer());
@@ -136,7 +141,19 @@ pub struct MultiLine<'s, M, S> {
     config: &'s Config,
     core: Core<'s, M, S>,
     slice: &'s [u8],
-    last_match: Option<Range>,
+    last_match: Option<PendingMatch>,
+}
+
+/// A match whose reporting has been delayed in order to group adjacent
+/// matches together.
+#[derive(Clone, Copy, Debug)]
+struct PendingMatch {
+    /// The full lines containing the match.
+    lines: Range,
+    /// The precise span of the match itself. When adjacent matches are
+    /// grouped, this covers the start of the first match through the end of
+    /// the last.
+    span: Range,
 }
 
 impl<'s, M: Matcher, S: Sink> MultiLine<'s, M, S> {
@@ -164,14 +181,21 @@ impl<'s, M: Matcher, S: Sink> MultiLine<'s, M, S> {
             if !self.core.detect_binary(self.slice, &binary_range)? {
                 let mut keepgoing = true;
                 while !self.slice[self.core.pos()..].is_empty() && keepgoing {
+                    self.config.check_cancelled()?;
+                    if self.config.interrupted() {
+                        break;
+                    }
                     keepgoing = self.sink()?;
                 }
                 if keepgoing {
                     keepgoing = match self.last_match.take() {
                         None => true,
                         Some(last_match) => {
-                            if self.sink_context(&last_match)? {
-                                self.sink_matched(&last_match)?;
+                            if self.sink_context(&last_match.lines)? {
+                                self.sink_matched(
+                                    &last_match.lines,
+                                    Some(&last_match.span),
+                                )?;
                             }
                             true
                         }
@@ -219,7 +243,8 @@ impl<'s, M: Matcher, S: Sink> MultiLine<'s, M, S> {
         // that a single line is never sinked more than once.
         match self.last_match.take() {
             None => {
-                self.last_match = Some(line);
+                self.last_match =
+                    Some(PendingMatch { lines: line, span: mat });
                 Ok(true)
             }
             Some(last_match) => {
@@ -236,15 +261,22 @@ impl<'s, M: Matcher, S: Sink> MultiLine<'s, M, S> {
                 //
                 // See: https://github.com/BurntSushi/ripgrep/issues/1311
                 // And also the associated commit fixing #1311.
-                if last_match.end() >= line.start() {
-                    self.last_match = Some(last_match.with_end(line.end()));
+                if last_match.lines.end() >= line.start() {
+                    self.last_match = Some(PendingMatch {
+                        lines: last_match.lines.with_end(line.end()),
+                        span: last_match.span.with_end(mat.end()),
+                    });
                     Ok(true)
                 } else {
-                    self.last_match = Some(line);
-                    if !self.sink_context(&last_match)? {
+                    self.last_match =
+                        Some(PendingMatch { lines: line, span: mat });
+                    if !self.sink_context(&last_match.lines)? {
                         return Ok(false);
                     }
-                    self.sink_matched(&last_match)
+                    self.sink_matched(
+                        &last_match.lines,
+                        Some(&last_match.span),
+                    )
                 }
             }
         }
@@ -282,14 +314,18 @@ impl<'s, M: Matcher, S: Sink> MultiLine<'s, M, S> {
             invert_match.end(),
         );
         while let Some(line) = stepper.next_match(self.slice) {
-            if !self.sink_matched(&line)? {
+            if !self.sink_matched(&line, None)? {
                 return Ok(false);
             }
         }
         Ok(true)
     }
 
-    fn sink_matched(&mut self, range: &Range) -> Result<bool, S::Error> {
+    fn sink_matched(
+        &mut self,
+        range: &Range,
+        span: Option<&Range>,
+    ) -> Result<bool, S::Error> {
         if range.is_empty() {
             // The only way we can produce an empty line for a match is if we
             // match the position immediately following the last byte that we
@@ -298,7 +334,7 @@ impl<'s, M: Matcher, S: Sink> MultiLine<'s, M, S> {
             // point anyway, so stop the search.
             return Ok(false);
         }
-        self.core.matched(self.slice, range)
+        self.core.matched(self.slice, range, span)
     }
 
     fn sink_context(&mut self, range: &Range) -> Result<bool, S::Error> {
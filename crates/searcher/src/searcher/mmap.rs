@@ -3,6 +3,26 @@ use std::path::Path;
 
 use memmap::Mmap;
 
+/// The kind of advice to give to the operating system about how a memory
+/// map will be accessed.
+///
+/// On Unix systems, this corresponds to the advice given to `madvise`. On
+/// other platforms, advice is ignored.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MmapAdvice {
+    /// Expect page references in sequential order.
+    ///
+    /// This corresponds to `MADV_SEQUENTIAL`, which may cause the OS to
+    /// read ahead more aggressively and free pages soon after they are
+    /// accessed.
+    Sequential,
+    /// Expect access in the near future.
+    ///
+    /// This corresponds to `MADV_WILLNEED`, which may cause the OS to
+    /// start reading pages before they are accessed.
+    WillNeed,
+}
+
 /// Controls the strategy used for determining when to use memory maps.
 ///
 /// If a searcher is called in circumstances where it is possible to use memory
@@ -15,7 +35,7 @@ pub struct MmapChoice(MmapChoiceImpl);
 
 #[derive(Clone, Debug)]
 enum MmapChoiceImpl {
-    Auto,
+    Auto(Option<MmapAdvice>),
     Never,
 }
 
@@ -48,7 +68,23 @@ impl MmapChoice {
     /// take the risk of, say, a `SIGBUS` occurring while attempting to read a
     /// memory map.
     pub unsafe fn auto() -> MmapChoice {
-        MmapChoice(MmapChoiceImpl::Auto)
+        MmapChoice(MmapChoiceImpl::Auto(None))
+    }
+
+    /// Use memory maps when they are believed to be advantageous, and give
+    /// the given advice to the operating system about how the memory maps
+    /// will be accessed.
+    ///
+    /// Advice is applied on a best effort basis. If the advice could not be
+    /// applied (including on platforms that do not support it), then the
+    /// memory map is used without it.
+    ///
+    /// # Safety
+    ///
+    /// This constructor has the same safety contract as
+    /// [`MmapChoice::auto`].
+    pub unsafe fn auto_with_advice(advice: MmapAdvice) -> MmapChoice {
+        MmapChoice(MmapChoiceImpl::Auto(Some(advice)))
     }
 
     /// Never use memory maps, no matter what. This is the default.
@@ -80,7 +116,10 @@ impl MmapChoice {
         // is itself not safe. Thus, this is a propagation of the caller's
         // assertion that using memory maps is safe.
         match unsafe { Mmap::map(file) } {
-            Ok(mmap) => Some(mmap),
+            Ok(mmap) => {
+                self.advise(&mmap, path);
+                Some(mmap)
+            }
             Err(err) => {
                 if let Some(path) = path {
                     log::debug!(
@@ -96,10 +135,41 @@ impl MmapChoice {
         }
     }
 
+    /// Apply any configured access advice to the given memory map.
+    ///
+    /// Advice is best effort: if it could not be applied, then the failure
+    /// is logged at the debug level and otherwise ignored.
+    #[cfg(unix)]
+    fn advise(&self, mmap: &Mmap, path: Option<&Path>) {
+        let advice = match self.0 {
+            MmapChoiceImpl::Auto(Some(advice)) => advice,
+            _ => return,
+        };
+        let advice = match advice {
+            MmapAdvice::Sequential => memmap::Advice::Sequential,
+            MmapAdvice::WillNeed => memmap::Advice::WillNeed,
+        };
+        if let Err(err) = mmap.advise(advice) {
+            if let Some(path) = path {
+                log::debug!(
+                    "{}: failed to advise memory map: {}",
+                    path.display(),
+                    err
+                );
+            } else {
+                log::debug!("failed to advise memory map: {}", err);
+            }
+        }
+    }
+
+    /// Advice is not supported on this platform, so this does nothing.
+    #[cfg(not(unix))]
+    fn advise(&self, _mmap: &Mmap, _path: Option<&Path>) {}
+
     /// Whether this strategy may employ memory maps or not.
     pub(crate) fn is_enabled(&self) -> bool {
         match self.0 {
-            MmapChoiceImpl::Auto => true,
+            MmapChoiceImpl::Auto(_) => true,
             MmapChoiceImpl::Never => false,
         }
     }
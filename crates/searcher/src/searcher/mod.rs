@@ -1,9 +1,12 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::cmp;
 use std::fmt;
 use std::fs::File;
 use std::io::{self, Read};
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use crate::line_buffer::{
     self, alloc_error, BufferAllocation, LineBuffer, LineBufferBuilder,
@@ -15,7 +18,7 @@ use encoding_rs;
 use encoding_rs_io::DecodeReaderBytesBuilder;
 use grep_matcher::{LineTerminator, Match, Matcher};
 
-pub use self::mmap::MmapChoice;
+pub use self::mmap::{MmapAdvice, MmapChoice};
 
 mod core;
 mod glue;
@@ -26,6 +29,74 @@ mod mmap;
 /// accurate name. This is only used in the searcher's internals.
 type Range = Match;
 
+/// A predicate over lines that identifies the start of a context block.
+///
+/// This is used with
+/// [`SearcherBuilder::context_start`](struct.SearcherBuilder.html#method.context_start)
+/// to extend the context around each match to an enclosing block, such as a
+/// function definition, instead of a fixed number of lines.
+#[derive(Clone)]
+pub struct ContextStart(Arc<dyn Fn(&[u8]) -> bool + Send + Sync + 'static>);
+
+impl fmt::Debug for ContextStart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContextStart").finish()
+    }
+}
+
+impl ContextStart {
+    /// Create a new context start predicate from the given function.
+    ///
+    /// The function is called with a single line, not including its line
+    /// terminator, and should return true when that line starts a new
+    /// context block.
+    pub fn new<F>(predicate: F) -> ContextStart
+    where
+        F: Fn(&[u8]) -> bool + Send + Sync + 'static,
+    {
+        ContextStart(Arc::new(predicate))
+    }
+
+    /// Returns true if the given line starts a context block.
+    pub(crate) fn is_start(&self, line: &[u8]) -> bool {
+        (self.0)(line)
+    }
+}
+
+/// A token for cancelling in-progress searches from another thread.
+///
+/// A token is a cheap, cloneable handle around a shared boolean flag. Give
+/// clones of one token to any number of searchers via
+/// [`SearcherBuilder::cancellation`](struct.SearcherBuilder.html#method.cancellation),
+/// and call [`cancel`](#method.cancel) (typically from another thread) to
+/// make every search using that token fail promptly with an error
+/// constructed via
+/// [`SinkError::error_cancelled`](trait.SinkError.html#method.error_cancelled).
+///
+/// Once cancelled, a token remains cancelled forever.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create a new token that has not been cancelled.
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Cancel this token.
+    ///
+    /// All searches configured with this token (or a clone of it) stop with
+    /// an error as soon as they observe the cancellation.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns true if and only if this token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
 /// The behavior of binary detection while searching.
 ///
 /// Binary detection is the process of _heuristically_ identifying whether a
@@ -139,6 +210,78 @@ impl Encoding {
             }
         }
     }
+
+    /// Detect the encoding of the given data using lightweight heuristics.
+    ///
+    /// This is meant to run on a small prefix of a file and is not a full
+    /// statistical detector. `None` is returned when no transcoding is
+    /// required, which is the case when the data is valid UTF-8 or when it
+    /// begins with a BOM. (In the latter case, BOM sniffing picks the
+    /// correct encoding.) Otherwise, this looks for UTF-16 without a BOM by
+    /// its NUL byte pattern, then tries Shift-JIS and GBK when the data is
+    /// dense with non-ASCII bytes, and finally falls back to Latin-1 (or
+    /// more precisely, windows-1252), which can decode any byte sequence.
+    pub fn detect(data: &[u8]) -> Option<Encoding> {
+        if data.is_empty()
+            || data.starts_with(&[0xEF, 0xBB, 0xBF])
+            || data.starts_with(&[0xFF, 0xFE])
+            || data.starts_with(&[0xFE, 0xFF])
+        {
+            return None;
+        }
+        // UTF-16 without a BOM: mostly ASCII text encoded as UTF-16 has a
+        // NUL in every other byte, in the high byte of each code unit. The
+        // high byte comes second in little endian and first in big endian.
+        // This check must come before the UTF-8 check below, since NUL
+        // bytes are themselves valid UTF-8.
+        let (mut even_nul, mut odd_nul) = (0, 0);
+        for (i, &byte) in data.iter().enumerate() {
+            if byte == 0 {
+                if i % 2 == 0 {
+                    even_nul += 1;
+                } else {
+                    odd_nul += 1;
+                }
+            }
+        }
+        if odd_nul * 4 >= data.len() {
+            return Some(Encoding(encoding_rs::UTF_16LE));
+        }
+        if even_nul * 4 >= data.len() {
+            return Some(Encoding(encoding_rs::UTF_16BE));
+        }
+        if is_utf8_prefix(data) {
+            return None;
+        }
+        // Legacy multi-byte encodings produce runs of non-ASCII bytes, while
+        // Latin-1 text typically has only scattered accented characters. So
+        // only consider Shift-JIS and GBK when non-ASCII bytes are dense,
+        // and otherwise assume Latin-1. This avoids misclassifying Western
+        // European text, since almost any byte sequence happens to be
+        // decodable as Shift-JIS or GBK.
+        let non_ascii = data.iter().filter(|&&b| b >= 0x80).count();
+        if non_ascii * 8 >= data.len() {
+            for &enc in &[encoding_rs::SHIFT_JIS, encoding_rs::GBK] {
+                let (_, _, had_errors) = enc.decode(data);
+                if !had_errors {
+                    return Some(Encoding(enc));
+                }
+            }
+        }
+        Some(Encoding(encoding_rs::WINDOWS_1252))
+    }
+}
+
+/// Returns true if the given data is valid UTF-8, allowing for a single
+/// incomplete code point at the end, as happens when a longer stream is
+/// truncated to a fixed size prefix.
+fn is_utf8_prefix(data: &[u8]) -> bool {
+    match std::str::from_utf8(data) {
+        Ok(_) => true,
+        Err(err) => {
+            err.error_len().is_none() && data.len() - err.valid_up_to() < 4
+        }
+    }
 }
 
 /// The internal configuration of a searcher. This is shared among several
@@ -153,6 +296,16 @@ pub struct Config {
     after_context: usize,
     /// The number of lines before a match to include.
     before_context: usize,
+    /// The number of bytes after a match to include, snapped to UTF-8
+    /// boundaries.
+    after_context_bytes: usize,
+    /// The number of bytes before a match to include, snapped to UTF-8
+    /// boundaries.
+    before_context_bytes: usize,
+    /// A predicate identifying lines that start a context block. When
+    /// present, the context around each match is extended to the enclosing
+    /// block.
+    context_start: Option<ContextStart>,
     /// Whether to enable unbounded context or not.
     passthru: bool,
     /// Whether to count line numbers.
@@ -176,6 +329,25 @@ pub struct Config {
     /// Whether to stop searching when a non-matching line is found after a
     /// matching line.
     stop_on_nonmatch: bool,
+    /// The number of consecutive non-matching lines after a matching line at
+    /// which to stop searching, if set.
+    stop_after_gap: Option<usize>,
+    /// The maximum number of bytes to search in each unit of input, if set.
+    /// Any bytes past the limit are ignored.
+    max_bytes_searched: Option<u64>,
+    /// A cancellation token that, when set, stops an in-progress search as
+    /// quickly as possible.
+    cancel: Option<Arc<AtomicBool>>,
+    /// A cancellation token that, when cancelled, aborts an in-progress
+    /// search with an error instead of stopping it gracefully.
+    cancellation: Option<CancellationToken>,
+    /// A wall clock limit on the duration of each search, if set.
+    timeout: Option<Duration>,
+    /// The instant at which the current search must stop, derived from
+    /// `timeout` when a search begins.
+    deadline: Cell<Option<Instant>>,
+    /// Whether the current search was stopped by its deadline.
+    timed_out: Cell<bool>,
 }
 
 impl Default for Config {
@@ -185,6 +357,9 @@ impl Default for Config {
             invert_match: false,
             after_context: 0,
             before_context: 0,
+            after_context_bytes: 0,
+            before_context_bytes: 0,
+            context_start: None,
             passthru: false,
             line_number: true,
             heap_limit: None,
@@ -194,6 +369,13 @@ impl Default for Config {
             encoding: None,
             bom_sniffing: true,
             stop_on_nonmatch: false,
+            stop_after_gap: None,
+            max_bytes_searched: None,
+            cancel: None,
+            cancellation: None,
+            timeout: None,
+            deadline: Cell::new(None),
+            timed_out: Cell::new(false),
         }
     }
 }
@@ -207,6 +389,83 @@ impl Config {
         cmp::max(self.before_context, self.after_context)
     }
 
+    /// Return the number of consecutive non-matching lines after a matching
+    /// line at which the search should stop, if any.
+    ///
+    /// `stop_on_nonmatch` is equivalent to a gap of `1` and takes precedence
+    /// over `stop_after_gap`.
+    fn stop_gap(&self) -> Option<usize> {
+        if self.stop_on_nonmatch {
+            Some(1)
+        } else {
+            self.stop_after_gap
+        }
+    }
+
+    /// Returns true if the cancellation token is present and has been set.
+    fn cancelled(&self) -> bool {
+        self.cancel.as_ref().map_or(false, |t| t.load(Ordering::Relaxed))
+    }
+
+    /// Returns an error if the aborting cancellation token is present and
+    /// has been cancelled. Search loops call this periodically so that a
+    /// cancelled search fails with a distinct error rather than stopping as
+    /// if it had reached the end of its input.
+    pub(crate) fn check_cancelled<E: SinkError>(&self) -> Result<(), E> {
+        match self.cancellation {
+            Some(ref token) if token.is_cancelled() => {
+                Err(E::error_cancelled())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Arm this configuration's search deadline, if a timeout is set.
+    ///
+    /// This is called when a search begins, so that the deadline is relative
+    /// to the start of that search.
+    fn start_timer(&self) {
+        self.deadline.set(self.timeout.map(|t| Instant::now() + t));
+        self.timed_out.set(false);
+    }
+
+    /// Returns true if the current search has run past its deadline.
+    ///
+    /// Once a search has timed out, this latches until the next search
+    /// begins.
+    fn deadline_exceeded(&self) -> bool {
+        if self.timed_out.get() {
+            return true;
+        }
+        match self.deadline.get() {
+            None => false,
+            Some(deadline) => {
+                if Instant::now() >= deadline {
+                    self.timed_out.set(true);
+                }
+                self.timed_out.get()
+            }
+        }
+    }
+
+    /// Returns true if the current search should stop early, either because
+    /// the cancellation token was set or because the search ran past its
+    /// deadline.
+    fn interrupted(&self) -> bool {
+        self.cancelled() || self.deadline_exceeded()
+    }
+
+    /// Truncate the given input to this configuration's byte budget, if one
+    /// is set.
+    fn budget<'s>(&self, slice: &'s [u8]) -> &'s [u8] {
+        match self.max_bytes_searched {
+            Some(limit) if (slice.len() as u64) > limit => {
+                &slice[..limit as usize]
+            }
+            _ => slice,
+        }
+    }
+
     /// Build a line buffer from this configuration.
     fn line_buffer(&self) -> LineBuffer {
         let mut builder = LineBufferBuilder::new();
@@ -321,6 +580,9 @@ impl SearcherBuilder {
         if config.passthru {
             config.before_context = 0;
             config.after_context = 0;
+            config.before_context_bytes = 0;
+            config.after_context_bytes = 0;
+            config.context_start = None;
         }
 
         let mut decode_builder = DecodeReaderBytesBuilder::new();
@@ -408,6 +670,29 @@ impl SearcherBuilder {
         self
     }
 
+    /// Whether to include a bounded number of bytes after every match.
+    ///
+    /// When this is set to a non-zero number, then the searcher will report
+    /// up to `byte_count` bytes immediately following every match via a
+    /// context with `SinkContextKind::Partial`. The reported bytes are
+    /// snapped to UTF-8 boundaries, so a multi-byte character is never
+    /// split. Fewer bytes may be reported when a match occurs near the edge
+    /// of the searcher's internal buffer.
+    ///
+    /// Unlike `after_context`, this bounds the size of the reported context
+    /// even when the input contains extremely long lines, which makes it
+    /// more appropriate for things like minified files or machine generated
+    /// logs.
+    ///
+    /// This is set to `0` by default.
+    pub fn after_context_bytes(
+        &mut self,
+        byte_count: usize,
+    ) -> &mut SearcherBuilder {
+        self.config.after_context_bytes = byte_count;
+        self
+    }
+
     /// Whether to include a fixed number of lines before every match.
     ///
     /// When this is set to a non-zero number, then the searcher will report
@@ -422,6 +707,53 @@ impl SearcherBuilder {
         self
     }
 
+    /// Whether to include a bounded number of bytes before every match.
+    ///
+    /// When this is set to a non-zero number, then the searcher will report
+    /// up to `byte_count` bytes immediately preceding every match via a
+    /// context with `SinkContextKind::Partial`. The reported bytes are
+    /// snapped to UTF-8 boundaries, so a multi-byte character is never
+    /// split. Bytes that were already reported, e.g., as part of a previous
+    /// match, are never reported again.
+    ///
+    /// Unlike `before_context`, this bounds the size of the reported context
+    /// even when the input contains extremely long lines, which makes it
+    /// more appropriate for things like minified files or machine generated
+    /// logs.
+    ///
+    /// This is set to `0` by default.
+    pub fn before_context_bytes(
+        &mut self,
+        byte_count: usize,
+    ) -> &mut SearcherBuilder {
+        self.config.before_context_bytes = byte_count;
+        self
+    }
+
+    /// Set a predicate that identifies lines that start a context block.
+    ///
+    /// When set, the context reported for each match is extended upward to
+    /// the closest preceding line for which the predicate returns true, and
+    /// downward until (but not including) the next such line. This can be
+    /// used to report the enclosing function for each match by passing a
+    /// predicate that recognizes function definitions.
+    ///
+    /// When fixed `before_context` or `after_context` line counts are also
+    /// set, the larger of the two contexts is reported.
+    ///
+    /// **Warning:** like multi line search, this requires having the entire
+    /// contents to search mapped in memory at once, since the distance to
+    /// the enclosing block boundaries is unbounded.
+    ///
+    /// This is unset by default.
+    pub fn context_start(
+        &mut self,
+        predicate: Option<ContextStart>,
+    ) -> &mut SearcherBuilder {
+        self.config.context_start = predicate;
+        self
+    }
+
     /// Whether to enable the "passthru" feature or not.
     ///
     /// When passthru is enabled, it effectively treats all non-matching lines
@@ -572,6 +904,92 @@ impl SearcherBuilder {
         self.config.stop_on_nonmatch = stop_on_nonmatch;
         self
     }
+
+    /// Stop searching a file once the given number of consecutive
+    /// non-matching lines is found after a matching line.
+    ///
+    /// This is a generalization of `stop_on_nonmatch` that tolerates small
+    /// gaps between matches, which is useful when searching files that are
+    /// only mostly sorted, such as logs. Setting this to `None` (the default)
+    /// disables it.
+    pub fn stop_after_gap(
+        &mut self,
+        gap: Option<usize>,
+    ) -> &mut SearcherBuilder {
+        self.config.stop_after_gap = gap;
+        self
+    }
+
+    /// Set a byte budget for each unit of input searched.
+    ///
+    /// When set, only the first `N` bytes of each file, slice or reader are
+    /// searched and the rest is ignored. Note that the budget is enforced on
+    /// the source data, so when it falls in the middle of a line, the final
+    /// line searched may be truncated.
+    ///
+    /// By default, no budget is set.
+    pub fn max_bytes_searched(
+        &mut self,
+        bytes: Option<u64>,
+    ) -> &mut SearcherBuilder {
+        self.config.max_bytes_searched = bytes;
+        self
+    }
+
+    /// Set a cancellation token for this searcher.
+    ///
+    /// The token is checked periodically while a search is in progress.
+    /// Once it has been set to `true` (typically from a signal handler or
+    /// another thread), the search stops as if the end of the input had
+    /// been reached: the sink is finished normally, so any output or
+    /// statistics gathered so far are preserved.
+    ///
+    /// By default, no token is set.
+    pub fn cancel_token(
+        &mut self,
+        token: Option<Arc<AtomicBool>>,
+    ) -> &mut SearcherBuilder {
+        self.config.cancel = token;
+        self
+    }
+
+    /// Set a cancellation token that aborts searches run by this searcher.
+    ///
+    /// The token is checked periodically while a search is in progress. Once
+    /// it has been cancelled (typically from another thread), the search
+    /// stops with an error constructed via `SinkError::error_cancelled`. For
+    /// sinks using `io::Error`, the error has kind
+    /// `io::ErrorKind::Interrupted`, which permits distinguishing a
+    /// cancelled search from one that ran to completion.
+    ///
+    /// This differs from `cancel_token`, which stops a search gracefully, as
+    /// if the end of the input had been reached.
+    ///
+    /// By default, no token is set.
+    pub fn cancellation(
+        &mut self,
+        token: Option<CancellationToken>,
+    ) -> &mut SearcherBuilder {
+        self.config.cancellation = token;
+        self
+    }
+
+    /// Set a wall clock limit on the duration of each search.
+    ///
+    /// When set, a search that runs past the limit stops as if the end of
+    /// the input had been reached: the sink is finished normally, so any
+    /// output or statistics gathered so far are preserved. Whether the most
+    /// recent search was stopped by its deadline can be queried via
+    /// `Searcher::timed_out`.
+    ///
+    /// By default, no limit is set.
+    pub fn timeout(
+        &mut self,
+        timeout: Option<Duration>,
+    ) -> &mut SearcherBuilder {
+        self.config.timeout = timeout;
+        self
+    }
 }
 
 /// A searcher executes searches over a haystack and writes results to a caller
@@ -645,6 +1063,53 @@ impl Searcher {
         self.search_file_maybe_path(matcher, Some(path), &file, write_to)
     }
 
+    /// Execute a search over the file with the given path, transparently
+    /// decompressing its contents if it is compressed, and write the results
+    /// to the given sink.
+    ///
+    /// Compression is detected by sniffing the magic bytes at the beginning
+    /// of the file rather than by its extension. Files compressed with gzip
+    /// or zstd are decoded in a streaming fashion, in process and without
+    /// spawning any child processes, and searched as with `search_reader`.
+    /// Files that are not compressed are searched exactly as with
+    /// `search_path`, including the use of memory maps when enabled.
+    ///
+    /// This routine requires the `decompress` feature to be enabled.
+    #[cfg(feature = "decompress")]
+    pub fn search_path_decompress<P, M, S>(
+        &mut self,
+        matcher: M,
+        path: P,
+        write_to: S,
+    ) -> Result<(), S::Error>
+    where
+        P: AsRef<Path>,
+        M: Matcher,
+        S: Sink,
+    {
+        use crate::decompress::{sniff, Compression};
+
+        let path = path.as_ref();
+        let mut file = File::open(path).map_err(S::Error::error_io)?;
+        match sniff(&mut file).map_err(S::Error::error_io)? {
+            Some(Compression::Gzip) => {
+                let rdr = flate2::read::MultiGzDecoder::new(file);
+                self.search_reader(matcher, rdr, write_to)
+            }
+            Some(Compression::Zstd) => {
+                let rdr = zstd::stream::read::Decoder::new(file)
+                    .map_err(S::Error::error_io)?;
+                self.search_reader(matcher, rdr, write_to)
+            }
+            None => self.search_file_maybe_path(
+                matcher,
+                Some(path),
+                &file,
+                write_to,
+            ),
+        }
+    }
+
     /// Execute a search over a file and write the results to the given sink.
     ///
     /// If memory maps are enabled and the searcher heuristically believes
@@ -675,6 +1140,7 @@ impl Searcher {
         M: Matcher,
         S: Sink,
     {
+        self.config.start_timer();
         if let Some(mmap) = self.config.mmap.open(file, path) {
             log::trace!("{:?}: searching via memory map", path);
             return self.search_slice(matcher, &mmap, write_to);
@@ -692,7 +1158,24 @@ impl Searcher {
             MultiLine::new(
                 self,
                 matcher,
-                &*self.multi_line_buffer.borrow(),
+                self.config.budget(&*self.multi_line_buffer.borrow()),
+                write_to,
+            )
+            .run()
+        } else if self.config.context_start.is_some() {
+            // Context block tracking needs to look backwards through the
+            // entire file, so an incremental search isn't possible here
+            // either.
+            log::trace!(
+                "{:?}: reading entire file on to heap for context blocks",
+                path
+            );
+            self.fill_multi_line_buffer_from_file::<S>(file)?;
+            log::trace!("{:?}: searching via slice-by-line strategy", path);
+            SliceByLine::new(
+                self,
+                matcher,
+                self.config.budget(&*self.multi_line_buffer.borrow()),
                 write_to,
             )
             .run()
@@ -725,7 +1208,12 @@ impl Searcher {
         S: Sink,
     {
         self.check_config(&matcher).map_err(S::Error::error_config)?;
+        self.config.start_timer();
 
+        // The byte budget is enforced on the source data, before any
+        // transcoding happens.
+        let read_from =
+            read_from.take(self.config.max_bytes_searched.unwrap_or(u64::MAX));
         let mut decode_buffer = self.decode_buffer.borrow_mut();
         let decoder = self
             .decode_builder
@@ -745,6 +1233,22 @@ impl Searcher {
                 write_to,
             )
             .run()
+        } else if self.config.context_start.is_some() {
+            log::trace!(
+                "generic reader: reading everything to heap for context \
+                 blocks"
+            );
+            self.fill_multi_line_buffer_from_reader::<_, S>(decoder)?;
+            log::trace!(
+                "generic reader: searching via slice-by-line strategy"
+            );
+            SliceByLine::new(
+                self,
+                matcher,
+                &*self.multi_line_buffer.borrow(),
+                write_to,
+            )
+            .run()
         } else {
             let mut line_buffer = self.line_buffer.borrow_mut();
             let rdr = LineBufferReader::new(decoder, &mut *line_buffer);
@@ -766,7 +1270,9 @@ impl Searcher {
         S: Sink,
     {
         self.check_config(&matcher).map_err(S::Error::error_config)?;
+        self.config.start_timer();
 
+        let slice = self.config.budget(slice);
         // We can search the slice directly, unless we need to do transcoding.
         if self.slice_needs_transcoding(slice) {
             log::trace!(
@@ -789,6 +1295,18 @@ impl Searcher {
         self.line_buffer.borrow_mut().set_binary_detection(detection.0);
     }
 
+    /// Set the encoding used by this searcher to transcode source data
+    /// before searching.
+    ///
+    /// This is useful for adjusting the encoding on a per file basis, e.g.,
+    /// based on the result of [`Encoding::detect`](struct.Encoding.html),
+    /// without rebuilding the searcher. When `None` is given, source data is
+    /// searched as-is, modulo BOM sniffing if it is enabled.
+    pub fn set_encoding(&mut self, encoding: Option<Encoding>) {
+        self.decode_builder.encoding(encoding.as_ref().map(|e| e.0));
+        self.config.encoding = encoding;
+    }
+
     /// Check that the searcher's configuration and the matcher are consistent
     /// with each other.
     fn check_config<M: Matcher>(&self, matcher: M) -> Result<(), ConfigError> {
@@ -862,6 +1380,34 @@ impl Searcher {
         self.config.stop_on_nonmatch
     }
 
+    /// Returns the number of consecutive non-matching lines after a matching
+    /// line at which this searcher will stop, if set.
+    #[inline]
+    pub fn stop_after_gap(&self) -> Option<usize> {
+        self.config.stop_after_gap
+    }
+
+    /// Returns the maximum number of bytes this searcher will search in each
+    /// unit of input, if set.
+    #[inline]
+    pub fn max_bytes_searched(&self) -> Option<u64> {
+        self.config.max_bytes_searched
+    }
+
+    /// Returns the wall clock limit on the duration of each search, if one
+    /// was set.
+    #[inline]
+    pub fn timeout(&self) -> Option<Duration> {
+        self.config.timeout
+    }
+
+    /// Returns true if and only if the most recent search was stopped early
+    /// because it ran past this searcher's timeout.
+    #[inline]
+    pub fn timed_out(&self) -> bool {
+        self.config.timed_out.get()
+    }
+
     /// Returns true if and only if this searcher will choose a multi-line
     /// strategy given the provided matcher.
     ///
@@ -904,6 +1450,27 @@ impl Searcher {
         self.config.before_context
     }
 
+    /// Returns the number of "after" context bytes to report. When partial
+    /// context reporting is not enabled, this returns `0`.
+    #[inline]
+    pub fn after_context_bytes(&self) -> usize {
+        self.config.after_context_bytes
+    }
+
+    /// Returns the number of "before" context bytes to report. When partial
+    /// context reporting is not enabled, this returns `0`.
+    #[inline]
+    pub fn before_context_bytes(&self) -> usize {
+        self.config.before_context_bytes
+    }
+
+    /// Returns the predicate identifying context block starts, if one was
+    /// set.
+    #[inline]
+    pub fn context_start(&self) -> Option<&ContextStart> {
+        self.config.context_start.as_ref()
+    }
+
     /// Returns true if and only if the searcher has "passthru" mode enabled.
     #[inline]
     pub fn passthru(&self) -> bool {
@@ -917,7 +1484,7 @@ impl Searcher {
         &self,
         file: &File,
     ) -> Result<(), S::Error> {
-        assert!(self.config.multi_line);
+        assert!(self.config.multi_line || self.config.context_start.is_some());
 
         let mut decode_buffer = self.decode_buffer.borrow_mut();
         let mut read_from = self
@@ -952,7 +1519,7 @@ impl Searcher {
         &self,
         mut read_from: R,
     ) -> Result<(), S::Error> {
-        assert!(self.config.multi_line);
+        assert!(self.config.multi_line || self.config.context_start.is_some());
 
         let mut buf = self.multi_line_buffer.borrow_mut();
         buf.clear();
@@ -1023,6 +1590,254 @@ mod tests {
     use super::*;
     use crate::testutil::{KitchenSink, RegexMatcher};
 
+    #[test]
+    fn encoding_detect() {
+        let detect = |data: &[u8]| Encoding::detect(data).map(|e| e.0.name());
+
+        // No transcoding needed: empty, ASCII, UTF-8 (including a prefix
+        // that ends in the middle of a code point) and anything with a BOM.
+        assert_eq!(None, detect(b""));
+        assert_eq!(None, detect(b"hello"));
+        assert_eq!(None, detect("café".as_bytes()));
+        assert_eq!(None, detect(b"caf\xC3"));
+        assert_eq!(None, detect(b"\xEF\xBB\xBFhello"));
+        assert_eq!(None, detect(b"\xFF\xFEh\x00e\x00"));
+        assert_eq!(None, detect(b"\xFE\xFF\x00h\x00e"));
+
+        // UTF-16 without a BOM.
+        assert_eq!(Some("UTF-16LE"), detect(b"h\x00e\x00l\x00l\x00o\x00"),);
+        assert_eq!(Some("UTF-16BE"), detect(b"\x00h\x00e\x00l\x00l\x00o"),);
+
+        // Scattered non-ASCII bytes look like Latin-1.
+        assert_eq!(
+            Some("windows-1252"),
+            detect(b"caf\xE9 au lait, voil\xE0 the rest is ascii"),
+        );
+
+        // Dense runs of valid Shift-JIS.
+        assert_eq!(
+            Some("Shift_JIS"),
+            detect(b"\x82\xb1\x82\xf1\x82\xc9\x82\xbf\x82\xcd"),
+        );
+    }
+
+    #[test]
+    fn partial_byte_context() {
+        use crate::sink::{Sink, SinkContext, SinkContextKind, SinkMatch};
+
+        #[derive(Debug, Default)]
+        struct Events(Vec<(String, String)>);
+
+        impl Sink for &mut Events {
+            type Error = std::io::Error;
+
+            fn matched(
+                &mut self,
+                _: &Searcher,
+                mat: &SinkMatch<'_>,
+            ) -> Result<bool, std::io::Error> {
+                let bytes = String::from_utf8_lossy(mat.bytes()).into_owned();
+                self.0.push(("match".to_string(), bytes));
+                Ok(true)
+            }
+
+            fn context(
+                &mut self,
+                _: &Searcher,
+                ctx: &SinkContext<'_>,
+            ) -> Result<bool, std::io::Error> {
+                assert_eq!(&SinkContextKind::Partial, ctx.kind());
+                let bytes = String::from_utf8_lossy(ctx.bytes()).into_owned();
+                self.0.push(("partial".to_string(), bytes));
+                Ok(true)
+            }
+        }
+
+        let ev =
+            |kind: &str, bytes: &str| (kind.to_string(), bytes.to_string());
+
+        let matcher = RegexMatcher::new("match");
+        let haystack = "aaaa aaaa aaaa\nmatch me\nbbbb bbbb bbbb\n";
+        let mut events = Events::default();
+        let mut searcher = SearcherBuilder::new()
+            .before_context_bytes(5)
+            .after_context_bytes(5)
+            .build();
+        searcher
+            .search_slice(matcher, haystack.as_bytes(), &mut events)
+            .unwrap();
+        assert_eq!(
+            vec![
+                ev("partial", "aaaa\n"),
+                ev("match", "match me\n"),
+                ev("partial", "bbbb "),
+            ],
+            events.0,
+        );
+
+        // The reported bytes are snapped to UTF-8 boundaries, so multi-byte
+        // characters are never split.
+        let matcher = RegexMatcher::new("foo");
+        let haystack = "ééé\nfoo\nééé\n";
+        let mut events = Events::default();
+        let mut searcher = SearcherBuilder::new()
+            .before_context_bytes(2)
+            .after_context_bytes(3)
+            .build();
+        searcher
+            .search_slice(matcher, haystack.as_bytes(), &mut events)
+            .unwrap();
+        assert_eq!(
+            vec![
+                ev("partial", "\n"),
+                ev("match", "foo\n"),
+                ev("partial", "é"),
+            ],
+            events.0,
+        );
+    }
+
+    #[test]
+    fn multi_line_match_absolute_range() {
+        use crate::sink::{Sink, SinkMatch};
+
+        #[derive(Debug, Default)]
+        struct Ranges(Vec<Option<std::ops::Range<u64>>>);
+
+        impl Sink for &mut Ranges {
+            type Error = std::io::Error;
+
+            fn matched(
+                &mut self,
+                _: &Searcher,
+                mat: &SinkMatch<'_>,
+            ) -> Result<bool, std::io::Error> {
+                self.0.push(mat.match_absolute_range());
+                Ok(true)
+            }
+        }
+
+        let haystack = "one\ntwo\nthree\nfour\n";
+
+        // In multi line search, the precise span of the match is reported,
+        // even though the sinked bytes cover the full lines.
+        let matcher = RegexMatcher::new("two\nthree");
+        let mut ranges = Ranges::default();
+        SearcherBuilder::new()
+            .multi_line(true)
+            .build()
+            .search_slice(matcher, haystack.as_bytes(), &mut ranges)
+            .unwrap();
+        assert_eq!(vec![Some(4..13)], ranges.0);
+
+        // In line oriented search, the precise span isn't tracked.
+        let matcher = RegexMatcher::new("two");
+        let mut ranges = Ranges::default();
+        SearcherBuilder::new()
+            .build()
+            .search_slice(matcher, haystack.as_bytes(), &mut ranges)
+            .unwrap();
+        assert_eq!(vec![None], ranges.0);
+    }
+
+    #[test]
+    fn cancellation() {
+        let haystack = "one\ntwo\nthree\nfour\n";
+        let token = CancellationToken::new();
+
+        // An uncancelled token doesn't disturb the search.
+        let mut sink = KitchenSink::new();
+        let mut searcher =
+            SearcherBuilder::new().cancellation(Some(token.clone())).build();
+        searcher
+            .search_slice(
+                RegexMatcher::new("two"),
+                haystack.as_bytes(),
+                &mut sink,
+            )
+            .unwrap();
+        assert!(!sink.as_bytes().is_empty());
+
+        // Once the token is cancelled, searches fail with a distinct error.
+        token.cancel();
+        let mut sink = KitchenSink::new();
+        let err = searcher
+            .search_slice(
+                RegexMatcher::new("two"),
+                haystack.as_bytes(),
+                &mut sink,
+            )
+            .unwrap_err();
+        assert_eq!(io::ErrorKind::Interrupted, err.kind());
+
+        // ... including multi line searches.
+        let err = SearcherBuilder::new()
+            .multi_line(true)
+            .cancellation(Some(token))
+            .build()
+            .search_slice(
+                RegexMatcher::new("two\nthree"),
+                haystack.as_bytes(),
+                &mut KitchenSink::new(),
+            )
+            .unwrap_err();
+        assert_eq!(io::ErrorKind::Interrupted, err.kind());
+    }
+
+    #[cfg(feature = "decompress")]
+    #[test]
+    fn search_path_decompress() {
+        use std::io::Write;
+
+        let haystack = "one\ntwo\nthree\nfour\n";
+
+        // The expected output is whatever a plain search produces.
+        let mut expected = KitchenSink::new();
+        SearcherBuilder::new()
+            .build()
+            .search_slice(
+                RegexMatcher::new("two"),
+                haystack.as_bytes(),
+                &mut expected,
+            )
+            .unwrap();
+
+        let mut path = std::env::temp_dir();
+        path.push(format!(
+            "grep-searcher-decompress-{}.gz",
+            std::process::id()
+        ));
+        let file = File::create(&path).unwrap();
+        let mut wtr =
+            flate2::write::GzEncoder::new(file, flate2::Compression::fast());
+        wtr.write_all(haystack.as_bytes()).unwrap();
+        wtr.finish().unwrap();
+
+        let mut sink = KitchenSink::new();
+        SearcherBuilder::new()
+            .build()
+            .search_path_decompress(RegexMatcher::new("two"), &path, &mut sink)
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(expected.as_bytes(), sink.as_bytes());
+
+        // A file without a recognized magic number is searched as is.
+        let mut path = std::env::temp_dir();
+        path.push(format!(
+            "grep-searcher-decompress-{}.txt",
+            std::process::id()
+        ));
+        std::fs::write(&path, haystack).unwrap();
+
+        let mut sink = KitchenSink::new();
+        SearcherBuilder::new()
+            .build()
+            .search_path_decompress(RegexMatcher::new("two"), &path, &mut sink)
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(expected.as_bytes(), sink.as_bytes());
+    }
+
     #[test]
     fn config_error_heap_limit() {
         let matcher = RegexMatcher::new("");
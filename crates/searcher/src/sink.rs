@@ -34,6 +34,14 @@ pub trait SinkError: Sized {
     fn error_config(err: ConfigError) -> Self {
         Self::error_message(err)
     }
+
+    /// A constructor for the error reported when a search is stopped by a
+    /// cancellation token. See `SearcherBuilder::cancellation`.
+    ///
+    /// By default, this is implemented via the `error_message` constructor.
+    fn error_cancelled() -> Self {
+        Self::error_message("search cancelled")
+    }
 }
 
 /// An `io::Error` can be used as an error for `Sink` implementations out of
@@ -46,6 +54,10 @@ impl SinkError for io::Error {
     fn error_io(err: io::Error) -> io::Error {
         err
     }
+
+    fn error_cancelled() -> io::Error {
+        io::Error::new(io::ErrorKind::Interrupted, "search cancelled")
+    }
 }
 
 /// A `Box<std::error::Error>` can be used as an error for `Sink`
@@ -367,6 +379,7 @@ pub struct SinkMatch<'b> {
     pub(crate) line_number: Option<u64>,
     pub(crate) buffer: &'b [u8],
     pub(crate) bytes_range_in_buffer: std::ops::Range<usize>,
+    pub(crate) match_absolute_range: Option<std::ops::Range<u64>>,
 }
 
 impl<'b> SinkMatch<'b> {
@@ -408,6 +421,22 @@ impl<'b> SinkMatch<'b> {
         self.line_number
     }
 
+    /// Returns the absolute byte offsets of the start and end of the match
+    /// that produced these lines, if available. Like
+    /// [`absolute_byte_offset`](#method.absolute_byte_offset), the offsets
+    /// are relative to the very beginning of the input in a search.
+    ///
+    /// This is only available in multi line search, where a match may begin
+    /// and end part way through the first and last lines reported in
+    /// `bytes`. When adjacent matches are grouped into a single `SinkMatch`,
+    /// this spans from the start of the first match to the end of the last.
+    /// In line oriented search this is always `None`, since the precise
+    /// spans of matches within a line are not tracked.
+    #[inline]
+    pub fn match_absolute_range(&self) -> Option<std::ops::Range<u64>> {
+        self.match_absolute_range.clone()
+    }
+
     /// TODO
     #[inline]
     pub fn buffer(&self) -> &'b [u8] {
@@ -428,6 +457,11 @@ pub enum SinkContextKind {
     Before,
     /// The line reported occurred after a match.
     After,
+    /// A bounded number of bytes reported around a match, as configured by
+    /// `SearcherBuilder::before_context_bytes` or
+    /// `SearcherBuilder::after_context_bytes`. Unlike the other kinds of
+    /// context, this may correspond to a fraction of a line.
+    Partial,
     /// Any other type of context reported, e.g., as a result of a searcher's
     /// "passthru" mode.
     Other,
@@ -1021,3 +1021,16 @@ rgtest!(stop_on_nonmatch, |dir: Dir, mut cmd: TestCommand| {
     cmd.args(&["--stop-on-nonmatch", "[235]"]);
     eqnice!("test:line2\ntest:line3\n", cmd.stdout());
 });
+
+rgtest!(stop_after_gap, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("test", "line1\nline2\nline3\nline4\nline5\nline6\nline7");
+    // A single non-matching line (line3) is tolerated, but the gap after
+    // line4 reaches two lines and stops the search before line7.
+    cmd.args(&["--stop-after-gap", "2", "[247]"]);
+    eqnice!("test:line2\ntest:line4\n", cmd.stdout());
+
+    // A gap of 0 disables the behavior entirely.
+    let mut cmd = dir.command();
+    cmd.args(&["--stop-after-gap", "0", "[247]"]);
+    eqnice!("test:line2\ntest:line4\ntest:line7\n", cmd.stdout());
+});
@@ -125,6 +125,8 @@ struct Stats {
     bytes_printed: u64,
     matched_lines: u64,
     matches: u64,
+    #[serde(default)]
+    matches_per_file: std::collections::BTreeMap<String, u64>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
@@ -358,3 +360,53 @@ rgtest!(r1412_look_behind_match_missing, |dir: Dir, mut cmd: TestCommand| {
     assert_eq!(m.lines, Data::text("bar\n"));
     assert_eq!(m.submatches.len(), 1);
 });
+
+// When multiple patterns are given, each submatch reports which pattern
+// produced it.
+rgtest!(submatch_pattern_index, |dir: Dir, mut cmd: TestCommand| {
+    // Pattern tracking is only implemented for the default Rust matcher.
+    if dir.is_pcre2() {
+        return;
+    }
+
+    dir.create("test", "say hello to the world\n");
+    cmd.args(&["--json", "-e", "world", "-e", "hello", "test"]);
+
+    let mut patterns = vec![];
+    for line in cmd.stdout().lines() {
+        let v: json::Value = json::from_str(line).unwrap();
+        if v["type"] != "match" {
+            continue;
+        }
+        for sub in v["data"]["submatches"].as_array().unwrap() {
+            patterns.push((
+                sub["match"]["text"].as_str().unwrap().to_string(),
+                sub["pattern"].as_u64().unwrap(),
+            ));
+        }
+    }
+    assert_eq!(
+        vec![("hello".to_string(), 1), ("world".to_string(), 0)],
+        patterns
+    );
+});
+
+// The summary message reports the number of matches found in each file.
+rgtest!(summary_matches_per_file, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("sherlock", SHERLOCK);
+    dir.create("watson", "Doctor Watsons\n");
+    cmd.arg("--json").arg("Watson");
+
+    let msgs = json_decode(&cmd.stdout());
+    let summary = msgs.last().unwrap().unwrap_summary();
+    assert_eq!(summary.stats.matches_per_file.len(), 2);
+    assert_eq!(summary.stats.matches_per_file.get("sherlock"), Some(&2));
+    assert_eq!(summary.stats.matches_per_file.get("watson"), Some(&1));
+
+    // Per-file counts are omitted entirely from per-file `end` messages.
+    for msg in msgs.iter() {
+        if let Message::End(ref end) = *msg {
+            assert!(end.stats.matches_per_file.is_empty());
+        }
+    }
+});
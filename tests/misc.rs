@@ -213,6 +213,112 @@ but Watson, Doctor has to have it taken out for him and dusted,
     eqnice!(expected, cmd.stdout());
 });
 
+rgtest!(write_replace, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("file", "hello world\nnothing\nhello again\n");
+    cmd.args(&["-r", "goodbye $1", "-W", "hello (\\w+)", "file"]);
+
+    let expected = "\
+file: 2 replacements
+2 replacements across 1 file
+";
+    eqnice!(expected, cmd.stdout());
+
+    let contents = std::fs::read_to_string(dir.path().join("file")).unwrap();
+    eqnice!("goodbye world\nnothing\ngoodbye again\n", contents);
+});
+
+rgtest!(write_replace_backup_suffix, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("file", "hello world\n");
+    cmd.args(&[
+        "-r",
+        "goodbye",
+        "-W",
+        "--backup-suffix",
+        ".bak",
+        "hello",
+        "file",
+    ]);
+
+    let expected = "\
+file: 1 replacement
+1 replacement across 1 file
+";
+    eqnice!(expected, cmd.stdout());
+
+    let contents = std::fs::read_to_string(dir.path().join("file")).unwrap();
+    eqnice!("goodbye world\n", contents);
+    let backup = std::fs::read_to_string(dir.path().join("file.bak")).unwrap();
+    eqnice!("hello world\n", backup);
+});
+
+rgtest!(write_replace_dry_run, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("file", "hello world\n");
+    cmd.args(&["-r", "goodbye", "-W", "--dry-run", "hello", "file"]);
+
+    let expected = "\
+file: 1 replacement
+1 replacement across 1 file (dry run)
+";
+    eqnice!(expected, cmd.stdout());
+
+    let contents = std::fs::read_to_string(dir.path().join("file")).unwrap();
+    eqnice!("hello world\n", contents);
+});
+
+rgtest!(search_archives_tar, |dir: Dir, mut cmd: TestCommand| {
+    let mut builder = tar::Builder::new(vec![]);
+    for (name, data) in
+        [("src/a.txt", "hello from tar\n"), ("src/b.rs", "hello()\n")]
+    {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, name, data.as_bytes()).unwrap();
+    }
+    dir.create_bytes("t.tar", &builder.into_inner().unwrap());
+    cmd.args(&["--search-archives", "hello", "t.tar"]);
+
+    let expected = "\
+t.tar!/src/a.txt:hello from tar
+t.tar!/src/b.rs:hello()
+";
+    eqnice!(expected, cmd.stdout());
+});
+
+rgtest!(search_archives_types, |dir: Dir, mut cmd: TestCommand| {
+    let mut builder = tar::Builder::new(vec![]);
+    for (name, data) in
+        [("src/a.txt", "hello from tar\n"), ("src/b.rs", "hello()\n")]
+    {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, name, data.as_bytes()).unwrap();
+    }
+    dir.create_bytes("t.tar", &builder.into_inner().unwrap());
+    cmd.args(&["--search-archives", "-t", "rust", "hello", "t.tar"]);
+
+    eqnice!("t.tar!/src/b.rs:hello()\n", cmd.stdout());
+});
+
+rgtest!(server_mode, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("a.txt", "hello world\n");
+    cmd.arg("--server");
+
+    let out = cmd.pipe(b"{\"id\":1,\"pattern\":\"hello\"}\n{\"id\":2}\n");
+    assert_eq!(
+        out.lines().next().unwrap(),
+        r#"{"id":1,"type":"query_begin"}"#
+    );
+    assert!(out.contains(r#""type":"match""#));
+    assert!(out.contains(r#"{"id":1,"matched":true,"type":"query_end"}"#));
+    assert!(out.contains(
+        r#"{"id":2,"message":"request must contain 'pattern' or 'patterns'","type":"error"}"#
+    ));
+});
+
 rgtest!(replace_with_only_matching, |dir: Dir, mut cmd: TestCommand| {
     dir.create("sherlock", SHERLOCK);
     cmd.arg("-o").arg("-r").arg("$1").arg(r"of (\w+)").arg("sherlock");
@@ -394,6 +500,14 @@ rgtest!(count_matches_via_only, |dir: Dir, mut cmd: TestCommand| {
     eqnice!(expected, cmd.stdout());
 });
 
+rgtest!(count_lines_and_matches, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("sherlock", SHERLOCK);
+    cmd.arg("--count-lines-and-matches").arg("the");
+
+    let expected = "sherlock:3:4\n";
+    eqnice!(expected, cmd.stdout());
+});
+
 rgtest!(include_zero, |dir: Dir, mut cmd: TestCommand| {
     dir.create("sherlock", SHERLOCK);
     cmd.args(&["--count", "--include-zero", "nada"]);
@@ -847,6 +961,28 @@ be, to a very large extent, the result of luck. Sherlock Holmes
     eqnice!(expected, cmd.stdout());
 });
 
+rgtest!(preprocessing_cache, |dir: Dir, mut cmd: TestCommand| {
+    if !cmd_exists("xzcat") {
+        return;
+    }
+
+    dir.create_bytes("sherlock.xz", include_bytes!("./data/sherlock.xz"));
+    cmd.args(&["--pre", "xzcat", "--pre-cache", "cache", "Sherlock"]);
+
+    let expected = "\
+sherlock.xz:For the Doctor Watsons of this world, as opposed to the Sherlock
+sherlock.xz:be, to a very large extent, the result of luck. Sherlock Holmes
+";
+    eqnice!(expected, cmd.stdout());
+    // The cache now holds one entry and a second run (served from the
+    // cache) produces identical results.
+    assert_eq!(
+        1,
+        std::fs::read_dir(dir.path().join("cache")).unwrap().count()
+    );
+    eqnice!(expected, cmd.stdout());
+});
+
 rgtest!(preprocessing_glob, |dir: Dir, mut cmd: TestCommand| {
     if !cmd_exists("xzcat") {
         return;
@@ -1099,6 +1235,15 @@ rgtest!(sort_files, |dir: Dir, mut cmd: TestCommand| {
     eqnice!(expected, cmd.args(["--sort", "path", "test"]).stdout());
 });
 
+rgtest!(sort_files_parallel, |dir: Dir, mut cmd: TestCommand| {
+    sort_setup(dir);
+    let expected = "a:test\nb:test\ndir/c:test\ndir/d:test\n";
+    eqnice!(
+        expected,
+        cmd.args(["--sort", "path", "-j", "4", "test"]).stdout()
+    );
+});
+
 rgtest!(sort_accessed, |dir: Dir, mut cmd: TestCommand| {
     sort_setup(dir);
     let expected = "a:test\ndir/c:test\nb:test\ndir/d:test\n";
@@ -1110,3 +1255,777 @@ rgtest!(sortr_accessed, |dir: Dir, mut cmd: TestCommand| {
     let expected = "dir/d:test\nb:test\ndir/c:test\na:test\n";
     eqnice!(expected, cmd.args(["--sortr", "accessed", "test"]).stdout());
 });
+
+rgtest!(newer_older_than, |dir: Dir, mut cmd: TestCommand| {
+    use std::time::{Duration, SystemTime};
+
+    dir.create("old", "test");
+    dir.create("new", "test");
+    let f = std::fs::File::options()
+        .write(true)
+        .open(dir.path().join("old"))
+        .unwrap();
+    f.set_modified(SystemTime::now() - Duration::from_secs(7 * 86_400))
+        .unwrap();
+
+    eqnice!("new:test\n", cmd.args(["--newer-than", "1d", "test"]).stdout());
+
+    let mut cmd = dir.command();
+    eqnice!("old:test\n", cmd.args(["--older-than", "1d", "test"]).stdout());
+});
+
+rgtest!(max_total_matches, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("a", "x\nx\nx\n");
+    dir.create("b", "x\nx\nx\n");
+    dir.create("c", "x\nx\nx\n");
+
+    let args = ["--sort", "path", "--max-total-matches", "4", "x"];
+    let expected = "a:x\na:x\na:x\nb:x\nb:x\nb:x\n";
+    eqnice!(expected, cmd.args(args).stdout());
+
+    let mut cmd = dir.command();
+    let args = ["--sort", "path", "--max-total-matches", "4", "-m1", "x"];
+    eqnice!("a:x\nb:x\nc:x\n", cmd.args(args).stdout());
+});
+
+rgtest!(context_start, |dir: Dir, mut cmd: TestCommand| {
+    dir.create(
+        "code.py",
+        "def foo():\n    a = 1\n\ndef bar():\n    b = needle\n    return b\n\ndef baz():\n    c = 3\n",
+    );
+
+    let args = ["--context-start", "^def ", "needle", "code.py"];
+    let expected = "def bar():\n    b = needle\n    return b\n\n";
+    eqnice!(expected, cmd.args(args).stdout());
+
+    // The fixed context sizes and the block context combine, with the
+    // larger of the two winning.
+    let mut cmd = dir.command();
+    let args = ["-B4", "--context-start", "^def ", "needle", "code.py"];
+    let expected = "\
+def foo():
+    a = 1
+
+def bar():
+    b = needle
+    return b
+
+";
+    eqnice!(expected, cmd.args(args).stdout());
+});
+
+rgtest!(all_match, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("a", "foo bar\nfoo only\nbar only\n");
+    dir.create("b", "foo only here\n");
+
+    let args = ["-e", "foo", "-e", "bar", "--all-match", "--sort", "path"];
+    eqnice!("a:foo bar\n", cmd.args(args).stdout());
+
+    // File granularity reports every matching line, but only from files
+    // where every pattern matches somewhere.
+    let mut cmd = dir.command();
+    let args =
+        ["-e", "foo", "-e", "bar", "--all-match=file", "--sort", "path"];
+    eqnice!("a:foo bar\na:foo only\na:bar only\n", cmd.args(args).stdout());
+
+    // With a single pattern, the flag has no effect.
+    let mut cmd = dir.command();
+    let args = ["-e", "foo", "--all-match", "--sort", "path"];
+    let expected = "a:foo bar\na:foo only\nb:foo only here\n";
+    eqnice!(expected, cmd.args(args).stdout());
+});
+
+rgtest!(replace_case_transforms, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("code.rs", "fn do_thing() {}\nlet my_var = 1;\n");
+
+    let args = [r"(\w+)_(\w+)", "-r", r"\U$1\E_$2", "code.rs"];
+    eqnice!("fn DO_thing() {}\nlet MY_var = 1;\n", cmd.args(args).stdout());
+
+    let mut cmd = dir.command();
+    let args = [r"my_(\w+)", "-r", "my_${1:upper}", "code.rs"];
+    eqnice!("let my_VAR = 1;\n", cmd.args(args).stdout());
+});
+
+rgtest!(files_format, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("a", "abcd");
+    dir.create_dir("sub");
+    dir.create("sub/b", "efgh");
+
+    let args = ["--files", "--files-format", "size,type", "--sort", "path"];
+    let expected = "4\tfile\ta\n4\tfile\tsub/b\n";
+    eqnice!(expected, cmd.args(args).stdout());
+});
+
+rgtest!(files_json, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("a", "abcd");
+
+    let args = ["--files", "--json"];
+    let got = cmd.args(args).stdout();
+    let msg: serde_json::Value = serde_json::from_str(&got).unwrap();
+    assert_eq!("file", msg["type"]);
+    assert_eq!("a", msg["data"]["path"]["text"]);
+    assert_eq!(4, msg["data"]["size"]);
+    assert_eq!("file", msg["data"]["file_type"]);
+    assert!(msg["data"]["mtime"].is_u64());
+});
+
+rgtest!(type_list_json, |_: Dir, mut cmd: TestCommand| {
+    let args = ["--type-list", "--json"];
+    let got = cmd.args(args).stdout();
+    let rust = got
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .find(|msg| msg["data"]["name"] == "rust")
+        .unwrap();
+    assert_eq!("file-type", rust["type"]);
+    assert!(rust["data"]["globs"]
+        .as_array()
+        .unwrap()
+        .contains(&serde_json::Value::from("*.rs")));
+});
+
+rgtest!(count_total, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("a", "x\nx y x\n");
+    dir.create("b", "x\n");
+
+    eqnice!("3\n", cmd.args(["--count-total", "x"]).stdout());
+
+    let mut cmd = dir.command();
+    let args = ["--count-total", "--count-matches", "x"];
+    eqnice!("4\n", cmd.args(args).stdout());
+});
+
+rgtest!(stats_format_json, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("a", "x\nx\n");
+
+    let args = ["--stats", "--stats-format", "json", "x"];
+    let got = cmd.args(args).stdout();
+    let last = got.lines().last().unwrap();
+    let msg: serde_json::Value = serde_json::from_str(last).unwrap();
+    assert_eq!("summary", msg["type"]);
+    assert_eq!(2, msg["data"]["stats"]["matched_lines"]);
+    assert_eq!(1, msg["data"]["stats"]["searches_with_match"]);
+});
+
+rgtest!(generate, |_: Dir, mut cmd: TestCommand| {
+    let man = cmd.args(["--generate", "man"]).stdout();
+    assert!(man.starts_with(".TH RG 1"));
+    assert!(man.contains("\\-\\-context\\-start"));
+});
+
+rgtest!(files_from, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("a.txt", "x\n");
+    dir.create("b.rs", "x\n");
+    dir.create("c.md", "x\n");
+    dir.create("list", "a.txt\nb.rs\n");
+
+    eqnice!(
+        "a.txt:x\nb.rs:x\n",
+        sort_lines(&cmd.args(["--files-from", "list", "x"]).stdout())
+    );
+
+    // Type filters still apply to paths read from the list.
+    let mut cmd = dir.command();
+    let args = ["--files-from", "list", "-t", "rust", "-H", "x"];
+    eqnice!("b.rs:x\n", cmd.args(args).stdout());
+});
+
+rgtest!(files_from0, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("a.txt", "x\n");
+    dir.create("b.rs", "x\n");
+    dir.create_bytes("list", b"a.txt\x00b.rs\x00");
+
+    eqnice!(
+        "a.txt:x\nb.rs:x\n",
+        sort_lines(&cmd.args(["--files-from0", "list", "x"]).stdout())
+    );
+});
+
+rgtest!(binary_files, |dir: Dir, mut cmd: TestCommand| {
+    dir.create_bytes("bin", b"hello\x00world\n");
+
+    let got = cmd.args(["--binary-files=binary", "hello", "bin"]).stdout();
+    assert!(got.contains("binary file matches"));
+
+    let mut cmd = dir.command();
+    let args = ["--binary-files=text", "hello", "bin"];
+    eqnice!("hello\u{0}world\n", cmd.args(args).stdout());
+
+    let mut cmd = dir.command();
+    cmd.args(["--binary-files=without-match", "hello", "bin"]);
+    cmd.assert_err();
+});
+
+rgtest!(path_config, |dir: Dir, mut cmd: TestCommand| {
+    dir.create_dir("src/a/b");
+    dir.create_dir("vendor");
+    dir.create("src/top.txt", "x\n");
+    dir.create("src/a/mid.txt", "x\n");
+    dir.create("src/a/b/deep.txt", "x\n");
+    dir.create("vendor/a.js", "x\n");
+    dir.create("vendor/a.min.js", "x\n");
+
+    let args = [
+        "x",
+        "--path-config",
+        "src:--max-depth=1",
+        "--path-config",
+        "vendor:--glob=!*.min.js",
+    ];
+    let expected = "\
+src/top.txt:x
+vendor/a.js:x
+";
+    eqnice!(expected, sort_lines(&cmd.args(args).stdout()));
+});
+
+rgtest!(encoding_auto, |dir: Dir, mut cmd: TestCommand| {
+    // "café au lait" in Latin-1 and "hello" in BOM-less UTF-16LE.
+    dir.create_bytes("latin1", b"caf\xE9 au lait\n");
+    dir.create_bytes(
+        "utf16le",
+        b"h\x00e\x00l\x00l\x00o\x00 \x00c\x00a\x00f\x00\xE9\x00\n\x00",
+    );
+
+    // Without detection, neither file matches.
+    cmd.args(["caf\u{E9}", "-j1"]);
+    cmd.assert_err();
+
+    let mut cmd = dir.command();
+    let expected = "\
+latin1:café au lait
+utf16le:hello café
+";
+    let args = ["-E", "auto", "caf\u{E9}", "-j1"];
+    eqnice!(expected, sort_lines(&cmd.args(args).stdout()));
+});
+
+rgtest!(label, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("unused", "");
+
+    let mut cmd = cmd.args(["--label", "x@HEAD", "hello", "-"]);
+    eqnice!("x@HEAD:hello\n", cmd.pipe(b"hello\n"));
+});
+
+rgtest!(vimgrep_ranges, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("file", "foo bar foo\nbaz\n");
+
+    let expected = "\
+file:1:1:1:4:foo bar foo
+file:1:9:1:12:foo bar foo
+";
+    eqnice!(expected, cmd.args(["--vimgrep-ranges", "foo"]).stdout());
+});
+
+rgtest!(flush_interval, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("a.txt", "x1\nx2\nx3\n");
+
+    // A zero interval flushes after every complete line, which must not
+    // corrupt or reorder the output of a single file.
+    let args = ["--flush-interval", "0ms", "x", "a.txt"];
+    eqnice!("x1\nx2\nx3\n", cmd.args(args).stdout());
+
+    // An invalid duration is an error, even for single threaded searches
+    // that never use the interval.
+    let mut cmd = dir.command();
+    cmd.args(["--flush-interval", "wat", "x", "a.txt"]);
+    cmd.assert_err();
+});
+
+rgtest!(only_replace, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("a.txt", "alice@example bob@test\n");
+    dir.create("b.txt", "carol@home\n");
+
+    // No file names or line numbers, even across multiple files.
+    let args = ["--only-replace", "$2,$1", r"(\w+)@(\w+)", "--sort", "path"];
+    let expected = "\
+example,alice
+test,bob
+home,carol
+";
+    eqnice!(expected, cmd.args(args).stdout());
+
+    // File names and line numbers are opt-in.
+    let mut cmd = dir.command();
+    let args = ["--only-replace", "$1", r"(\w+)@", "-Hn", "--sort", "path"];
+    let expected = "\
+a.txt:1:alice
+a.txt:1:bob
+b.txt:1:carol
+";
+    eqnice!(expected, cmd.args(args).stdout());
+});
+
+rgtest!(sort_size, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("small", "test\n");
+    dir.create("large", "test\ntest\ntest\n");
+    dir.create("medium", "test\ntest\n");
+
+    let expected = "small:1\nmedium:2\nlarge:3\n";
+    eqnice!(expected, cmd.args(["--sort", "size", "-c", "test"]).stdout());
+
+    let mut cmd = dir.command();
+    let expected = "large:3\nmedium:2\nsmall:1\n";
+    eqnice!(expected, cmd.args(["--sortr", "size", "-c", "test"]).stdout());
+});
+
+rgtest!(max_memory, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("a.txt", "test\n");
+
+    // A generous budget doesn't change anything.
+    let args = ["--max-memory", "100M", "test"];
+    eqnice!("a.txt:test\n", cmd.args(args).stdout());
+
+    // A budget too small for the thread count is refused up front.
+    let mut cmd = dir.command();
+    cmd.args(["--max-memory", "1M", "-j4", "test"]);
+    cmd.assert_err();
+
+    // A line that doesn't fit in the searcher's share is an error rather
+    // than an unbounded allocation.
+    let mut cmd = dir.command();
+    dir.create("long.txt", &format!("{} test\n", "a".repeat(2 << 20)));
+    cmd.args(["--max-memory", "2M", "-j1", "--no-mmap", "test", "long.txt"]);
+    cmd.assert_err();
+});
+
+rgtest!(errors_json, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("exists", "x\n");
+
+    cmd.args(["--errors", "json", "x", "exists", "missing"]);
+    // The missing file makes the command exit with an error, so bypass the
+    // success check that `TestCommand::output` performs.
+    let output = cmd.cmd().output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let msg: serde_json::Value =
+        serde_json::from_str(stderr.lines().next().unwrap()).unwrap();
+    eqnice!("error", msg["type"].as_str().unwrap());
+    assert!(msg["data"]["message"].as_str().unwrap().contains("missing"));
+});
+
+rgtest!(rgtypes, |dir: Dir, mut cmd: TestCommand| {
+    dir.create(".rgtypes", "# generated files\ngen:*.gen\n");
+    dir.create("a.gen", "x\n");
+    dir.create("b.rs", "x\n");
+
+    eqnice!("a.gen:x\n", cmd.args(["-t", "gen", "x"]).stdout());
+
+    // Without the project-local definitions, the type doesn't exist.
+    let mut cmd = dir.command();
+    cmd.args(["--no-rgtypes", "-t", "gen", "x"]);
+    cmd.assert_err();
+});
+
+rgtest!(hyperlink_format, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("file", "x\n");
+
+    let args =
+        ["--hyperlink-format", "x://h{path}:{line}", "-Hn", "x", "file"];
+    let got = cmd.args(args).stdout();
+    assert!(got.starts_with("\x1b]8;;x://h"), "got: {:?}", got);
+    assert!(
+        got.contains(":1\x1b\\file\x1b]8;;\x1b\\:1:x\n"),
+        "got: {:?}",
+        got
+    );
+
+    // A template without {path} is rejected.
+    let mut cmd = dir.command();
+    cmd.args(["--hyperlink-format", "x://nope", "x", "file"]);
+    cmd.assert_err();
+
+    // Hyperlinks are off by default when stdout isn't a supporting tty.
+    let mut cmd = dir.command();
+    eqnice!("file:x\n", cmd.args(["-H", "x", "file"]).stdout());
+});
+
+rgtest!(pattern_file_flags, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("patterns", "i:FOO\nw:bar\nx:a.b\n:i:plain\n");
+    dir.create(
+        "haystack",
+        "foo\nbar\nsubaru\na.b\naxb\nx:a.b\ni:plain\nnothing\n",
+    );
+
+    cmd.args(["-f", "patterns", "haystack"]);
+    eqnice!("foo\nbar\na.b\nx:a.b\ni:plain\n", cmd.stdout());
+
+    // With -F, the prefix syntax doesn't apply and lines are literals.
+    let mut cmd = dir.command();
+    cmd.args(["-F", "-f", "patterns", "haystack"]);
+    eqnice!("x:a.b\n", cmd.stdout());
+});
+
+rgtest!(decompress_cmd, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("hay.fake", "foo\n");
+
+    // 'cat' stands in for a real decompressor; what matters is that the
+    // custom command is used for the mapped extension.
+    cmd.args(["-z", "--decompress-cmd", "fake=cat", "foo", "hay.fake"]);
+    eqnice!("foo\n", cmd.stdout());
+
+    // A value without a command is rejected.
+    let mut cmd = dir.command();
+    cmd.args(["-z", "--decompress-cmd", "fake", "foo", "hay.fake"]);
+    cmd.assert_err();
+});
+
+rgtest!(checkpoint_resume, |dir: Dir, mut cmd: TestCommand| {
+    // The checkpoint file lives outside the searched directory so that it
+    // isn't picked up by the search itself.
+    dir.create_dir("src");
+    dir.create("src/a.txt", "x\n");
+    dir.create("src/b.txt", "x\n");
+
+    cmd.args(["--checkpoint", "progress", "--sort", "path", "x", "src"]);
+    eqnice!("src/a.txt:x\nsrc/b.txt:x\n", cmd.stdout());
+
+    // Resuming skips the files recorded in the first run, so only files
+    // created since then are searched.
+    dir.create("src/c.txt", "x\n");
+    let mut cmd = dir.command();
+    cmd.args([
+        "--checkpoint",
+        "progress",
+        "--resume",
+        "progress",
+        "--sort",
+        "path",
+        "x",
+        "src",
+    ]);
+    eqnice!("src/c.txt:x\n", cmd.stdout());
+
+    // Everything has been searched now, so a resumed run finds nothing.
+    let mut cmd = dir.command();
+    cmd.args(["--resume", "progress", "--sort", "path", "x", "src"]);
+    cmd.assert_err();
+});
+
+rgtest!(dedup_hardlinks, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("a.txt", "x\n");
+    std::fs::hard_link(dir.path().join("a.txt"), dir.path().join("b.txt"))
+        .unwrap();
+
+    // Both links are searched by default.
+    cmd.args(["--sort", "path", "x"]);
+    eqnice!("a.txt:x\nb.txt:x\n", cmd.stdout());
+
+    // With dedup enabled, the underlying file is searched only once. Which
+    // link is reported depends on traversal order, so just count results.
+    let mut cmd = dir.command();
+    cmd.args(["--dedup-hardlinks", "-c", "x"]);
+    let got = cmd.stdout();
+    assert_eq!(1, got.lines().count(), "got: {:?}", got);
+
+    // The same file given as multiple explicit roots is also suppressed.
+    let mut cmd = dir.command();
+    cmd.args(["--dedup-hardlinks", "x", "a.txt", "a.txt"]);
+    eqnice!("a.txt:x\n", cmd.stdout());
+});
+
+rgtest!(group_by_dir, |dir: Dir, mut cmd: TestCommand| {
+    dir.create_dir("a");
+    dir.create_dir("b");
+    dir.create("a/one.txt", "x\ny\nx\n");
+    dir.create("a/two.txt", "x\n");
+    dir.create("b/three.txt", "x\n");
+    dir.create("b/none.txt", "nope\n");
+
+    cmd.args(["--group-by", "dir", "x"]);
+    let expected = "\
+a
+a/one.txt:x
+a/one.txt:x
+a/two.txt:x
+(3 matches in 2 files)
+
+b
+b/three.txt:x
+(1 matches in 1 files)
+";
+    eqnice!(expected, cmd.stdout());
+
+    // Grouping is ignored for summary formats.
+    let mut cmd = dir.command();
+    cmd.args(["--group-by", "dir", "--sort", "path", "-c", "x"]);
+    eqnice!("a/one.txt:2\na/two.txt:1\nb/three.txt:1\n", cmd.stdout());
+});
+
+rgtest!(no_messages_classes, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("exists", "x\n");
+
+    // Suppressing the io class silences the missing-file error while the
+    // search results are still printed. The exit code still reflects that
+    // an error occurred.
+    cmd.args(["--no-messages=io", "x", "exists", "missing"]);
+    let output = cmd.cmd().output().unwrap();
+    eqnice!("exists:x\n", String::from_utf8_lossy(&output.stdout));
+    eqnice!("", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(Some(2), output.status.code());
+
+    // Other classes leave the io error alone.
+    let mut cmd = dir.command();
+    cmd.args(["--no-messages=other", "x", "exists", "missing"]);
+    let output = cmd.cmd().output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("missing"), "stderr: {:?}", stderr);
+
+    // An unknown class is rejected.
+    let mut cmd = dir.command();
+    cmd.args(["--no-messages=bogus", "x", "exists"]);
+    cmd.assert_err();
+});
+
+// Streaming non-regular files is easiest to exercise with a Unix FIFO; the
+// same code path handles named pipes on Windows.
+#[cfg(unix)]
+rgtest!(search_stream, |dir: Dir, mut cmd: TestCommand| {
+    let fifo = dir.path().join("fifo");
+    assert!(std::process::Command::new("mkfifo")
+        .arg(&fifo)
+        .status()
+        .unwrap()
+        .success());
+    let fifo_writer = fifo.clone();
+    let writer = std::thread::spawn(move || {
+        std::fs::write(fifo_writer, "hello x\nworld\n").unwrap();
+    });
+
+    cmd.args(["--allow-special-files", "x", "fifo"]);
+    eqnice!("hello x\n", cmd.stdout());
+    writer.join().unwrap();
+
+    // Without --allow-special-files, special files are skipped with a
+    // message, since reading them can block indefinitely.
+    let mut cmd = dir.command();
+    cmd.args(["x", "fifo"]);
+    let output = cmd.cmd().output().unwrap();
+    eqnice!("", String::from_utf8_lossy(&output.stdout));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--allow-special-files"), "stderr: {stderr:?}");
+});
+
+rgtest!(bench, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("haystack", "a x\nb\nc x\n");
+
+    cmd.args(["--bench=2", "x"]);
+    let stdout = cmd.stdout();
+    assert!(stdout.contains("walked 1 files"), "stdout: {:?}", stdout);
+    assert!(stdout.contains("run 1:"), "stdout: {:?}", stdout);
+    assert!(stdout.contains("run 2:"), "stdout: {:?}", stdout);
+    assert!(stdout.contains("2 matches"), "stdout: {:?}", stdout);
+
+    // The exit code still reflects whether anything matched.
+    let mut cmd = dir.command();
+    cmd.args(["--bench", "nothinghere"]);
+    let output = cmd.cmd().output().unwrap();
+    assert_eq!(Some(1), output.status.code());
+
+    // Zero runs is nonsensical.
+    let mut cmd = dir.command();
+    cmd.args(["--bench=0", "x"]);
+    cmd.assert_err();
+});
+
+rgtest!(explain, |dir: Dir, mut cmd: TestCommand| {
+    // .gitignore files are only respected inside git repositories.
+    dir.create_dir(".git");
+    dir.create(".gitignore", "target\n*.log\n!keep.log\n");
+    dir.create_dir("target/debug");
+    dir.create("target/debug/out.txt", "x\n");
+    dir.create("app.log", "x\n");
+    dir.create("keep.log", "x\n");
+    dir.create("main.rs", "x\n");
+
+    cmd.args([
+        "--explain",
+        "target/debug/out.txt",
+        "--explain",
+        "app.log",
+        "--explain",
+        "keep.log",
+        "--explain",
+        "main.rs",
+    ]);
+    let expected = "\
+target/debug/out.txt:
+  target: ignored by glob 'target' from ROOT/.gitignore
+  not searched
+app.log:
+  app.log: ignored by glob '*.log' from ROOT/.gitignore
+  not searched
+keep.log:
+  keep.log: whitelisted by glob '!keep.log' from ROOT/.gitignore
+  searched
+main.rs:
+  no rule matched
+  searched
+";
+    let root = dir.path().display().to_string();
+    eqnice!(expected.replace("ROOT", &root), cmd.stdout());
+
+    // The normal filtering flags apply, e.g., overrides and file types.
+    // The exit code is 1 since no explained path would be searched.
+    let mut cmd = dir.command();
+    cmd.args(["--glob", "!*.rs", "--explain", "main.rs"]);
+    let output = cmd.cmd().output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("override glob '!*.rs'"), "stdout: {stdout}");
+    assert_eq!(Some(1), output.status.code());
+});
+
+rgtest!(max_filesize_searched, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("huge.log", "match near top\nfiller\nmatch at bottom\n");
+
+    // Only the first 20 bytes are searched, so the match at the bottom is
+    // never seen. The truncation is noted on stderr.
+    cmd.args(["--max-filesize-searched", "20", "match"]);
+    let output = cmd.cmd().output().unwrap();
+    eqnice!(
+        "huge.log:match near top\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("only the first 20 bytes were searched"),
+        "stderr: {stderr:?}"
+    );
+
+    // --no-messages suppresses the note.
+    let mut cmd = dir.command();
+    cmd.args(["--max-filesize-searched", "20", "--no-messages", "match"]);
+    eqnice!("huge.log:match near top\n", cmd.stdout());
+
+    // A large enough budget changes nothing.
+    let mut cmd = dir.command();
+    cmd.args(["--max-filesize-searched", "1K", "--sort", "path", "match"]);
+    eqnice!(
+        "huge.log:match near top\nhuge.log:match at bottom\n",
+        cmd.stdout()
+    );
+});
+
+rgtest!(colors_extended_styles, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("input.txt", "test\n");
+    cmd.args([
+        "--color",
+        "ansi",
+        "--colors",
+        "path:none",
+        "--colors",
+        "match:style:italic",
+        "--colors",
+        "match:style:dimmed",
+        "--colors",
+        "match:style:strikethrough",
+        "test",
+        "input.txt",
+    ]);
+    // Bold comes from the default specs; italic, dimmed and strikethrough
+    // are the escape codes 3, 2 and 9 respectively.
+    eqnice!(
+        "\x1b[0m\x1b[1m\x1b[2m\x1b[3m\x1b[9m\x1b[31mtest\x1b[0m\n",
+        cmd.stdout()
+    );
+
+    // The special color 'none' resets a previously set background without
+    // clearing the rest of the spec.
+    let mut cmd = dir.command();
+    cmd.args([
+        "--color",
+        "ansi",
+        "--colors",
+        "path:none",
+        "--colors",
+        "match:bg:yellow",
+        "--colors",
+        "match:bg:none",
+        "test",
+        "input.txt",
+    ]);
+    eqnice!("\x1b[0m\x1b[1m\x1b[31mtest\x1b[0m\n", cmd.stdout());
+
+    cmd = dir.command();
+    cmd.args(["--colors", "match:style:blink", "test", "input.txt"]);
+    cmd.assert_err();
+});
+
+rgtest!(timeout_per_file, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("slow.log", "needle\n");
+
+    // A zero timeout expires before any of the file is searched, so nothing
+    // is found and a warning lands on stderr.
+    cmd.args(["--timeout-per-file", "0", "needle"]);
+    let output = cmd.cmd().output().unwrap();
+    assert_eq!(Some(1), output.status.code());
+    eqnice!("", String::from_utf8_lossy(&output.stdout));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("search timed out"), "stderr: {stderr:?}");
+
+    // --no-messages suppresses the warning but keeps the early stop.
+    let mut cmd = dir.command();
+    cmd.args(["--timeout-per-file", "0", "--no-messages", "needle"]);
+    let output = cmd.cmd().output().unwrap();
+    assert_eq!(Some(1), output.status.code());
+    eqnice!("", String::from_utf8_lossy(&output.stderr));
+
+    // A generous timeout changes nothing.
+    let mut cmd = dir.command();
+    cmd.args(["--timeout-per-file", "10s", "needle"]);
+    eqnice!("slow.log:needle\n", cmd.stdout());
+
+    cmd = dir.command();
+    cmd.args(["--timeout-per-file", "oops", "needle"]);
+    cmd.assert_err();
+});
+
+rgtest!(path_format, |dir: Dir, mut cmd: TestCommand| {
+    dir.create("a.txt", "x\n");
+
+    cmd.args(["--files", "--path-format", "absolute"]);
+    let root = dir.path().display().to_string();
+    eqnice!(format!("{root}/a.txt\n"), cmd.stdout());
+
+    // An absolute search root is printed relative to the current working
+    // directory with 'relative'.
+    let mut cmd = dir.command();
+    cmd.args(["--files", "--path-format", "relative"]);
+    cmd.arg(dir.path().join("a.txt"));
+    eqnice!("a.txt\n", cmd.stdout());
+
+    // By default, paths are printed as given.
+    let mut cmd = dir.command();
+    cmd.arg("--files");
+    cmd.arg(dir.path().join("a.txt"));
+    eqnice!(format!("{root}/a.txt\n"), cmd.stdout());
+
+    cmd = dir.command();
+    cmd.args(["--files", "--path-format", "bogus"]);
+    cmd.assert_err();
+});
+
+rgtest!(sort_modified_parallel, |dir: Dir, mut cmd: TestCommand| {
+    use std::time::{Duration, SystemTime};
+
+    dir.create("old", "test");
+    dir.create("mid", "test");
+    dir.create("new", "test");
+    let base = SystemTime::now() - Duration::from_secs(3600);
+    for (i, name) in ["old", "mid", "new"].iter().enumerate() {
+        let file = std::fs::File::options()
+            .write(true)
+            .open(dir.path().join(name))
+            .unwrap();
+        let modified = base + Duration::from_secs(60 * i as u64);
+        file.set_modified(modified).unwrap();
+    }
+
+    // An explicit thread count with a stat-based sort runs the search in
+    // parallel while still emitting results in sorted order.
+    cmd.args(["--sort", "modified", "--threads", "8", "test"]);
+    eqnice!("old:test\nmid:test\nnew:test\n", cmd.stdout());
+
+    let mut cmd = dir.command();
+    cmd.args(["--sortr", "modified", "--threads", "8", "test"]);
+    eqnice!("new:test\nmid:test\nold:test\n", cmd.stdout());
+});
@@ -402,7 +402,8 @@ rgtest!(r428_unrecognized_style, |dir: Dir, mut cmd: TestCommand| {
     let stderr = String::from_utf8_lossy(&output.stderr);
     let expected = "\
 unrecognized style attribute ''. Choose from: nobold, bold, nointense, \
-intense, nounderline, underline.
+intense, nounderline, underline, noitalic, italic, nodimmed, dimmed, \
+nostrikethrough, strikethrough.
 ";
     eqnice!(expected, stderr);
 });